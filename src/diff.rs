@@ -0,0 +1,369 @@
+use crate::node::{DomException, Node, NodeKind};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// A change to a live tree, produced by `Node::diff`. Nodes are the live
+// nodes themselves, so patches are applied in order to the tree they were
+// computed against.
+#[derive(Clone, Debug)]
+pub enum Patch {
+    // insert `node`, a copy taken from the new tree, before `before` or last
+    Insert {
+        parent: Rc<RefCell<Node>>,
+        node: Rc<RefCell<Node>>,
+        before: Option<Rc<RefCell<Node>>>,
+    },
+    Remove {
+        node: Rc<RefCell<Node>>,
+    },
+    // move an existing child to before `before` or to the end of `parent`
+    Move {
+        parent: Rc<RefCell<Node>>,
+        node: Rc<RefCell<Node>>,
+        before: Option<Rc<RefCell<Node>>>,
+    },
+    // sets the attribute, or removes it when `value` is None
+    SetAttribute {
+        element: Rc<RefCell<Node>>,
+        name: String,
+        value: Option<String>,
+    },
+    // sets the data of a text or comment node
+    SetText {
+        node: Rc<RefCell<Node>>,
+        text: String,
+    },
+}
+
+impl Patch {
+    pub fn apply(&self) -> Result<(), DomException> {
+        match self {
+            Patch::Insert {
+                parent,
+                node,
+                before,
+            }
+            | Patch::Move {
+                parent,
+                node,
+                before,
+            } => {
+                Node::insert_before(parent, node, before.as_ref())?;
+            }
+            Patch::Remove { node } => {
+                let parent = node.borrow().parent().ok_or(DomException::NotFoundError)?;
+                Node::remove_child(&parent, node)?;
+            }
+            Patch::SetAttribute {
+                element,
+                name,
+                value,
+            } => match value {
+                Some(value) => Node::set_attribute(element, name, value),
+                None => Node::remove_attribute(element, name),
+            },
+            Patch::SetText { node, text } => Node::set_data(node, text),
+        }
+        Ok(())
+    }
+}
+
+pub fn apply_patches(patches: &[Patch]) -> Result<(), DomException> {
+    patches.iter().try_for_each(|p| p.apply())
+}
+
+impl Node {
+    // Computes the patches that make `old` and its descendants look like
+    // `new`, e.g. a re-parse of an edited file, keeping every old node that
+    // has a counterpart in `new`. The roots are always taken to correspond.
+    //
+    // Children are paired up by id first and then in order by node name
+    // (tag, doctype name, `#text` or `#comment`). Unpaired old children are
+    // removed, unpaired new ones inserted, and only the paired children
+    // outside a longest run that is already in order are moved.
+    pub fn diff(old: &Rc<RefCell<Node>>, new: &Rc<RefCell<Node>>) -> Vec<Patch> {
+        let mut patches = Vec::new();
+        diff_node(old, new, &mut patches);
+        patches
+    }
+}
+
+fn diff_node(old: &Rc<RefCell<Node>>, new: &Rc<RefCell<Node>>, patches: &mut Vec<Patch>) {
+    match (&old.borrow().kind, &new.borrow().kind) {
+        (NodeKind::Element(ref o), NodeKind::Element(ref n)) => {
+            for attribute in o.attributes() {
+                if !n.has_attribute(attribute.name()) {
+                    patches.push(Patch::SetAttribute {
+                        element: old.clone(),
+                        name: attribute.name().to_string(),
+                        value: None,
+                    });
+                }
+            }
+            for attribute in n.attributes() {
+                if o.get_attribute(attribute.name()) != Some(attribute.value()) {
+                    patches.push(Patch::SetAttribute {
+                        element: old.clone(),
+                        name: attribute.name().to_string(),
+                        value: Some(attribute.value().to_string()),
+                    });
+                }
+            }
+        }
+        (NodeKind::Text(ref o), NodeKind::Text(ref n))
+        | (NodeKind::Comment(ref o), NodeKind::Comment(ref n))
+            if o != n =>
+        {
+            patches.push(Patch::SetText {
+                node: old.clone(),
+                text: n.clone(),
+            });
+        }
+        _ => {}
+    }
+
+    // https://html.spec.whatwg.org/multipage/scripting.html#template-contents
+    let contents = (
+        old.borrow().template_contents(),
+        new.borrow().template_contents(),
+    );
+    if let (Some(old_contents), Some(new_contents)) = contents {
+        diff_children(&old_contents, &new_contents, patches);
+    }
+    diff_children(old, new, patches);
+}
+
+fn diff_children(old: &Rc<RefCell<Node>>, new: &Rc<RefCell<Node>>, patches: &mut Vec<Patch>) {
+    let old_children: Vec<_> = Node::children(old).collect();
+    let new_children: Vec<_> = Node::children(new).collect();
+
+    // index into `old_children` of each new child's counterpart
+    let mut pairs: Vec<Option<usize>> = vec![None; new_children.len()];
+    let mut used = vec![false; old_children.len()];
+
+    for (i, n) in new_children.iter().enumerate() {
+        if let Some(id) = id_of(n) {
+            let found = old_children.iter().enumerate().position(|(j, o)| {
+                !used[j] && id_of(o).as_deref() == Some(id.as_str()) && same_name(o, n)
+            });
+            if let Some(j) = found {
+                used[j] = true;
+                pairs[i] = Some(j);
+            }
+        }
+    }
+    for (i, n) in new_children.iter().enumerate() {
+        if pairs[i].is_some() || id_of(n).is_some() {
+            continue;
+        }
+        let found = old_children
+            .iter()
+            .enumerate()
+            .position(|(j, o)| !used[j] && id_of(o).is_none() && same_name(o, n));
+        if let Some(j) = found {
+            used[j] = true;
+            pairs[i] = Some(j);
+        }
+    }
+
+    for (j, o) in old_children.iter().enumerate() {
+        if !used[j] {
+            patches.push(Patch::Remove { node: o.clone() });
+        }
+    }
+
+    let paired: Vec<usize> = pairs.iter().flatten().copied().collect();
+    let mut in_order = vec![false; old_children.len()];
+    for j in longest_increasing_subsequence(&paired) {
+        in_order[j] = true;
+    }
+
+    // Placing children from the last one back means the node each one goes
+    // before is already where it belongs.
+    let mut before: Option<Rc<RefCell<Node>>> = None;
+    for (i, n) in new_children.iter().enumerate().rev() {
+        let node = match pairs[i] {
+            Some(j) => {
+                let o = &old_children[j];
+                if !in_order[j] {
+                    patches.push(Patch::Move {
+                        parent: old.clone(),
+                        node: o.clone(),
+                        before: before.clone(),
+                    });
+                }
+                o.clone()
+            }
+            None => {
+                let copy = Node::clone_node(n, true);
+                patches.push(Patch::Insert {
+                    parent: old.clone(),
+                    node: copy.clone(),
+                    before: before.clone(),
+                });
+                copy
+            }
+        };
+        before = Some(node);
+    }
+
+    for (i, n) in new_children.iter().enumerate() {
+        if let Some(j) = pairs[i] {
+            diff_node(&old_children[j], n, patches);
+        }
+    }
+}
+
+fn id_of(node: &Rc<RefCell<Node>>) -> Option<String> {
+    match node.borrow().kind {
+        NodeKind::Element(ref e) => e.get_attribute("id").map(|id| id.to_string()),
+        _ => None,
+    }
+}
+
+// whether the two nodes can stand for each other
+fn same_name(a: &Rc<RefCell<Node>>, b: &Rc<RefCell<Node>>) -> bool {
+    match (&a.borrow().kind, &b.borrow().kind) {
+        (NodeKind::Element(ref x), NodeKind::Element(ref y)) => x.kind() == y.kind(),
+        (NodeKind::DocumentType(ref x), NodeKind::DocumentType(ref y)) => x == y,
+        (NodeKind::Text(_), NodeKind::Text(_)) | (NodeKind::Comment(_), NodeKind::Comment(_)) => {
+            true
+        }
+        _ => false,
+    }
+}
+
+// the values of one longest strictly increasing subsequence of `values`
+fn longest_increasing_subsequence(values: &[usize]) -> Vec<usize> {
+    // tails[k]: index of the smallest last value of a run of length k + 1
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous: Vec<Option<usize>> = vec![None; values.len()];
+
+    for (i, &v) in values.iter().enumerate() {
+        let k = tails.partition_point(|&t| values[t] < v);
+        if k > 0 {
+            previous[i] = Some(tails[k - 1]);
+        }
+        if k == tails.len() {
+            tails.push(i);
+        } else {
+            tails[k] = i;
+        }
+    }
+
+    let mut run = Vec::new();
+    let mut current = tails.last().copied();
+    while let Some(i) = current {
+        run.push(values[i]);
+        current = previous[i];
+    }
+    run.reverse();
+    run
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::html_parser::parse;
+
+    fn kinds(patches: &[Patch]) -> Vec<&'static str> {
+        patches
+            .iter()
+            .map(|p| match p {
+                Patch::Insert { .. } => "insert",
+                Patch::Remove { .. } => "remove",
+                Patch::Move { .. } => "move",
+                Patch::SetAttribute { .. } => "set-attribute",
+                Patch::SetText { .. } => "set-text",
+            })
+            .collect()
+    }
+
+    // diffs, applies the patches and checks the result matches `new`
+    fn patch(old: &Rc<RefCell<Node>>, new: &str) -> Vec<Patch> {
+        let new = parse(new);
+        let patches = Node::diff(old, &new);
+        apply_patches(&patches).unwrap();
+        assert_eq!(Node::dump_tree(old), Node::dump_tree(&new));
+        patches
+    }
+
+    #[test]
+    fn test_identical_trees() {
+        let html = "<!DOCTYPE html><div id=a class=x><p>one</p>two</div>";
+        assert!(Node::diff(&parse(html), &parse(html)).is_empty());
+    }
+
+    #[test]
+    fn test_text_and_attributes() {
+        let document = parse("<div id=a class=x title=t><p>one</p></div>");
+        let p = Node::get_elements_by_tag_name(&document, "p")
+            .item(0)
+            .unwrap();
+
+        let patches = patch(&document, "<div id=a class=y lang=en><p>uno</p></div>");
+        assert_eq!(
+            kinds(&patches),
+            vec![
+                "set-attribute",
+                "set-attribute",
+                "set-attribute",
+                "set-text"
+            ]
+        );
+        // the live nodes are kept
+        assert!(Rc::ptr_eq(
+            &Node::get_elements_by_tag_name(&document, "p")
+                .item(0)
+                .unwrap(),
+            &p
+        ));
+    }
+
+    #[test]
+    fn test_template_contents() {
+        let document = parse("<template><p>a</p><hr></template>");
+        let patches = patch(&document, "<template><p>b</p></template>");
+        assert_eq!(kinds(&patches), vec!["remove", "set-text"]);
+    }
+
+    #[test]
+    fn test_insert_and_remove() {
+        let document = parse("<div><p>one</p><span>x</span><p>two</p></div>");
+        let patches = patch(&document, "<div><p>one</p><p>two</p><hr><p>three</p></div>");
+        assert_eq!(kinds(&patches), vec!["remove", "insert", "insert"]);
+    }
+
+    #[test]
+    fn test_moves_keyed_children() {
+        let document = parse("<div><p id=a>a</p><p id=b>b</p><p id=c>c</p><p id=d>d</p></div>");
+        let a = Node::get_element_by_id(&document, "a").unwrap();
+
+        // moving one child to the end only moves that child
+        let patches = patch(
+            &document,
+            "<div><p id=b>b</p><p id=c>c</p><p id=d>d</p><p id=a>a</p></div>",
+        );
+        assert_eq!(kinds(&patches), vec!["move"]);
+        assert!(Rc::ptr_eq(
+            &Node::get_element_by_id(&document, "a").unwrap(),
+            &a
+        ));
+
+        let patches = patch(
+            &document,
+            "<div><p id=d>d</p><p id=c>c!</p><p id=b>b</p><p id=a>a</p></div>",
+        );
+        assert_eq!(kinds(&patches), vec!["move", "move", "set-text"]);
+    }
+
+    #[test]
+    fn test_longest_increasing_subsequence() {
+        assert_eq!(longest_increasing_subsequence(&[]), Vec::<usize>::new());
+        assert_eq!(longest_increasing_subsequence(&[1, 2, 3, 0]), vec![1, 2, 3]);
+        assert_eq!(
+            longest_increasing_subsequence(&[3, 0, 4, 1, 2, 5]),
+            vec![0, 1, 2, 5]
+        );
+    }
+}
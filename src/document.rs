@@ -0,0 +1,594 @@
+use crate::element::{Element, HtmlElementKind};
+use crate::html_collection::{CollectionFilter, HtmlCollection};
+use crate::loader::{FileLoader, ResourceLoader};
+use crate::mutation_observer::{queue_mutation_record, MutationRecord, MutationRecordType};
+use crate::node::{Node, NodeKind};
+use crate::selection::Selection;
+use crate::style::{Device, InvalidationState, StyleSheetList, Stylist};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+// https://dom.spec.whatwg.org/#concept-document-mode
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum QuirksMode {
+    #[default]
+    NoQuirks,
+    Quirks,
+    LimitedQuirks,
+}
+
+// https://html.spec.whatwg.org/multipage/dom.html#current-document-readiness
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DocumentReadyState {
+    Loading,
+    Interactive,
+    #[default]
+    Complete,
+}
+
+// https://dom.spec.whatwg.org/#interface-document
+#[derive(Debug)]
+pub struct Document {
+    url: String,
+    character_set: String,
+    content_type: String,
+    mode: QuirksMode,
+    ready_state: DocumentReadyState,
+    // elements by id, kept up to date by the tree mutation methods on `Node`
+    id_index: HashMap<String, Vec<Weak<RefCell<Node>>>>,
+    // bumped on every change to the tree, used to invalidate cached collections
+    version: u64,
+    resource_loader: Rc<dyn ResourceLoader>,
+    // the style sheet list and what it was collected with, boxed to keep
+    // `NodeKind` small
+    pub(crate) style_sheets: Box<StyleSheetList>,
+    // the version styles were last resolved at, and the stylist they were
+    // resolved with
+    pub(crate) style_version: Option<u64>,
+    pub(crate) stylist: Option<Rc<Stylist>>,
+    // what media queries are evaluated against, boxed to keep `NodeKind`
+    // small
+    pub(crate) device: Box<Device>,
+    // the hovered element and the restyle counters, boxed as well
+    pub(crate) invalidation: Box<InvalidationState>,
+    // https://w3c.github.io/selection-api/#dfn-selection
+    //
+    // created the first time it is asked for
+    pub(crate) selection: Option<Rc<RefCell<Selection>>>,
+}
+
+// https://dom.spec.whatwg.org/#concept-node-clone
+//
+// The id index is derived from the tree, so a copy of a document keeps the
+// metadata but starts without one and rebuilds it as nodes are inserted.
+impl Clone for Document {
+    fn clone(&self) -> Self {
+        Self {
+            url: self.url.clone(),
+            character_set: self.character_set.clone(),
+            content_type: self.content_type.clone(),
+            mode: self.mode,
+            resource_loader: self.resource_loader.clone(),
+            ..Self::new()
+        }
+    }
+}
+
+impl Default for Document {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Document {
+    pub fn new() -> Self {
+        Self {
+            url: String::from("about:blank"),
+            character_set: String::from("UTF-8"),
+            content_type: String::from("text/html"),
+            mode: QuirksMode::NoQuirks,
+            ready_state: DocumentReadyState::Complete,
+            id_index: HashMap::new(),
+            version: 0,
+            resource_loader: Rc::new(FileLoader),
+            style_sheets: Box::default(),
+            style_version: None,
+            stylist: None,
+            device: Box::default(),
+            invalidation: Box::default(),
+            selection: None,
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn set_url(&mut self, url: &str) {
+        self.url = url.to_string();
+    }
+
+    // https://html.spec.whatwg.org/multipage/urls-and-fetching.html#document-base-url
+    //
+    // There is no <base> element support, so this is always the document URL.
+    pub fn base_url(&self) -> &str {
+        &self.url
+    }
+
+    // https://dom.spec.whatwg.org/#dom-document-characterset
+    pub fn character_set(&self) -> &str {
+        &self.character_set
+    }
+
+    pub fn set_character_set(&mut self, character_set: &str) {
+        self.character_set = character_set.to_string();
+    }
+
+    pub fn content_type(&self) -> &str {
+        &self.content_type
+    }
+
+    pub fn mode(&self) -> QuirksMode {
+        self.mode
+    }
+
+    pub(crate) fn set_mode(&mut self, mode: QuirksMode) {
+        self.mode = mode;
+    }
+
+    // https://dom.spec.whatwg.org/#dom-document-compatmode
+    pub fn compat_mode(&self) -> &'static str {
+        match self.mode {
+            QuirksMode::Quirks => "BackCompat",
+            _ => "CSS1Compat",
+        }
+    }
+
+    pub fn ready_state(&self) -> DocumentReadyState {
+        self.ready_state
+    }
+
+    pub(crate) fn set_ready_state(&mut self, ready_state: DocumentReadyState) {
+        self.ready_state = ready_state;
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    // loads the style sheets of `<link>` elements
+    pub fn resource_loader(&self) -> Rc<dyn ResourceLoader> {
+        self.resource_loader.clone()
+    }
+
+    // a new loader may succeed where the old one failed
+    pub fn set_resource_loader(&mut self, loader: Rc<dyn ResourceLoader>) {
+        self.resource_loader = loader;
+        self.style_sheets.failed_loads.clear();
+        self.style_sheets.version = None;
+        self.style_version = None;
+    }
+
+    pub(crate) fn bump_version(&mut self) {
+        self.version += 1;
+    }
+
+    fn register_id(&mut self, id: &str, element: &Rc<RefCell<Node>>) {
+        let entry = self.id_index.entry(id.to_string()).or_default();
+        entry.retain(|e| e.strong_count() > 0);
+        entry.push(Rc::downgrade(element));
+    }
+
+    fn unregister_id(&mut self, id: &str, element: &Rc<RefCell<Node>>) {
+        if let Some(entry) = self.id_index.get_mut(id) {
+            entry.retain(|e| e.upgrade().is_some_and(|e| !Rc::ptr_eq(&e, element)));
+            if entry.is_empty() {
+                self.id_index.remove(id);
+            }
+        }
+    }
+
+    fn elements_with_id(&self, id: &str) -> Vec<Rc<RefCell<Node>>> {
+        match self.id_index.get(id) {
+            Some(entry) => entry.iter().filter_map(|e| e.upgrade()).collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+impl Node {
+    // The document `node` is connected to, if its root is a document.
+    pub fn connected_document(node: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+        let root = Node::inclusive_ancestors(node).last()?;
+        if root.borrow().is_document() {
+            Some(root)
+        } else {
+            None
+        }
+    }
+
+    // https://dom.spec.whatwg.org/#dom-document-documentelement
+    pub fn document_element(document: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+        Node::children(document).find(|n| n.borrow().is_element())
+    }
+
+    // https://html.spec.whatwg.org/multipage/dom.html#dom-document-head
+    pub fn head(document: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+        Self::html_child(document, HtmlElementKind::Head)
+    }
+
+    // https://html.spec.whatwg.org/multipage/dom.html#dom-document-body
+    pub fn body(document: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+        Self::html_child(document, HtmlElementKind::Body)
+    }
+
+    // the first child of the <html> document element of the given kind
+    fn html_child(
+        document: &Rc<RefCell<Node>>,
+        kind: HtmlElementKind,
+    ) -> Option<Rc<RefCell<Node>>> {
+        let html = Self::document_element(document)?;
+        if !Self::is_element_of_kind(&html, &HtmlElementKind::Html) {
+            return None;
+        }
+        Node::children(&html).find(|n| Self::is_element_of_kind(n, &kind))
+    }
+
+    fn is_element_of_kind(node: &Rc<RefCell<Node>>, kind: &HtmlElementKind) -> bool {
+        match node.borrow().kind {
+            NodeKind::Element(ref e) => e.kind() == kind,
+            _ => false,
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/dom.html#document.title
+    pub fn title(document: &Rc<RefCell<Node>>) -> String {
+        let title = Node::descendants(document)
+            .find(|n| Self::is_element_of_kind(n, &HtmlElementKind::Title));
+        match title.and_then(|t| Node::text_content(&t)) {
+            Some(text) => text.split_ascii_whitespace().collect::<Vec<_>>().join(" "),
+            None => String::new(),
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/dom.html#document.title
+    //
+    // Creates the <title> in <head> when there is none; does nothing
+    // without a head either.
+    pub fn set_title(document: &Rc<RefCell<Node>>, value: &str) {
+        let title = Node::descendants(document)
+            .find(|n| Self::is_element_of_kind(n, &HtmlElementKind::Title));
+        let title = match (title, Self::head(document)) {
+            (Some(title), _) => title,
+            (None, Some(head)) => {
+                let title = Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new(
+                    HtmlElementKind::Title,
+                )))));
+                Node::insert(&head, &title, None);
+                title
+            }
+            (None, None) => return,
+        };
+        Node::set_text_content(&title, value);
+    }
+
+    // https://dom.spec.whatwg.org/#dom-nonelementparentnode-getelementbyid
+    pub fn get_element_by_id(document: &Rc<RefCell<Node>>, id: &str) -> Option<Rc<RefCell<Node>>> {
+        let candidates = match document.borrow().kind {
+            NodeKind::Document(ref d) => d.elements_with_id(id),
+            _ => return None,
+        };
+
+        // with duplicate ids the first element in tree order wins
+        match candidates.len() {
+            0 => None,
+            1 => candidates.into_iter().next(),
+            _ => Node::descendants(document).find(|n| candidates.iter().any(|c| Rc::ptr_eq(c, n))),
+        }
+    }
+
+    // https://dom.spec.whatwg.org/#concept-getelementsbytagname
+    pub fn get_elements_by_tag_name(root: &Rc<RefCell<Node>>, tag_name: &str) -> HtmlCollection {
+        HtmlCollection::new(
+            root,
+            CollectionFilter::TagName(tag_name.to_ascii_lowercase()),
+        )
+    }
+
+    // https://dom.spec.whatwg.org/#concept-getelementsbyclassname
+    pub fn get_elements_by_class_name(
+        root: &Rc<RefCell<Node>>,
+        class_names: &str,
+    ) -> HtmlCollection {
+        let class_names = class_names
+            .split_ascii_whitespace()
+            .map(|c| c.to_string())
+            .collect();
+        HtmlCollection::new(root, CollectionFilter::ClassNames(class_names))
+    }
+
+    // https://dom.spec.whatwg.org/#dom-element-setattribute
+    //
+    // Does nothing when `node` is not an element.
+    pub fn set_attribute(node: &Rc<RefCell<Node>>, name: &str, value: &str) {
+        let name = name.to_ascii_lowercase();
+        let old_id = Self::id_of(node);
+        let old_value = Self::get_attribute(node, &name);
+        match node.borrow_mut().kind {
+            NodeKind::Element(ref mut e) => e.set_attribute(&name, value),
+            _ => return,
+        }
+        Self::attribute_changed(node, &name, old_id, old_value);
+    }
+
+    pub fn remove_attribute(node: &Rc<RefCell<Node>>, name: &str) {
+        let old_id = Self::id_of(node);
+        let removed = match node.borrow_mut().kind {
+            NodeKind::Element(ref mut e) => e.remove_attribute(name),
+            _ => None,
+        };
+        if let Some(removed) = removed {
+            Self::attribute_changed(node, name, old_id, Some(removed.value().to_string()));
+        }
+    }
+
+    pub fn get_attribute(node: &Rc<RefCell<Node>>, name: &str) -> Option<String> {
+        match node.borrow().kind {
+            NodeKind::Element(ref e) => e.get_attribute(name).map(|v| v.to_string()),
+            _ => None,
+        }
+    }
+
+    // https://dom.spec.whatwg.org/#concept-id
+    //
+    // An empty `id` attribute gives no id.
+    fn id_of(node: &Rc<RefCell<Node>>) -> Option<String> {
+        Self::get_attribute(node, "id").filter(|id| !id.is_empty())
+    }
+
+    // https://dom.spec.whatwg.org/#concept-element-attributes-change
+    fn attribute_changed(
+        node: &Rc<RefCell<Node>>,
+        name: &str,
+        old_id: Option<String>,
+        old_value: Option<String>,
+    ) {
+        let mut record = MutationRecord::new(MutationRecordType::Attributes, node);
+        record.attribute_name = Some(name.to_string());
+        queue_mutation_record(record, old_value.clone());
+
+        let document = match Self::connected_document(node) {
+            Some(d) => d,
+            None => return,
+        };
+
+        let new_id = Self::id_of(node);
+        if let NodeKind::Document(ref mut d) = document.borrow_mut().kind {
+            if name == "id" {
+                if let Some(ref id) = old_id {
+                    d.unregister_id(id, node);
+                }
+                if let Some(ref id) = new_id {
+                    d.register_id(id, node);
+                }
+            }
+            d.bump_version();
+        }
+        let value = Self::get_attribute(node, name);
+        Self::invalidate_attribute(
+            &document,
+            node,
+            name,
+            old_value.as_deref(),
+            value.as_deref(),
+        );
+    }
+
+    // called by `Node::insert` once `node` is linked under a connected parent
+    pub(crate) fn register_subtree(document: &Rc<RefCell<Node>>, node: &Rc<RefCell<Node>>) {
+        let ids: Vec<_> = Node::inclusive_descendants(node)
+            .filter_map(|n| Self::id_of(&n).map(|id| (id, n)))
+            .collect();
+
+        let mut document = document.borrow_mut();
+        if let NodeKind::Document(ref mut d) = document.kind {
+            for (id, element) in ids {
+                d.register_id(&id, &element);
+            }
+            d.bump_version();
+        }
+    }
+
+    // called by `Node::remove` before `node` is unlinked from a connected parent
+    pub(crate) fn unregister_subtree(document: &Rc<RefCell<Node>>, node: &Rc<RefCell<Node>>) {
+        let ids: Vec<_> = Node::inclusive_descendants(node)
+            .filter_map(|n| Self::id_of(&n).map(|id| (id, n)))
+            .collect();
+
+        let mut document = document.borrow_mut();
+        if let NodeKind::Document(ref mut d) = document.kind {
+            for (id, element) in ids {
+                d.unregister_id(&id, &element);
+            }
+            d.bump_version();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::html_parser::parse;
+
+    #[test]
+    fn test_document_metadata() {
+        let document = parse("<!DOCTYPE html><html><head></head><body><p>x</p></body></html>");
+        let n = document.borrow();
+        let d = match n.kind {
+            NodeKind::Document(ref d) => d,
+            _ => unreachable!(),
+        };
+
+        assert_eq!(d.url(), "about:blank");
+        assert_eq!(d.base_url(), "about:blank");
+        assert_eq!(d.character_set(), "UTF-8");
+        assert_eq!(d.content_type(), "text/html");
+        assert_eq!(d.mode(), QuirksMode::NoQuirks);
+        assert_eq!(d.compat_mode(), "CSS1Compat");
+        assert_eq!(d.ready_state(), DocumentReadyState::Complete);
+    }
+
+    #[test]
+    fn test_quirks_mode() {
+        for html in ["<p>x</p>", "<!DOCTYPE foo><p>x</p>"] {
+            let document = parse(html);
+            let mode = match document.borrow().kind {
+                NodeKind::Document(ref d) => d.mode(),
+                _ => unreachable!(),
+            };
+            assert_eq!(mode, QuirksMode::Quirks, "{}", html);
+        }
+    }
+
+    #[test]
+    fn test_document_accessors() {
+        let document = parse("<title>  a \n title </title><p>x</p>");
+
+        assert_eq!(
+            Node::outer_html(&Node::document_element(&document).unwrap()),
+            Node::outer_html(&document)
+        );
+        assert_eq!(
+            Node::inner_html(&Node::head(&document).unwrap()),
+            "<title>  a \n title </title>"
+        );
+        assert_eq!(
+            Node::inner_html(&Node::body(&document).unwrap()),
+            "<p>x</p>"
+        );
+        assert_eq!(Node::title(&document), "a title");
+
+        Node::set_title(&document, "new");
+        assert_eq!(Node::title(&document), "new");
+
+        let untitled = parse("<p>x</p>");
+        assert_eq!(Node::title(&untitled), "");
+        Node::set_title(&untitled, "created");
+        assert_eq!(
+            Node::inner_html(&Node::head(&untitled).unwrap()),
+            "<title>created</title>"
+        );
+    }
+
+    #[test]
+    fn test_get_element_by_id() {
+        let document = parse("<div id=a><p id=b>x</p></div><span id=b></span>");
+
+        let a = Node::get_element_by_id(&document, "a").unwrap();
+        assert_eq!(
+            Node::outer_html(&a),
+            "<div id=\"a\"><p id=\"b\">x</p></div>"
+        );
+        // duplicate ids resolve to the first element in tree order
+        let b = Node::get_element_by_id(&document, "b").unwrap();
+        assert_eq!(Node::outer_html(&b), "<p id=\"b\">x</p>");
+        assert!(Node::get_element_by_id(&document, "c").is_none());
+    }
+
+    #[test]
+    fn test_id_index_follows_mutations() {
+        let document = parse("<div id=a><p id=b>x</p></div><span id=b></span>");
+        let a = Node::get_element_by_id(&document, "a").unwrap();
+
+        Node::detach(&a);
+        assert!(Node::get_element_by_id(&document, "a").is_none());
+        assert_eq!(
+            Node::outer_html(&Node::get_element_by_id(&document, "b").unwrap()),
+            "<span id=\"b\"></span>"
+        );
+
+        // ids inside a detached subtree are not indexed
+        let p = a.borrow().first_child().unwrap();
+        Node::set_attribute(&p, "id", "c");
+        assert!(Node::get_element_by_id(&document, "c").is_none());
+
+        let body = Node::get_elements_by_tag_name(&document, "body")
+            .item(0)
+            .unwrap();
+        Node::append_child_node(&body, &a).unwrap();
+        assert!(Rc::ptr_eq(
+            &Node::get_element_by_id(&document, "c").unwrap(),
+            &p
+        ));
+
+        Node::set_attribute(&p, "id", "d");
+        assert!(Node::get_element_by_id(&document, "c").is_none());
+        assert!(Rc::ptr_eq(
+            &Node::get_element_by_id(&document, "d").unwrap(),
+            &p
+        ));
+
+        Node::remove_attribute(&p, "id");
+        assert!(Node::get_element_by_id(&document, "d").is_none());
+    }
+
+    #[test]
+    fn test_empty_ids() {
+        let document = parse("<p id=\"\">a</p><p id=b>b</p>");
+        assert!(Node::get_element_by_id(&document, "").is_none());
+
+        // neither when set later
+        let b = Node::get_element_by_id(&document, "b").unwrap();
+        Node::set_attribute(&b, "id", "");
+        assert!(Node::get_element_by_id(&document, "").is_none());
+        assert!(Node::get_element_by_id(&document, "b").is_none());
+        Node::set_attribute(&b, "id", "b");
+        assert!(Rc::ptr_eq(
+            &Node::get_element_by_id(&document, "b").unwrap(),
+            &b
+        ));
+    }
+
+    #[test]
+    fn test_get_elements_by_tag_name() {
+        let document = parse("<div><p>a</p><div><p>b</p></div></div>");
+
+        let paragraphs = Node::get_elements_by_tag_name(&document, "p");
+        assert_eq!(paragraphs.len(), 2);
+        assert_eq!(Node::inner_html(&paragraphs.item(1).unwrap()), "b");
+
+        let divs = Node::get_elements_by_tag_name(
+            &paragraphs.item(0).unwrap().borrow().parent().unwrap(),
+            "div",
+        );
+        assert_eq!(divs.len(), 1);
+
+        // html, head, body, div, p, div, p
+        assert_eq!(Node::get_elements_by_tag_name(&document, "*").len(), 7);
+    }
+
+    #[test]
+    fn test_get_elements_by_class_name_is_live() {
+        let document = parse("<p class='a b'>1</p><p class=a>2</p><p class=b>3</p>");
+
+        let both = Node::get_elements_by_class_name(&document, "b a");
+        let a = Node::get_elements_by_class_name(&document, "a");
+        assert_eq!(both.len(), 1);
+        assert_eq!(a.len(), 2);
+        assert_eq!(Node::get_elements_by_class_name(&document, " ").len(), 0);
+
+        let third = Node::get_elements_by_tag_name(&document, "p")
+            .item(2)
+            .unwrap();
+        Node::set_attribute(&third, "class", "a b");
+        assert_eq!(both.len(), 2);
+        assert_eq!(a.len(), 3);
+
+        Node::detach(&a.item(0).unwrap());
+        assert_eq!(
+            a.iter().map(|n| Node::inner_html(&n)).collect::<Vec<_>>(),
+            vec!["2", "3"]
+        );
+    }
+}
@@ -1,7 +1,27 @@
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attribute {
+    name: String,
+    value: String,
+}
+
+impl Attribute {
+    pub fn new(name: String, value: String) -> Self {
+        Self { name, value }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Element {
     kind: HtmlElementKind,
-    // attributes: Vec<Attribute>,
+    attributes: Vec<Attribute>,
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -10,11 +30,23 @@ pub enum HtmlElementKind {
     Head,
     Body,
     Title,
+    Meta,
+    Link,
+    Style,
+    Script,
+    Template,
     P,
     Div,
     Span,
     H1,
     H2,
+    Ol,
+    Ul,
+    Li,
+    Br,
+    Hr,
+    Img,
+    Input,
 }
 
 impl PartialEq for Element {
@@ -25,24 +57,87 @@ impl PartialEq for Element {
 
 impl Element {
     pub fn new(kind: HtmlElementKind) -> Self {
-        Self { kind }
+        Self {
+            kind,
+            attributes: Vec::new(),
+        }
+    }
+
+    pub fn with_attributes(kind: HtmlElementKind, attributes: Vec<Attribute>) -> Self {
+        Self { kind, attributes }
     }
 
     pub fn kind(&self) -> &HtmlElementKind {
         &self.kind
     }
 
+    pub fn attributes(&self) -> &[Attribute] {
+        &self.attributes
+    }
+
+    pub fn get_attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|a| a.name == name)
+            .map(|a| a.value.as_str())
+    }
+
+    pub fn has_attribute(&self, name: &str) -> bool {
+        self.attributes.iter().any(|a| a.name == name)
+    }
+
+    // https://dom.spec.whatwg.org/#dom-element-setattribute
+    pub fn set_attribute(&mut self, name: &str, value: &str) {
+        let name = name.to_ascii_lowercase();
+        match self.attributes.iter_mut().find(|a| a.name == name) {
+            Some(a) => a.value = value.to_string(),
+            None => self
+                .attributes
+                .push(Attribute::new(name, value.to_string())),
+        }
+    }
+
+    pub fn remove_attribute(&mut self, name: &str) -> Option<Attribute> {
+        let index = self.attributes.iter().position(|a| a.name == name)?;
+        Some(self.attributes.remove(index))
+    }
+
+    // https://html.spec.whatwg.org/multipage/syntax.html#void-elements
+    pub fn is_void(&self) -> bool {
+        matches!(
+            self.kind,
+            HtmlElementKind::Meta
+                | HtmlElementKind::Link
+                | HtmlElementKind::Br
+                | HtmlElementKind::Hr
+                | HtmlElementKind::Img
+                | HtmlElementKind::Input
+        )
+    }
+
     pub fn to_string(&self) -> String {
         match self.kind {
             HtmlElementKind::Html => String::from("html"),
             HtmlElementKind::Head => String::from("head"),
             HtmlElementKind::Body => String::from("body"),
             HtmlElementKind::Title => String::from("title"),
+            HtmlElementKind::Meta => String::from("meta"),
+            HtmlElementKind::Link => String::from("link"),
+            HtmlElementKind::Style => String::from("style"),
+            HtmlElementKind::Script => String::from("script"),
+            HtmlElementKind::Template => String::from("template"),
             HtmlElementKind::P => String::from("p"),
             HtmlElementKind::Div => String::from("div"),
             HtmlElementKind::Span => String::from("span"),
             HtmlElementKind::H1 => String::from("h1"),
             HtmlElementKind::H2 => String::from("h2"),
+            HtmlElementKind::Ol => String::from("ol"),
+            HtmlElementKind::Ul => String::from("ul"),
+            HtmlElementKind::Li => String::from("li"),
+            HtmlElementKind::Br => String::from("br"),
+            HtmlElementKind::Hr => String::from("hr"),
+            HtmlElementKind::Img => String::from("img"),
+            HtmlElementKind::Input => String::from("input"),
         }
     }
 
@@ -52,14 +147,53 @@ impl Element {
             "head" => Self::new(HtmlElementKind::Head),
             "body" => Self::new(HtmlElementKind::Body),
             "title" => Self::new(HtmlElementKind::Title),
+            "meta" => Self::new(HtmlElementKind::Meta),
+            "link" => Self::new(HtmlElementKind::Link),
+            "style" => Self::new(HtmlElementKind::Style),
+            "script" => Self::new(HtmlElementKind::Script),
+            "template" => Self::new(HtmlElementKind::Template),
             "p" => Self::new(HtmlElementKind::P),
             "div" => Self::new(HtmlElementKind::Div),
             "span" => Self::new(HtmlElementKind::Span),
             "h1" => Self::new(HtmlElementKind::H1),
             "h2" => Self::new(HtmlElementKind::H2),
+            "ol" => Self::new(HtmlElementKind::Ol),
+            "ul" => Self::new(HtmlElementKind::Ul),
+            "li" => Self::new(HtmlElementKind::Li),
+            "br" => Self::new(HtmlElementKind::Br),
+            "hr" => Self::new(HtmlElementKind::Hr),
+            "img" => Self::new(HtmlElementKind::Img),
+            "input" => Self::new(HtmlElementKind::Input),
             _ => panic!("Unknown element: {}", s),
         }
     }
+
+    pub fn is_known(s: &str) -> bool {
+        matches!(
+            s,
+            "html"
+                | "head"
+                | "body"
+                | "title"
+                | "meta"
+                | "link"
+                | "style"
+                | "script"
+                | "template"
+                | "p"
+                | "div"
+                | "span"
+                | "h1"
+                | "h2"
+                | "ol"
+                | "ul"
+                | "li"
+                | "br"
+                | "hr"
+                | "img"
+                | "input"
+        )
+    }
 }
 
 #[cfg(test)]
@@ -71,4 +205,19 @@ mod tests {
         let element = Element::new(HtmlElementKind::Html);
         assert_eq!(element.to_string(), "html");
     }
+
+    #[test]
+    fn test_attributes() {
+        let mut element = Element::new(HtmlElementKind::Div);
+        element.set_attribute("ID", "main");
+        element.set_attribute("class", "a");
+        element.set_attribute("class", "b");
+
+        assert_eq!(element.get_attribute("id"), Some("main"));
+        assert_eq!(element.get_attribute("class"), Some("b"));
+        assert_eq!(element.attributes().len(), 2);
+
+        element.remove_attribute("id");
+        assert!(!element.has_attribute("id"));
+    }
 }
@@ -0,0 +1,450 @@
+use crate::node::Node;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+// https://dom.spec.whatwg.org/#dom-event-type
+#[derive(Clone, Debug, PartialEq)]
+pub enum EventType {
+    Click,
+    Input,
+    KeyDown,
+    Load,
+    DomContentLoaded,
+    Custom(String),
+}
+
+impl EventType {
+    pub fn name(&self) -> &str {
+        match self {
+            EventType::Click => "click",
+            EventType::Input => "input",
+            EventType::KeyDown => "keydown",
+            EventType::Load => "load",
+            EventType::DomContentLoaded => "DOMContentLoaded",
+            EventType::Custom(name) => name,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "click" => EventType::Click,
+            "input" => EventType::Input,
+            "keydown" => EventType::KeyDown,
+            "load" => EventType::Load,
+            "DOMContentLoaded" => EventType::DomContentLoaded,
+            _ => EventType::Custom(name.to_string()),
+        }
+    }
+}
+
+// https://dom.spec.whatwg.org/#dom-event-eventphase
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EventPhase {
+    None,
+    Capturing,
+    AtTarget,
+    Bubbling,
+}
+
+// https://dom.spec.whatwg.org/#interface-event
+#[derive(Debug)]
+pub struct Event {
+    event_type: EventType,
+    bubbles: bool,
+    cancelable: bool,
+    // the key for keydown events, e.g. "Enter" or "a"
+    key: Option<String>,
+    target: Option<Rc<RefCell<Node>>>,
+    current_target: Option<Rc<RefCell<Node>>>,
+    phase: EventPhase,
+    stop_propagation: bool,
+    stop_immediate_propagation: bool,
+    canceled: bool,
+    dispatching: bool,
+}
+
+impl Event {
+    // An event with the bubbles and cancelable flags browsers use for
+    // `event_type`.
+    pub fn new(event_type: EventType) -> Self {
+        let (bubbles, cancelable) = match event_type {
+            EventType::Click | EventType::KeyDown => (true, true),
+            EventType::Input | EventType::DomContentLoaded => (true, false),
+            EventType::Load | EventType::Custom(_) => (false, false),
+        };
+        Self::with_flags(event_type, bubbles, cancelable)
+    }
+
+    pub fn with_flags(event_type: EventType, bubbles: bool, cancelable: bool) -> Self {
+        Self {
+            event_type,
+            bubbles,
+            cancelable,
+            key: None,
+            target: None,
+            current_target: None,
+            phase: EventPhase::None,
+            stop_propagation: false,
+            stop_immediate_propagation: false,
+            canceled: false,
+            dispatching: false,
+        }
+    }
+
+    pub fn key_down(key: &str) -> Self {
+        let mut event = Self::new(EventType::KeyDown);
+        event.key = Some(key.to_string());
+        event
+    }
+
+    pub fn event_type(&self) -> &EventType {
+        &self.event_type
+    }
+
+    pub fn bubbles(&self) -> bool {
+        self.bubbles
+    }
+
+    pub fn cancelable(&self) -> bool {
+        self.cancelable
+    }
+
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
+    pub fn target(&self) -> Option<Rc<RefCell<Node>>> {
+        self.target.clone()
+    }
+
+    pub fn current_target(&self) -> Option<Rc<RefCell<Node>>> {
+        self.current_target.clone()
+    }
+
+    pub fn event_phase(&self) -> EventPhase {
+        self.phase
+    }
+
+    // https://dom.spec.whatwg.org/#dom-event-stoppropagation
+    pub fn stop_propagation(&mut self) {
+        self.stop_propagation = true;
+    }
+
+    // https://dom.spec.whatwg.org/#dom-event-stopimmediatepropagation
+    pub fn stop_immediate_propagation(&mut self) {
+        self.stop_propagation = true;
+        self.stop_immediate_propagation = true;
+    }
+
+    // https://dom.spec.whatwg.org/#dom-event-preventdefault
+    pub fn prevent_default(&mut self) {
+        if self.cancelable {
+            self.canceled = true;
+        }
+    }
+
+    pub fn default_prevented(&self) -> bool {
+        self.canceled
+    }
+}
+
+pub type EventCallback = Rc<dyn Fn(&mut Event)>;
+
+// https://dom.spec.whatwg.org/#concept-event-listener
+#[derive(Clone)]
+pub struct EventListener {
+    event_type: EventType,
+    callback: EventCallback,
+    capture: bool,
+    once: bool,
+}
+
+impl fmt::Debug for EventListener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventListener")
+            .field("event_type", &self.event_type)
+            .field("capture", &self.capture)
+            .field("once", &self.once)
+            .finish()
+    }
+}
+
+impl EventListener {
+    fn is(&self, event_type: &EventType, callback: &EventCallback, capture: bool) -> bool {
+        self.event_type == *event_type
+            && Rc::ptr_eq(&self.callback, callback)
+            && self.capture == capture
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EventListenerOptions {
+    pub capture: bool,
+    // remove the listener after its first invocation
+    pub once: bool,
+}
+
+// https://dom.spec.whatwg.org/#interface-eventtarget
+impl Node {
+    // https://dom.spec.whatwg.org/#dom-eventtarget-addeventlistener
+    //
+    // Adding the same callback twice for the same type and phase is a no-op.
+    pub fn add_event_listener(
+        node: &Rc<RefCell<Node>>,
+        event_type: EventType,
+        callback: &EventCallback,
+        options: EventListenerOptions,
+    ) {
+        let mut n = node.borrow_mut();
+        if n.event_listeners
+            .iter()
+            .any(|l| l.is(&event_type, callback, options.capture))
+        {
+            return;
+        }
+        n.event_listeners.push(EventListener {
+            event_type,
+            callback: callback.clone(),
+            capture: options.capture,
+            once: options.once,
+        });
+    }
+
+    // https://dom.spec.whatwg.org/#dom-eventtarget-removeeventlistener
+    pub fn remove_event_listener(
+        node: &Rc<RefCell<Node>>,
+        event_type: &EventType,
+        callback: &EventCallback,
+        capture: bool,
+    ) {
+        node.borrow_mut()
+            .event_listeners
+            .retain(|l| !l.is(event_type, callback, capture));
+    }
+
+    // https://dom.spec.whatwg.org/#concept-event-dispatch
+    //
+    // Returns false when a listener canceled the event.
+    pub fn dispatch_event(target: &Rc<RefCell<Node>>, event: &mut Event) -> bool {
+        if event.dispatching {
+            return !event.canceled;
+        }
+        event.dispatching = true;
+        event.target = Some(target.clone());
+        event.stop_propagation = false;
+        event.stop_immediate_propagation = false;
+        event.canceled = false;
+
+        // the path is fixed before any listener runs, so listeners moving
+        // nodes around don't change who receives the event
+        let path: Vec<_> = Node::inclusive_ancestors(target).collect();
+
+        for node in path.iter().skip(1).rev() {
+            if event.stop_propagation {
+                break;
+            }
+            invoke(node, event, EventPhase::Capturing);
+        }
+        if !event.stop_propagation {
+            invoke(target, event, EventPhase::AtTarget);
+        }
+        if event.bubbles {
+            for node in path.iter().skip(1) {
+                if event.stop_propagation {
+                    break;
+                }
+                invoke(node, event, EventPhase::Bubbling);
+            }
+        }
+
+        event.dispatching = false;
+        event.phase = EventPhase::None;
+        event.current_target = None;
+        !event.canceled
+    }
+}
+
+// https://dom.spec.whatwg.org/#concept-event-listener-invoke
+fn invoke(node: &Rc<RefCell<Node>>, event: &mut Event, phase: EventPhase) {
+    event.phase = phase;
+    event.current_target = Some(node.clone());
+
+    // listeners added during dispatch don't run for this event
+    let listeners: Vec<_> = node
+        .borrow()
+        .event_listeners
+        .iter()
+        .filter(|l| l.event_type == event.event_type)
+        .filter(|l| match phase {
+            EventPhase::Capturing => l.capture,
+            EventPhase::Bubbling => !l.capture,
+            _ => true,
+        })
+        .cloned()
+        .collect();
+
+    // at the target, capturing listeners still run before the others
+    let (capturing, others): (Vec<_>, Vec<_>) = listeners.into_iter().partition(|l| l.capture);
+
+    for listener in capturing.into_iter().chain(others) {
+        // skip listeners removed by an earlier listener
+        let removed = !node
+            .borrow()
+            .event_listeners
+            .iter()
+            .any(|l| l.is(&listener.event_type, &listener.callback, listener.capture));
+        if removed {
+            continue;
+        }
+        if listener.once {
+            Node::remove_event_listener(
+                node,
+                &listener.event_type,
+                &listener.callback,
+                listener.capture,
+            );
+        }
+
+        (listener.callback)(event);
+
+        if event.stop_immediate_propagation {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::html_parser::parse;
+
+    // a listener that records "<id>:<phase>" for every event it sees
+    fn recorder(log: &Rc<RefCell<Vec<String>>>) -> EventCallback {
+        let log = log.clone();
+        Rc::new(move |event: &mut Event| {
+            let id = event
+                .current_target()
+                .and_then(|n| Node::get_attribute(&n, "id"))
+                .unwrap_or_else(|| String::from("document"));
+            log.borrow_mut()
+                .push(format!("{}:{:?}", id, event.event_phase()));
+        })
+    }
+
+    const CAPTURE: EventListenerOptions = EventListenerOptions {
+        capture: true,
+        once: false,
+    };
+
+    #[test]
+    fn test_dispatch_phases() {
+        let document = parse("<div id=a><p id=b><span id=c>x</span></p></div>");
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let listener = recorder(&log);
+
+        for id in ["a", "b", "c"] {
+            let node = Node::get_element_by_id(&document, id).unwrap();
+            Node::add_event_listener(&node, EventType::Click, &listener, Default::default());
+            Node::add_event_listener(&node, EventType::Click, &listener, CAPTURE);
+        }
+        Node::add_event_listener(&document, EventType::Click, &listener, CAPTURE);
+
+        let c = Node::get_element_by_id(&document, "c").unwrap();
+        let mut event = Event::new(EventType::Click);
+        assert!(Node::dispatch_event(&c, &mut event));
+
+        assert_eq!(
+            *log.borrow(),
+            vec![
+                "document:Capturing",
+                "a:Capturing",
+                "b:Capturing",
+                "c:AtTarget",
+                "c:AtTarget",
+                "b:Bubbling",
+                "a:Bubbling",
+            ]
+        );
+        assert!(Rc::ptr_eq(&event.target().unwrap(), &c));
+        assert_eq!(event.event_phase(), EventPhase::None);
+    }
+
+    #[test]
+    fn test_non_bubbling_event() {
+        let document = parse("<div id=a><p id=b></p></div>");
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let listener = recorder(&log);
+        let a = Node::get_element_by_id(&document, "a").unwrap();
+        let b = Node::get_element_by_id(&document, "b").unwrap();
+        Node::add_event_listener(&a, EventType::Load, &listener, Default::default());
+        Node::add_event_listener(&a, EventType::Load, &listener, CAPTURE);
+        Node::add_event_listener(&b, EventType::Load, &listener, Default::default());
+
+        Node::dispatch_event(&b, &mut Event::new(EventType::Load));
+
+        assert_eq!(*log.borrow(), vec!["a:Capturing", "b:AtTarget"]);
+    }
+
+    #[test]
+    fn test_stop_propagation_and_prevent_default() {
+        let document = parse("<div id=a><p id=b></p></div>");
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let a = Node::get_element_by_id(&document, "a").unwrap();
+        let b = Node::get_element_by_id(&document, "b").unwrap();
+
+        let stop: EventCallback = Rc::new(|event: &mut Event| {
+            event.prevent_default();
+            event.stop_immediate_propagation();
+        });
+        Node::add_event_listener(&b, EventType::KeyDown, &stop, Default::default());
+        Node::add_event_listener(&b, EventType::KeyDown, &recorder(&log), Default::default());
+        Node::add_event_listener(&a, EventType::KeyDown, &recorder(&log), Default::default());
+
+        let mut event = Event::key_down("Enter");
+        assert!(!Node::dispatch_event(&b, &mut event));
+        assert!(event.default_prevented());
+        assert_eq!(event.key(), Some("Enter"));
+        assert!(log.borrow().is_empty());
+
+        // input events can't be canceled
+        let mut event = Event::new(EventType::Input);
+        event.prevent_default();
+        assert!(Node::dispatch_event(&b, &mut event));
+    }
+
+    #[test]
+    fn test_remove_and_once() {
+        let document = parse("<div id=a></div>");
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let a = Node::get_element_by_id(&document, "a").unwrap();
+        let listener = recorder(&log);
+        let once = EventListenerOptions {
+            capture: false,
+            once: true,
+        };
+
+        Node::add_event_listener(&a, EventType::Click, &listener, Default::default());
+        // duplicates are ignored
+        Node::add_event_listener(&a, EventType::Click, &listener, Default::default());
+        Node::add_event_listener(&a, EventType::Input, &listener, once);
+
+        Node::dispatch_event(&a, &mut Event::new(EventType::Click));
+        Node::dispatch_event(&a, &mut Event::new(EventType::Input));
+        Node::dispatch_event(&a, &mut Event::new(EventType::Input));
+        Node::remove_event_listener(&a, &EventType::Click, &listener, false);
+        Node::dispatch_event(&a, &mut Event::new(EventType::Click));
+
+        assert_eq!(*log.borrow(), vec!["a:AtTarget", "a:AtTarget"]);
+    }
+
+    #[test]
+    fn test_event_type_names() {
+        assert_eq!(EventType::DomContentLoaded.name(), "DOMContentLoaded");
+        assert_eq!(EventType::from_name("keydown"), EventType::KeyDown);
+        assert_eq!(
+            EventType::from_name("custom"),
+            EventType::Custom(String::from("custom"))
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use crate::node::{Node, NodeKind};
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+#[derive(Clone, Debug)]
+pub enum CollectionFilter {
+    // "*" matches every element
+    TagName(String),
+    // elements carrying all of the classes; empty matches nothing
+    ClassNames(Vec<String>),
+}
+
+impl CollectionFilter {
+    fn matches(&self, node: &Rc<RefCell<Node>>) -> bool {
+        let n = node.borrow();
+        let element = match n.kind {
+            NodeKind::Element(ref e) => e,
+            _ => return false,
+        };
+
+        match self {
+            CollectionFilter::TagName(name) => name == "*" || *name == element.to_string(),
+            CollectionFilter::ClassNames(class_names) => {
+                let classes: Vec<&str> = match element.get_attribute("class") {
+                    Some(c) => c.split_ascii_whitespace().collect(),
+                    None => return false,
+                };
+                !class_names.is_empty() && class_names.iter().all(|c| classes.contains(&c.as_str()))
+            }
+        }
+    }
+}
+
+// https://dom.spec.whatwg.org/#interface-htmlcollection
+//
+// The elements are collected lazily on access. While the root is connected
+// to a document the result is cached until the document's tree changes, so
+// repeated `len`/`item` calls between mutations don't re-walk the tree.
+pub struct HtmlCollection {
+    root: Rc<RefCell<Node>>,
+    filter: CollectionFilter,
+    cache: RefCell<Option<CachedElements>>,
+}
+
+struct CachedElements {
+    version: u64,
+    elements: Vec<Weak<RefCell<Node>>>,
+}
+
+impl HtmlCollection {
+    pub fn new(root: &Rc<RefCell<Node>>, filter: CollectionFilter) -> Self {
+        Self {
+            root: root.clone(),
+            filter,
+            cache: RefCell::new(None),
+        }
+    }
+
+    fn elements(&self) -> Vec<Rc<RefCell<Node>>> {
+        let version = Node::connected_document(&self.root).and_then(|d| match d.borrow().kind {
+            NodeKind::Document(ref d) => Some(d.version()),
+            _ => None,
+        });
+
+        if let (Some(version), Some(ref cached)) = (version, &*self.cache.borrow()) {
+            if version == cached.version {
+                return cached.elements.iter().filter_map(|e| e.upgrade()).collect();
+            }
+        }
+
+        let elements: Vec<_> = Node::descendants(&self.root)
+            .filter(|n| self.filter.matches(n))
+            .collect();
+        if let Some(version) = version {
+            *self.cache.borrow_mut() = Some(CachedElements {
+                version,
+                elements: elements.iter().map(Rc::downgrade).collect(),
+            });
+        }
+        elements
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn item(&self, index: usize) -> Option<Rc<RefCell<Node>>> {
+        self.elements().into_iter().nth(index)
+    }
+
+    // https://dom.spec.whatwg.org/#dom-htmlcollection-nameditem-key
+    pub fn named_item(&self, key: &str) -> Option<Rc<RefCell<Node>>> {
+        if key.is_empty() {
+            return None;
+        }
+        self.elements().into_iter().find(|n| {
+            Node::get_attribute(n, "id").as_deref() == Some(key)
+                || Node::get_attribute(n, "name").as_deref() == Some(key)
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Rc<RefCell<Node>>> {
+        self.elements().into_iter()
+    }
+}
@@ -0,0 +1,318 @@
+use crate::document::Document;
+use crate::element::{Attribute, Element};
+use crate::node::{DomException, Node, NodeKind};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+// https://infra.spec.whatwg.org/#html-namespace
+pub const HTML_NAMESPACE: &str = "http://www.w3.org/1999/xhtml";
+
+// JSON snapshots of a tree, for golden tests and other tools. The schema is
+// one object per node:
+//
+// {
+//   "kind": "element",
+//   "tag": "p",
+//   "namespace": "http://www.w3.org/1999/xhtml",
+//   "attributes": [{ "name": "class", "value": "intro" }],
+//   "children": [{ "kind": "text", "text": "hello" }]
+// }
+//
+// Fields that don't apply to a kind are left out. A doctype keeps its name
+// in `tag`, text and comments their data in `text`. A `<template>` keeps
+// its contents as a `document-fragment` node in `content`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct JsonNode {
+    pub kind: JsonNodeKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<JsonAttribute>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<JsonNode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<Box<JsonNode>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum JsonNodeKind {
+    Document,
+    DocumentFragment,
+    Doctype,
+    Element,
+    Text,
+    Comment,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct JsonAttribute {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug)]
+pub enum JsonError {
+    // not valid JSON, or not a node
+    Syntax(serde_json::Error),
+    // a field the node's kind needs, e.g. `tag` on an element
+    MissingField(JsonNodeKind, &'static str),
+    UnknownTag(String),
+    UnsupportedNamespace(String),
+    // the children can't be inserted, e.g. an element under a text node
+    Dom(DomException),
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::Syntax(e) => write!(f, "invalid JSON: {}", e),
+            JsonError::MissingField(kind, field) => {
+                write!(f, "{:?} node without `{}`", kind, field)
+            }
+            JsonError::UnknownTag(tag) => write!(f, "unknown element: {}", tag),
+            JsonError::UnsupportedNamespace(ns) => write!(f, "unsupported namespace: {}", ns),
+            JsonError::Dom(e) => write!(f, "invalid tree: {:?}", e),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+impl From<serde_json::Error> for JsonError {
+    fn from(e: serde_json::Error) -> Self {
+        JsonError::Syntax(e)
+    }
+}
+
+impl From<DomException> for JsonError {
+    fn from(e: DomException) -> Self {
+        JsonError::Dom(e)
+    }
+}
+
+impl JsonNode {
+    fn new(kind: JsonNodeKind) -> Self {
+        Self {
+            kind,
+            tag: None,
+            namespace: None,
+            attributes: Vec::new(),
+            text: None,
+            children: Vec::new(),
+            content: None,
+        }
+    }
+
+    pub fn from_node(node: &Rc<RefCell<Node>>) -> Self {
+        let mut json = match node.borrow().kind {
+            NodeKind::Document(_) => Self::new(JsonNodeKind::Document),
+            NodeKind::DocumentFragment => Self::new(JsonNodeKind::DocumentFragment),
+            NodeKind::DocumentType(ref name) => Self {
+                tag: Some(name.clone()),
+                ..Self::new(JsonNodeKind::Doctype)
+            },
+            NodeKind::Element(ref e) => Self {
+                tag: Some(e.to_string()),
+                namespace: Some(String::from(HTML_NAMESPACE)),
+                attributes: e
+                    .attributes()
+                    .iter()
+                    .map(|a| JsonAttribute {
+                        name: a.name().to_string(),
+                        value: a.value().to_string(),
+                    })
+                    .collect(),
+                ..Self::new(JsonNodeKind::Element)
+            },
+            NodeKind::Text(ref s) => Self {
+                text: Some(s.clone()),
+                ..Self::new(JsonNodeKind::Text)
+            },
+            NodeKind::Comment(ref s) => Self {
+                text: Some(s.clone()),
+                ..Self::new(JsonNodeKind::Comment)
+            },
+        };
+
+        json.children = Node::children(node).map(|c| Self::from_node(&c)).collect();
+        json.content = node
+            .borrow()
+            .template_contents()
+            .map(|c| Box::new(Self::from_node(&c)));
+        json
+    }
+
+    // Builds a new tree. A document gets fresh metadata and its id index is
+    // filled in as the children are appended.
+    pub fn to_node(&self) -> Result<Rc<RefCell<Node>>, JsonError> {
+        let kind = match self.kind {
+            JsonNodeKind::Document => NodeKind::Document(Document::new()),
+            JsonNodeKind::DocumentFragment => NodeKind::DocumentFragment,
+            JsonNodeKind::Doctype => NodeKind::DocumentType(self.required_tag()?.to_string()),
+            JsonNodeKind::Element => {
+                let tag = self.required_tag()?;
+                match self.namespace.as_deref() {
+                    None | Some(HTML_NAMESPACE) => {}
+                    Some(ns) => return Err(JsonError::UnsupportedNamespace(ns.to_string())),
+                }
+                if !Element::is_known(tag) {
+                    return Err(JsonError::UnknownTag(tag.to_string()));
+                }
+                let attributes = self
+                    .attributes
+                    .iter()
+                    .map(|a| Attribute::new(a.name.clone(), a.value.clone()))
+                    .collect();
+                NodeKind::Element(Element::with_attributes(
+                    Element::from_str(tag).kind().clone(),
+                    attributes,
+                ))
+            }
+            JsonNodeKind::Text => NodeKind::Text(self.text.clone().unwrap_or_default()),
+            JsonNodeKind::Comment => NodeKind::Comment(self.text.clone().unwrap_or_default()),
+        };
+
+        let node = Rc::new(RefCell::new(Node::new(kind)));
+        for child in self.children.iter() {
+            Node::append_child_node(&node, &child.to_node()?)?;
+        }
+        // the contents only exist on a template, and are ignored elsewhere
+        let contents = node.borrow().template_contents();
+        if let (Some(contents), Some(content)) = (contents, self.content.as_ref()) {
+            for child in content.children.iter() {
+                Node::append_child_node(&contents, &child.to_node()?)?;
+            }
+        }
+        Ok(node)
+    }
+
+    fn required_tag(&self) -> Result<&str, JsonError> {
+        self.tag
+            .as_deref()
+            .ok_or(JsonError::MissingField(self.kind, "tag"))
+    }
+}
+
+impl Node {
+    pub fn to_json(node: &Rc<RefCell<Node>>) -> String {
+        serde_json::to_string_pretty(&JsonNode::from_node(node)).expect("a node always serializes")
+    }
+
+    pub fn from_json(json: &str) -> Result<Rc<RefCell<Node>>, JsonError> {
+        serde_json::from_str::<JsonNode>(json)?.to_node()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::html_parser::parse;
+
+    #[test]
+    fn test_schema() {
+        let document = parse("<!DOCTYPE html><p class=intro>hello<!--c--></p>");
+        let p = Node::get_elements_by_tag_name(&document, "p")
+            .item(0)
+            .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&Node::to_json(&p)).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "kind": "element",
+                "tag": "p",
+                "namespace": "http://www.w3.org/1999/xhtml",
+                "attributes": [{ "name": "class", "value": "intro" }],
+                "children": [
+                    { "kind": "text", "text": "hello" },
+                    { "kind": "comment", "text": "c" },
+                ],
+            })
+        );
+
+        let doctype = JsonNode::from_node(&document.borrow().first_child().unwrap());
+        assert_eq!(doctype.kind, JsonNodeKind::Doctype);
+        assert_eq!(doctype.tag.as_deref(), Some("html"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let html = "<!DOCTYPE html><html><head><title>t</title></head>\
+                    <body><div id=a><p>one</p><br>two</div></body></html>";
+        let document = parse(html);
+        let json = Node::to_json(&document);
+
+        let rebuilt = Node::from_json(&json).unwrap();
+        assert!(rebuilt.borrow().is_document());
+        assert_eq!(Node::dump_tree(&rebuilt), Node::dump_tree(&document));
+        assert_eq!(Node::to_json(&rebuilt), json);
+        // the rebuilt document indexes its ids
+        assert!(Node::get_element_by_id(&rebuilt, "a").is_some());
+    }
+
+    #[test]
+    fn test_template_round_trip() {
+        let document = parse("<template><p>a</p></template>");
+        let template = Node::get_elements_by_tag_name(&document, "template")
+            .item(0)
+            .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&Node::to_json(&template)).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "kind": "element",
+                "tag": "template",
+                "namespace": "http://www.w3.org/1999/xhtml",
+                "content": {
+                    "kind": "document-fragment",
+                    "children": [{
+                        "kind": "element",
+                        "tag": "p",
+                        "namespace": "http://www.w3.org/1999/xhtml",
+                        "children": [{ "kind": "text", "text": "a" }],
+                    }],
+                },
+            })
+        );
+
+        let rebuilt = Node::from_json(&Node::to_json(&document)).unwrap();
+        assert_eq!(Node::dump_tree(&rebuilt), Node::dump_tree(&document));
+        assert_eq!(Node::outer_html(&rebuilt), Node::outer_html(&document));
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert!(matches!(
+            Node::from_json("{\"kind\": \"element\""),
+            Err(JsonError::Syntax(_))
+        ));
+        assert!(matches!(
+            Node::from_json("{\"kind\": \"element\"}"),
+            Err(JsonError::MissingField(JsonNodeKind::Element, "tag"))
+        ));
+        assert!(matches!(
+            Node::from_json("{\"kind\": \"element\", \"tag\": \"marquee\"}"),
+            Err(JsonError::UnknownTag(ref t)) if t == "marquee"
+        ));
+        assert!(matches!(
+            Node::from_json(
+                "{\"kind\": \"element\", \"tag\": \"p\", \
+                 \"namespace\": \"http://www.w3.org/2000/svg\"}"
+            ),
+            Err(JsonError::UnsupportedNamespace(_))
+        ));
+        assert!(matches!(
+            Node::from_json(
+                "{\"kind\": \"text\", \"text\": \"x\", \
+                 \"children\": [{\"kind\": \"text\", \"text\": \"y\"}]}"
+            ),
+            Err(JsonError::Dom(DomException::HierarchyRequestError))
+        ));
+    }
+}
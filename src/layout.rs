@@ -0,0 +1,592 @@
+use crate::element::HtmlElementKind;
+use crate::node::{Node, NodeKind};
+use crate::selector::PseudoElement;
+use crate::style::values::DisplayOutside;
+use crate::style::{generated_content, generates_box, ComputedStyle, Counters};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// https://drafts.csswg.org/css-display-3/#box-tree
+//
+// The boxes the styled tree generates: one per element that isn't
+// `display: none` or `display: contents`, one per pseudo-element with
+// content, and one per run of text.
+#[derive(Clone, Debug)]
+pub enum LayoutBox {
+    Element {
+        node: Rc<RefCell<Node>>,
+        // the pseudo-element of `node` the box is for
+        pseudo_element: Option<PseudoElement>,
+        style: Rc<ComputedStyle>,
+        children: Vec<LayoutBox>,
+    },
+    Text {
+        text: String,
+        style: Rc<ComputedStyle>,
+    },
+}
+
+impl LayoutBox {
+    // the box tree of the document element, from the styles of the last
+    // style update
+    pub fn build(document: &Rc<RefCell<Node>>) -> Option<LayoutBox> {
+        let root = Node::document_element(document)?;
+        let mut counters = Counters::new();
+        build_element(&root, 0, &mut counters).pop()
+    }
+
+    pub fn style(&self) -> &Rc<ComputedStyle> {
+        match self {
+            LayoutBox::Element { style, .. } | LayoutBox::Text { style, .. } => style,
+        }
+    }
+
+    // https://drafts.csswg.org/css-display-3/#inline-level
+    fn is_block_level(&self) -> bool {
+        match self {
+            LayoutBox::Element {
+                pseudo_element: None,
+                style,
+                ..
+            } => style.display().is_block_level(),
+            _ => false,
+        }
+    }
+
+    fn is_line_break(&self) -> bool {
+        match self {
+            LayoutBox::Element {
+                node,
+                pseudo_element: None,
+                ..
+            } => matches!(node.borrow().kind,
+                NodeKind::Element(ref e) if *e.kind() == HtmlElementKind::Br),
+            _ => false,
+        }
+    }
+
+    // Dumps the box and those inside it, one line each, in the format of
+    // `Node::dump_tree`.
+    pub fn dump(&self) -> String {
+        let mut output = String::new();
+        self.dump_box(0, &mut output);
+        output
+    }
+
+    fn dump_box(&self, depth: usize, output: &mut String) {
+        let indent = "  ".repeat(depth);
+        match self {
+            LayoutBox::Element {
+                node,
+                pseudo_element,
+                children,
+                ..
+            } => {
+                let name = match node.borrow().kind {
+                    NodeKind::Element(ref e) => e.to_string(),
+                    _ => String::new(),
+                };
+                match pseudo_element {
+                    Some(p) => output.push_str(&format!("| {}<{}::{}>\n", indent, name, p.name())),
+                    None => output.push_str(&format!("| {}<{}>\n", indent, name)),
+                }
+                for child in children {
+                    child.dump_box(depth + 1, output);
+                }
+            }
+            LayoutBox::Text { text, .. } => {
+                output.push_str(&format!("| {}\"{}\"\n", indent, text));
+            }
+        }
+    }
+}
+
+// The boxes of `element` at `depth` of the tree: none for `display: none`,
+// and those of its children for `display: contents`.
+fn build_element(
+    element: &Rc<RefCell<Node>>,
+    depth: usize,
+    counters: &mut Counters,
+) -> Vec<LayoutBox> {
+    let style = match Node::computed_style(element) {
+        Some(style) if !style.display().is_none() => style,
+        _ => return Vec::new(),
+    };
+    counters.update(element, None, depth, &style);
+
+    let mut children = Vec::new();
+    for pseudo_element in [PseudoElement::Marker, PseudoElement::Before] {
+        children.extend(build_pseudo_element(
+            element,
+            pseudo_element,
+            depth + 1,
+            counters,
+        ));
+    }
+    for child in Node::children(element) {
+        let text = match child.borrow().kind {
+            NodeKind::Text(ref text) => text.clone(),
+            NodeKind::Element(_) => {
+                children.extend(build_element(&child, depth + 1, counters));
+                continue;
+            }
+            _ => continue,
+        };
+        // https://drafts.csswg.org/css-text-3/#white-space-phase-1
+        //
+        // text of nothing but white space collapses away
+        if !text.chars().all(char::is_whitespace) {
+            children.push(LayoutBox::Text {
+                text,
+                style: style.clone(),
+            });
+        }
+    }
+    children.extend(build_pseudo_element(
+        element,
+        PseudoElement::After,
+        depth + 1,
+        counters,
+    ));
+    counters.leave(depth);
+
+    let display = style.display();
+    if display.outside == DisplayOutside::Contents {
+        return children;
+    }
+    if display.is_block_level() {
+        if let Some(first_letter) = Node::pseudo_element_style(element, PseudoElement::FirstLetter)
+        {
+            split_first_letter(&mut children, element, &first_letter);
+        }
+        if let Some(first_line) = Node::pseudo_element_style(element, PseudoElement::FirstLine) {
+            wrap_first_line(&mut children, element, &first_line);
+        }
+    }
+    vec![LayoutBox::Element {
+        node: element.clone(),
+        pseudo_element: None,
+        style,
+        children,
+    }]
+}
+
+// https://drafts.csswg.org/css-pseudo-4/#generated-content
+//
+// The box of a `::marker`, `::before` or `::after` whose `content`
+// generates one, after the pseudo-element's counters are applied.
+fn build_pseudo_element(
+    element: &Rc<RefCell<Node>>,
+    pseudo_element: PseudoElement,
+    depth: usize,
+    counters: &mut Counters,
+) -> Option<LayoutBox> {
+    let style = Node::pseudo_element_style(element, pseudo_element)?;
+    if !generates_box(&style, pseudo_element) {
+        return None;
+    }
+    counters.update(element, Some(pseudo_element), depth, &style);
+    let text = generated_content(&style, element, pseudo_element, counters)?;
+    Some(LayoutBox::Element {
+        node: element.clone(),
+        pseudo_element: Some(pseudo_element),
+        style: style.clone(),
+        children: vec![LayoutBox::Text { text, style }],
+    })
+}
+
+// https://drafts.csswg.org/css-pseudo-4/#first-letter-pattern
+//
+// Puts the first letter of the first text in `boxes`, with the punctuation
+// around it, in a `::first-letter` box. The search goes into inline boxes
+// and a first block, but not past a line break or into a marker. Returns
+// whether it's done.
+fn split_first_letter(
+    boxes: &mut Vec<LayoutBox>,
+    element: &Rc<RefCell<Node>>,
+    style: &Rc<ComputedStyle>,
+) -> bool {
+    for i in 0..boxes.len() {
+        if boxes[i].is_line_break() {
+            return true;
+        }
+        let text = match &mut boxes[i] {
+            LayoutBox::Element {
+                pseudo_element: Some(PseudoElement::Marker),
+                ..
+            } => continue,
+            // the first letter of an inner block that has its own
+            LayoutBox::Element {
+                pseudo_element: Some(PseudoElement::FirstLetter),
+                ..
+            } => return true,
+            LayoutBox::Element { children, .. } => {
+                if split_first_letter(children, element, style) {
+                    return true;
+                }
+                continue;
+            }
+            LayoutBox::Text { text, .. } if text.trim().is_empty() => continue,
+            LayoutBox::Text { text, .. } => text.clone(),
+        };
+
+        let (start, end) = match first_letter(&text) {
+            Some(range) => range,
+            None => return true,
+        };
+        let text_style = boxes[i].style().clone();
+        let mut split = Vec::new();
+        if start > 0 {
+            split.push(LayoutBox::Text {
+                text: text[..start].to_string(),
+                style: text_style.clone(),
+            });
+        }
+        split.push(LayoutBox::Element {
+            node: element.clone(),
+            pseudo_element: Some(PseudoElement::FirstLetter),
+            style: style.clone(),
+            children: vec![LayoutBox::Text {
+                text: text[start..end].to_string(),
+                style: style.clone(),
+            }],
+        });
+        if end < text.len() {
+            split.push(LayoutBox::Text {
+                text: text[end..].to_string(),
+                style: text_style,
+            });
+        }
+        boxes.splice(i..i + 1, split);
+        return true;
+    }
+    false
+}
+
+// the byte range of the first letter of `text` after its leading white
+// space, with the punctuation before and after it
+fn first_letter(text: &str) -> Option<(usize, usize)> {
+    let start = text.len() - text.trim_start().len();
+    let mut chars = text[start..].char_indices().peekable();
+    while chars.next_if(|(_, c)| is_punctuation(*c)).is_some() {}
+    chars.next_if(|(_, c)| c.is_alphanumeric())?;
+    while chars.next_if(|(_, c)| is_punctuation(*c)).is_some() {}
+    let end = chars.peek().map_or(text.len(), |(i, _)| start + i);
+    Some((start, end))
+}
+
+// https://drafts.csswg.org/css-pseudo-4/#first-letter-pseudo
+//
+// the opening and closing punctuation and quotes that go with the letter
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation()
+        || matches!(c, '\u{00A1}' | '\u{00AB}' | '\u{00BB}' | '\u{00BF}')
+        || ('\u{2018}'..='\u{201F}').contains(&c)
+}
+
+// https://drafts.csswg.org/css-pseudo-4/#first-line-pseudo
+//
+// Wraps the inline content at the start of `boxes`, up to a line break or a
+// block, in a `::first-line` box, or else goes into the first block, whose
+// first line is the first line of `element` too.
+fn wrap_first_line(
+    boxes: &mut Vec<LayoutBox>,
+    element: &Rc<RefCell<Node>>,
+    style: &Rc<ComputedStyle>,
+) {
+    // the marker sits outside the line
+    let start = match boxes.first() {
+        Some(LayoutBox::Element {
+            pseudo_element: Some(PseudoElement::Marker),
+            ..
+        }) => 1,
+        _ => 0,
+    };
+    let mut end = start;
+    while end < boxes.len() && !boxes[end].is_block_level() {
+        end += 1;
+        if boxes[end - 1].is_line_break() {
+            break;
+        }
+    }
+
+    if end > start {
+        let line: Vec<LayoutBox> = boxes.drain(start..end).collect();
+        boxes.insert(
+            start,
+            LayoutBox::Element {
+                node: element.clone(),
+                pseudo_element: Some(PseudoElement::FirstLine),
+                style: style.clone(),
+                children: line,
+            },
+        );
+    } else if let Some(LayoutBox::Element { children, .. }) = boxes.get_mut(start) {
+        wrap_first_line(children, element, style);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::html_parser::parse;
+
+    // the box tree of the body of a document with `css`
+    fn dump(css: &str, body: &str) -> String {
+        let document = parse(&format!("<style>{}</style><body>{}</body>", css, body));
+        Node::update_style(&document);
+        let root = LayoutBox::build(&document).unwrap();
+        let body = match root {
+            LayoutBox::Element { children, .. } => children.into_iter().last().unwrap(),
+            _ => panic!("not an element box"),
+        };
+        body.dump()
+    }
+
+    #[test]
+    fn test_before_and_after() {
+        assert_eq!(
+            dump(
+                "p::before { content: \"[\" attr(id) \"] \" } \
+                 p::after { content: open-quote \"!\" close-quote } \
+                 #b::before { content: none }",
+                "<p id=a>x</p><p id=b>y</p><div>z</div>",
+            ),
+            "| <body>\n\
+             |   <p>\n\
+             |     <p::before>\n\
+             |       \"[a] \"\n\
+             |     \"x\"\n\
+             |     <p::after>\n\
+             |       \"\u{201C}!\u{201D}\"\n\
+             |   <p>\n\
+             |     \"y\"\n\
+             |     <p::after>\n\
+             |       \"\u{201C}!\u{201D}\"\n\
+             |   <div>\n\
+             |     \"z\"\n"
+        );
+        // `normal` and `display: none` generate nothing
+        assert_eq!(
+            dump(
+                "p::before { color: red } p::after { content: \"a\"; display: none }",
+                "<p>x</p>"
+            ),
+            "| <body>\n\
+             |   <p>\n\
+             |     \"x\"\n"
+        );
+    }
+
+    #[test]
+    fn test_counters() {
+        let css = "body { counter-reset: h } \
+                   h1 { counter-increment: h; counter-reset: s } \
+                   h2 { counter-increment: s } \
+                   h1::before { content: counter(h) \". \" } \
+                   h2::before { content: counter(h) \".\" counter(s) \" \" } \
+                   div { counter-reset: n } \
+                   span { counter-increment: n } \
+                   span::before { content: counters(n, \".\") }";
+        assert_eq!(
+            dump(
+                css,
+                "<h1>a</h1><h2>b</h2><h2>c</h2><h1>d</h1><h2>e</h2>\
+                 <div><span>f</span><div><span>g</span><span>h</span></div><span>i</span></div>",
+            ),
+            "| <body>\n\
+             |   <h1>\n\
+             |     <h1::before>\n\
+             |       \"1. \"\n\
+             |     \"a\"\n\
+             |   <h2>\n\
+             |     <h2::before>\n\
+             |       \"1.1 \"\n\
+             |     \"b\"\n\
+             |   <h2>\n\
+             |     <h2::before>\n\
+             |       \"1.2 \"\n\
+             |     \"c\"\n\
+             |   <h1>\n\
+             |     <h1::before>\n\
+             |       \"2. \"\n\
+             |     \"d\"\n\
+             |   <h2>\n\
+             |     <h2::before>\n\
+             |       \"2.1 \"\n\
+             |     \"e\"\n\
+             |   <div>\n\
+             |     <span>\n\
+             |       <span::before>\n\
+             |         \"1\"\n\
+             |       \"f\"\n\
+             |     <div>\n\
+             |       <span>\n\
+             |         <span::before>\n\
+             |           \"1.1\"\n\
+             |         \"g\"\n\
+             |       <span>\n\
+             |         <span::before>\n\
+             |           \"1.2\"\n\
+             |         \"h\"\n\
+             |     <span>\n\
+             |       <span::before>\n\
+             |         \"1.3\"\n\
+             |       \"i\"\n"
+        );
+    }
+
+    #[test]
+    fn test_markers() {
+        let css = ".list { counter-reset: list-item } \
+                   .list div { display: list-item } \
+                   .numbered div { list-style-type: decimal } \
+                   #hidden { display: none } \
+                   #custom::marker { content: \"-> \" } \
+                   #none { list-style-type: none }";
+        assert_eq!(
+            dump(
+                css,
+                "<div class=list><div>a</div><div id=custom>b</div><div id=none>c</div></div>\
+                 <div class='list numbered'><div>d</div><div id=hidden>e</div><div>f</div></div>",
+            ),
+            "| <body>\n\
+             |   <div>\n\
+             |     <div>\n\
+             |       <div::marker>\n\
+             |         \"\u{2022} \"\n\
+             |       \"a\"\n\
+             |     <div>\n\
+             |       <div::marker>\n\
+             |         \"-> \"\n\
+             |       \"b\"\n\
+             |     <div>\n\
+             |       \"c\"\n\
+             |   <div>\n\
+             |     <div>\n\
+             |       <div::marker>\n\
+             |         \"1. \"\n\
+             |       \"d\"\n\
+             |     <div>\n\
+             |       <div::marker>\n\
+             |         \"2. \"\n\
+             |       \"f\"\n"
+        );
+    }
+
+    #[test]
+    fn test_lists() {
+        // the text of every marker, in order
+        fn markers(layout_box: &LayoutBox, output: &mut Vec<String>) {
+            if let LayoutBox::Element {
+                pseudo_element,
+                children,
+                ..
+            } = layout_box
+            {
+                match (pseudo_element, children.first()) {
+                    (Some(PseudoElement::Marker), Some(LayoutBox::Text { text, .. })) => {
+                        output.push(text.clone())
+                    }
+                    _ => children.iter().for_each(|c| markers(c, output)),
+                }
+            }
+        }
+        let document = parse(
+            "<style>.cjk { list-style-type: cjk-decimal } \
+             .kana { list-style-type: hiragana } \
+             .skip { counter-set: list-item 20 }</style>\
+             <ol><li>a<li>b<ol type=i><li>c<li>d</ol><li value=10>e<li>f</ol>\
+             <ol reversed><li>g<li>h<li>i</ol>\
+             <ol start=5 reversed><li>j<li>k</ol>\
+             <ol start=-1><li>l<li class=skip>m<li>n</ol>\
+             <ul><li>o<ul><li>p<ul type=disc><li>q<li type=square>r</ul></ul></ul>\
+             <ol class=cjk><li>s<li>t</ol>\
+             <ol class=kana start=3><li>u</ol>",
+        );
+        Node::update_style(&document);
+        let mut output = Vec::new();
+        markers(&LayoutBox::build(&document).unwrap(), &mut output);
+
+        assert_eq!(
+            output,
+            [
+                "1. ",
+                "2. ",
+                "i. ",
+                "ii. ",
+                "10. ",
+                "11. ", // nested and `value`
+                "3. ",
+                "2. ",
+                "1. ", // reversed
+                "5. ",
+                "4. ", // reversed from `start`
+                "-1. ",
+                "20. ",
+                "21. ", // `counter-set`
+                "\u{2022} ",
+                "\u{25E6} ",
+                "\u{2022} ",
+                "\u{25AA} ", // nested bullets
+                "一、",
+                "二、",
+                "う、",
+            ]
+        );
+
+        // the count of a reversed list saturates rather than overflowing
+        let document =
+            parse("<ol reversed><li style=\"counter-increment: list-item -2147483648\">a</ol>");
+        Node::update_style(&document);
+        let mut output = Vec::new();
+        markers(&LayoutBox::build(&document).unwrap(), &mut output);
+        assert_eq!(output, ["-1. "]);
+    }
+
+    #[test]
+    fn test_first_letter_and_first_line() {
+        let css = "p::first-line { color: blue } p::first-letter { font-size: 2em } \
+                   div::first-line { color: red }";
+        let document = parse(&format!(
+            "<style>{}</style><p id=p>  \u{201C}Hello,\u{201D} <span>world</span><br>again</p>\
+             <div id=d><p>inner</p>after</div>",
+            css
+        ));
+        Node::update_style(&document);
+        let body = match LayoutBox::build(&document).unwrap() {
+            LayoutBox::Element { children, .. } => children.into_iter().last().unwrap(),
+            _ => panic!("not an element box"),
+        };
+        assert_eq!(
+            body.dump(),
+            "| <body>\n\
+             |   <p>\n\
+             |     <p::first-line>\n\
+             |       \"  \"\n\
+             |       <p::first-letter>\n\
+             |         \"\u{201C}H\"\n\
+             |       \"ello,\u{201D} \"\n\
+             |       <span>\n\
+             |         \"world\"\n\
+             |       <br>\n\
+             |     \"again\"\n\
+             |   <div>\n\
+             |     <p>\n\
+             |       <div::first-line>\n\
+             |         <p::first-line>\n\
+             |           <p::first-letter>\n\
+             |             \"i\"\n\
+             |           \"nner\"\n\
+             |     \"after\"\n"
+        );
+
+        // `::first-letter` inherits from `::first-line`
+        let p = Node::get_element_by_id(&document, "p").unwrap();
+        let first_letter = Node::pseudo_element_style(&p, PseudoElement::FirstLetter).unwrap();
+        assert_eq!(first_letter.get_text("color").unwrap(), "rgb(0, 0, 255)");
+        assert_eq!(first_letter.get_text("font-size").unwrap(), "32px");
+        let d = Node::get_element_by_id(&document, "d").unwrap();
+        assert!(Node::pseudo_element_style(&d, PseudoElement::FirstLetter).is_none());
+    }
+}
@@ -0,0 +1,176 @@
+use std::fmt;
+use std::fs;
+use std::time::SystemTime;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LoadError {
+    // the URL scheme can't be loaded, e.g. `https:` without a network stack
+    UnsupportedScheme(String),
+    Io(String),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::UnsupportedScheme(url) => write!(f, "unsupported URL: {}", url),
+            LoadError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+// Loads subresources such as style sheets. Documents start with a
+// `FileLoader`; embedders with other ways of fetching can replace it.
+pub trait ResourceLoader: fmt::Debug {
+    fn load(&self, url: &str) -> Result<String, LoadError>;
+
+    // When the resource last changed, if the loader can tell. A sheet
+    // whose stamp changed is loaded again the next time the document's
+    // sheets are collected.
+    fn modified(&self, _url: &str) -> Option<SystemTime> {
+        None
+    }
+}
+
+// Loads `file:` URLs and plain paths.
+#[derive(Clone, Debug, Default)]
+pub struct FileLoader;
+
+impl ResourceLoader for FileLoader {
+    fn load(&self, url: &str) -> Result<String, LoadError> {
+        let path = file_path(url)?;
+        fs::read_to_string(path).map_err(|e| LoadError::Io(format!("{}: {}", path, e)))
+    }
+
+    fn modified(&self, url: &str) -> Option<SystemTime> {
+        fs::metadata(file_path(url).ok()?).ok()?.modified().ok()
+    }
+}
+
+// the path a `file:` URL or plain path refers to
+fn file_path(url: &str) -> Result<&str, LoadError> {
+    match url.strip_prefix("file://") {
+        Some(path) => Ok(path),
+        None if has_scheme(url) => Err(LoadError::UnsupportedScheme(url.to_string())),
+        None => Ok(url),
+    }
+}
+
+// whether `url` starts with a scheme such as `https:`, as opposed to a path
+fn has_scheme(url: &str) -> bool {
+    match url.find(':') {
+        Some(i) => {
+            url[..i].starts_with(|c: char| c.is_ascii_alphabetic())
+                && url[..i]
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+// https://url.spec.whatwg.org/#concept-basic-url-parser
+//
+// Resolves `href` against `base` for hierarchical URLs and paths only; a
+// full URL parser is out of scope.
+pub fn resolve_url(base: &str, href: &str) -> String {
+    if has_scheme(href) || !base.contains('/') {
+        return href.to_string();
+    }
+
+    let (prefix, base_path) = match base.find("://") {
+        Some(i) => {
+            let after = &base[i + 3..];
+            let path_start = after.find('/').map_or(base.len(), |p| i + 3 + p);
+            (&base[..path_start], &base[path_start..])
+        }
+        None => ("", base),
+    };
+    let path = if href.starts_with('/') {
+        href.to_string()
+    } else {
+        // relative to the directory of the base
+        let directory = &base_path[..base_path.rfind('/').map_or(0, |i| i + 1)];
+        format!("{}{}", directory, href)
+    };
+    format!("{}{}", prefix, remove_dot_segments(&path))
+}
+
+// https://datatracker.ietf.org/doc/html/rfc3986#section-5.2.4
+fn remove_dot_segments(path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    let parts: Vec<&str> = path.split('/').collect();
+    for (i, part) in parts.iter().enumerate() {
+        let last = i == parts.len() - 1;
+        match *part {
+            "." => {
+                if last {
+                    segments.push("");
+                }
+            }
+            ".." => {
+                if segments.len() > 1 || segments.first().is_some_and(|s| !s.is_empty()) {
+                    segments.pop();
+                }
+                if last {
+                    segments.push("");
+                }
+            }
+            part => segments.push(part),
+        }
+    }
+    segments.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_url() {
+        assert_eq!(
+            resolve_url("file:///site/index.html", "style.css"),
+            "file:///site/style.css"
+        );
+        assert_eq!(
+            resolve_url("file:///site/a/index.html", "../css/./b.css"),
+            "file:///site/css/b.css"
+        );
+        assert_eq!(
+            resolve_url("file:///site/index.html", "/b.css"),
+            "file:///b.css"
+        );
+        assert_eq!(resolve_url("pages/index.html", "a.css"), "pages/a.css");
+        assert_eq!(resolve_url("/pages/index.html", "../a.css"), "/a.css");
+        assert_eq!(
+            resolve_url("file:///index.html", "https://example.com/a.css"),
+            "https://example.com/a.css"
+        );
+        assert_eq!(resolve_url("about:blank", "a.css"), "a.css");
+    }
+
+    #[test]
+    fn test_file_loader() {
+        let path = std::env::temp_dir().join("mmbr-loader-test.css");
+        fs::write(&path, "p { color: red }").unwrap();
+
+        let loader = FileLoader;
+        let path = path.to_str().unwrap();
+        assert_eq!(loader.load(path).unwrap(), "p { color: red }");
+        assert_eq!(
+            loader.load(&format!("file://{}", path)).unwrap(),
+            "p { color: red }"
+        );
+        assert!(loader.modified(path).is_some());
+        assert!(matches!(
+            loader.load("/no/such/file.css"),
+            Err(LoadError::Io(_))
+        ));
+        assert_eq!(loader.modified("/no/such/file.css"), None);
+        assert_eq!(
+            loader.load("https://example.com/a.css"),
+            Err(LoadError::UnsupportedScheme(String::from(
+                "https://example.com/a.css"
+            )))
+        );
+    }
+}
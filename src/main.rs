@@ -1,36 +1,122 @@
+pub mod diff;
+pub mod document;
 pub mod element;
+pub mod event;
+pub mod html_collection;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod layout;
+pub mod loader;
+pub mod mutation_observer;
 pub mod node;
 pub mod parser;
+pub mod range;
+pub mod selection;
+pub mod selector;
+pub mod serializer;
+pub mod style;
+pub mod text;
+pub mod traversal;
+pub mod tree_dump;
+pub mod tree_walker;
 
 use gtk::prelude::*;
 use gtk::{Application, ApplicationWindow};
-use std::env;
+use node::{Node, NodeKind};
+use parser::{html_parser::HtmlPerser, html_tokenizer::HtmlTokenizer};
+use std::{env, fs, process};
+use style::{ColorScheme, Device, Viewport};
 
 fn input() -> Vec<String> {
     let args: Vec<String> = env::args().collect();
     args
 }
 
+// `mmbr --dump-tree <file>` prints the parsed tree instead of opening a window
+fn dump_tree(path: &str) {
+    let html = match fs::read_to_string(path) {
+        Ok(html) => html,
+        Err(e) => {
+            eprintln!("failed to read {}: {}", path, e);
+            process::exit(1);
+        }
+    };
+
+    let root = HtmlPerser::new(HtmlTokenizer::new(html)).construct_tree();
+    print!("{}", Node::dump_tree(&root));
+}
+
 fn main() {
     let html = input();
+    if html.len() == 3 && html[1] == "--dump-tree" {
+        dump_tree(&html[2]);
+        return;
+    }
+
     println!("{:?}", html);
 
     let app = Application::builder().application_id("mmbr").build();
 
     app.connect_activate(build_ui);
 
-    app.run();
+    // the file argument is read by `build_ui`, not by GTK
+    app.run_with_args(&html[..1]);
 }
 
+// `mmbr <file>` opens the file and keeps its styles in step with the window
 fn build_ui(app: &Application) {
     let window = ApplicationWindow::builder()
         .application(app)
         .title("mmbr")
+        .default_width(800)
+        .default_height(600)
         .build();
 
+    // the document URL is the file's, so that relative links resolve
+    // against its directory
+    let file = input().get(1).and_then(|path| {
+        let path = fs::canonicalize(path).ok()?;
+        let html = fs::read_to_string(&path).ok()?;
+        Some((format!("file://{}", path.display()), html))
+    });
+    if let Some((url, html)) = file {
+        let document = HtmlPerser::new(HtmlTokenizer::new(html)).construct_tree();
+        if let NodeKind::Document(ref mut d) = document.borrow_mut().kind {
+            d.set_url(&url);
+        }
+        Node::set_device(&document, device(&window));
+        Node::update_style(&document);
+
+        // GTK keeps the default size in step with the size the user resizes
+        // the window to; styles are only resolved again when that crosses a
+        // media query breakpoint
+        let resized = move |window: &ApplicationWindow| {
+            Node::set_device(&document, device(window));
+            Node::update_style(&document);
+        };
+        window.connect_default_width_notify(resized.clone());
+        window.connect_default_height_notify(resized);
+    }
+
     window.present();
 }
 
+fn device(window: &ApplicationWindow) -> Device {
+    let (width, height) = window.default_size();
+    let dark = gtk::Settings::default().is_some_and(|s| s.is_gtk_application_prefer_dark_theme());
+    Device {
+        viewport: Viewport {
+            width: width.max(0) as f64,
+            height: height.max(0) as f64,
+        },
+        device_pixel_ratio: window.scale_factor() as f64,
+        color_scheme: match dark {
+            true => ColorScheme::Dark,
+            false => ColorScheme::Light,
+        },
+    }
+}
+
 #[cfg(test)]
 mod test {
     #[test]
@@ -0,0 +1,380 @@
+use crate::node::{DomException, Node};
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+// https://dom.spec.whatwg.org/#dom-mutationrecord-type
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MutationRecordType {
+    ChildList,
+    Attributes,
+    CharacterData,
+}
+
+// https://dom.spec.whatwg.org/#interface-mutationrecord
+#[derive(Clone, Debug)]
+pub struct MutationRecord {
+    pub record_type: MutationRecordType,
+    pub target: Rc<RefCell<Node>>,
+    pub added_nodes: Vec<Rc<RefCell<Node>>>,
+    pub removed_nodes: Vec<Rc<RefCell<Node>>>,
+    pub previous_sibling: Option<Rc<RefCell<Node>>>,
+    pub next_sibling: Option<Rc<RefCell<Node>>>,
+    pub attribute_name: Option<String>,
+    pub old_value: Option<String>,
+}
+
+impl MutationRecord {
+    pub(crate) fn new(record_type: MutationRecordType, target: &Rc<RefCell<Node>>) -> Self {
+        Self {
+            record_type,
+            target: target.clone(),
+            added_nodes: Vec::new(),
+            removed_nodes: Vec::new(),
+            previous_sibling: None,
+            next_sibling: None,
+            attribute_name: None,
+            old_value: None,
+        }
+    }
+}
+
+// https://dom.spec.whatwg.org/#dictdef-mutationobserverinit
+#[derive(Clone, Debug, Default)]
+pub struct MutationObserverInit {
+    pub child_list: bool,
+    pub attributes: bool,
+    pub character_data: bool,
+    pub subtree: bool,
+    pub attribute_old_value: bool,
+    pub character_data_old_value: bool,
+    // only report changes to these attributes
+    pub attribute_filter: Option<Vec<String>>,
+}
+
+pub type MutationCallback = Box<dyn Fn(Vec<MutationRecord>, &Rc<MutationObserver>)>;
+
+// https://dom.spec.whatwg.org/#interface-mutationobserver
+//
+// Records are queued as the tree changes and handed to the callback in a
+// batch by `notify_mutation_observers`, which the embedder calls where a
+// browser would perform a microtask checkpoint.
+pub struct MutationObserver {
+    callback: MutationCallback,
+    records: RefCell<Vec<MutationRecord>>,
+    // the nodes this observer is registered on, for `disconnect`
+    nodes: RefCell<Vec<Weak<RefCell<Node>>>>,
+}
+
+impl fmt::Debug for MutationObserver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MutationObserver")
+            .field("records", &self.records.borrow().len())
+            .finish()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct RegisteredObserver {
+    observer: Rc<MutationObserver>,
+    options: MutationObserverInit,
+}
+
+thread_local! {
+    // observers with records waiting for `notify_mutation_observers`
+    static PENDING_OBSERVERS: RefCell<Vec<Rc<MutationObserver>>> = const { RefCell::new(Vec::new()) };
+}
+
+impl MutationObserver {
+    pub fn new(callback: MutationCallback) -> Rc<Self> {
+        Rc::new(Self {
+            callback,
+            records: RefCell::new(Vec::new()),
+            nodes: RefCell::new(Vec::new()),
+        })
+    }
+
+    // https://dom.spec.whatwg.org/#dom-mutationobserver-observe
+    pub fn observe(
+        self: &Rc<Self>,
+        target: &Rc<RefCell<Node>>,
+        mut options: MutationObserverInit,
+    ) -> Result<(), DomException> {
+        if options.attribute_old_value || options.attribute_filter.is_some() {
+            options.attributes = true;
+        }
+        if options.character_data_old_value {
+            options.character_data = true;
+        }
+        if !options.child_list && !options.attributes && !options.character_data {
+            return Err(DomException::TypeError);
+        }
+
+        let mut node = target.borrow_mut();
+        match node
+            .registered_observers
+            .iter_mut()
+            .find(|r| Rc::ptr_eq(&r.observer, self))
+        {
+            // observing the same node again replaces the options
+            Some(registered) => registered.options = options,
+            None => {
+                node.registered_observers.push(RegisteredObserver {
+                    observer: self.clone(),
+                    options,
+                });
+                self.nodes.borrow_mut().push(Rc::downgrade(target));
+            }
+        }
+        Ok(())
+    }
+
+    // https://dom.spec.whatwg.org/#dom-mutationobserver-disconnect
+    pub fn disconnect(self: &Rc<Self>) {
+        for node in self.nodes.borrow_mut().drain(..) {
+            if let Some(node) = node.upgrade() {
+                node.borrow_mut()
+                    .registered_observers
+                    .retain(|r| !Rc::ptr_eq(&r.observer, self));
+            }
+        }
+        self.records.borrow_mut().clear();
+    }
+
+    // https://dom.spec.whatwg.org/#dom-mutationobserver-takerecords
+    pub fn take_records(&self) -> Vec<MutationRecord> {
+        self.records.take()
+    }
+}
+
+// https://dom.spec.whatwg.org/#notify-mutation-observers
+//
+// Delivers the queued records. Records queued by the callbacks themselves
+// are delivered before this returns.
+pub fn notify_mutation_observers() {
+    loop {
+        let observers = PENDING_OBSERVERS.with(|p| p.take());
+        if observers.is_empty() {
+            return;
+        }
+        for observer in observers {
+            let records = observer.take_records();
+            if !records.is_empty() {
+                (observer.callback)(records, &observer);
+            }
+        }
+    }
+}
+
+// https://dom.spec.whatwg.org/#queue-a-mutation-record
+pub(crate) fn queue_mutation_record(record: MutationRecord, old_value: Option<String>) {
+    // observer and whether it wants the old value
+    let mut interested: Vec<(Rc<MutationObserver>, bool)> = Vec::new();
+
+    for node in Node::inclusive_ancestors(&record.target) {
+        let is_target = Rc::ptr_eq(&node, &record.target);
+        for registered in node.borrow().registered_observers.iter() {
+            let options = &registered.options;
+            if !is_target && !options.subtree {
+                continue;
+            }
+            let wants_old_value = match record.record_type {
+                MutationRecordType::ChildList if options.child_list => false,
+                MutationRecordType::Attributes if options.attributes => {
+                    let name = record.attribute_name.as_deref().unwrap_or_default();
+                    if let Some(ref filter) = options.attribute_filter {
+                        if !filter.iter().any(|f| f == name) {
+                            continue;
+                        }
+                    }
+                    options.attribute_old_value
+                }
+                MutationRecordType::CharacterData if options.character_data => {
+                    options.character_data_old_value
+                }
+                _ => continue,
+            };
+
+            match interested
+                .iter_mut()
+                .find(|(o, _)| Rc::ptr_eq(o, &registered.observer))
+            {
+                Some((_, old)) => *old |= wants_old_value,
+                None => interested.push((registered.observer.clone(), wants_old_value)),
+            }
+        }
+    }
+
+    for (observer, wants_old_value) in interested {
+        let mut record = record.clone();
+        if wants_old_value {
+            record.old_value = old_value.clone();
+        }
+        observer.records.borrow_mut().push(record);
+
+        PENDING_OBSERVERS.with(|p| {
+            let mut pending = p.borrow_mut();
+            if !pending.iter().any(|o| Rc::ptr_eq(o, &observer)) {
+                pending.push(observer);
+            }
+        });
+    }
+}
+
+// https://dom.spec.whatwg.org/#queue-a-tree-mutation-record
+pub(crate) fn queue_tree_mutation_record(
+    target: &Rc<RefCell<Node>>,
+    added_nodes: Vec<Rc<RefCell<Node>>>,
+    removed_nodes: Vec<Rc<RefCell<Node>>>,
+    previous_sibling: Option<Rc<RefCell<Node>>>,
+    next_sibling: Option<Rc<RefCell<Node>>>,
+) {
+    let mut record = MutationRecord::new(MutationRecordType::ChildList, target);
+    record.added_nodes = added_nodes;
+    record.removed_nodes = removed_nodes;
+    record.previous_sibling = previous_sibling;
+    record.next_sibling = next_sibling;
+    queue_mutation_record(record, None);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::NodeKind;
+    use crate::parser::html_parser::parse;
+
+    fn collecting_observer() -> (Rc<MutationObserver>, Rc<RefCell<Vec<MutationRecord>>>) {
+        let delivered = Rc::new(RefCell::new(Vec::new()));
+        let sink = delivered.clone();
+        let observer = MutationObserver::new(Box::new(move |records, _| {
+            sink.borrow_mut().extend(records);
+        }));
+        (observer, delivered)
+    }
+
+    fn text(s: &str) -> Rc<RefCell<Node>> {
+        Rc::new(RefCell::new(Node::new(NodeKind::Text(s.to_string()))))
+    }
+
+    #[test]
+    fn test_child_list_records() {
+        let document = parse("<div id=a><p id=b>x</p></div>");
+        let a = Node::get_element_by_id(&document, "a").unwrap();
+        let b = Node::get_element_by_id(&document, "b").unwrap();
+        let (observer, delivered) = collecting_observer();
+        observer
+            .observe(
+                &a,
+                MutationObserverInit {
+                    child_list: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        let t = text("y");
+        Node::append_child_node(&a, &t).unwrap();
+        Node::detach(&b);
+        // not reported without the subtree option
+        Node::append_child_node(&b, &text("z")).unwrap();
+        assert!(delivered.borrow().is_empty());
+
+        notify_mutation_observers();
+        let records = delivered.borrow();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].record_type, MutationRecordType::ChildList);
+        assert!(Rc::ptr_eq(&records[0].added_nodes[0], &t));
+        assert!(Rc::ptr_eq(
+            records[0].previous_sibling.as_ref().unwrap(),
+            &b
+        ));
+        assert!(Rc::ptr_eq(&records[1].removed_nodes[0], &b));
+        assert!(records[1].previous_sibling.is_none());
+        assert!(Rc::ptr_eq(records[1].next_sibling.as_ref().unwrap(), &t));
+    }
+
+    #[test]
+    fn test_attribute_and_character_data_records() {
+        let document = parse("<div id=a class=x><p id=b>x</p></div>");
+        let a = Node::get_element_by_id(&document, "a").unwrap();
+        let b = Node::get_element_by_id(&document, "b").unwrap();
+        let (observer, _) = collecting_observer();
+        observer
+            .observe(
+                &a,
+                MutationObserverInit {
+                    subtree: true,
+                    attribute_old_value: true,
+                    attribute_filter: Some(vec![String::from("class")]),
+                    character_data_old_value: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        Node::set_attribute(&a, "class", "y");
+        Node::set_attribute(&a, "title", "ignored");
+        Node::set_attribute(&b, "class", "z");
+        let t = b.borrow().first_child().unwrap();
+        Node::set_data(&t, "changed");
+
+        let records = observer.take_records();
+        let summary: Vec<_> = records
+            .iter()
+            .map(|r| (r.record_type, r.attribute_name.clone(), r.old_value.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    MutationRecordType::Attributes,
+                    Some(String::from("class")),
+                    Some(String::from("x"))
+                ),
+                (
+                    MutationRecordType::Attributes,
+                    Some(String::from("class")),
+                    None
+                ),
+                (
+                    MutationRecordType::CharacterData,
+                    None,
+                    Some(String::from("x"))
+                ),
+            ]
+        );
+
+        // taken records are not delivered again
+        notify_mutation_observers();
+        assert!(observer.take_records().is_empty());
+    }
+
+    #[test]
+    fn test_disconnect_and_invalid_options() {
+        let document = parse("<div id=a></div>");
+        let a = Node::get_element_by_id(&document, "a").unwrap();
+        let (observer, delivered) = collecting_observer();
+
+        assert_eq!(
+            observer.observe(&a, MutationObserverInit::default()),
+            Err(DomException::TypeError)
+        );
+
+        observer
+            .observe(
+                &a,
+                MutationObserverInit {
+                    attributes: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        Node::set_attribute(&a, "class", "x");
+        observer.disconnect();
+        Node::set_attribute(&a, "class", "y");
+
+        notify_mutation_observers();
+        assert!(delivered.borrow().is_empty());
+        assert!(a.borrow().registered_observers.is_empty());
+    }
+}
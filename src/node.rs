@@ -1,26 +1,76 @@
-use crate::element::Element;
+use crate::document::Document;
+use crate::element::{Element, HtmlElementKind};
+use crate::event::EventListener;
+use crate::mutation_observer::{queue_tree_mutation_record, RegisteredObserver};
+use crate::range::{adjust_for_insert, adjust_for_remove};
+use crate::selector::PseudoElement;
+use crate::style::{ComputedStyle, RestyleHint};
 use std::cell::RefCell;
 use std::rc::{Rc, Weak};
 
+// https://webidl.spec.whatwg.org/#idl-DOMException-error-names
+#[derive(Clone, Debug, PartialEq)]
+pub enum DomException {
+    HierarchyRequestError,
+    NotFoundError,
+    NotSupportedError,
+    SyntaxError,
+    IndexSizeError,
+    InvalidNodeTypeError,
+    InvalidStateError,
+    // not a DOMException name, thrown as a simple TypeError by the same APIs
+    TypeError,
+}
+
 #[derive(Clone, Debug)]
 pub struct Node {
     pub kind: NodeKind,
+    owner_document: Option<Weak<RefCell<Node>>>,
     parent: Option<Weak<RefCell<Node>>>,
     first_child: Option<Rc<RefCell<Node>>>,
     last_child: Option<Weak<RefCell<Node>>>,
     next_sibling: Option<Rc<RefCell<Node>>>,
     previous_sibling: Option<Weak<RefCell<Node>>>,
+    pub(crate) event_listeners: Vec<EventListener>,
+    pub(crate) registered_observers: Vec<RegisteredObserver>,
+    // set by `Node::resolve_styles`
+    pub(crate) computed_style: Option<Rc<ComputedStyle>>,
+    // https://drafts.csswg.org/css-pseudo-4/
+    pub(crate) pseudo_element_styles: Vec<(PseudoElement, Rc<ComputedStyle>)>,
+    // what the next `update_style` has to restyle here, and whether it has
+    // to look further down for nodes it has to restyle
+    pub(crate) restyle_hint: RestyleHint,
+    pub(crate) dirty_descendants: bool,
+    // https://html.spec.whatwg.org/multipage/scripting.html#template-contents
+    //
+    // the fragment a template element keeps the markup inside it in, out of
+    // the tree
+    template_contents: Option<Rc<RefCell<Node>>>,
 }
 
 impl Node {
     pub fn new(kind: NodeKind) -> Self {
+        let template_contents = match kind {
+            NodeKind::Element(ref e) if *e.kind() == HtmlElementKind::Template => {
+                Some(Rc::new(RefCell::new(Node::new(NodeKind::DocumentFragment))))
+            }
+            _ => None,
+        };
         Self {
             kind,
+            owner_document: None,
             parent: None,
             first_child: None,
             last_child: None,
             next_sibling: None,
             previous_sibling: None,
+            event_listeners: Vec::new(),
+            registered_observers: Vec::new(),
+            computed_style: None,
+            pseudo_element_styles: Vec::new(),
+            restyle_hint: RestyleHint::None,
+            dirty_descendants: false,
+            template_contents,
         }
     }
 
@@ -28,8 +78,12 @@ impl Node {
         self.kind.clone()
     }
 
+    pub fn template_contents(&self) -> Option<Rc<RefCell<Node>>> {
+        self.template_contents.clone()
+    }
+
     pub fn parent(&self) -> Option<Rc<RefCell<Node>>> {
-        self.parent.as_ref().map(|n| n.upgrade().unwrap().clone())
+        self.parent.as_ref().and_then(|n| n.upgrade())
     }
 
     pub fn first_child(&self) -> Option<Rc<RefCell<Node>>> {
@@ -37,9 +91,7 @@ impl Node {
     }
 
     pub fn last_child(&self) -> Option<Rc<RefCell<Node>>> {
-        self.last_child
-            .as_ref()
-            .map(|n| n.upgrade().unwrap().clone())
+        self.last_child.as_ref().and_then(|n| n.upgrade())
     }
 
     pub fn next_sibling(&self) -> Option<Rc<RefCell<Node>>> {
@@ -47,39 +99,506 @@ impl Node {
     }
 
     pub fn previous_sibling(&self) -> Option<Rc<RefCell<Node>>> {
-        self.previous_sibling
+        self.previous_sibling.as_ref().and_then(|n| n.upgrade())
+    }
+
+    pub fn has_child_nodes(&self) -> bool {
+        self.first_child.is_some()
+    }
+
+    // https://dom.spec.whatwg.org/#concept-node-document
+    pub fn node_document(node: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+        if node.borrow().is_document() {
+            return Some(node.clone());
+        }
+        node.borrow()
+            .owner_document
             .as_ref()
-            .map(|n| n.upgrade().unwrap().clone())
+            .and_then(|d| d.upgrade())
+    }
+
+    // https://dom.spec.whatwg.org/#concept-tree-inclusive-ancestor
+    pub fn is_inclusive_ancestor_of(node: &Rc<RefCell<Node>>, other: &Rc<RefCell<Node>>) -> bool {
+        Self::inclusive_ancestors(other).any(|n| Rc::ptr_eq(&n, node))
+    }
+
+    // https://dom.spec.whatwg.org/#dom-node-appendchild
+    pub fn append_child_node(
+        parent: &Rc<RefCell<Node>>,
+        child_node: &Rc<RefCell<Node>>,
+    ) -> Result<Rc<RefCell<Node>>, DomException> {
+        Self::insert_before(parent, child_node, None)
+    }
+
+    // https://dom.spec.whatwg.org/#dom-parentnode-prepend
+    pub fn prepend(
+        parent: &Rc<RefCell<Node>>,
+        node: &Rc<RefCell<Node>>,
+    ) -> Result<Rc<RefCell<Node>>, DomException> {
+        let first_child = parent.borrow().first_child();
+        Self::insert_before(parent, node, first_child.as_ref())
+    }
+
+    // https://dom.spec.whatwg.org/#concept-node-pre-insert
+    pub fn insert_before(
+        parent: &Rc<RefCell<Node>>,
+        node: &Rc<RefCell<Node>>,
+        child: Option<&Rc<RefCell<Node>>>,
+    ) -> Result<Rc<RefCell<Node>>, DomException> {
+        Self::ensure_pre_insertion_validity(parent, node, child)?;
+
+        // if child is node, insert before node's next sibling instead
+        let mut reference_child = child.cloned();
+        if let Some(ref c) = reference_child {
+            if Rc::ptr_eq(c, node) {
+                reference_child = node.borrow().next_sibling();
+            }
+        }
+
+        Self::insert(parent, node, reference_child.as_ref());
+        Ok(node.clone())
+    }
+
+    // https://dom.spec.whatwg.org/#concept-node-replace
+    pub fn replace_child(
+        parent: &Rc<RefCell<Node>>,
+        node: &Rc<RefCell<Node>>,
+        child: &Rc<RefCell<Node>>,
+    ) -> Result<Rc<RefCell<Node>>, DomException> {
+        Self::ensure_replace_validity(parent, node, child)?;
+
+        let mut reference_child = child.borrow().next_sibling();
+        if let Some(ref r) = reference_child {
+            if Rc::ptr_eq(r, node) {
+                reference_child = node.borrow().next_sibling();
+            }
+        }
+
+        if !Rc::ptr_eq(child, node) {
+            Self::remove(child, false);
+        }
+        Self::insert(parent, node, reference_child.as_ref());
+        Ok(child.clone())
+    }
+
+    // https://dom.spec.whatwg.org/#concept-node-pre-remove
+    pub fn remove_child(
+        parent: &Rc<RefCell<Node>>,
+        child: &Rc<RefCell<Node>>,
+    ) -> Result<Rc<RefCell<Node>>, DomException> {
+        match child.borrow().parent() {
+            Some(ref p) if Rc::ptr_eq(p, parent) => {}
+            _ => return Err(DomException::NotFoundError),
+        }
+
+        Self::remove(child, false);
+        Ok(child.clone())
+    }
+
+    // https://dom.spec.whatwg.org/#dom-childnode-remove
+    pub fn detach(node: &Rc<RefCell<Node>>) {
+        if node.borrow().parent.is_some() {
+            Self::remove(node, false);
+        }
+    }
+
+    // https://dom.spec.whatwg.org/#concept-node-clone
+    pub fn clone_node(node: &Rc<RefCell<Node>>, deep: bool) -> Rc<RefCell<Node>> {
+        let mut copy = Node::new(node.borrow().kind());
+        copy.owner_document = node.borrow().owner_document.clone();
+        let copy = Rc::new(RefCell::new(copy));
+
+        if deep {
+            for child in Self::children(node) {
+                let child_copy = Self::clone_node(&child, true);
+                Self::insert(&copy, &child_copy, None);
+            }
+
+            // https://html.spec.whatwg.org/multipage/scripting.html#the-template-element:concept-node-clone-ext
+            let contents = node.borrow().template_contents();
+            let contents_copy = copy.borrow().template_contents();
+            if let (Some(contents), Some(contents_copy)) = (contents, contents_copy) {
+                for child in Self::children(&contents) {
+                    let child_copy = Self::clone_node(&child, true);
+                    Self::insert(&contents_copy, &child_copy, None);
+                }
+            }
+        }
+
+        copy
+    }
+
+    // https://dom.spec.whatwg.org/#concept-node-adopt
+    pub fn adopt(
+        document: &Rc<RefCell<Node>>,
+        node: &Rc<RefCell<Node>>,
+    ) -> Result<Rc<RefCell<Node>>, DomException> {
+        if node.borrow().is_document() {
+            return Err(DomException::NotSupportedError);
+        }
+
+        Self::detach(node);
+        Self::set_owner_document(node, document);
+        Ok(node.clone())
+    }
+
+    fn set_owner_document(node: &Rc<RefCell<Node>>, document: &Rc<RefCell<Node>>) {
+        for n in Self::inclusive_descendants(node) {
+            n.borrow_mut().owner_document = Some(Rc::downgrade(document));
+        }
+    }
+
+    // https://dom.spec.whatwg.org/#concept-node-ensure-pre-insertion-validity
+    pub(crate) fn ensure_pre_insertion_validity(
+        parent: &Rc<RefCell<Node>>,
+        node: &Rc<RefCell<Node>>,
+        child: Option<&Rc<RefCell<Node>>>,
+    ) -> Result<(), DomException> {
+        Self::ensure_insertable(parent, node)?;
+
+        if let Some(c) = child {
+            match c.borrow().parent() {
+                Some(ref p) if Rc::ptr_eq(p, parent) => {}
+                _ => return Err(DomException::NotFoundError),
+            }
+        }
+
+        if !parent.borrow().is_document() {
+            return Ok(());
+        }
+
+        let children = Self::child_nodes(parent);
+        let child_index = child.map(|c| Self::index_of(&children, c));
+
+        let inserts_element = match node.borrow().kind {
+            NodeKind::Element(_) => true,
+            NodeKind::DocumentFragment => Self::ensure_fragment_insertable(node)?,
+            _ => false,
+        };
+
+        match node.borrow().kind {
+            _ if inserts_element => {
+                if children.iter().any(|c| c.borrow().is_element()) {
+                    return Err(DomException::HierarchyRequestError);
+                }
+                // a doctype must not follow the document element
+                if let Some(index) = child_index {
+                    if children[index..].iter().any(|c| c.borrow().is_doctype()) {
+                        return Err(DomException::HierarchyRequestError);
+                    }
+                }
+            }
+            NodeKind::DocumentType(_) => {
+                if children.iter().any(|c| c.borrow().is_doctype()) {
+                    return Err(DomException::HierarchyRequestError);
+                }
+                // the doctype must precede the document element
+                let before = match child_index {
+                    Some(index) => &children[..index],
+                    None => &children[..],
+                };
+                if before.iter().any(|c| c.borrow().is_element()) {
+                    return Err(DomException::HierarchyRequestError);
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    // https://dom.spec.whatwg.org/#concept-node-replace (steps 1-6)
+    fn ensure_replace_validity(
+        parent: &Rc<RefCell<Node>>,
+        node: &Rc<RefCell<Node>>,
+        child: &Rc<RefCell<Node>>,
+    ) -> Result<(), DomException> {
+        Self::ensure_insertable(parent, node)?;
+
+        match child.borrow().parent() {
+            Some(ref p) if Rc::ptr_eq(p, parent) => {}
+            _ => return Err(DomException::NotFoundError),
+        }
+
+        if !parent.borrow().is_document() {
+            return Ok(());
+        }
+
+        let children = Self::child_nodes(parent);
+        let child_index = Self::index_of(&children, child);
+        let others = |c: &&Rc<RefCell<Node>>| !Rc::ptr_eq(c, child);
+
+        let inserts_element = match node.borrow().kind {
+            NodeKind::Element(_) => true,
+            NodeKind::DocumentFragment => Self::ensure_fragment_insertable(node)?,
+            _ => false,
+        };
+
+        match node.borrow().kind {
+            _ if inserts_element => {
+                if children
+                    .iter()
+                    .filter(others)
+                    .any(|c| c.borrow().is_element())
+                {
+                    return Err(DomException::HierarchyRequestError);
+                }
+                if children[child_index + 1..]
+                    .iter()
+                    .any(|c| c.borrow().is_doctype())
+                {
+                    return Err(DomException::HierarchyRequestError);
+                }
+            }
+            NodeKind::DocumentType(_) => {
+                if children
+                    .iter()
+                    .filter(others)
+                    .any(|c| c.borrow().is_doctype())
+                {
+                    return Err(DomException::HierarchyRequestError);
+                }
+                if children[..child_index]
+                    .iter()
+                    .any(|c| c.borrow().is_element())
+                {
+                    return Err(DomException::HierarchyRequestError);
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    // A fragment inserted into a document may hold at most one element and
+    // no text. Returns whether it holds an element.
+    fn ensure_fragment_insertable(fragment: &Rc<RefCell<Node>>) -> Result<bool, DomException> {
+        let mut elements = 0;
+        for child in Self::children(fragment) {
+            match child.borrow().kind {
+                NodeKind::Element(_) => elements += 1,
+                NodeKind::Text(_) => return Err(DomException::HierarchyRequestError),
+                _ => {}
+            }
+        }
+        match elements {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(DomException::HierarchyRequestError),
+        }
+    }
+
+    // checks shared by pre-insert and replace that do not depend on the child
+    fn ensure_insertable(
+        parent: &Rc<RefCell<Node>>,
+        node: &Rc<RefCell<Node>>,
+    ) -> Result<(), DomException> {
+        match parent.borrow().kind {
+            NodeKind::Document(_) | NodeKind::DocumentFragment | NodeKind::Element(_) => {}
+            _ => return Err(DomException::HierarchyRequestError),
+        }
+
+        if Self::is_inclusive_ancestor_of(node, parent) {
+            return Err(DomException::HierarchyRequestError);
+        }
+
+        let parent_is_document = parent.borrow().is_document();
+        match node.borrow().kind {
+            NodeKind::Document(_) => Err(DomException::HierarchyRequestError),
+            NodeKind::Text(_) if parent_is_document => Err(DomException::HierarchyRequestError),
+            NodeKind::DocumentType(_) if !parent_is_document => {
+                Err(DomException::HierarchyRequestError)
+            }
+            _ => Ok(()),
+        }
     }
 
-    pub fn append_child_node(&mut self, child_node: &Rc<RefCell<Node>>) {
-        if self.first_child.is_some() {
-            self.first_child.as_ref().unwrap().borrow_mut().next_sibling = Some(child_node.clone());
-            child_node.borrow_mut().previous_sibling =
-                Some(Rc::downgrade(&self.first_child.as_ref().unwrap()));
+    // https://dom.spec.whatwg.org/#concept-node-insert
+    //
+    // Inserts without any validity checks. The HTML parser relies on this
+    // to build trees that `insert_before` would reject, e.g. text directly
+    // under the document.
+    pub(crate) fn insert(
+        parent: &Rc<RefCell<Node>>,
+        node: &Rc<RefCell<Node>>,
+        child: Option<&Rc<RefCell<Node>>>,
+    ) {
+        // a fragment is replaced by its children
+        let nodes = if node.borrow().is_document_fragment() {
+            let nodes = Self::child_nodes(node);
+            if nodes.is_empty() {
+                return;
+            }
+            for n in nodes.iter() {
+                Self::remove(n, true);
+            }
+            queue_tree_mutation_record(node, Vec::new(), nodes.clone(), None, None);
+            nodes
         } else {
-            self.first_child = Some(child_node.clone());
+            Self::detach(node);
+            vec![node.clone()]
+        };
+
+        adjust_for_insert(parent, child, nodes.len());
+
+        let previous_sibling = match child {
+            Some(c) => c.borrow().previous_sibling(),
+            None => parent.borrow().last_child(),
+        };
+
+        let document = Self::node_document(parent);
+        for n in nodes.iter() {
+            if let Some(ref document) = document {
+                Self::set_owner_document(n, document);
+            }
+            Self::link(parent, n, child);
+        }
+
+        if let Some(document) = Self::connected_document(parent) {
+            for n in nodes.iter() {
+                Self::register_subtree(&document, n);
+                Self::invalidate_insertion(&document, n);
+            }
+        }
+
+        queue_tree_mutation_record(parent, nodes, Vec::new(), previous_sibling, child.cloned());
+    }
+
+    // links `node` in as a child of `parent` before `child`
+    fn link(
+        parent: &Rc<RefCell<Node>>,
+        node: &Rc<RefCell<Node>>,
+        child: Option<&Rc<RefCell<Node>>>,
+    ) {
+        let previous_sibling = match child {
+            Some(c) => c.borrow().previous_sibling(),
+            None => parent.borrow().last_child(),
+        };
+
+        {
+            let mut n = node.borrow_mut();
+            n.parent = Some(Rc::downgrade(parent));
+            n.previous_sibling = previous_sibling.as_ref().map(Rc::downgrade);
+            n.next_sibling = child.cloned();
+        }
+
+        match previous_sibling {
+            Some(ref prev) => prev.borrow_mut().next_sibling = Some(node.clone()),
+            None => parent.borrow_mut().first_child = Some(node.clone()),
+        }
+        match child {
+            Some(c) => c.borrow_mut().previous_sibling = Some(Rc::downgrade(node)),
+            None => parent.borrow_mut().last_child = Some(Rc::downgrade(node)),
+        }
+    }
+
+    // https://dom.spec.whatwg.org/#concept-node-remove
+    fn remove(node: &Rc<RefCell<Node>>, suppress_observers: bool) {
+        let parent = match node.borrow().parent() {
+            Some(p) => p,
+            None => return,
+        };
+        adjust_for_remove(node, &parent);
+        let document = Self::connected_document(&parent);
+        if let Some(ref document) = document {
+            Self::unregister_subtree(document, node);
+        }
+
+        let previous_sibling = node.borrow().previous_sibling();
+        let next_sibling = node.borrow().next_sibling();
+
+        match previous_sibling {
+            Some(ref prev) => prev.borrow_mut().next_sibling = next_sibling.clone(),
+            None => parent.borrow_mut().first_child = next_sibling.clone(),
+        }
+        match next_sibling {
+            Some(ref next) => {
+                next.borrow_mut().previous_sibling = previous_sibling.as_ref().map(Rc::downgrade)
+            }
+            None => parent.borrow_mut().last_child = previous_sibling.as_ref().map(Rc::downgrade),
+        }
+
+        {
+            let mut n = node.borrow_mut();
+            n.parent = None;
+            n.previous_sibling = None;
+            n.next_sibling = None;
+        }
+        if let Some(ref document) = document {
+            Self::invalidate_removal(document, &parent, next_sibling.as_ref());
+        }
+
+        if !suppress_observers {
+            queue_tree_mutation_record(
+                &parent,
+                Vec::new(),
+                vec![node.clone()],
+                previous_sibling,
+                next_sibling,
+            );
+        }
+    }
+
+    fn child_nodes(parent: &Rc<RefCell<Node>>) -> Vec<Rc<RefCell<Node>>> {
+        Self::children(parent).collect()
+    }
+
+    fn index_of(children: &[Rc<RefCell<Node>>], child: &Rc<RefCell<Node>>) -> usize {
+        children
+            .iter()
+            .position(|c| Rc::ptr_eq(c, child))
+            .expect("child must be in the list")
+    }
+
+    // https://dom.spec.whatwg.org/#dom-node-nodetype
+    pub fn node_type(&self) -> u32 {
+        match self.kind {
+            NodeKind::Element(_) => 1,
+            NodeKind::Text(_) => 3,
+            NodeKind::Comment(_) => 8,
+            NodeKind::Document(_) => 9,
+            NodeKind::DocumentType(_) => 10,
+            NodeKind::DocumentFragment => 11,
         }
+    }
+
+    pub fn is_document(&self) -> bool {
+        matches!(self.kind, NodeKind::Document(_))
+    }
+
+    pub fn is_element(&self) -> bool {
+        matches!(self.kind, NodeKind::Element(_))
+    }
 
-        self.last_child = Some(Rc::downgrade(&child_node));
-        child_node.borrow_mut().parent =
-            Some(Rc::downgrade(&Rc::new(RefCell::new(self.to_owned()))));
+    pub fn is_document_fragment(&self) -> bool {
+        matches!(self.kind, NodeKind::DocumentFragment)
+    }
+
+    pub fn is_doctype(&self) -> bool {
+        matches!(self.kind, NodeKind::DocumentType(_))
     }
 }
 
 #[derive(Clone, Debug)]
 pub enum NodeKind {
-    Document,
+    Document(Document),
+    DocumentFragment,
+    DocumentType(String),
     Element(Element),
     Text(String),
+    Comment(String),
 }
 
 impl PartialEq for NodeKind {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
-            (NodeKind::Document, NodeKind::Document) => true,
+            (NodeKind::Document(_), NodeKind::Document(_)) => true,
+            (NodeKind::DocumentFragment, NodeKind::DocumentFragment) => true,
+            (NodeKind::DocumentType(n1), NodeKind::DocumentType(n2)) => n1 == n2,
             (NodeKind::Element(e1), NodeKind::Element(e2)) => e1.kind() == e2.kind(),
             (NodeKind::Text(s1), NodeKind::Text(s2)) => s1 == s2,
+            (NodeKind::Comment(s1), NodeKind::Comment(s2)) => s1 == s2,
             _ => false,
         }
     }
@@ -88,22 +607,315 @@ impl PartialEq for NodeKind {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::element::HtmlElementKind;
+
+    fn new_node(kind: NodeKind) -> Rc<RefCell<Node>> {
+        Rc::new(RefCell::new(Node::new(kind)))
+    }
+
+    fn text(s: &str) -> Rc<RefCell<Node>> {
+        new_node(NodeKind::Text(String::from(s)))
+    }
+
+    fn element(kind: HtmlElementKind) -> Rc<RefCell<Node>> {
+        new_node(NodeKind::Element(Element::new(kind)))
+    }
+
+    // collects children front to back and checks the back links agree
+    fn children_of(parent: &Rc<RefCell<Node>>) -> Vec<NodeKind> {
+        let mut forward = Vec::new();
+        let mut child = parent.borrow().first_child();
+        while let Some(c) = child {
+            assert!(Rc::ptr_eq(&c.borrow().parent().unwrap(), parent));
+            forward.push(c.borrow().kind());
+            child = c.borrow().next_sibling();
+        }
+
+        let mut backward = Vec::new();
+        let mut child = parent.borrow().last_child();
+        while let Some(c) = child {
+            backward.push(c.borrow().kind());
+            child = c.borrow().previous_sibling();
+        }
+        backward.reverse();
+
+        assert_eq!(forward, backward);
+        forward
+    }
+
+    fn texts(list: &[&str]) -> Vec<NodeKind> {
+        list.iter().map(|s| NodeKind::Text(s.to_string())).collect()
+    }
 
     #[test]
     fn test_append_child_node() {
-        let mut node = Node::new(NodeKind::Document);
-        let child_node = Rc::new(RefCell::new(Node::new(NodeKind::Text(String::from(
-            "hello",
-        )))));
-        node.append_child_node(&child_node);
+        let node = element(HtmlElementKind::Div);
+        let child_node = text("hello");
+        Node::append_child_node(&node, &child_node).unwrap();
 
         assert_eq!(
-            node.first_child().unwrap().borrow().kind(),
+            node.borrow().first_child().unwrap().borrow().kind(),
             NodeKind::Text(String::from("hello"))
         );
         assert_eq!(
-            node.last_child().unwrap().borrow().kind(),
+            node.borrow().last_child().unwrap().borrow().kind(),
             NodeKind::Text(String::from("hello"))
         );
+        assert!(Rc::ptr_eq(&child_node.borrow().parent().unwrap(), &node));
+    }
+
+    #[test]
+    fn test_append_many_children() {
+        let parent = element(HtmlElementKind::Div);
+        for s in ["a", "b", "c", "d", "e"] {
+            Node::append_child_node(&parent, &text(s)).unwrap();
+        }
+
+        assert_eq!(children_of(&parent), texts(&["a", "b", "c", "d", "e"]));
+    }
+
+    #[test]
+    fn test_insert_before() {
+        let parent = element(HtmlElementKind::Div);
+        let a = text("a");
+        let c = text("c");
+        Node::append_child_node(&parent, &a).unwrap();
+        Node::append_child_node(&parent, &c).unwrap();
+
+        Node::insert_before(&parent, &text("b"), Some(&c)).unwrap();
+        Node::insert_before(&parent, &text("first"), Some(&a)).unwrap();
+        Node::insert_before(&parent, &text("last"), None).unwrap();
+        // inserting a node before itself leaves it in place
+        Node::insert_before(&parent, &c, Some(&c)).unwrap();
+
+        assert_eq!(
+            children_of(&parent),
+            texts(&["first", "a", "b", "c", "last"])
+        );
+    }
+
+    #[test]
+    fn test_prepend() {
+        let parent = element(HtmlElementKind::Div);
+        Node::prepend(&parent, &text("c")).unwrap();
+        Node::prepend(&parent, &text("b")).unwrap();
+        Node::prepend(&parent, &text("a")).unwrap();
+
+        assert_eq!(children_of(&parent), texts(&["a", "b", "c"]));
+    }
+
+    #[test]
+    fn test_remove_child() {
+        let parent = element(HtmlElementKind::Div);
+        let nodes: Vec<_> = ["a", "b", "c", "d"].iter().map(|s| text(s)).collect();
+        for n in &nodes {
+            Node::append_child_node(&parent, n).unwrap();
+        }
+
+        Node::remove_child(&parent, &nodes[1]).unwrap();
+        assert_eq!(children_of(&parent), texts(&["a", "c", "d"]));
+        Node::remove_child(&parent, &nodes[0]).unwrap();
+        assert_eq!(children_of(&parent), texts(&["c", "d"]));
+        Node::remove_child(&parent, &nodes[3]).unwrap();
+        assert_eq!(children_of(&parent), texts(&["c"]));
+
+        assert!(nodes[1].borrow().parent().is_none());
+        assert!(nodes[1].borrow().next_sibling().is_none());
+        assert_eq!(
+            Node::remove_child(&parent, &nodes[1]).err(),
+            Some(DomException::NotFoundError)
+        );
+    }
+
+    #[test]
+    fn test_replace_child() {
+        let parent = element(HtmlElementKind::Div);
+        let nodes: Vec<_> = ["a", "b", "c"].iter().map(|s| text(s)).collect();
+        for n in &nodes {
+            Node::append_child_node(&parent, n).unwrap();
+        }
+
+        let old = Node::replace_child(&parent, &text("x"), &nodes[1]).unwrap();
+        assert!(Rc::ptr_eq(&old, &nodes[1]));
+        assert_eq!(children_of(&parent), texts(&["a", "x", "c"]));
+
+        // replacing with a sibling moves it into place
+        Node::replace_child(&parent, &nodes[2], &nodes[0]).unwrap();
+        assert_eq!(children_of(&parent), texts(&["c", "x"]));
+    }
+
+    #[test]
+    fn test_detach_moves_between_parents() {
+        let first = element(HtmlElementKind::Div);
+        let second = element(HtmlElementKind::Span);
+        let child = text("a");
+        Node::append_child_node(&first, &text("x")).unwrap();
+        Node::append_child_node(&first, &child).unwrap();
+        Node::append_child_node(&first, &text("y")).unwrap();
+
+        Node::append_child_node(&second, &child).unwrap();
+        assert_eq!(children_of(&first), texts(&["x", "y"]));
+        assert_eq!(children_of(&second), texts(&["a"]));
+
+        Node::detach(&child);
+        assert_eq!(children_of(&second), texts(&[]));
+        assert!(child.borrow().parent().is_none());
+    }
+
+    #[test]
+    fn test_clone_node() {
+        let parent = element(HtmlElementKind::Div);
+        let span = element(HtmlElementKind::Span);
+        Node::append_child_node(&parent, &span).unwrap();
+        Node::append_child_node(&span, &text("a")).unwrap();
+        Node::append_child_node(&parent, &text("b")).unwrap();
+
+        let shallow = Node::clone_node(&parent, false);
+        assert!(!shallow.borrow().has_child_nodes());
+
+        let deep = Node::clone_node(&parent, true);
+        assert_eq!(
+            children_of(&deep),
+            vec![
+                NodeKind::Element(Element::new(HtmlElementKind::Span)),
+                NodeKind::Text(String::from("b"))
+            ]
+        );
+        let span_copy = deep.borrow().first_child().unwrap();
+        assert!(!Rc::ptr_eq(&span_copy, &span));
+        assert_eq!(children_of(&span_copy), texts(&["a"]));
+    }
+
+    #[test]
+    fn test_adopt() {
+        let document = new_node(NodeKind::Document(Document::new()));
+        let other = new_node(NodeKind::Document(Document::new()));
+        let html = element(HtmlElementKind::Html);
+        let body = element(HtmlElementKind::Body);
+        Node::append_child_node(&document, &html).unwrap();
+        Node::append_child_node(&html, &body).unwrap();
+        assert!(Rc::ptr_eq(&Node::node_document(&body).unwrap(), &document));
+
+        Node::adopt(&other, &html).unwrap();
+        assert!(document.borrow().first_child().is_none());
+        assert!(Rc::ptr_eq(&Node::node_document(&body).unwrap(), &other));
+        assert_eq!(
+            Node::adopt(&other, &document).err(),
+            Some(DomException::NotSupportedError)
+        );
+    }
+
+    #[test]
+    fn test_pre_insertion_validity() {
+        let document = new_node(NodeKind::Document(Document::new()));
+        let html = element(HtmlElementKind::Html);
+        let body = element(HtmlElementKind::Body);
+        Node::append_child_node(&document, &html).unwrap();
+        Node::append_child_node(&html, &body).unwrap();
+
+        // a node can't be inserted into its own subtree
+        assert_eq!(
+            Node::append_child_node(&body, &html).err(),
+            Some(DomException::HierarchyRequestError)
+        );
+        // text can't be a child of a document or a parent itself
+        assert_eq!(
+            Node::append_child_node(&document, &text("a")).err(),
+            Some(DomException::HierarchyRequestError)
+        );
+        assert_eq!(
+            Node::append_child_node(&text("a"), &text("b")).err(),
+            Some(DomException::HierarchyRequestError)
+        );
+        // only one document element
+        assert_eq!(
+            Node::append_child_node(&document, &element(HtmlElementKind::Div)).err(),
+            Some(DomException::HierarchyRequestError)
+        );
+        // the reference child must belong to the parent
+        assert_eq!(
+            Node::insert_before(&html, &text("a"), Some(&html)).err(),
+            Some(DomException::NotFoundError)
+        );
+
+        // a doctype goes before the document element, and only once
+        let doctype = new_node(NodeKind::DocumentType(String::from("html")));
+        assert_eq!(
+            Node::append_child_node(&document, &doctype).err(),
+            Some(DomException::HierarchyRequestError)
+        );
+        Node::insert_before(&document, &doctype, Some(&html)).unwrap();
+        assert_eq!(
+            Node::prepend(
+                &document,
+                &new_node(NodeKind::DocumentType(String::from("html")))
+            )
+            .err(),
+            Some(DomException::HierarchyRequestError)
+        );
+        assert_eq!(
+            Node::append_child_node(&html, &new_node(NodeKind::DocumentType(String::new()))).err(),
+            Some(DomException::HierarchyRequestError)
+        );
+
+        // replacing the document element with another element is fine
+        let new_html = element(HtmlElementKind::Html);
+        Node::replace_child(&document, &new_html, &html).unwrap();
+        assert!(Rc::ptr_eq(
+            &document.borrow().last_child().unwrap(),
+            &new_html
+        ));
+    }
+
+    #[test]
+    fn test_insert_document_fragment() {
+        let div = element(HtmlElementKind::Div);
+        Node::append_child_node(&div, &text("c")).unwrap();
+
+        let fragment = new_node(NodeKind::DocumentFragment);
+        Node::append_child_node(&fragment, &text("a")).unwrap();
+        Node::append_child_node(&fragment, &text("b")).unwrap();
+
+        let first = div.borrow().first_child().unwrap();
+        Node::insert_before(&div, &fragment, Some(&first)).unwrap();
+        assert_eq!(children_of(&div), texts(&["a", "b", "c"]));
+        assert!(!fragment.borrow().has_child_nodes());
+
+        // an empty fragment inserts nothing
+        Node::append_child_node(&div, &fragment).unwrap();
+        assert_eq!(children_of(&div), texts(&["a", "b", "c"]));
+    }
+
+    #[test]
+    fn test_document_fragment_validity() {
+        let document = new_node(NodeKind::Document(Document::new()));
+
+        let with_text = new_node(NodeKind::DocumentFragment);
+        Node::append_child_node(&with_text, &text("a")).unwrap();
+        assert_eq!(
+            Node::append_child_node(&document, &with_text).err(),
+            Some(DomException::HierarchyRequestError)
+        );
+
+        let two_elements = new_node(NodeKind::DocumentFragment);
+        Node::append_child_node(&two_elements, &element(HtmlElementKind::Html)).unwrap();
+        Node::append_child_node(&two_elements, &element(HtmlElementKind::Html)).unwrap();
+        assert_eq!(
+            Node::append_child_node(&document, &two_elements).err(),
+            Some(DomException::HierarchyRequestError)
+        );
+
+        let one_element = new_node(NodeKind::DocumentFragment);
+        let html = element(HtmlElementKind::Html);
+        Node::append_child_node(&one_element, &html).unwrap();
+        Node::append_child_node(&document, &one_element).unwrap();
+        assert!(Rc::ptr_eq(&document.borrow().first_child().unwrap(), &html));
+
+        // the document already has an element
+        Node::append_child_node(&one_element, &element(HtmlElementKind::Html)).unwrap();
+        assert_eq!(
+            Node::append_child_node(&document, &one_element).err(),
+            Some(DomException::HierarchyRequestError)
+        );
     }
 }
@@ -0,0 +1,143 @@
+mod parser;
+mod tokenizer;
+
+pub use parser::{
+    consume_declaration_list, consume_rule_list, parse_component_values, parse_declaration_list,
+    parse_stylesheet,
+};
+pub use tokenizer::{serialize_identifier, serialize_string, CssToken, CssTokenizer};
+
+use crate::selector::SelectorList;
+use crate::style::collapse_shorthands;
+use std::fmt;
+
+// https://drafts.csswg.org/css-syntax-3/#component-value
+#[derive(Clone, Debug, PartialEq)]
+pub enum ComponentValue {
+    Token(CssToken),
+    // the function name and its arguments, without the closing paren
+    Function(String, Vec<ComponentValue>),
+    // a `(`, `[` or `{` block, keyed by its opening character
+    Block(char, Vec<ComponentValue>),
+}
+
+// https://drafts.csswg.org/cssom/#css-style-sheets
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StyleSheet {
+    pub rules: Vec<CssRule>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CssRule {
+    Style(StyleRule),
+    At(AtRule),
+}
+
+// https://drafts.csswg.org/cssom/#the-cssstylerule-interface
+#[derive(Clone, Debug, PartialEq)]
+pub struct StyleRule {
+    pub selector_text: String,
+    pub selectors: SelectorList,
+    pub declarations: Vec<Declaration>,
+}
+
+// https://drafts.csswg.org/css-syntax-3/#at-rule
+//
+// Kept unparsed; the block holds rules or declarations depending on the
+// at-rule and is read with `consume_rule_list` or `consume_declaration_list`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AtRule {
+    pub name: String,
+    pub prelude: Vec<ComponentValue>,
+    pub block: Option<Vec<ComponentValue>>,
+}
+
+// https://drafts.csswg.org/css-syntax-3/#declaration
+#[derive(Clone, Debug, PartialEq)]
+pub struct Declaration {
+    // lowercased, except for custom properties
+    pub name: String,
+    pub value: Vec<ComponentValue>,
+    pub important: bool,
+}
+
+impl ComponentValue {
+    pub fn is_whitespace(&self) -> bool {
+        matches!(self, ComponentValue::Token(CssToken::Whitespace))
+    }
+}
+
+impl Declaration {
+    pub fn value_text(&self) -> String {
+        serialize_values(&self.value)
+    }
+}
+
+pub fn serialize_values(values: &[ComponentValue]) -> String {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+impl fmt::Display for ComponentValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ComponentValue::Token(token) => write!(f, "{}", token),
+            ComponentValue::Function(name, arguments) => write!(
+                f,
+                "{}({})",
+                serialize_identifier(name),
+                serialize_values(arguments)
+            ),
+            ComponentValue::Block(open, values) => {
+                let close = match open {
+                    '(' => ')',
+                    '[' => ']',
+                    _ => '}',
+                };
+                write!(f, "{}{}{}", open, serialize_values(values), close)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Declaration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.value_text())?;
+        if self.important {
+            write!(f, " !important")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for CssRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CssRule::Style(rule) => {
+                write!(f, "{} {{", rule.selector_text)?;
+                for declaration in collapse_shorthands(&rule.declarations) {
+                    write!(f, " {};", declaration)?;
+                }
+                write!(f, " }}")
+            }
+            CssRule::At(rule) => {
+                write!(f, "@{}", serialize_identifier(&rule.name))?;
+                if !rule.prelude.is_empty() {
+                    write!(f, " {}", serialize_values(&rule.prelude))?;
+                }
+                match rule.block {
+                    Some(ref block) => write!(f, " {{{}}}", serialize_values(block)),
+                    None => write!(f, ";"),
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for StyleSheet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for rule in self.rules.iter() {
+            writeln!(f, "{}", rule)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,337 @@
+use super::*;
+use crate::selector::parse_selector_list;
+use crate::style::expand_declaration;
+
+// https://drafts.csswg.org/css-syntax-3/#parse-a-stylesheet
+pub fn parse_stylesheet(css: &str) -> StyleSheet {
+    StyleSheet {
+        rules: consume_rules(&parse_component_values(css), true),
+    }
+}
+
+// https://drafts.csswg.org/css-syntax-3/#parse-a-list-of-declarations
+//
+// For `style` attributes.
+pub fn parse_declaration_list(css: &str) -> Vec<Declaration> {
+    consume_declaration_list(&parse_component_values(css))
+}
+
+// https://drafts.csswg.org/css-syntax-3/#parse-a-list-of-component-values
+pub fn parse_component_values(css: &str) -> Vec<ComponentValue> {
+    let mut parser = CssParser::new(css);
+    let mut values = Vec::new();
+    while let Some(value) = parser.consume_component_value() {
+        values.push(value);
+    }
+    values
+}
+
+// https://drafts.csswg.org/css-syntax-3/#consume-list-of-rules
+//
+// For the block of a grouping at-rule such as `@media`.
+pub fn consume_rule_list(values: &[ComponentValue]) -> Vec<CssRule> {
+    consume_rules(values, false)
+}
+
+// https://drafts.csswg.org/css-syntax-3/#consume-list-of-declarations
+//
+// Declarations that fail to parse are skipped up to the next `;`, and
+// shorthands are expanded into their longhands.
+pub fn consume_declaration_list(values: &[ComponentValue]) -> Vec<Declaration> {
+    let mut declarations = Vec::new();
+    let mut i = 0;
+    while i < values.len() {
+        match values[i] {
+            ComponentValue::Token(CssToken::Whitespace | CssToken::Semicolon) => i += 1,
+            // no at-rules are allowed in declaration blocks yet
+            ComponentValue::Token(CssToken::AtKeyword(_)) => {
+                consume_at_rule(values, &mut i);
+            }
+            ComponentValue::Token(CssToken::Ident(_)) => {
+                let start = i;
+                skip_to_semicolon(values, &mut i);
+                if let Some(declaration) = consume_declaration(&values[start..i]) {
+                    declarations.extend(expand_declaration(declaration));
+                }
+            }
+            _ => skip_to_semicolon(values, &mut i),
+        }
+    }
+    declarations
+}
+
+fn skip_to_semicolon(values: &[ComponentValue], i: &mut usize) {
+    while *i < values.len() && values[*i] != ComponentValue::Token(CssToken::Semicolon) {
+        *i += 1;
+    }
+}
+
+fn consume_rules(values: &[ComponentValue], top_level: bool) -> Vec<CssRule> {
+    let mut rules = Vec::new();
+    let mut i = 0;
+    while i < values.len() {
+        match values[i] {
+            ComponentValue::Token(CssToken::Whitespace) => i += 1,
+            ComponentValue::Token(CssToken::Cdo | CssToken::Cdc) if top_level => i += 1,
+            ComponentValue::Token(CssToken::AtKeyword(_)) => {
+                rules.push(CssRule::At(consume_at_rule(values, &mut i)));
+            }
+            _ => {
+                if let Some(rule) = consume_qualified_rule(values, &mut i) {
+                    rules.push(CssRule::Style(rule));
+                }
+            }
+        }
+    }
+    rules
+}
+
+// https://drafts.csswg.org/css-syntax-3/#consume-at-rule
+fn consume_at_rule(values: &[ComponentValue], i: &mut usize) -> AtRule {
+    let name = match values[*i] {
+        ComponentValue::Token(CssToken::AtKeyword(ref name)) => name.to_ascii_lowercase(),
+        _ => unreachable!("not an at-rule"),
+    };
+    *i += 1;
+
+    let mut prelude = Vec::new();
+    let mut block = None;
+    while let Some(value) = values.get(*i) {
+        *i += 1;
+        match value {
+            ComponentValue::Token(CssToken::Semicolon) => break,
+            ComponentValue::Block('{', contents) => {
+                block = Some(contents.clone());
+                break;
+            }
+            value => prelude.push(value.clone()),
+        }
+    }
+
+    AtRule {
+        name,
+        prelude: trim_whitespace(&prelude).to_vec(),
+        block,
+    }
+}
+
+// https://drafts.csswg.org/css-syntax-3/#consume-qualified-rule
+//
+// Rules without a block or whose selector doesn't parse are dropped.
+fn consume_qualified_rule(values: &[ComponentValue], i: &mut usize) -> Option<StyleRule> {
+    let start = *i;
+    while let Some(value) = values.get(*i) {
+        *i += 1;
+        if let ComponentValue::Block('{', contents) = value {
+            let selector_text = serialize_values(trim_whitespace(&values[start..*i - 1]));
+            let selectors = parse_selector_list(&selector_text).ok()?;
+            return Some(StyleRule {
+                selector_text,
+                selectors,
+                declarations: consume_declaration_list(contents),
+            });
+        }
+    }
+    None
+}
+
+// https://drafts.csswg.org/css-syntax-3/#consume-declaration
+fn consume_declaration(values: &[ComponentValue]) -> Option<Declaration> {
+    let name = match values.first() {
+        Some(ComponentValue::Token(CssToken::Ident(name))) if name.starts_with("--") => {
+            name.clone()
+        }
+        Some(ComponentValue::Token(CssToken::Ident(name))) => name.to_ascii_lowercase(),
+        _ => return None,
+    };
+
+    let rest = trim_whitespace(&values[1..]);
+    if rest.first() != Some(&ComponentValue::Token(CssToken::Colon)) {
+        return None;
+    }
+    let mut value = trim_whitespace(&rest[1..]);
+
+    // `!` and `important` may be separated by whitespace
+    let mut important = false;
+    let mut significant = value.iter().enumerate().filter(|(_, v)| !v.is_whitespace());
+    if let (Some((_, ComponentValue::Token(CssToken::Ident(last)))), Some((bang, before))) =
+        (significant.next_back(), significant.next_back())
+    {
+        if *before == ComponentValue::Token(CssToken::Delim('!'))
+            && last.eq_ignore_ascii_case("important")
+        {
+            important = true;
+            value = trim_whitespace(&value[..bang]);
+        }
+    }
+
+    // only custom properties may be empty
+    if value.is_empty() && !name.starts_with("--") {
+        return None;
+    }
+    Some(Declaration {
+        name,
+        value: value.to_vec(),
+        important,
+    })
+}
+
+fn trim_whitespace(values: &[ComponentValue]) -> &[ComponentValue] {
+    let start = values
+        .iter()
+        .position(|v| !v.is_whitespace())
+        .unwrap_or(values.len());
+    let end = values
+        .iter()
+        .rposition(|v| !v.is_whitespace())
+        .map_or(start, |e| e + 1);
+    &values[start..end]
+}
+
+struct CssParser {
+    tokens: Vec<CssToken>,
+    pos: usize,
+}
+
+impl CssParser {
+    fn new(css: &str) -> Self {
+        Self {
+            tokens: CssTokenizer::new(String::from(css)).collect(),
+            pos: 0,
+        }
+    }
+
+    fn next_token(&mut self) -> CssToken {
+        let token = self.tokens.get(self.pos).cloned().unwrap_or(CssToken::Eof);
+        self.pos += 1;
+        token
+    }
+
+    // https://drafts.csswg.org/css-syntax-3/#consume-component-value
+    fn consume_component_value(&mut self) -> Option<ComponentValue> {
+        let value = match self.next_token() {
+            CssToken::Eof => return None,
+            CssToken::OpenCurly => {
+                ComponentValue::Block('{', self.consume_until(CssToken::CloseCurly))
+            }
+            CssToken::OpenSquare => {
+                ComponentValue::Block('[', self.consume_until(CssToken::CloseSquare))
+            }
+            CssToken::OpenParen => {
+                ComponentValue::Block('(', self.consume_until(CssToken::CloseParen))
+            }
+            CssToken::Function(name) => {
+                ComponentValue::Function(name, self.consume_until(CssToken::CloseParen))
+            }
+            token => ComponentValue::Token(token),
+        };
+        Some(value)
+    }
+
+    // https://drafts.csswg.org/css-syntax-3/#consume-simple-block
+    //
+    // The end of the input closes any open blocks.
+    fn consume_until(&mut self, close: CssToken) -> Vec<ComponentValue> {
+        let mut values = Vec::new();
+        loop {
+            match self.tokens.get(self.pos) {
+                None | Some(CssToken::Eof) => return values,
+                Some(token) if *token == close => {
+                    self.pos += 1;
+                    return values;
+                }
+                _ => values.extend(self.consume_component_value()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn declarations(css: &str) -> Vec<String> {
+        parse_declaration_list(css)
+            .iter()
+            .map(|d| d.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_stylesheet() {
+        let sheet = parse_stylesheet(
+            "<!-- p.a, div > span { color: red; margin : 0 auto } -->\n\
+             @import url(a.css);\n\
+             @media (min-width: 100px) { h1 { font-size: 2em } }",
+        );
+        assert_eq!(
+            sheet.to_string(),
+            "p.a, div > span { color: red; margin: 0 auto; }\n\
+             @import url(\"a.css\");\n\
+             @media (min-width: 100px) { h1 { font-size: 2em } }\n"
+        );
+
+        let media = match sheet.rules[2] {
+            CssRule::At(ref rule) => rule,
+            _ => panic!("expected an at-rule"),
+        };
+        assert_eq!(media.name, "media");
+        let nested = consume_rule_list(media.block.as_ref().unwrap());
+        assert_eq!(nested.len(), 1);
+        match nested[0] {
+            CssRule::Style(ref rule) => assert_eq!(rule.declarations[0].name, "font-size"),
+            _ => panic!("expected a style rule"),
+        }
+    }
+
+    #[test]
+    fn test_rule_error_recovery() {
+        let sheet = parse_stylesheet(
+            "p!! { color: red } \
+             div { color: blue } \
+             } span { color: green } \
+             h1 { color: black",
+        );
+        // the bad selector and the stray `}` each drop a rule; the unclosed
+        // block is closed by the end of the input
+        assert_eq!(
+            sheet.to_string(),
+            "div { color: blue; }\nh1 { color: black; }\n"
+        );
+    }
+
+    #[test]
+    fn test_declaration_error_recovery() {
+        assert_eq!(
+            declarations(
+                "color red; ;; 12px: x; margin: ; width: calc(1px + 2%); \
+                 @foo bar; --block: 1px { ; } 2px; --empty:; COLOR: Blue"
+            ),
+            vec![
+                "width: calc(1px + 2%)",
+                "--block: 1px { ; } 2px",
+                "--empty: ",
+                "color: Blue",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_important() {
+        let list =
+            parse_declaration_list("color: red !important; margin: 0 ! IMPORTANT; a: !important");
+        assert_eq!(
+            list.iter()
+                .map(|d| (d.name.as_str(), d.value_text(), d.important))
+                .collect::<Vec<_>>(),
+            vec![
+                ("color", String::from("red"), true),
+                // shorthands expand into their longhands
+                ("margin-top", String::from("0"), true),
+                ("margin-right", String::from("0"), true),
+                ("margin-bottom", String::from("0"), true),
+                ("margin-left", String::from("0"), true),
+            ]
+        );
+    }
+}
@@ -0,0 +1,733 @@
+use std::fmt;
+
+// https://drafts.csswg.org/css-syntax-3/#tokenization
+#[derive(Clone, Debug, PartialEq)]
+pub enum CssToken {
+    Ident(String),
+    Function(String),
+    AtKeyword(String),
+    // `id` is set when the name would also be a valid identifier
+    Hash {
+        value: String,
+        id: bool,
+    },
+    String(String),
+    BadString,
+    Url(String),
+    BadUrl,
+    Delim(char),
+    Number {
+        value: f64,
+        integer: bool,
+    },
+    Percentage(f64),
+    Dimension {
+        value: f64,
+        integer: bool,
+        unit: String,
+    },
+    Whitespace,
+    Cdo,
+    Cdc,
+    Colon,
+    Semicolon,
+    Comma,
+    OpenSquare,
+    CloseSquare,
+    OpenParen,
+    CloseParen,
+    OpenCurly,
+    CloseCurly,
+    Eof,
+}
+
+pub struct CssTokenizer {
+    input: Vec<char>,
+    pos: usize,
+    done: bool,
+}
+
+impl CssTokenizer {
+    pub fn new(css: String) -> Self {
+        Self {
+            input: preprocess(&css),
+            pos: 0,
+            done: false,
+        }
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.input.get(self.pos + offset).copied()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.peek_at(0)
+    }
+
+    fn consume(&mut self) -> Option<char> {
+        let c = self.peek();
+        if c.is_some() {
+            self.pos += 1;
+        }
+        c
+    }
+
+    fn consume_if(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    // https://drafts.csswg.org/css-syntax-3/#consume-comments
+    fn consume_comments(&mut self) {
+        while self.peek() == Some('/') && self.peek_at(1) == Some('*') {
+            self.pos += 2;
+            loop {
+                match self.consume() {
+                    Some('*') if self.peek() == Some('/') => {
+                        self.pos += 1;
+                        break;
+                    }
+                    Some(_) => {}
+                    None => return,
+                }
+            }
+        }
+    }
+
+    // https://drafts.csswg.org/css-syntax-3/#consume-token
+    fn consume_token(&mut self) -> CssToken {
+        self.consume_comments();
+
+        let c = match self.consume() {
+            Some(c) => c,
+            None => return CssToken::Eof,
+        };
+        match c {
+            c if is_whitespace(c) => {
+                while self.peek().is_some_and(is_whitespace) {
+                    self.pos += 1;
+                }
+                CssToken::Whitespace
+            }
+            '"' | '\'' => self.consume_string(c),
+            '#' => {
+                if self.peek().is_some_and(is_name) || self.starts_escape(0) {
+                    let id = self.starts_ident(0);
+                    CssToken::Hash {
+                        value: self.consume_name(),
+                        id,
+                    }
+                } else {
+                    CssToken::Delim('#')
+                }
+            }
+            '(' => CssToken::OpenParen,
+            ')' => CssToken::CloseParen,
+            '[' => CssToken::OpenSquare,
+            ']' => CssToken::CloseSquare,
+            '{' => CssToken::OpenCurly,
+            '}' => CssToken::CloseCurly,
+            ',' => CssToken::Comma,
+            ':' => CssToken::Colon,
+            ';' => CssToken::Semicolon,
+            '+' | '.' if self.starts_number(-1) => {
+                self.pos -= 1;
+                self.consume_numeric()
+            }
+            '-' if self.starts_number(-1) => {
+                self.pos -= 1;
+                self.consume_numeric()
+            }
+            '-' if self.peek() == Some('-') && self.peek_at(1) == Some('>') => {
+                self.pos += 2;
+                CssToken::Cdc
+            }
+            '-' if self.starts_ident(-1) => {
+                self.pos -= 1;
+                self.consume_ident_like()
+            }
+            '<' if self.peek() == Some('!')
+                && self.peek_at(1) == Some('-')
+                && self.peek_at(2) == Some('-') =>
+            {
+                self.pos += 3;
+                CssToken::Cdo
+            }
+            '@' if self.starts_ident(0) => CssToken::AtKeyword(self.consume_name()),
+            '\\' if self.starts_escape(-1) => {
+                self.pos -= 1;
+                self.consume_ident_like()
+            }
+            c if c.is_ascii_digit() => {
+                self.pos -= 1;
+                self.consume_numeric()
+            }
+            c if is_name_start(c) => {
+                self.pos -= 1;
+                self.consume_ident_like()
+            }
+            c => CssToken::Delim(c),
+        }
+    }
+
+    // the characters at `offset` from the current position, as needed by
+    // the "check if ... would start" algorithms
+    fn chars_at(&self, offset: isize) -> [Option<char>; 3] {
+        let at = |i: isize| {
+            let pos = self.pos as isize + offset + i;
+            if pos < 0 {
+                None
+            } else {
+                self.input.get(pos as usize).copied()
+            }
+        };
+        [at(0), at(1), at(2)]
+    }
+
+    fn starts_escape(&self, offset: isize) -> bool {
+        let [c1, c2, _] = self.chars_at(offset);
+        is_valid_escape(c1, c2)
+    }
+
+    fn starts_ident(&self, offset: isize) -> bool {
+        let [c1, c2, c3] = self.chars_at(offset);
+        would_start_ident(c1, c2, c3)
+    }
+
+    // https://drafts.csswg.org/css-syntax-3/#starts-with-a-number
+    fn starts_number(&self, offset: isize) -> bool {
+        let [c1, c2, c3] = self.chars_at(offset);
+        match c1 {
+            Some('+' | '-') => {
+                c2.is_some_and(|c| c.is_ascii_digit())
+                    || (c2 == Some('.') && c3.is_some_and(|c| c.is_ascii_digit()))
+            }
+            Some('.') => c2.is_some_and(|c| c.is_ascii_digit()),
+            Some(c) => c.is_ascii_digit(),
+            None => false,
+        }
+    }
+
+    // https://drafts.csswg.org/css-syntax-3/#consume-string-token
+    fn consume_string(&mut self, ending: char) -> CssToken {
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                None => return CssToken::String(value),
+                Some(c) if c == ending => {
+                    self.pos += 1;
+                    return CssToken::String(value);
+                }
+                // left for the next token
+                Some('\n') => return CssToken::BadString,
+                Some('\\') => match self.peek_at(1) {
+                    None => self.pos += 1,
+                    Some('\n') => self.pos += 2,
+                    Some(_) => {
+                        self.pos += 1;
+                        value.push(self.consume_escape());
+                    }
+                },
+                Some(c) => {
+                    self.pos += 1;
+                    value.push(c);
+                }
+            }
+        }
+    }
+
+    // https://drafts.csswg.org/css-syntax-3/#consume-escaped-code-point
+    //
+    // Called after the backslash.
+    fn consume_escape(&mut self) -> char {
+        let c = match self.consume() {
+            Some(c) => c,
+            None => return '\u{fffd}',
+        };
+        if !c.is_ascii_hexdigit() {
+            return c;
+        }
+
+        let mut hex = String::from(c);
+        while hex.len() < 6 && self.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+            hex.push(self.consume().unwrap());
+        }
+        if self.peek().is_some_and(is_whitespace) {
+            self.pos += 1;
+        }
+        match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+            Some(c) if c != '\0' => c,
+            _ => '\u{fffd}',
+        }
+    }
+
+    // https://drafts.csswg.org/css-syntax-3/#consume-name
+    fn consume_name(&mut self) -> String {
+        let mut name = String::new();
+        loop {
+            match self.peek() {
+                Some(c) if is_name(c) => {
+                    self.pos += 1;
+                    name.push(c);
+                }
+                Some('\\') if self.starts_escape(0) => {
+                    self.pos += 1;
+                    name.push(self.consume_escape());
+                }
+                _ => return name,
+            }
+        }
+    }
+
+    // https://drafts.csswg.org/css-syntax-3/#consume-number
+    fn consume_number(&mut self) -> (f64, bool) {
+        let mut repr = String::new();
+        let mut integer = true;
+        let digits = |t: &mut Self, repr: &mut String| {
+            while let Some(c) = t.peek().filter(|c| c.is_ascii_digit()) {
+                t.pos += 1;
+                repr.push(c);
+            }
+        };
+
+        if let Some(c @ ('+' | '-')) = self.peek() {
+            self.pos += 1;
+            repr.push(c);
+        }
+        digits(self, &mut repr);
+        if self.peek() == Some('.') && self.peek_at(1).is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+            repr.push('.');
+            digits(self, &mut repr);
+            integer = false;
+        }
+        if let Some(e @ ('e' | 'E')) = self.peek() {
+            let sign = matches!(self.peek_at(1), Some('+' | '-'));
+            let digit_at = if sign { 2 } else { 1 };
+            if self.peek_at(digit_at).is_some_and(|c| c.is_ascii_digit()) {
+                repr.push(e);
+                self.pos += 1;
+                if sign {
+                    repr.push(self.consume().unwrap());
+                }
+                digits(self, &mut repr);
+                integer = false;
+            }
+        }
+
+        (repr.parse().unwrap_or(0.0), integer)
+    }
+
+    // https://drafts.csswg.org/css-syntax-3/#consume-numeric-token
+    fn consume_numeric(&mut self) -> CssToken {
+        let (value, integer) = self.consume_number();
+        if self.starts_ident(0) {
+            CssToken::Dimension {
+                value,
+                integer,
+                unit: self.consume_name(),
+            }
+        } else if self.peek() == Some('%') {
+            self.pos += 1;
+            CssToken::Percentage(value)
+        } else {
+            CssToken::Number { value, integer }
+        }
+    }
+
+    // https://drafts.csswg.org/css-syntax-3/#consume-ident-like-token
+    fn consume_ident_like(&mut self) -> CssToken {
+        let name = self.consume_name();
+        if self.peek() != Some('(') {
+            return CssToken::Ident(name);
+        }
+        self.pos += 1;
+
+        if name.eq_ignore_ascii_case("url") {
+            // a quoted url is an ordinary function taking a string
+            let mut ahead = 0;
+            while self.peek_at(ahead).is_some_and(is_whitespace) {
+                ahead += 1;
+            }
+            if !matches!(self.peek_at(ahead), Some('"' | '\'')) {
+                return self.consume_url();
+            }
+        }
+        CssToken::Function(name)
+    }
+
+    // https://drafts.csswg.org/css-syntax-3/#consume-url-token
+    fn consume_url(&mut self) -> CssToken {
+        let mut value = String::new();
+        while self.peek().is_some_and(is_whitespace) {
+            self.pos += 1;
+        }
+        loop {
+            match self.consume() {
+                Some(')') | None => return CssToken::Url(value),
+                Some(c) if is_whitespace(c) => {
+                    while self.peek().is_some_and(is_whitespace) {
+                        self.pos += 1;
+                    }
+                    if self.peek().is_none() || self.consume_if(')') {
+                        return CssToken::Url(value);
+                    }
+                    self.consume_bad_url_remnants();
+                    return CssToken::BadUrl;
+                }
+                Some('"' | '\'' | '(') => {
+                    self.consume_bad_url_remnants();
+                    return CssToken::BadUrl;
+                }
+                Some(c) if is_non_printable(c) => {
+                    self.consume_bad_url_remnants();
+                    return CssToken::BadUrl;
+                }
+                Some('\\') => {
+                    if self.starts_escape(-1) {
+                        value.push(self.consume_escape());
+                    } else {
+                        self.consume_bad_url_remnants();
+                        return CssToken::BadUrl;
+                    }
+                }
+                Some(c) => value.push(c),
+            }
+        }
+    }
+
+    // https://drafts.csswg.org/css-syntax-3/#consume-remnants-of-bad-url
+    fn consume_bad_url_remnants(&mut self) {
+        loop {
+            match self.consume() {
+                Some(')') | None => return,
+                Some('\\') if self.starts_escape(-1) => {
+                    self.consume_escape();
+                }
+                Some(_) => {}
+            }
+        }
+    }
+}
+
+impl Iterator for CssTokenizer {
+    type Item = CssToken;
+
+    // yields a single `Eof` token after the last one
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let token = self.consume_token();
+        if token == CssToken::Eof {
+            self.done = true;
+        }
+        Some(token)
+    }
+}
+
+// https://drafts.csswg.org/css-syntax-3/#input-preprocessing
+fn preprocess(css: &str) -> Vec<char> {
+    let mut input = Vec::with_capacity(css.len());
+    let mut chars = css.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' => {
+                if chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                input.push('\n');
+            }
+            '\x0c' => input.push('\n'),
+            '\0' => input.push('\u{fffd}'),
+            c => input.push(c),
+        }
+    }
+    input
+}
+
+fn is_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n')
+}
+
+// https://drafts.csswg.org/css-syntax-3/#ident-start-code-point
+fn is_name_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || !c.is_ascii()
+}
+
+// https://drafts.csswg.org/css-syntax-3/#ident-code-point
+fn is_name(c: char) -> bool {
+    is_name_start(c) || c.is_ascii_digit() || c == '-'
+}
+
+fn is_non_printable(c: char) -> bool {
+    matches!(c, '\0'..='\x08' | '\x0b' | '\x0e'..='\x1f' | '\x7f')
+}
+
+// https://drafts.csswg.org/css-syntax-3/#starts-with-a-valid-escape
+fn is_valid_escape(c1: Option<char>, c2: Option<char>) -> bool {
+    c1 == Some('\\') && c2 != Some('\n')
+}
+
+// https://drafts.csswg.org/css-syntax-3/#would-start-an-identifier
+fn would_start_ident(c1: Option<char>, c2: Option<char>, c3: Option<char>) -> bool {
+    match c1 {
+        Some('-') => c2.is_some_and(|c| is_name_start(c) || c == '-') || is_valid_escape(c2, c3),
+        Some('\\') => is_valid_escape(c1, c2),
+        Some(c) => is_name_start(c),
+        None => false,
+    }
+}
+
+// https://drafts.csswg.org/cssom/#serialize-an-identifier
+pub fn serialize_identifier(ident: &str) -> String {
+    let chars: Vec<char> = ident.chars().collect();
+    let mut output = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        match c {
+            '\0' => output.push('\u{fffd}'),
+            '\x01'..='\x1f' | '\x7f' => output.push_str(&format!("\\{:x} ", c as u32)),
+            '0'..='9' if i == 0 || (i == 1 && chars[0] == '-') => {
+                output.push_str(&format!("\\{:x} ", c as u32))
+            }
+            '-' if i == 0 && chars.len() == 1 => output.push_str("\\-"),
+            c if is_name(c) => output.push(c),
+            c => {
+                output.push('\\');
+                output.push(c);
+            }
+        }
+    }
+    output
+}
+
+// https://drafts.csswg.org/cssom/#serialize-a-string
+pub fn serialize_string(s: &str) -> String {
+    let mut output = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '\0' => output.push('\u{fffd}'),
+            '\x01'..='\x1f' | '\x7f' => output.push_str(&format!("\\{:x} ", c as u32)),
+            '"' | '\\' => {
+                output.push('\\');
+                output.push(c);
+            }
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+    output
+}
+
+fn write_number(f: &mut fmt::Formatter<'_>, value: f64) -> fmt::Result {
+    write!(f, "{}", value)
+}
+
+impl fmt::Display for CssToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CssToken::Ident(s) => write!(f, "{}", serialize_identifier(s)),
+            CssToken::Function(s) => write!(f, "{}(", serialize_identifier(s)),
+            CssToken::AtKeyword(s) => write!(f, "@{}", serialize_identifier(s)),
+            CssToken::Hash { value, .. } => {
+                write!(f, "#")?;
+                for c in value.chars() {
+                    if is_name(c) {
+                        write!(f, "{}", c)?;
+                    } else {
+                        write!(f, "\\{}", c)?;
+                    }
+                }
+                Ok(())
+            }
+            CssToken::String(s) => write!(f, "{}", serialize_string(s)),
+            CssToken::BadString => writeln!(f, "\""),
+            CssToken::Url(s) => write!(f, "url({})", serialize_string(s)),
+            CssToken::BadUrl => write!(f, "url(())"),
+            CssToken::Delim(c) => write!(f, "{}", c),
+            CssToken::Number { value, .. } => write_number(f, *value),
+            CssToken::Percentage(value) => {
+                write_number(f, *value)?;
+                write!(f, "%")
+            }
+            CssToken::Dimension { value, unit, .. } => {
+                write_number(f, *value)?;
+                // keep `1e3` from reading as an exponent
+                let mut rest = unit.chars().skip(1).skip_while(|c| matches!(c, '+' | '-'));
+                if unit.starts_with(['e', 'E']) && rest.next().is_some_and(|c| c.is_ascii_digit()) {
+                    write!(f, "\\{:x} ", unit.as_bytes()[0])?;
+                    unit[1..].chars().try_for_each(|c| match c {
+                        c if is_name(c) => write!(f, "{}", c),
+                        c => write!(f, "\\{}", c),
+                    })
+                } else {
+                    write!(f, "{}", serialize_identifier(unit))
+                }
+            }
+            CssToken::Whitespace => write!(f, " "),
+            CssToken::Cdo => write!(f, "<!--"),
+            CssToken::Cdc => write!(f, "-->"),
+            CssToken::Colon => write!(f, ":"),
+            CssToken::Semicolon => write!(f, ";"),
+            CssToken::Comma => write!(f, ","),
+            CssToken::OpenSquare => write!(f, "["),
+            CssToken::CloseSquare => write!(f, "]"),
+            CssToken::OpenParen => write!(f, "("),
+            CssToken::CloseParen => write!(f, ")"),
+            CssToken::OpenCurly => write!(f, "{{"),
+            CssToken::CloseCurly => write!(f, "}}"),
+            CssToken::Eof => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokenize(css: &str) -> Vec<CssToken> {
+        CssTokenizer::new(String::from(css))
+            .take_while(|t| *t != CssToken::Eof)
+            .collect()
+    }
+
+    fn ident(s: &str) -> CssToken {
+        CssToken::Ident(String::from(s))
+    }
+
+    #[test]
+    fn test_rule() {
+        assert_eq!(
+            tokenize("p.a > #b{color:red /* c */}"),
+            vec![
+                ident("p"),
+                CssToken::Delim('.'),
+                ident("a"),
+                CssToken::Whitespace,
+                CssToken::Delim('>'),
+                CssToken::Whitespace,
+                CssToken::Hash {
+                    value: String::from("b"),
+                    id: true
+                },
+                CssToken::OpenCurly,
+                ident("color"),
+                CssToken::Colon,
+                ident("red"),
+                CssToken::Whitespace,
+                CssToken::CloseCurly,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_numbers() {
+        assert_eq!(
+            tokenize("12 -3.5 +.5e1 50% 2em 1e3px -x --y"),
+            vec![
+                CssToken::Number {
+                    value: 12.0,
+                    integer: true
+                },
+                CssToken::Whitespace,
+                CssToken::Number {
+                    value: -3.5,
+                    integer: false
+                },
+                CssToken::Whitespace,
+                CssToken::Number {
+                    value: 5.0,
+                    integer: false
+                },
+                CssToken::Whitespace,
+                CssToken::Percentage(50.0),
+                CssToken::Whitespace,
+                CssToken::Dimension {
+                    value: 2.0,
+                    integer: true,
+                    unit: String::from("em")
+                },
+                CssToken::Whitespace,
+                CssToken::Dimension {
+                    value: 1000.0,
+                    integer: false,
+                    unit: String::from("px")
+                },
+                CssToken::Whitespace,
+                ident("-x"),
+                CssToken::Whitespace,
+                ident("--y"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_strings() {
+        assert_eq!(
+            tokenize("'a\\'b' \"x\ny"),
+            vec![
+                CssToken::String(String::from("a'b")),
+                CssToken::Whitespace,
+                // the newline ends the string and is tokenized again
+                CssToken::BadString,
+                CssToken::Whitespace,
+                ident("y"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_urls() {
+        assert_eq!(
+            tokenize("url( a.png ) url(\"b.png\") url(a b)"),
+            vec![
+                CssToken::Url(String::from("a.png")),
+                CssToken::Whitespace,
+                CssToken::Function(String::from("url")),
+                CssToken::String(String::from("b.png")),
+                CssToken::CloseParen,
+                CssToken::Whitespace,
+                CssToken::BadUrl,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_escapes_and_serialization() {
+        let tokens = tokenize("\\31 0 #\\31 a @media <!-- -->");
+        assert_eq!(
+            tokens,
+            vec![
+                ident("10"),
+                CssToken::Whitespace,
+                CssToken::Hash {
+                    value: String::from("1a"),
+                    id: true
+                },
+                CssToken::Whitespace,
+                CssToken::AtKeyword(String::from("media")),
+                CssToken::Whitespace,
+                CssToken::Cdo,
+                CssToken::Whitespace,
+                CssToken::Cdc,
+            ]
+        );
+
+        let css: String = tokens.iter().map(|t| t.to_string()).collect();
+        assert_eq!(css, "\\31 0 #1a @media <!-- -->");
+        // the hash loses its escape and with it the id flag
+        let again: String = tokenize(&css).iter().map(|t| t.to_string()).collect();
+        assert_eq!(again, css);
+
+        let dimension = CssToken::Dimension {
+            value: 1.0,
+            integer: true,
+            unit: String::from("e3"),
+        };
+        assert_eq!(dimension.to_string(), "1\\65 3");
+        assert_eq!(tokenize(&dimension.to_string()), vec![dimension]);
+    }
+}
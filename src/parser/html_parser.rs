@@ -3,11 +3,13 @@ use std::{cell::RefCell, rc::Rc};
 use super::html_tokenizer::*;
 
 use crate::{
-    element::{Element, HtmlElementKind},
+    document::{Document, DocumentReadyState, QuirksMode},
+    element::{Attribute, Element, HtmlElementKind},
+    event::{Event, EventType},
     node::{Node, NodeKind},
 };
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 pub enum InsertionMode {
     Initial,
     BeforeHtml,
@@ -17,6 +19,7 @@ pub enum InsertionMode {
     Text,
     AfterHead,
     AfterBody,
+    AfterAfterBody,
 }
 
 pub struct HtmlPerser {
@@ -24,26 +27,30 @@ pub struct HtmlPerser {
     tokenizer: HtmlTokenizer,
     stack_of_open_elements: Vec<Rc<RefCell<Node>>>,
     insertion_mode: InsertionMode,
+    original_insertion_mode: InsertionMode,
+    head_element: Option<Rc<RefCell<Node>>>,
 }
 
 impl HtmlPerser {
     pub fn new(tokenizer: HtmlTokenizer) -> Self {
         Self {
-            root: Rc::new(RefCell::new(Node::new(NodeKind::Document))),
+            root: Rc::new(RefCell::new(Node::new(NodeKind::Document(Document::new())))),
             tokenizer,
             stack_of_open_elements: Vec::new(),
             insertion_mode: InsertionMode::Initial,
+            original_insertion_mode: InsertionMode::Initial,
+            head_element: None,
         }
     }
 
     fn is_whitespace(&self, c: char) -> bool {
-        c == ' ' || c == '\n' || c == '\t'
+        c == ' ' || c == '\n' || c == '\t' || c == '\r' || c == '\x0c'
     }
 
     // create a text node
     fn create_char(&self, c: char) -> Node {
         let s = String::from(c);
-        return Node::new(NodeKind::Text(s));
+        Node::new(NodeKind::Text(s))
     }
 
     fn current_node(&self) -> &Rc<RefCell<Node>> {
@@ -53,33 +60,49 @@ impl HtmlPerser {
         }
     }
 
-    // insert a character into the tree
+    // https://html.spec.whatwg.org/multipage/parsing.html#appropriate-place-for-inserting-a-node
+    //
+    // what goes into a template element goes into its template contents
+    fn appropriate_place(target: &Rc<RefCell<Node>>) -> Rc<RefCell<Node>> {
+        let contents = target.borrow().template_contents();
+        contents.unwrap_or_else(|| target.clone())
+    }
+
+    // insert a character into the tree, extending the text node right
+    // before the insertion point if there is one
     fn insert_char(&mut self, c: char) {
-        let current_node = self.current_node();
+        let parent = Self::appropriate_place(self.current_node());
 
-        match current_node.borrow_mut().kind {
-            NodeKind::Text(ref mut s) => {
+        if let Some(last_child) = parent.borrow().last_child() {
+            if let NodeKind::Text(ref mut s) = last_child.borrow_mut().kind {
                 s.push(c);
                 return;
             }
-            _ => {}
         }
 
         let node = Rc::new(RefCell::new(self.create_char(c)));
 
-        current_node.borrow_mut().append_child_node(&node);
+        Node::insert(&parent, &node, None);
+    }
 
-        self.stack_of_open_elements.push(node);
+    fn insert_comment(&mut self, data: &str, parent: &Rc<RefCell<Node>>) {
+        let node = Rc::new(RefCell::new(Node::new(NodeKind::Comment(data.to_owned()))));
+        Node::insert(&Self::appropriate_place(parent), &node, None);
     }
 
     fn append_element(&mut self, tag_name: String) {
+        self.append_element_with_attributes(tag_name, Vec::new());
+    }
+
+    fn append_element_with_attributes(&mut self, tag_name: String, attributes: Vec<Attribute>) {
+        let element = Element::from_str(&tag_name);
         let new_node = Rc::new(RefCell::new(Node::new(NodeKind::Element(
-            Element::from_str(&tag_name),
+            Element::with_attributes(element.kind().clone(), attributes),
         ))));
 
-        let current_node = self.current_node();
+        let parent = Self::appropriate_place(self.current_node());
 
-        current_node.borrow_mut().append_child_node(&new_node);
+        Node::insert(&parent, &new_node, None);
         self.stack_of_open_elements.push(new_node);
     }
 
@@ -98,31 +121,122 @@ impl HtmlPerser {
         }
     }
 
+    // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-inbody
+    //
+    // An `li` start tag closes the open `li` of the same list, unless the
+    // new one is in a list or an element closer to it.
+    fn close_list_item(&mut self) {
+        let kind = |node: &Rc<RefCell<Node>>| match node.borrow().kind {
+            NodeKind::Element(ref e) => Some(e.kind().clone()),
+            _ => None,
+        };
+        let closest = self.stack_of_open_elements.iter().rev().find(|n| {
+            !matches!(
+                kind(n),
+                Some(HtmlElementKind::Div | HtmlElementKind::P | HtmlElementKind::Span)
+            )
+        });
+        if closest.and_then(kind) == Some(HtmlElementKind::Li) {
+            self.pop_until(HtmlElementKind::Li);
+        }
+    }
+
+    fn contain_in_stack(&self, kind: &HtmlElementKind) -> bool {
+        self.stack_of_open_elements
+            .iter()
+            .any(|n| n.borrow().kind == NodeKind::Element(Element::new(kind.clone())))
+    }
+
     pub fn construct_tree(&mut self) -> Rc<RefCell<Node>> {
+        self.set_ready_state(DocumentReadyState::Loading);
+        let root = self.build_tree();
+        self.stop_parsing();
+        root
+    }
+
+    // https://html.spec.whatwg.org/multipage/parsing.html#the-end
+    fn stop_parsing(&mut self) {
+        self.set_ready_state(DocumentReadyState::Interactive);
+        Node::dispatch_event(&self.root, &mut Event::new(EventType::DomContentLoaded));
+        self.set_ready_state(DocumentReadyState::Complete);
+    }
+
+    fn set_ready_state(&self, ready_state: DocumentReadyState) {
+        if let NodeKind::Document(ref mut d) = self.root.borrow_mut().kind {
+            d.set_ready_state(ready_state);
+        }
+    }
+
+    fn set_quirks_mode(&self, mode: QuirksMode) {
+        if let NodeKind::Document(ref mut d) = self.root.borrow_mut().kind {
+            d.set_mode(mode);
+        }
+    }
+
+    fn build_tree(&mut self) -> Rc<RefCell<Node>> {
         let mut token = self.tokenizer.next();
 
         while token.is_some() {
             match self.insertion_mode {
                 // https://html.spec.whatwg.org/multipage/parsing.html#the-initial-insertion-mode
-                InsertionMode::Initial => {
-                    self.insertion_mode = InsertionMode::BeforeHtml;
-                    continue;
-                }
+                InsertionMode::Initial => match token {
+                    Some(HtmlToken::Char(c)) if self.is_whitespace(c) => {
+                        token = self.tokenizer.next();
+                        continue;
+                    }
+                    Some(HtmlToken::Comment(ref data)) => {
+                        let root = self.root.clone();
+                        self.insert_comment(data, &root);
+                        token = self.tokenizer.next();
+                        continue;
+                    }
+                    Some(HtmlToken::Doctype(ref name)) => {
+                        let doctype = Rc::new(RefCell::new(Node::new(NodeKind::DocumentType(
+                            name.to_owned(),
+                        ))));
+                        Node::insert(&self.root, &doctype, None);
+                        // only <!DOCTYPE html> renders in no-quirks mode
+                        if !name.eq_ignore_ascii_case("html") {
+                            self.set_quirks_mode(QuirksMode::Quirks);
+                        }
+
+                        self.insertion_mode = InsertionMode::BeforeHtml;
+                        token = self.tokenizer.next();
+                        continue;
+                    }
+                    _ => {
+                        // a document without a doctype is in quirks mode
+                        self.set_quirks_mode(QuirksMode::Quirks);
+                        self.insertion_mode = InsertionMode::BeforeHtml;
+                        continue;
+                    }
+                },
                 // https://html.spec.whatwg.org/multipage/parsing.html#the-before-html-insertion-mode
                 InsertionMode::BeforeHtml => match token {
                     Some(HtmlToken::Char(c)) if self.is_whitespace(c) => {
                         token = self.tokenizer.next();
                         continue;
                     }
-                    Some(HtmlToken::StartTag(ref tag_name)) if tag_name == "html" => {
-                        self.append_element(tag_name.to_owned());
+                    Some(HtmlToken::Comment(ref data)) => {
+                        let root = self.root.clone();
+                        self.insert_comment(data, &root);
+                        token = self.tokenizer.next();
+                        continue;
+                    }
+                    Some(HtmlToken::StartTag(ref tag_name, ref attributes))
+                        if tag_name == "html" =>
+                    {
+                        self.append_element_with_attributes(
+                            tag_name.to_owned(),
+                            attributes.clone(),
+                        );
 
                         self.insertion_mode = InsertionMode::BeforeHead;
                         token = self.tokenizer.next();
                         continue;
                     }
                     Some(HtmlToken::EndTag(ref s))
-                        if s != "haed" || s != "body" || s != "html" || s != "br" =>
+                        if !matches!(s.as_str(), "head" | "body" | "html" | "br") =>
                     {
                         token = self.tokenizer.next();
                         continue;
@@ -131,38 +245,175 @@ impl HtmlPerser {
                         return self.root.clone();
                     }
                     _ => {
+                        self.append_element(String::from("html"));
                         self.insertion_mode = InsertionMode::BeforeHead;
                         continue;
                     }
                 },
                 // https://html.spec.whatwg.org/multipage/parsing.html#the-before-head-insertion-mode
-                InsertionMode::BeforeHead => {
-                    // TODO: implement head tag later
-                    self.insertion_mode = InsertionMode::InHead;
-                }
+                InsertionMode::BeforeHead => match token {
+                    Some(HtmlToken::Char(c)) if self.is_whitespace(c) => {
+                        token = self.tokenizer.next();
+                        continue;
+                    }
+                    Some(HtmlToken::Comment(ref data)) => {
+                        let current_node = self.current_node().clone();
+                        self.insert_comment(data, &current_node);
+                        token = self.tokenizer.next();
+                        continue;
+                    }
+                    Some(HtmlToken::StartTag(ref tag_name, ref attributes))
+                        if tag_name == "head" =>
+                    {
+                        self.append_element_with_attributes(
+                            tag_name.to_owned(),
+                            attributes.clone(),
+                        );
+                        self.head_element = Some(self.current_node().clone());
+
+                        self.insertion_mode = InsertionMode::InHead;
+                        token = self.tokenizer.next();
+                        continue;
+                    }
+                    Some(HtmlToken::EndTag(ref s))
+                        if !matches!(s.as_str(), "head" | "body" | "html" | "br") =>
+                    {
+                        // ignore token
+                        token = self.tokenizer.next();
+                        continue;
+                    }
+                    _ => {
+                        self.append_element(String::from("head"));
+                        self.head_element = Some(self.current_node().clone());
+
+                        self.insertion_mode = InsertionMode::InHead;
+                        continue;
+                    }
+                },
                 // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-inhead
-                InsertionMode::InHead => {
-                    // TODO: implement head tag later
-                    self.insertion_mode = InsertionMode::AfterHead;
-                }
+                InsertionMode::InHead => match token {
+                    Some(HtmlToken::Char(c)) if self.is_whitespace(c) => {
+                        self.insert_char(c);
+                        token = self.tokenizer.next();
+                        continue;
+                    }
+                    Some(HtmlToken::Comment(ref data)) => {
+                        let current_node = self.current_node().clone();
+                        self.insert_comment(data, &current_node);
+                        token = self.tokenizer.next();
+                        continue;
+                    }
+                    Some(HtmlToken::StartTag(ref tag_name, ref attributes))
+                        if tag_name == "meta" || tag_name == "link" =>
+                    {
+                        self.append_element_with_attributes(
+                            tag_name.to_owned(),
+                            attributes.clone(),
+                        );
+                        self.stack_of_open_elements.pop();
+                        token = self.tokenizer.next();
+                        continue;
+                    }
+                    Some(HtmlToken::StartTag(ref tag_name, ref attributes))
+                        if matches!(tag_name.as_str(), "title" | "style" | "script") =>
+                    {
+                        self.append_element_with_attributes(
+                            tag_name.to_owned(),
+                            attributes.clone(),
+                        );
+                        self.original_insertion_mode = self.insertion_mode;
+                        self.insertion_mode = InsertionMode::Text;
+                        token = self.tokenizer.next();
+                        continue;
+                    }
+                    Some(HtmlToken::StartTag(ref tag_name, _)) if tag_name == "head" => {
+                        // ignore token
+                        token = self.tokenizer.next();
+                        continue;
+                    }
+                    Some(HtmlToken::EndTag(ref s)) if s == "head" => {
+                        self.stack_of_open_elements.pop();
+                        self.insertion_mode = InsertionMode::AfterHead;
+                        token = self.tokenizer.next();
+                        continue;
+                    }
+                    Some(HtmlToken::EndTag(ref s))
+                        if !matches!(s.as_str(), "body" | "html" | "br") =>
+                    {
+                        // ignore token
+                        token = self.tokenizer.next();
+                        continue;
+                    }
+                    _ => {
+                        self.stack_of_open_elements.pop();
+                        self.insertion_mode = InsertionMode::AfterHead;
+                        continue;
+                    }
+                },
+                // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-incdata
+                InsertionMode::Text => match token {
+                    Some(HtmlToken::Char(c)) => {
+                        self.insert_char(c);
+                        token = self.tokenizer.next();
+                        continue;
+                    }
+                    Some(HtmlToken::EndTag(_)) => {
+                        self.stack_of_open_elements.pop();
+                        self.insertion_mode = self.original_insertion_mode;
+                        token = self.tokenizer.next();
+                        continue;
+                    }
+                    _ => {
+                        self.stack_of_open_elements.pop();
+                        self.insertion_mode = self.original_insertion_mode;
+                        continue;
+                    }
+                },
                 // https://html.spec.whatwg.org/multipage/parsing.html#the-after-head-insertion-mode
                 InsertionMode::AfterHead => match token {
                     Some(HtmlToken::Char(c)) if self.is_whitespace(c) => {
+                        self.insert_char(c);
+                        token = self.tokenizer.next();
+                        continue;
+                    }
+                    Some(HtmlToken::Comment(ref data)) => {
+                        let current_node = self.current_node().clone();
+                        self.insert_comment(data, &current_node);
                         token = self.tokenizer.next();
                         continue;
                     }
-                    Some(HtmlToken::StartTag(ref tag_name)) if tag_name == "body" => {
-                        self.append_element(tag_name.to_owned());
+                    Some(HtmlToken::StartTag(ref tag_name, ref attributes))
+                        if tag_name == "body" =>
+                    {
+                        self.append_element_with_attributes(
+                            tag_name.to_owned(),
+                            attributes.clone(),
+                        );
                         token = self.tokenizer.next();
                         self.insertion_mode = InsertionMode::InBody;
                         continue;
                     }
-                    Some(HtmlToken::StartTag(ref tag_name)) if tag_name == "head" => {
+                    Some(HtmlToken::StartTag(ref tag_name, _))
+                        if matches!(
+                            tag_name.as_str(),
+                            "meta" | "link" | "title" | "style" | "script"
+                        ) =>
+                    {
+                        // reopen the head element and process the token there
+                        if let Some(head) = self.head_element.clone() {
+                            self.stack_of_open_elements.push(head);
+                        }
+                        self.insertion_mode = InsertionMode::InHead;
+                        continue;
+                    }
+                    Some(HtmlToken::StartTag(ref tag_name, _)) if tag_name == "head" => {
                         // ignore token
                         token = self.tokenizer.next();
                         continue;
                     }
-                    Some(HtmlToken::EndTag(ref s)) if s != "body" || s != "html" || s != "br" => {
+                    Some(HtmlToken::EndTag(ref s))
+                        if !matches!(s.as_str(), "body" | "html" | "br") =>
+                    {
                         // ignore token
                         token = self.tokenizer.next();
                         continue;
@@ -171,6 +422,7 @@ impl HtmlPerser {
                         return self.root.clone();
                     }
                     _ => {
+                        self.append_element(String::from("body"));
                         self.insertion_mode = InsertionMode::InBody;
                         continue;
                     }
@@ -182,20 +434,72 @@ impl HtmlPerser {
                         token = self.tokenizer.next();
                         continue;
                     }
-                    Some(HtmlToken::StartTag(ref tag_name)) => match tag_name.as_str() {
-                        "p" | "div" | "span" | "h1" | "h2" => {
-                            self.append_element(tag_name.to_owned());
+                    Some(HtmlToken::Comment(ref data)) => {
+                        let current_node = self.current_node().clone();
+                        self.insert_comment(data, &current_node);
+                        token = self.tokenizer.next();
+                        continue;
+                    }
+                    Some(HtmlToken::Doctype(_)) => {
+                        // ignore token
+                        token = self.tokenizer.next();
+                        continue;
+                    }
+                    Some(HtmlToken::StartTag(ref tag_name, ref attributes)) => {
+                        match tag_name.as_str() {
+                            "html" | "head" | "body" => {
+                                // ignore token
+                            }
+                            "title" | "style" | "script" => {
+                                self.append_element_with_attributes(
+                                    tag_name.to_owned(),
+                                    attributes.clone(),
+                                );
+                                self.original_insertion_mode = self.insertion_mode;
+                                self.insertion_mode = InsertionMode::Text;
+                            }
+                            "li" => {
+                                self.close_list_item();
+                                self.append_element_with_attributes(
+                                    tag_name.to_owned(),
+                                    attributes.clone(),
+                                );
+                            }
+                            "meta" | "link" | "br" | "hr" | "img" | "input" => {
+                                self.append_element_with_attributes(
+                                    tag_name.to_owned(),
+                                    attributes.clone(),
+                                );
+                                self.stack_of_open_elements.pop();
+                            }
+                            _ if Element::is_known(tag_name) => {
+                                self.append_element_with_attributes(
+                                    tag_name.to_owned(),
+                                    attributes.clone(),
+                                );
+                            }
+                            _ => {
+                                println!("Unknown tag: {}", tag_name);
+                            }
+                        }
+                        token = self.tokenizer.next();
+                        continue;
+                    }
+                    Some(HtmlToken::EndTag(ref tag_name)) => match tag_name.as_str() {
+                        "body" => {
+                            self.insertion_mode = InsertionMode::AfterBody;
                             token = self.tokenizer.next();
                             continue;
                         }
-                        _ => {
-                            println!("Unknown tag: {}", tag_name);
-                            token = self.tokenizer.next();
+                        "html" => {
+                            self.insertion_mode = InsertionMode::AfterBody;
+                            continue;
                         }
-                    },
-                    Some(HtmlToken::EndTag(ref tag_name)) => match tag_name.as_str() {
-                        "p" | "div" | "span" | "h1" | "h2" => {
-                            self.stack_of_open_elements.pop();
+                        _ if Element::is_known(tag_name) => {
+                            let kind = Element::from_str(tag_name).kind().clone();
+                            if self.contain_in_stack(&kind) {
+                                self.pop_until(kind);
+                            }
                             token = self.tokenizer.next();
                             continue;
                         }
@@ -208,9 +512,54 @@ impl HtmlPerser {
                         return self.root.clone();
                     }
                 },
-                InsertionMode::Text => {}
-                InsertionMode::AfterBody => {}
-                _ => {}
+                // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-afterbody
+                InsertionMode::AfterBody => match token {
+                    Some(HtmlToken::Char(c)) if self.is_whitespace(c) => {
+                        self.insert_char(c);
+                        token = self.tokenizer.next();
+                        continue;
+                    }
+                    Some(HtmlToken::Comment(ref data)) => {
+                        if let Some(html) = self.stack_of_open_elements.first().cloned() {
+                            self.insert_comment(data, &html);
+                        }
+                        token = self.tokenizer.next();
+                        continue;
+                    }
+                    Some(HtmlToken::EndTag(ref s)) if s == "html" => {
+                        self.insertion_mode = InsertionMode::AfterAfterBody;
+                        token = self.tokenizer.next();
+                        continue;
+                    }
+                    Some(HtmlToken::Eof) | None => {
+                        return self.root.clone();
+                    }
+                    _ => {
+                        self.insertion_mode = InsertionMode::InBody;
+                        continue;
+                    }
+                },
+                // https://html.spec.whatwg.org/multipage/parsing.html#the-after-after-body-insertion-mode
+                InsertionMode::AfterAfterBody => match token {
+                    Some(HtmlToken::Char(c)) if self.is_whitespace(c) => {
+                        self.insert_char(c);
+                        token = self.tokenizer.next();
+                        continue;
+                    }
+                    Some(HtmlToken::Comment(ref data)) => {
+                        let root = self.root.clone();
+                        self.insert_comment(data, &root);
+                        token = self.tokenizer.next();
+                        continue;
+                    }
+                    Some(HtmlToken::Eof) | None => {
+                        return self.root.clone();
+                    }
+                    _ => {
+                        self.insertion_mode = InsertionMode::InBody;
+                        continue;
+                    }
+                },
             }
         }
 
@@ -218,10 +567,64 @@ impl HtmlPerser {
     }
 }
 
+// the document for `html`, shared by the tests of every module
+#[cfg(test)]
+pub(crate) fn parse(html: &str) -> Rc<RefCell<Node>> {
+    HtmlPerser::new(HtmlTokenizer::new(String::from(html))).construct_tree()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_construct_tree() {
+        let html = "<!DOCTYPE html><!-- a --><html><head><style>p > a {}</style></head>\
+                    <body class=main>x</body><!-- b --></html><!-- c -->";
+        let root = HtmlPerser::new(HtmlTokenizer::new(String::from(html))).construct_tree();
+        let root = root.borrow();
+
+        let doctype = root.first_child().unwrap();
+        assert_eq!(
+            doctype.borrow().kind(),
+            NodeKind::DocumentType(String::from("html"))
+        );
+        let comment = doctype.borrow().next_sibling().unwrap();
+        assert_eq!(
+            comment.borrow().kind(),
+            NodeKind::Comment(String::from(" a "))
+        );
+        assert_eq!(
+            root.last_child().unwrap().borrow().kind(),
+            NodeKind::Comment(String::from(" c "))
+        );
+
+        let html = comment.borrow().next_sibling().unwrap();
+        let head = html.borrow().first_child().unwrap();
+        let style = head.borrow().first_child().unwrap();
+        assert_eq!(
+            style.borrow().first_child().unwrap().borrow().kind(),
+            NodeKind::Text(String::from("p > a {}"))
+        );
+
+        let body = head.borrow().next_sibling().unwrap();
+        match body.borrow().kind() {
+            NodeKind::Element(e) => {
+                assert_eq!(*e.kind(), HtmlElementKind::Body);
+                assert_eq!(e.get_attribute("class"), Some("main"));
+            }
+            _ => panic!("expected body"),
+        }
+        assert_eq!(
+            body.borrow().first_child().unwrap().borrow().kind(),
+            NodeKind::Text(String::from("x"))
+        );
+        assert_eq!(
+            html.borrow().last_child().unwrap().borrow().kind(),
+            NodeKind::Comment(String::from(" b "))
+        );
+    }
+
     #[test]
     fn test_insert_char() {
         let mut parser = HtmlPerser::new(HtmlTokenizer::new(String::from("")));
@@ -230,8 +633,8 @@ mod tests {
         parser.insert_char('b');
         parser.insert_char('c');
 
+        assert_eq!(Node::dump_tree(&parser.root), "| \"abc\"\n");
         let root = parser.root.borrow();
-        println!("{:#?}", root);
 
         assert_eq!(
             root.first_child().unwrap().borrow().kind(),
@@ -245,8 +648,8 @@ mod tests {
 
         parser.append_element(String::from("div"));
 
+        assert_eq!(Node::dump_tree(&parser.root), "| <div>\n");
         let root = parser.root.borrow();
-        println!("{:#?}", root);
 
         assert_eq!(
             root.first_child().unwrap().borrow().kind(),
@@ -264,12 +667,46 @@ mod tests {
 
         parser.pop_until(HtmlElementKind::Div);
 
+        // popping leaves the elements in the tree
+        assert_eq!(
+            Node::dump_tree(&parser.root),
+            "| <div>\n\
+             |   <span>\n\
+             |     <p>\n"
+        );
         let root = parser.root.borrow();
-        println!("{:#?}", root);
 
         assert_eq!(
             root.first_child().unwrap().borrow().kind(),
             NodeKind::Element(Element::from_str("div"))
         );
     }
+
+    #[test]
+    fn test_list_items() {
+        let root = HtmlPerser::new(HtmlTokenizer::new(String::from(
+            "<ul><li>a<li><p>b<li>c<ol><li>d<li>e</ol></ul>",
+        )))
+        .construct_tree();
+
+        assert_eq!(
+            Node::dump_tree(&root),
+            "| <html>\n\
+             |   <head>\n\
+             |   <body>\n\
+             |     <ul>\n\
+             |       <li>\n\
+             |         \"a\"\n\
+             |       <li>\n\
+             |         <p>\n\
+             |           \"b\"\n\
+             |       <li>\n\
+             |         \"c\"\n\
+             |         <ol>\n\
+             |           <li>\n\
+             |             \"d\"\n\
+             |           <li>\n\
+             |             \"e\"\n"
+        );
+    }
 }
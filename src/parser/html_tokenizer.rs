@@ -1,23 +1,62 @@
+use crate::element::Attribute;
+
 pub enum State {
     Data,
+    RcData,
+    RawText,
     TagOpen,
     EndTagOpen,
     TagName,
+    BeforeAttributeName,
+    AttributeName,
+    AfterAttributeName,
+    BeforeAttributeValue,
+    AttributeValueDoubleQuoted,
+    AttributeValueSingleQuoted,
+    AttributeValueUnquoted,
+    AfterAttributeValueQuoted,
+    SelfClosingStartTag,
+    MarkupDeclarationOpen,
+    Comment,
+    BogusComment,
+    Doctype,
+    DoctypeName,
+    AfterDoctypeName,
 }
 
 #[derive(Debug, PartialEq)]
 pub enum HtmlToken {
     Char(char),
-    StartTag(String),
+    StartTag(String, Vec<Attribute>),
     EndTag(String),
+    Comment(String),
+    Doctype(String),
     Eof,
 }
 
+// https://html.spec.whatwg.org/multipage/named-characters.html
+// Only the references commonly found in hand written documents.
+const NAMED_CHARACTER_REFERENCES: [(&str, char); 11] = [
+    ("amp;", '&'),
+    ("lt;", '<'),
+    ("gt;", '>'),
+    ("quot;", '"'),
+    ("apos;", '\''),
+    ("nbsp;", '\u{a0}'),
+    ("copy;", '\u{a9}'),
+    ("amp", '&'),
+    ("lt", '<'),
+    ("gt", '>'),
+    ("quot", '"'),
+];
+
 pub struct HtmlTokenizer {
     input: Vec<char>,
     state: State,
     pos: usize,
     current_token: Option<HtmlToken>,
+    current_attribute: Option<(String, String)>,
+    last_start_tag: String,
     reconsume: bool,
 }
 
@@ -28,14 +67,12 @@ impl HtmlTokenizer {
             pos: 0,
             input: html.chars().collect(),
             current_token: None,
+            current_attribute: None,
+            last_start_tag: String::new(),
             reconsume: false,
         }
     }
 
-    fn is_eof(&self) -> bool {
-        self.pos >= self.input.len()
-    }
-
     fn consume_input(&mut self) -> char {
         if self.reconsume {
             self.reconsume = false;
@@ -50,13 +87,25 @@ impl HtmlTokenizer {
         }
     }
 
+    // whether the input right after the current character starts with `s`
+    fn next_chars_are(&self, s: &str) -> bool {
+        let mut pos = self.pos;
+        for expected in s.chars() {
+            match self.input.get(pos) {
+                Some(c) if c.eq_ignore_ascii_case(&expected) => pos += 1,
+                _ => return false,
+            }
+        }
+        true
+    }
+
     fn is_whitespace(&self, c: char) -> bool {
-        c == ' ' || c == '\n' || c == '\t'
+        c == ' ' || c == '\n' || c == '\t' || c == '\r' || c == '\x0c'
     }
 
     fn create_start_tag_token(&mut self) {
         self.reconsume = true;
-        self.current_token = Some(HtmlToken::StartTag(String::new()));
+        self.current_token = Some(HtmlToken::StartTag(String::new(), Vec::new()));
     }
 
     fn create_end_tag_token(&mut self) {
@@ -66,11 +115,115 @@ impl HtmlTokenizer {
 
     fn append_tag_name(&mut self, c: char) {
         match self.current_token {
-            Some(HtmlToken::StartTag(ref mut tag_name)) => tag_name.push(c),
+            Some(HtmlToken::StartTag(ref mut tag_name, _)) => tag_name.push(c),
             Some(HtmlToken::EndTag(ref mut tag_name)) => tag_name.push(c),
             _ => panic!("Unexpected token: {:?}", self.current_token),
         }
     }
+
+    fn start_new_attribute(&mut self) {
+        self.finish_attribute();
+        self.current_attribute = Some((String::new(), String::new()));
+    }
+
+    fn append_attribute_name(&mut self, c: char) {
+        if let Some((ref mut name, _)) = self.current_attribute {
+            name.push(c);
+        }
+    }
+
+    fn append_attribute_value(&mut self, c: char) {
+        if let Some((_, ref mut value)) = self.current_attribute {
+            value.push(c);
+        }
+    }
+
+    // moves the attribute being built onto the tag, dropping duplicates
+    fn finish_attribute(&mut self) {
+        let (name, value) = match self.current_attribute.take() {
+            Some(a) => a,
+            None => return,
+        };
+        if let Some(HtmlToken::StartTag(_, ref mut attributes)) = self.current_token {
+            if !attributes.iter().any(|a| a.name() == name) {
+                attributes.push(Attribute::new(name, value));
+            }
+        }
+    }
+
+    fn emit_tag(&mut self) -> Option<HtmlToken> {
+        self.finish_attribute();
+        self.state = State::Data;
+
+        // the contents of these elements are not markup
+        if let Some(HtmlToken::StartTag(ref tag_name, _)) = self.current_token {
+            self.state = match tag_name.as_str() {
+                "title" => State::RcData,
+                "style" | "script" => State::RawText,
+                _ => State::Data,
+            };
+            self.last_start_tag = tag_name.clone();
+        }
+        self.current_token.take()
+    }
+
+    // https://html.spec.whatwg.org/multipage/parsing.html#character-reference-state
+    fn consume_character_reference(&mut self) -> char {
+        if self.next_chars_are("#") {
+            let hex = self.next_chars_are("#x");
+            let start = self.pos + if hex { 2 } else { 1 };
+            let radix = if hex { 16 } else { 10 };
+            let digits: String = self.input[start..]
+                .iter()
+                .take_while(|c| c.is_digit(radix))
+                .collect();
+            if digits.is_empty() {
+                return '&';
+            }
+
+            self.pos = start + digits.len();
+            if self.input.get(self.pos) == Some(&';') {
+                self.pos += 1;
+            }
+            return u32::from_str_radix(&digits, radix)
+                .ok()
+                .and_then(char::from_u32)
+                .filter(|c| *c != '\0')
+                .unwrap_or('\u{fffd}');
+        }
+
+        for (name, c) in NAMED_CHARACTER_REFERENCES {
+            if self.next_chars_are(name) {
+                self.pos += name.len();
+                return c;
+            }
+        }
+        '&'
+    }
+
+    // in RAWTEXT and RCDATA only the end tag of the element that started
+    // them is recognized
+    fn consume_appropriate_end_tag(&mut self) -> Option<HtmlToken> {
+        let end_tag = format!("/{}", self.last_start_tag);
+        if !self.next_chars_are(&end_tag) {
+            return None;
+        }
+        match self.input.get(self.pos + end_tag.len()) {
+            Some(c) if self.is_whitespace(*c) || *c == '/' || *c == '>' => {}
+            None => {}
+            _ => return None,
+        }
+
+        self.pos += end_tag.len();
+        while let Some(c) = self.input.get(self.pos) {
+            self.pos += 1;
+            if *c == '>' {
+                break;
+            }
+        }
+        self.state = State::Data;
+        Some(HtmlToken::EndTag(self.last_start_tag.clone()))
+    }
 }
 
 impl Iterator for HtmlTokenizer {
@@ -94,8 +247,29 @@ impl Iterator for HtmlTokenizer {
                         self.state = State::TagOpen;
                         continue;
                     }
-                    if self.is_eof() {
-                        return Some(HtmlToken::Eof);
+                    if c == '&' {
+                        return Some(HtmlToken::Char(self.consume_character_reference()));
+                    }
+
+                    return Some(HtmlToken::Char(c));
+                }
+                State::RcData => {
+                    if c == '<' {
+                        if let Some(token) = self.consume_appropriate_end_tag() {
+                            return Some(token);
+                        }
+                    }
+                    if c == '&' {
+                        return Some(HtmlToken::Char(self.consume_character_reference()));
+                    }
+
+                    return Some(HtmlToken::Char(c));
+                }
+                State::RawText => {
+                    if c == '<' {
+                        if let Some(token) = self.consume_appropriate_end_tag() {
+                            return Some(token);
+                        }
                     }
 
                     return Some(HtmlToken::Char(c));
@@ -105,38 +279,239 @@ impl Iterator for HtmlTokenizer {
                         self.state = State::EndTagOpen;
                         continue;
                     }
-                    if c.is_alphabetic() {
+                    if c == '!' {
+                        self.state = State::MarkupDeclarationOpen;
+                        continue;
+                    }
+                    if c.is_ascii_alphabetic() {
                         self.state = State::TagName;
                         self.create_start_tag_token();
                         continue;
                     }
-                    if self.is_eof() {
-                        return Some(HtmlToken::Eof);
-                    }
 
+                    // not a tag after all, emit the '<' as text
+                    self.reconsume = true;
                     self.state = State::Data;
+                    return Some(HtmlToken::Char('<'));
                 }
                 State::EndTagOpen => {
-                    if c.is_alphabetic() {
+                    if c.is_ascii_alphabetic() {
                         self.state = State::TagName;
                         self.create_end_tag_token();
                         continue;
                     }
-                    if self.is_eof() {
-                        return Some(HtmlToken::Eof);
+                    if c == '>' {
+                        self.state = State::Data;
                     }
                 }
                 State::TagName => {
+                    if self.is_whitespace(c) {
+                        self.state = State::BeforeAttributeName;
+                        continue;
+                    }
+                    if c == '/' {
+                        self.state = State::SelfClosingStartTag;
+                        continue;
+                    }
+                    if c == '>' {
+                        return self.emit_tag();
+                    }
+
+                    self.append_tag_name(c.to_ascii_lowercase());
+                }
+                State::BeforeAttributeName => {
+                    if self.is_whitespace(c) {
+                        continue;
+                    }
+                    if c == '/' || c == '>' {
+                        self.reconsume = true;
+                        self.state = State::AfterAttributeName;
+                        continue;
+                    }
+
+                    self.start_new_attribute();
+                    self.reconsume = true;
+                    self.state = State::AttributeName;
+                }
+                State::AttributeName => {
+                    if self.is_whitespace(c) || c == '/' || c == '>' {
+                        self.reconsume = true;
+                        self.state = State::AfterAttributeName;
+                        continue;
+                    }
+                    if c == '=' {
+                        self.state = State::BeforeAttributeValue;
+                        continue;
+                    }
+
+                    self.append_attribute_name(c.to_ascii_lowercase());
+                }
+                State::AfterAttributeName => {
+                    if self.is_whitespace(c) {
+                        continue;
+                    }
+                    if c == '/' {
+                        self.state = State::SelfClosingStartTag;
+                        continue;
+                    }
+                    if c == '=' {
+                        self.state = State::BeforeAttributeValue;
+                        continue;
+                    }
+                    if c == '>' {
+                        return self.emit_tag();
+                    }
+
+                    self.start_new_attribute();
+                    self.reconsume = true;
+                    self.state = State::AttributeName;
+                }
+                State::BeforeAttributeValue => {
+                    if self.is_whitespace(c) {
+                        continue;
+                    }
+                    if c == '"' {
+                        self.state = State::AttributeValueDoubleQuoted;
+                        continue;
+                    }
+                    if c == '\'' {
+                        self.state = State::AttributeValueSingleQuoted;
+                        continue;
+                    }
+                    if c == '>' {
+                        return self.emit_tag();
+                    }
+
+                    self.reconsume = true;
+                    self.state = State::AttributeValueUnquoted;
+                }
+                State::AttributeValueDoubleQuoted | State::AttributeValueSingleQuoted => {
+                    let quote = match self.state {
+                        State::AttributeValueDoubleQuoted => '"',
+                        _ => '\'',
+                    };
+                    if c == quote {
+                        self.state = State::AfterAttributeValueQuoted;
+                        continue;
+                    }
+                    if c == '&' {
+                        let c = self.consume_character_reference();
+                        self.append_attribute_value(c);
+                        continue;
+                    }
+
+                    self.append_attribute_value(c);
+                }
+                State::AttributeValueUnquoted => {
+                    if self.is_whitespace(c) {
+                        self.state = State::BeforeAttributeName;
+                        continue;
+                    }
+                    if c == '&' {
+                        let c = self.consume_character_reference();
+                        self.append_attribute_value(c);
+                        continue;
+                    }
+                    if c == '>' {
+                        return self.emit_tag();
+                    }
+
+                    self.append_attribute_value(c);
+                }
+                State::AfterAttributeValueQuoted => {
+                    if self.is_whitespace(c) {
+                        self.state = State::BeforeAttributeName;
+                        continue;
+                    }
+                    if c == '/' {
+                        self.state = State::SelfClosingStartTag;
+                        continue;
+                    }
                     if c == '>' {
+                        return self.emit_tag();
+                    }
+
+                    self.reconsume = true;
+                    self.state = State::BeforeAttributeName;
+                }
+                State::SelfClosingStartTag => {
+                    if c == '>' {
+                        return self.emit_tag();
+                    }
+
+                    self.reconsume = true;
+                    self.state = State::BeforeAttributeName;
+                }
+                State::MarkupDeclarationOpen => {
+                    self.current_token = Some(HtmlToken::Comment(String::new()));
+                    if c == '-' && self.next_chars_are("-") {
+                        self.pos += 1;
+                        self.state = State::Comment;
+                        continue;
+                    }
+                    if c.eq_ignore_ascii_case(&'d') && self.next_chars_are("octype") {
+                        self.pos += 6;
+                        self.current_token = Some(HtmlToken::Doctype(String::new()));
+                        self.state = State::Doctype;
+                        continue;
+                    }
+
+                    self.reconsume = true;
+                    self.state = State::BogusComment;
+                }
+                State::Comment => {
+                    if c == '-' && self.next_chars_are("->") {
+                        self.pos += 2;
                         self.state = State::Data;
                         return self.current_token.take();
                     }
-                    if c.is_alphabetic() {
-                        self.append_tag_name(c);
+
+                    if let Some(HtmlToken::Comment(ref mut data)) = self.current_token {
+                        data.push(c);
+                    }
+                }
+                State::BogusComment => {
+                    if c == '>' {
+                        self.state = State::Data;
+                        return self.current_token.take();
+                    }
+
+                    if let Some(HtmlToken::Comment(ref mut data)) = self.current_token {
+                        data.push(c);
+                    }
+                }
+                // https://html.spec.whatwg.org/multipage/parsing.html#before-doctype-name-state
+                State::Doctype => {
+                    if c == '>' {
+                        self.state = State::Data;
+                        return self.current_token.take();
+                    }
+                    if !self.is_whitespace(c) {
+                        self.reconsume = true;
+                        self.state = State::DoctypeName;
+                    }
+                }
+                // https://html.spec.whatwg.org/multipage/parsing.html#doctype-name-state
+                State::DoctypeName => {
+                    if c == '>' {
+                        self.state = State::Data;
+                        return self.current_token.take();
+                    }
+                    if self.is_whitespace(c) {
+                        self.state = State::AfterDoctypeName;
                         continue;
                     }
-                    if self.is_eof() {
-                        return Some(HtmlToken::Eof);
+                    if let Some(HtmlToken::Doctype(ref mut name)) = self.current_token {
+                        name.push(c.to_ascii_lowercase());
+                    }
+                }
+                // https://html.spec.whatwg.org/multipage/parsing.html#after-doctype-name-state
+                //
+                // only the name is kept, public and system identifiers are dropped
+                State::AfterDoctypeName => {
+                    if c == '>' {
+                        self.state = State::Data;
+                        return self.current_token.take();
                     }
                 }
             }
@@ -146,13 +521,15 @@ impl Iterator for HtmlTokenizer {
 
 #[cfg(test)]
 mod tests {
+    use crate::element::Attribute;
+
     #[test]
     fn test_starttag() {
         let html = String::from("<html>");
         let mut tokenizer = super::HtmlTokenizer::new(html);
         assert_eq!(
             tokenizer.next(),
-            Some(super::HtmlToken::StartTag("html".to_string()))
+            Some(super::HtmlToken::StartTag("html".to_string(), vec![]))
         );
         assert_eq!(tokenizer.next(), Some(super::HtmlToken::Eof));
         assert_eq!(tokenizer.next(), None);
@@ -164,7 +541,7 @@ mod tests {
         let mut tokenizer = super::HtmlTokenizer::new(html);
         assert_eq!(
             tokenizer.next(),
-            Some(super::HtmlToken::StartTag("html".to_string()))
+            Some(super::HtmlToken::StartTag("html".to_string(), vec![]))
         );
         assert_eq!(
             tokenizer.next(),
@@ -180,7 +557,7 @@ mod tests {
         let mut tokenizer = super::HtmlTokenizer::new(html);
         assert_eq!(
             tokenizer.next(),
-            Some(super::HtmlToken::StartTag("html".to_string()))
+            Some(super::HtmlToken::StartTag("html".to_string(), vec![]))
         );
         assert_eq!(tokenizer.next(), Some(super::HtmlToken::Char('h')));
         assert_eq!(tokenizer.next(), Some(super::HtmlToken::Char('e')));
@@ -194,4 +571,111 @@ mod tests {
         assert_eq!(tokenizer.next(), Some(super::HtmlToken::Eof));
         assert_eq!(tokenizer.next(), None);
     }
+
+    #[test]
+    fn test_trailing_char() {
+        let html = String::from("<p>hi");
+        let mut tokenizer = super::HtmlTokenizer::new(html);
+        assert_eq!(
+            tokenizer.next(),
+            Some(super::HtmlToken::StartTag("p".to_string(), vec![]))
+        );
+        assert_eq!(tokenizer.next(), Some(super::HtmlToken::Char('h')));
+        assert_eq!(tokenizer.next(), Some(super::HtmlToken::Char('i')));
+        assert_eq!(tokenizer.next(), Some(super::HtmlToken::Eof));
+    }
+
+    #[test]
+    fn test_attributes() {
+        let html =
+            String::from("<div id=main class='a b' hidden data-x=\"&lt;1&amp;\" ID=dup><h1>");
+        let mut tokenizer = super::HtmlTokenizer::new(html);
+        assert_eq!(
+            tokenizer.next(),
+            Some(super::HtmlToken::StartTag(
+                "div".to_string(),
+                vec![
+                    Attribute::new("id".to_string(), "main".to_string()),
+                    Attribute::new("class".to_string(), "a b".to_string()),
+                    Attribute::new("hidden".to_string(), "".to_string()),
+                    Attribute::new("data-x".to_string(), "<1&".to_string()),
+                ]
+            ))
+        );
+        assert_eq!(
+            tokenizer.next(),
+            Some(super::HtmlToken::StartTag("h1".to_string(), vec![]))
+        );
+    }
+
+    #[test]
+    fn test_self_closing_and_character_references() {
+        let html = String::from("<br/>&amp;&#65;&#x42;&bogus;");
+        let mut tokenizer = super::HtmlTokenizer::new(html);
+        assert_eq!(
+            tokenizer.next(),
+            Some(super::HtmlToken::StartTag("br".to_string(), vec![]))
+        );
+        let chars: String = tokenizer
+            .take_while(|t| *t != super::HtmlToken::Eof)
+            .map(|t| match t {
+                super::HtmlToken::Char(c) => c,
+                _ => panic!("unexpected token {:?}", t),
+            })
+            .collect();
+        assert_eq!(chars, "&AB&bogus;");
+    }
+
+    #[test]
+    fn test_comment_and_doctype() {
+        let html = String::from("<!DOCTYPE html><!-- a -- b --><!bogus>");
+        let mut tokenizer = super::HtmlTokenizer::new(html);
+        assert_eq!(
+            tokenizer.next(),
+            Some(super::HtmlToken::Doctype("html".to_string()))
+        );
+        assert_eq!(
+            tokenizer.next(),
+            Some(super::HtmlToken::Comment(" a -- b ".to_string()))
+        );
+        assert_eq!(
+            tokenizer.next(),
+            Some(super::HtmlToken::Comment("bogus".to_string()))
+        );
+        assert_eq!(tokenizer.next(), Some(super::HtmlToken::Eof));
+
+        let html = String::from(
+            "<!doctype html ><!DOCTYPE HTML\n><!DOCTYPE html PUBLIC \"-//W3C//DTD HTML 4.01//EN\" \
+             \"http://www.w3.org/TR/html4/strict.dtd\">",
+        );
+        let tokens: Vec<super::HtmlToken> = super::HtmlTokenizer::new(html)
+            .take_while(|t| *t != super::HtmlToken::Eof)
+            .collect();
+        assert_eq!(tokens.len(), 3);
+        assert!(tokens
+            .iter()
+            .all(|t| *t == super::HtmlToken::Doctype("html".to_string())));
+    }
+
+    #[test]
+    fn test_raw_text() {
+        let html = String::from("<style>p > a { }</p></style>");
+        let mut tokenizer = super::HtmlTokenizer::new(html);
+        assert_eq!(
+            tokenizer.next(),
+            Some(super::HtmlToken::StartTag("style".to_string(), vec![]))
+        );
+        let mut text = String::new();
+        loop {
+            match tokenizer.next() {
+                Some(super::HtmlToken::Char(c)) => text.push(c),
+                Some(token) => {
+                    assert_eq!(token, super::HtmlToken::EndTag("style".to_string()));
+                    break;
+                }
+                None => panic!("unexpected end of input"),
+            }
+        }
+        assert_eq!(text, "p > a { }</p>");
+    }
 }
@@ -1,2 +1,3 @@
-mod html_parser;
-mod html_tokenizer;
+pub mod css;
+pub mod html_parser;
+pub mod html_tokenizer;
@@ -0,0 +1,866 @@
+use crate::node::{DomException, Node, NodeKind};
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+// https://dom.spec.whatwg.org/#concept-range-bp
+#[derive(Clone, Debug)]
+pub struct BoundaryPoint {
+    pub node: Rc<RefCell<Node>>,
+    pub offset: usize,
+}
+
+impl BoundaryPoint {
+    pub fn new(node: &Rc<RefCell<Node>>, offset: usize) -> Self {
+        Self {
+            node: node.clone(),
+            offset,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct RangeState {
+    start: BoundaryPoint,
+    end: BoundaryPoint,
+}
+
+// https://dom.spec.whatwg.org/#interface-range
+//
+// A handle to a live range: clones share the same boundary points, and the
+// tree mutation methods on `Node` keep them in place as the tree changes.
+// Offsets into text count chars rather than UTF-16 code units.
+#[derive(Clone, Debug)]
+pub struct Range(Rc<RefCell<RangeState>>);
+
+thread_local! {
+    static LIVE_RANGES: RefCell<Vec<Weak<RefCell<RangeState>>>> = const { RefCell::new(Vec::new()) };
+}
+
+impl Range {
+    // https://dom.spec.whatwg.org/#dom-range-range
+    pub fn new(document: &Rc<RefCell<Node>>) -> Self {
+        Self::from_points(
+            BoundaryPoint::new(document, 0),
+            BoundaryPoint::new(document, 0),
+        )
+    }
+
+    fn from_points(start: BoundaryPoint, end: BoundaryPoint) -> Self {
+        let state = Rc::new(RefCell::new(RangeState { start, end }));
+        LIVE_RANGES.with(|ranges| {
+            let mut ranges = ranges.borrow_mut();
+            ranges.retain(|r| r.strong_count() > 0);
+            ranges.push(Rc::downgrade(&state));
+        });
+        Range(state)
+    }
+
+    pub fn start(&self) -> BoundaryPoint {
+        self.0.borrow().start.clone()
+    }
+
+    pub fn end(&self) -> BoundaryPoint {
+        self.0.borrow().end.clone()
+    }
+
+    pub fn start_container(&self) -> Rc<RefCell<Node>> {
+        self.0.borrow().start.node.clone()
+    }
+
+    pub fn start_offset(&self) -> usize {
+        self.0.borrow().start.offset
+    }
+
+    pub fn end_container(&self) -> Rc<RefCell<Node>> {
+        self.0.borrow().end.node.clone()
+    }
+
+    pub fn end_offset(&self) -> usize {
+        self.0.borrow().end.offset
+    }
+
+    // https://dom.spec.whatwg.org/#range-collapsed
+    pub fn collapsed(&self) -> bool {
+        let state = self.0.borrow();
+        Rc::ptr_eq(&state.start.node, &state.end.node) && state.start.offset == state.end.offset
+    }
+
+    // https://dom.spec.whatwg.org/#concept-range-root
+    pub fn root(&self) -> Rc<RefCell<Node>> {
+        root(&self.start_container())
+    }
+
+    // https://dom.spec.whatwg.org/#dom-range-commonancestorcontainer
+    pub fn common_ancestor_container(&self) -> Rc<RefCell<Node>> {
+        let end = self.end_container();
+        Node::inclusive_ancestors(&self.start_container())
+            .find(|n| Node::is_inclusive_ancestor_of(n, &end))
+            .expect("the boundary points share a root")
+    }
+
+    // https://dom.spec.whatwg.org/#dom-range-clonerange
+    pub fn clone_range(&self) -> Range {
+        Self::from_points(self.start(), self.end())
+    }
+
+    // Whether both handles refer to the same live range.
+    pub fn same_range(&self, other: &Range) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+
+    // https://dom.spec.whatwg.org/#concept-range-bp-set
+    fn set_boundary(
+        &self,
+        node: &Rc<RefCell<Node>>,
+        offset: usize,
+        is_start: bool,
+    ) -> Result<(), DomException> {
+        if node.borrow().is_doctype() {
+            return Err(DomException::InvalidNodeTypeError);
+        }
+        if offset > length(node) {
+            return Err(DomException::IndexSizeError);
+        }
+
+        let point = BoundaryPoint::new(node, offset);
+        let mut state = self.0.borrow_mut();
+        let same_root = Rc::ptr_eq(&root(node), &root(&state.start.node));
+        if is_start {
+            if !same_root || compare_points(&point, &state.end) == Ordering::Greater {
+                state.end = point.clone();
+            }
+            state.start = point;
+        } else {
+            if !same_root || compare_points(&point, &state.start) == Ordering::Less {
+                state.start = point.clone();
+            }
+            state.end = point;
+        }
+        Ok(())
+    }
+
+    // https://dom.spec.whatwg.org/#dom-range-setstart
+    pub fn set_start(&self, node: &Rc<RefCell<Node>>, offset: usize) -> Result<(), DomException> {
+        self.set_boundary(node, offset, true)
+    }
+
+    // https://dom.spec.whatwg.org/#dom-range-setend
+    pub fn set_end(&self, node: &Rc<RefCell<Node>>, offset: usize) -> Result<(), DomException> {
+        self.set_boundary(node, offset, false)
+    }
+
+    pub fn set_start_before(&self, node: &Rc<RefCell<Node>>) -> Result<(), DomException> {
+        let parent = parent_of(node)?;
+        self.set_start(&parent, index(node))
+    }
+
+    pub fn set_start_after(&self, node: &Rc<RefCell<Node>>) -> Result<(), DomException> {
+        let parent = parent_of(node)?;
+        self.set_start(&parent, index(node) + 1)
+    }
+
+    pub fn set_end_before(&self, node: &Rc<RefCell<Node>>) -> Result<(), DomException> {
+        let parent = parent_of(node)?;
+        self.set_end(&parent, index(node))
+    }
+
+    pub fn set_end_after(&self, node: &Rc<RefCell<Node>>) -> Result<(), DomException> {
+        let parent = parent_of(node)?;
+        self.set_end(&parent, index(node) + 1)
+    }
+
+    // https://dom.spec.whatwg.org/#dom-range-collapse
+    pub fn collapse(&self, to_start: bool) {
+        let mut state = self.0.borrow_mut();
+        if to_start {
+            state.end = state.start.clone();
+        } else {
+            state.start = state.end.clone();
+        }
+    }
+
+    // https://dom.spec.whatwg.org/#concept-range-select
+    pub fn select_node(&self, node: &Rc<RefCell<Node>>) -> Result<(), DomException> {
+        let parent = parent_of(node)?;
+        let index = index(node);
+        let mut state = self.0.borrow_mut();
+        state.start = BoundaryPoint::new(&parent, index);
+        state.end = BoundaryPoint::new(&parent, index + 1);
+        Ok(())
+    }
+
+    // https://dom.spec.whatwg.org/#dom-range-selectnodecontents
+    pub fn select_node_contents(&self, node: &Rc<RefCell<Node>>) -> Result<(), DomException> {
+        if node.borrow().is_doctype() {
+            return Err(DomException::InvalidNodeTypeError);
+        }
+        let mut state = self.0.borrow_mut();
+        state.start = BoundaryPoint::new(node, 0);
+        state.end = BoundaryPoint::new(node, length(node));
+        Ok(())
+    }
+
+    // https://dom.spec.whatwg.org/#dom-range-ispointinrange
+    pub fn is_point_in_range(
+        &self,
+        node: &Rc<RefCell<Node>>,
+        offset: usize,
+    ) -> Result<bool, DomException> {
+        if !Rc::ptr_eq(&root(node), &self.root()) {
+            return Ok(false);
+        }
+        if node.borrow().is_doctype() {
+            return Err(DomException::InvalidNodeTypeError);
+        }
+        if offset > length(node) {
+            return Err(DomException::IndexSizeError);
+        }
+
+        let point = BoundaryPoint::new(node, offset);
+        let state = self.0.borrow();
+        Ok(compare_points(&point, &state.start) != Ordering::Less
+            && compare_points(&point, &state.end) != Ordering::Greater)
+    }
+
+    // https://dom.spec.whatwg.org/#contained
+    pub fn contains_node(&self, node: &Rc<RefCell<Node>>) -> bool {
+        let state = self.0.borrow();
+        Rc::ptr_eq(&root(node), &root(&state.start.node))
+            && compare_points(&BoundaryPoint::new(node, 0), &state.start) == Ordering::Greater
+            && compare_points(&BoundaryPoint::new(node, length(node)), &state.end) == Ordering::Less
+    }
+
+    // https://dom.spec.whatwg.org/#partially-contained
+    pub fn partially_contains_node(&self, node: &Rc<RefCell<Node>>) -> bool {
+        Node::is_inclusive_ancestor_of(node, &self.start_container())
+            != Node::is_inclusive_ancestor_of(node, &self.end_container())
+    }
+
+    // https://dom.spec.whatwg.org/#dom-range-deletecontents
+    //
+    // Leaves the range where extracting would; the removed contents are
+    // dropped instead of returned.
+    pub fn delete_contents(&self) -> Result<(), DomException> {
+        self.extract_contents().map(|_| ())
+    }
+
+    // https://dom.spec.whatwg.org/#concept-range-extract
+    pub fn extract_contents(&self) -> Result<Rc<RefCell<Node>>, DomException> {
+        self.copy_contents(true)
+    }
+
+    // https://dom.spec.whatwg.org/#concept-range-clone
+    pub fn clone_contents(&self) -> Result<Rc<RefCell<Node>>, DomException> {
+        self.copy_contents(false)
+    }
+
+    // The extract and clone algorithms only differ in whether the range's
+    // contents are moved out of the tree or copied.
+    fn copy_contents(&self, extract: bool) -> Result<Rc<RefCell<Node>>, DomException> {
+        let BoundaryPoint {
+            node: start_node,
+            offset: start_offset,
+        } = self.start();
+        let BoundaryPoint {
+            node: end_node,
+            offset: end_offset,
+        } = self.end();
+
+        let fragment = Rc::new(RefCell::new(Node::new(NodeKind::DocumentFragment)));
+        if let Some(document) = Node::node_document(&start_node) {
+            Node::adopt(&document, &fragment)?;
+        }
+        if self.collapsed() {
+            return Ok(fragment);
+        }
+
+        if Rc::ptr_eq(&start_node, &end_node) && is_character_data(&start_node) {
+            let clone = Node::clone_node(&start_node, false);
+            let data = substring(
+                &data_of(&start_node),
+                start_offset,
+                end_offset - start_offset,
+            );
+            Node::set_data(&clone, &data);
+            Node::append_child_node(&fragment, &clone)?;
+            if extract {
+                Node::replace_data(&start_node, start_offset, end_offset - start_offset, "");
+            }
+            return Ok(fragment);
+        }
+
+        let common_ancestor = self.common_ancestor_container();
+        let first_partially_contained = if Node::is_inclusive_ancestor_of(&start_node, &end_node) {
+            None
+        } else {
+            Node::children(&common_ancestor).find(|c| self.partially_contains_node(c))
+        };
+        let last_partially_contained = if Node::is_inclusive_ancestor_of(&end_node, &start_node) {
+            None
+        } else {
+            Node::children(&common_ancestor)
+                .rev()
+                .find(|c| self.partially_contains_node(c))
+        };
+        let contained: Vec<_> = Node::children(&common_ancestor)
+            .filter(|c| self.contains_node(c))
+            .collect();
+        if contained.iter().any(|c| c.borrow().is_doctype()) {
+            return Err(DomException::HierarchyRequestError);
+        }
+
+        // where the range collapses to once the contents are gone
+        let new_point = if Node::is_inclusive_ancestor_of(&start_node, &end_node) {
+            BoundaryPoint::new(&start_node, start_offset)
+        } else {
+            let reference = Node::inclusive_ancestors(&start_node)
+                .find(|n| match n.borrow().parent() {
+                    Some(ref p) => Node::is_inclusive_ancestor_of(p, &end_node),
+                    None => true,
+                })
+                .expect("the start node has a root");
+            let parent = reference
+                .borrow()
+                .parent()
+                .expect("the reference node has a parent");
+            BoundaryPoint::new(&parent, index(&reference) + 1)
+        };
+
+        if let Some(ref child) = first_partially_contained {
+            let clone = Node::clone_node(child, false);
+            if is_character_data(child) {
+                let count = length(&start_node) - start_offset;
+                Node::set_data(
+                    &clone,
+                    &substring(&data_of(&start_node), start_offset, count),
+                );
+                Node::append_child_node(&fragment, &clone)?;
+                if extract {
+                    Node::replace_data(&start_node, start_offset, count, "");
+                }
+            } else {
+                Node::append_child_node(&fragment, &clone)?;
+                let subrange = Self::from_points(
+                    BoundaryPoint::new(&start_node, start_offset),
+                    BoundaryPoint::new(child, length(child)),
+                );
+                Node::append_child_node(&clone, &subrange.copy_contents(extract)?)?;
+            }
+        }
+
+        for child in contained {
+            let child = if extract {
+                child
+            } else {
+                Node::clone_node(&child, true)
+            };
+            Node::append_child_node(&fragment, &child)?;
+        }
+
+        if let Some(ref child) = last_partially_contained {
+            let clone = Node::clone_node(child, false);
+            if is_character_data(child) {
+                Node::set_data(&clone, &substring(&data_of(&end_node), 0, end_offset));
+                Node::append_child_node(&fragment, &clone)?;
+                if extract {
+                    Node::replace_data(&end_node, 0, end_offset, "");
+                }
+            } else {
+                Node::append_child_node(&fragment, &clone)?;
+                let subrange = Self::from_points(
+                    BoundaryPoint::new(child, 0),
+                    BoundaryPoint::new(&end_node, end_offset),
+                );
+                Node::append_child_node(&clone, &subrange.copy_contents(extract)?)?;
+            }
+        }
+
+        if extract {
+            let mut state = self.0.borrow_mut();
+            state.start = new_point.clone();
+            state.end = new_point;
+        }
+        Ok(fragment)
+    }
+
+    // https://dom.spec.whatwg.org/#concept-range-insert
+    pub fn insert_node(&self, node: &Rc<RefCell<Node>>) -> Result<(), DomException> {
+        let BoundaryPoint {
+            node: start_node,
+            offset: start_offset,
+        } = self.start();
+        let start_is_text = matches!(start_node.borrow().kind, NodeKind::Text(_));
+        if matches!(start_node.borrow().kind, NodeKind::Comment(_))
+            || (start_is_text && start_node.borrow().parent().is_none())
+            || Rc::ptr_eq(&start_node, node)
+        {
+            return Err(DomException::HierarchyRequestError);
+        }
+
+        let mut reference = if start_is_text {
+            Some(start_node.clone())
+        } else {
+            Node::children(&start_node).nth(start_offset)
+        };
+        let parent = match reference {
+            Some(ref r) => r
+                .borrow()
+                .parent()
+                .expect("the reference node has a parent"),
+            None => start_node.clone(),
+        };
+        Node::ensure_pre_insertion_validity(&parent, node, reference.as_ref())?;
+
+        if start_is_text {
+            reference = Some(Node::split_text(&start_node, start_offset)?);
+        }
+        if reference.as_ref().is_some_and(|r| Rc::ptr_eq(r, node)) {
+            reference = node.borrow().next_sibling();
+        }
+        Node::detach(node);
+
+        let mut new_offset = match reference {
+            Some(ref r) => index(r),
+            None => length(&parent),
+        };
+        new_offset += if node.borrow().is_document_fragment() {
+            length(node)
+        } else {
+            1
+        };
+
+        Node::insert_before(&parent, node, reference.as_ref())?;
+
+        if self.collapsed() {
+            self.0.borrow_mut().end = BoundaryPoint::new(&parent, new_offset);
+        }
+        Ok(())
+    }
+
+    // https://dom.spec.whatwg.org/#dom-range-surroundcontents
+    pub fn surround_contents(&self, new_parent: &Rc<RefCell<Node>>) -> Result<(), DomException> {
+        let common_ancestor = self.common_ancestor_container();
+        let partially_contained_non_text = Node::inclusive_descendants(&common_ancestor).any(|n| {
+            !matches!(n.borrow().kind, NodeKind::Text(_)) && self.partially_contains_node(&n)
+        });
+        if partially_contained_non_text {
+            return Err(DomException::InvalidStateError);
+        }
+        match new_parent.borrow().kind {
+            NodeKind::Document(_) | NodeKind::DocumentType(_) | NodeKind::DocumentFragment => {
+                return Err(DomException::InvalidNodeTypeError)
+            }
+            _ => {}
+        }
+
+        let fragment = self.extract_contents()?;
+        for child in Node::children(new_parent).collect::<Vec<_>>() {
+            Node::detach(&child);
+        }
+        self.insert_node(new_parent)?;
+        Node::append_child_node(new_parent, &fragment)?;
+        self.select_node(new_parent)
+    }
+}
+
+// https://dom.spec.whatwg.org/#dom-range-stringifier
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let BoundaryPoint {
+            node: start_node,
+            offset: start_offset,
+        } = self.start();
+        let BoundaryPoint {
+            node: end_node,
+            offset: end_offset,
+        } = self.end();
+        let is_text = |n: &Rc<RefCell<Node>>| matches!(n.borrow().kind, NodeKind::Text(_));
+
+        if Rc::ptr_eq(&start_node, &end_node) && is_text(&start_node) {
+            let data = data_of(&start_node);
+            return write!(
+                f,
+                "{}",
+                substring(&data, start_offset, end_offset - start_offset)
+            );
+        }
+
+        if is_text(&start_node) {
+            let data = data_of(&start_node);
+            write!(
+                f,
+                "{}",
+                substring(&data, start_offset, length(&start_node) - start_offset)
+            )?;
+        }
+        for text in Node::descendants(&self.common_ancestor_container()) {
+            if is_text(&text) && self.contains_node(&text) {
+                write!(f, "{}", data_of(&text))?;
+            }
+        }
+        if is_text(&end_node) {
+            write!(f, "{}", substring(&data_of(&end_node), 0, end_offset))?;
+        }
+        Ok(())
+    }
+}
+
+// https://dom.spec.whatwg.org/#concept-range-bp-position
+pub(crate) fn compare_points(a: &BoundaryPoint, b: &BoundaryPoint) -> Ordering {
+    if Rc::ptr_eq(&a.node, &b.node) {
+        return a.offset.cmp(&b.offset);
+    }
+    if tree_order(&a.node, &b.node) == Ordering::Greater {
+        return compare_points(b, a).reverse();
+    }
+    if Node::is_inclusive_ancestor_of(&a.node, &b.node) {
+        let child = Node::inclusive_ancestors(&b.node)
+            .find(|n| n.borrow().parent().is_some_and(|p| Rc::ptr_eq(&p, &a.node)))
+            .expect("a is an ancestor of b");
+        if index(&child) < a.offset {
+            return Ordering::Greater;
+        }
+    }
+    Ordering::Less
+}
+
+// https://dom.spec.whatwg.org/#concept-tree-order
+fn tree_order(a: &Rc<RefCell<Node>>, b: &Rc<RefCell<Node>>) -> Ordering {
+    let mut a_path: Vec<_> = Node::inclusive_ancestors(a).collect();
+    let mut b_path: Vec<_> = Node::inclusive_ancestors(b).collect();
+    a_path.reverse();
+    b_path.reverse();
+
+    for (x, y) in a_path.iter().zip(b_path.iter()) {
+        if !Rc::ptr_eq(x, y) {
+            return index(x).cmp(&index(y));
+        }
+    }
+    // one is an inclusive ancestor of the other
+    a_path.len().cmp(&b_path.len())
+}
+
+// https://dom.spec.whatwg.org/#concept-node-length
+pub(crate) fn length(node: &Rc<RefCell<Node>>) -> usize {
+    match node.borrow().kind {
+        NodeKind::DocumentType(_) => 0,
+        NodeKind::Text(ref s) | NodeKind::Comment(ref s) => s.chars().count(),
+        _ => Node::children(node).count(),
+    }
+}
+
+// https://dom.spec.whatwg.org/#concept-tree-index
+pub(crate) fn index(node: &Rc<RefCell<Node>>) -> usize {
+    Node::preceding_siblings(node).count()
+}
+
+fn root(node: &Rc<RefCell<Node>>) -> Rc<RefCell<Node>> {
+    Node::inclusive_ancestors(node)
+        .last()
+        .expect("a node is its own inclusive ancestor")
+}
+
+fn parent_of(node: &Rc<RefCell<Node>>) -> Result<Rc<RefCell<Node>>, DomException> {
+    node.borrow()
+        .parent()
+        .ok_or(DomException::InvalidNodeTypeError)
+}
+
+fn is_character_data(node: &Rc<RefCell<Node>>) -> bool {
+    matches!(node.borrow().kind, NodeKind::Text(_) | NodeKind::Comment(_))
+}
+
+fn data_of(node: &Rc<RefCell<Node>>) -> String {
+    match node.borrow().kind {
+        NodeKind::Text(ref s) | NodeKind::Comment(ref s) => s.clone(),
+        _ => String::new(),
+    }
+}
+
+pub(crate) fn substring(s: &str, offset: usize, count: usize) -> String {
+    s.chars().skip(offset).take(count).collect()
+}
+
+// Calls `f` with the boundary points of every live range.
+fn for_each_live_point(mut f: impl FnMut(&mut BoundaryPoint)) {
+    let ranges: Vec<_> =
+        LIVE_RANGES.with(|r| r.borrow().iter().filter_map(|r| r.upgrade()).collect());
+    for range in ranges {
+        let mut state = range.borrow_mut();
+        f(&mut state.start);
+        f(&mut state.end);
+    }
+}
+
+fn has_live_ranges() -> bool {
+    LIVE_RANGES.with(|r| r.borrow().iter().any(|r| r.strong_count() > 0))
+}
+
+// https://dom.spec.whatwg.org/#concept-node-insert (step 4)
+pub(crate) fn adjust_for_insert(
+    parent: &Rc<RefCell<Node>>,
+    child: Option<&Rc<RefCell<Node>>>,
+    count: usize,
+) {
+    let child = match child {
+        Some(c) if has_live_ranges() => c,
+        _ => return,
+    };
+    let index = index(child);
+    for_each_live_point(|point| {
+        if Rc::ptr_eq(&point.node, parent) && point.offset > index {
+            point.offset += count;
+        }
+    });
+}
+
+// https://dom.spec.whatwg.org/#concept-node-remove (steps 4-7)
+pub(crate) fn adjust_for_remove(node: &Rc<RefCell<Node>>, parent: &Rc<RefCell<Node>>) {
+    if !has_live_ranges() {
+        return;
+    }
+    let index = index(node);
+    for_each_live_point(|point| {
+        if Node::is_inclusive_ancestor_of(node, &point.node) {
+            *point = BoundaryPoint::new(parent, index);
+        } else if Rc::ptr_eq(&point.node, parent) && point.offset > index {
+            point.offset -= 1;
+        }
+    });
+}
+
+// https://dom.spec.whatwg.org/#concept-cd-replace (steps 8-11)
+pub(crate) fn adjust_for_replace_data(
+    node: &Rc<RefCell<Node>>,
+    offset: usize,
+    count: usize,
+    new_length: usize,
+) {
+    for_each_live_point(|point| {
+        if !Rc::ptr_eq(&point.node, node) || point.offset <= offset {
+            return;
+        }
+        if point.offset <= offset + count {
+            point.offset = offset;
+        } else {
+            point.offset = point.offset + new_length - count;
+        }
+    });
+}
+
+// https://dom.spec.whatwg.org/#concept-text-split (step 7)
+pub(crate) fn adjust_for_split(
+    node: &Rc<RefCell<Node>>,
+    new_node: &Rc<RefCell<Node>>,
+    offset: usize,
+) {
+    let parent = match node.borrow().parent() {
+        Some(p) => p,
+        None => return,
+    };
+    if !has_live_ranges() {
+        return;
+    }
+    let index = index(node);
+    for_each_live_point(|point| {
+        if Rc::ptr_eq(&point.node, node) && point.offset > offset {
+            *point = BoundaryPoint::new(new_node, point.offset - offset);
+        } else if Rc::ptr_eq(&point.node, &parent) && point.offset == index + 1 {
+            point.offset += 1;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::element::{Element, HtmlElementKind};
+    use crate::parser::html_parser::parse;
+
+    fn by_id(document: &Rc<RefCell<Node>>, id: &str) -> Rc<RefCell<Node>> {
+        Node::get_element_by_id(document, id).unwrap()
+    }
+
+    fn first_child(node: &Rc<RefCell<Node>>) -> Rc<RefCell<Node>> {
+        node.borrow().first_child().unwrap()
+    }
+
+    #[test]
+    fn test_set_boundaries() {
+        let document = parse("<p id=a>hello</p><p id=b>world</p>");
+        let hello = first_child(&by_id(&document, "a"));
+        let world = first_child(&by_id(&document, "b"));
+        let range = Range::new(&document);
+
+        range.set_start(&hello, 1).unwrap();
+        range.set_end(&world, 3).unwrap();
+        assert!(!range.collapsed());
+        assert_eq!(range.to_string(), "ellowor");
+        assert!(Rc::ptr_eq(
+            &range.common_ancestor_container(),
+            &Node::body(&document).unwrap()
+        ));
+
+        // a start after the end collapses the range
+        range.set_start(&world, 4).unwrap();
+        assert!(range.collapsed());
+        assert_eq!(range.end_offset(), 4);
+
+        assert_eq!(
+            range.set_start(&hello, 6).err(),
+            Some(DomException::IndexSizeError)
+        );
+        let doctype = Rc::new(RefCell::new(Node::new(NodeKind::DocumentType(
+            String::from("html"),
+        ))));
+        assert_eq!(
+            range.set_end(&doctype, 0).err(),
+            Some(DomException::InvalidNodeTypeError)
+        );
+    }
+
+    #[test]
+    fn test_select_node_and_points() {
+        let document = parse("<div id=a><p id=b>x</p><p id=c>y</p></div>");
+        let a = by_id(&document, "a");
+        let c = by_id(&document, "c");
+        let range = Range::new(&document);
+
+        range.select_node(&c).unwrap();
+        assert!(Rc::ptr_eq(&range.start_container(), &a));
+        assert_eq!((range.start_offset(), range.end_offset()), (1, 2));
+        assert!(range.contains_node(&c));
+        assert!(!range.contains_node(&by_id(&document, "b")));
+        assert!(range.is_point_in_range(&first_child(&c), 0).unwrap());
+        assert!(!range.is_point_in_range(&a, 0).unwrap());
+
+        range.select_node_contents(&a).unwrap();
+        assert_eq!(range.to_string(), "xy");
+    }
+
+    #[test]
+    fn test_clone_and_extract_contents() {
+        let document = parse("<div id=a><p id=b>hello</p><span>mid</span><p id=c>world</p></div>");
+        let a = by_id(&document, "a");
+        let range = Range::new(&document);
+        range
+            .set_start(&first_child(&by_id(&document, "b")), 2)
+            .unwrap();
+        range
+            .set_end(&first_child(&by_id(&document, "c")), 3)
+            .unwrap();
+
+        let copy = range.clone_contents().unwrap();
+        assert_eq!(
+            Node::inner_html(&copy),
+            "<p id=\"b\">llo</p><span>mid</span><p id=\"c\">wor</p>"
+        );
+        // cloning leaves the tree alone
+        assert_eq!(Node::text_content(&a).unwrap(), "hellomidworld");
+
+        let extracted = range.extract_contents().unwrap();
+        assert_eq!(Node::inner_html(&extracted), Node::inner_html(&copy));
+        assert_eq!(Node::inner_html(&a), "<p id=\"b\">he</p><p id=\"c\">ld</p>");
+        assert!(range.collapsed());
+        assert!(Rc::ptr_eq(&range.start_container(), &a));
+        assert_eq!(range.start_offset(), 1);
+    }
+
+    #[test]
+    fn test_extract_within_text() {
+        let document = parse("<p id=a>hello world</p>");
+        let text = first_child(&by_id(&document, "a"));
+        let range = Range::new(&document);
+        range.set_start(&text, 2).unwrap();
+        range.set_end(&text, 7).unwrap();
+
+        range.delete_contents().unwrap();
+        assert_eq!(Node::text_content(&text).unwrap(), "heorld");
+        assert!(range.collapsed());
+        assert_eq!(range.start_offset(), 2);
+    }
+
+    #[test]
+    fn test_insert_node_and_surround_contents() {
+        let document = parse("<p id=a>hello world</p>");
+        let p = by_id(&document, "a");
+        let text = first_child(&p);
+        let range = Range::new(&document);
+        range.set_start(&text, 6).unwrap();
+        range.set_end(&text, 11).unwrap();
+
+        let span = Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new(
+            HtmlElementKind::Span,
+        )))));
+        range.surround_contents(&span).unwrap();
+        assert_eq!(Node::inner_html(&p), "hello <span>world</span>");
+        assert!(Rc::ptr_eq(&range.start_container(), &p));
+        assert_eq!((range.start_offset(), range.end_offset()), (1, 2));
+
+        let br = Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new(
+            HtmlElementKind::Br,
+        )))));
+        range.collapse(true);
+        range.insert_node(&br).unwrap();
+        assert_eq!(Node::inner_html(&p), "hello <br><span>world</span>");
+        assert_eq!((range.start_offset(), range.end_offset()), (1, 2));
+    }
+
+    #[test]
+    fn test_surround_rejects_partial_elements() {
+        let document = parse("<p id=a>one</p><p id=b>two</p>");
+        let range = Range::new(&document);
+        range
+            .set_start(&first_child(&by_id(&document, "a")), 1)
+            .unwrap();
+        range
+            .set_end(&first_child(&by_id(&document, "b")), 1)
+            .unwrap();
+
+        let span = Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new(
+            HtmlElementKind::Span,
+        )))));
+        assert_eq!(
+            range.surround_contents(&span).err(),
+            Some(DomException::InvalidStateError)
+        );
+    }
+
+    #[test]
+    fn test_live_range_follows_mutations() {
+        let document = parse("<div id=a><p id=b>x</p><p id=c>y</p></div>");
+        let a = by_id(&document, "a");
+        let b = by_id(&document, "b");
+        let c = by_id(&document, "c");
+        let range = Range::new(&document);
+        range.set_start(&a, 1).unwrap();
+        range.set_end(&first_child(&c), 1).unwrap();
+
+        // inserting before the start boundary shifts it
+        let span = Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new(
+            HtmlElementKind::Span,
+        )))));
+        Node::prepend(&a, &span).unwrap();
+        assert_eq!(range.start_offset(), 2);
+
+        Node::detach(&b);
+        assert_eq!(range.start_offset(), 1);
+
+        // removing the end container moves the end to where it was
+        Node::detach(&c);
+        assert!(Rc::ptr_eq(&range.end_container(), &a));
+        assert_eq!(range.end_offset(), 1);
+
+        let text = Rc::new(RefCell::new(Node::new(NodeKind::Text(String::from(
+            "abcdef",
+        )))));
+        Node::append_child_node(&a, &text).unwrap();
+        range.set_start(&text, 2).unwrap();
+        range.set_end(&text, 5).unwrap();
+        Node::replace_data(&text, 1, 2, "");
+        assert_eq!((range.start_offset(), range.end_offset()), (1, 3));
+        assert_eq!(range.to_string(), "de");
+    }
+}
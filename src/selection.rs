@@ -0,0 +1,398 @@
+use crate::node::{DomException, Node, NodeKind};
+use crate::range::{compare_points, length, BoundaryPoint, Range};
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+// https://w3c.github.io/selection-api/#dfn-direction
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SelectionDirection {
+    Forwards,
+    Backwards,
+    Directionless,
+}
+
+// https://w3c.github.io/selection-api/#selection-interface
+//
+// The selection of one document, holding at most one range. The document
+// owns it; the selection only keeps a weak reference to the document so
+// the two don't keep each other alive.
+#[derive(Debug)]
+pub struct Selection {
+    document: Weak<RefCell<Node>>,
+    range: Option<Range>,
+    direction: SelectionDirection,
+}
+
+impl Node {
+    // https://w3c.github.io/selection-api/#dom-document-getselection
+    //
+    // The same selection every time, None for other nodes.
+    pub fn get_selection(document: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Selection>>> {
+        if let NodeKind::Document(ref d) = document.borrow().kind {
+            if let Some(ref selection) = d.selection {
+                return Some(selection.clone());
+            }
+        }
+        match document.borrow_mut().kind {
+            NodeKind::Document(ref mut d) => {
+                let selection = Rc::new(RefCell::new(Selection::new(document)));
+                d.selection = Some(selection.clone());
+                Some(selection)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Selection {
+    fn new(document: &Rc<RefCell<Node>>) -> Self {
+        Self {
+            document: Rc::downgrade(document),
+            range: None,
+            direction: SelectionDirection::Directionless,
+        }
+    }
+
+    // whether `node` is in the selection's document
+    fn in_document(&self, node: &Rc<RefCell<Node>>) -> bool {
+        match (
+            self.document.upgrade(),
+            Node::inclusive_ancestors(node).last(),
+        ) {
+            (Some(document), Some(root)) => Rc::ptr_eq(&document, &root),
+            _ => false,
+        }
+    }
+
+    fn check_point(node: &Rc<RefCell<Node>>, offset: usize) -> Result<(), DomException> {
+        if node.borrow().is_doctype() {
+            return Err(DomException::InvalidNodeTypeError);
+        }
+        if offset > length(node) {
+            return Err(DomException::IndexSizeError);
+        }
+        Ok(())
+    }
+
+    pub fn range_count(&self) -> usize {
+        match self.range {
+            Some(ref range) if self.in_document(&range.start_container()) => 1,
+            _ => 0,
+        }
+    }
+
+    pub fn direction(&self) -> SelectionDirection {
+        self.direction
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-anchornode
+    pub fn anchor(&self) -> Option<BoundaryPoint> {
+        let range = self.range.as_ref()?;
+        match self.direction {
+            SelectionDirection::Backwards => Some(range.end()),
+            _ => Some(range.start()),
+        }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-focusnode
+    pub fn focus(&self) -> Option<BoundaryPoint> {
+        let range = self.range.as_ref()?;
+        match self.direction {
+            SelectionDirection::Backwards => Some(range.start()),
+            _ => Some(range.end()),
+        }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-iscollapsed
+    pub fn is_collapsed(&self) -> bool {
+        self.range.as_ref().is_none_or(|r| r.collapsed())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-getrangeat
+    pub fn get_range_at(&self, index: usize) -> Result<Range, DomException> {
+        match self.range {
+            Some(ref range) if index == 0 => Ok(range.clone()),
+            _ => Err(DomException::IndexSizeError),
+        }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-addrange
+    //
+    // Ignored when the range is in another tree or a range is already
+    // selected.
+    pub fn add_range(&mut self, range: &Range) {
+        if !self.in_document(&range.root()) || self.range.is_some() {
+            return;
+        }
+        self.range = Some(range.clone());
+        self.direction = SelectionDirection::Forwards;
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-removerange
+    pub fn remove_range(&mut self, range: &Range) -> Result<(), DomException> {
+        match self.range {
+            Some(ref r) if r.same_range(range) => {
+                self.remove_all_ranges();
+                Ok(())
+            }
+            _ => Err(DomException::NotFoundError),
+        }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-removeallranges
+    pub fn remove_all_ranges(&mut self) {
+        self.range = None;
+        self.direction = SelectionDirection::Directionless;
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-collapse
+    //
+    // Without a node the selection is emptied.
+    pub fn collapse(
+        &mut self,
+        node: Option<&Rc<RefCell<Node>>>,
+        offset: usize,
+    ) -> Result<(), DomException> {
+        let node = match node {
+            Some(n) => n,
+            None => {
+                self.remove_all_ranges();
+                return Ok(());
+            }
+        };
+        Self::check_point(node, offset)?;
+        if !self.in_document(node) {
+            return Ok(());
+        }
+
+        let range = Range::new(node);
+        range.set_start(node, offset)?;
+        self.range = Some(range);
+        self.direction = SelectionDirection::Directionless;
+        Ok(())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-collapsetostart
+    pub fn collapse_to_start(&mut self) -> Result<(), DomException> {
+        let start = self
+            .range
+            .as_ref()
+            .ok_or(DomException::InvalidStateError)?
+            .start();
+        self.collapse(Some(&start.node), start.offset)
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-collapsetoend
+    pub fn collapse_to_end(&mut self) -> Result<(), DomException> {
+        let end = self
+            .range
+            .as_ref()
+            .ok_or(DomException::InvalidStateError)?
+            .end();
+        self.collapse(Some(&end.node), end.offset)
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-extend
+    //
+    // Moves the focus to the given point, keeping the anchor.
+    pub fn extend(&mut self, node: &Rc<RefCell<Node>>, offset: usize) -> Result<(), DomException> {
+        let anchor = self.anchor().ok_or(DomException::InvalidStateError)?;
+        if !self.in_document(node) {
+            return Ok(());
+        }
+        Self::check_point(node, offset)?;
+
+        let focus = BoundaryPoint::new(node, offset);
+        let range = Range::new(node);
+        if compare_points(&anchor, &focus) == Ordering::Greater {
+            range.set_start(&focus.node, focus.offset)?;
+            range.set_end(&anchor.node, anchor.offset)?;
+            self.direction = SelectionDirection::Backwards;
+        } else {
+            range.set_start(&anchor.node, anchor.offset)?;
+            range.set_end(&focus.node, focus.offset)?;
+            self.direction = SelectionDirection::Forwards;
+        }
+        self.range = Some(range);
+        Ok(())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-setbaseandextent
+    pub fn set_base_and_extent(
+        &mut self,
+        anchor_node: &Rc<RefCell<Node>>,
+        anchor_offset: usize,
+        focus_node: &Rc<RefCell<Node>>,
+        focus_offset: usize,
+    ) -> Result<(), DomException> {
+        Self::check_point(anchor_node, anchor_offset)?;
+        Self::check_point(focus_node, focus_offset)?;
+        if !self.in_document(anchor_node) || !self.in_document(focus_node) {
+            return Ok(());
+        }
+
+        self.collapse(Some(anchor_node), anchor_offset)?;
+        self.extend(focus_node, focus_offset)
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-selectallchildren
+    pub fn select_all_children(&mut self, node: &Rc<RefCell<Node>>) -> Result<(), DomException> {
+        if node.borrow().is_doctype() {
+            return Err(DomException::InvalidNodeTypeError);
+        }
+        if !self.in_document(node) {
+            return Ok(());
+        }
+
+        let range = Range::new(node);
+        range.select_node_contents(node)?;
+        self.range = Some(range);
+        self.direction = SelectionDirection::Forwards;
+        Ok(())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-deletefromdocument
+    pub fn delete_from_document(&self) -> Result<(), DomException> {
+        match self.range {
+            Some(ref range) => range.delete_contents(),
+            None => Ok(()),
+        }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-containsnode
+    pub fn contains_node(&self, node: &Rc<RefCell<Node>>, allow_partial: bool) -> bool {
+        let range = match self.range {
+            Some(ref r) if self.in_document(node) => r,
+            _ => return false,
+        };
+        range.contains_node(node) || (allow_partial && range.partially_contains_node(node))
+    }
+}
+
+// https://w3c.github.io/selection-api/#dom-selection-stringifier
+impl fmt::Display for Selection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.range {
+            Some(ref range) => write!(f, "{}", range),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::html_parser::parse;
+
+    fn text_of(document: &Rc<RefCell<Node>>, id: &str) -> Rc<RefCell<Node>> {
+        Node::get_element_by_id(document, id)
+            .unwrap()
+            .borrow()
+            .first_child()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_collapse_and_extend() {
+        let document = parse("<p id=a>hello</p><p id=b>world</p>");
+        let hello = text_of(&document, "a");
+        let world = text_of(&document, "b");
+        let selection = Node::get_selection(&document).unwrap();
+        let mut selection = selection.borrow_mut();
+        assert_eq!(selection.range_count(), 0);
+        assert!(selection.is_collapsed());
+
+        selection.collapse(Some(&world), 2).unwrap();
+        assert_eq!(selection.range_count(), 1);
+        assert!(selection.is_collapsed());
+
+        // extending backwards swaps the anchor to the end of the range
+        selection.extend(&hello, 3).unwrap();
+        assert_eq!(selection.direction(), SelectionDirection::Backwards);
+        assert_eq!(selection.to_string(), "lowo");
+        let anchor = selection.anchor().unwrap();
+        assert!(Rc::ptr_eq(&anchor.node, &world));
+        assert_eq!(anchor.offset, 2);
+        assert_eq!(selection.focus().unwrap().offset, 3);
+        assert!(selection.contains_node(&Node::get_element_by_id(&document, "a").unwrap(), true));
+        assert!(!selection.contains_node(&Node::get_element_by_id(&document, "a").unwrap(), false));
+
+        selection.collapse_to_start().unwrap();
+        assert!(selection.is_collapsed());
+        assert!(Rc::ptr_eq(&selection.anchor().unwrap().node, &hello));
+
+        selection.collapse(None, 0).unwrap();
+        assert_eq!(selection.range_count(), 0);
+        assert_eq!(
+            selection.extend(&hello, 0).err(),
+            Some(DomException::InvalidStateError)
+        );
+    }
+
+    #[test]
+    fn test_ranges_and_other_trees() {
+        let document = parse("<p id=a>hello</p>");
+        let other = parse("<p id=a>other</p>");
+        let selection = Node::get_selection(&document).unwrap();
+        let mut selection = selection.borrow_mut();
+
+        // points in another document are ignored
+        selection.collapse(Some(&text_of(&other, "a")), 1).unwrap();
+        assert_eq!(selection.range_count(), 0);
+
+        let range = Range::new(&document);
+        range
+            .select_node_contents(&text_of(&document, "a"))
+            .unwrap();
+        selection.add_range(&range);
+        assert_eq!(selection.to_string(), "hello");
+        assert!(selection.get_range_at(0).unwrap().same_range(&range));
+        assert_eq!(
+            selection.get_range_at(1).err(),
+            Some(DomException::IndexSizeError)
+        );
+
+        selection.delete_from_document().unwrap();
+        assert_eq!(Node::text_content(&text_of(&document, "a")).unwrap(), "");
+
+        assert_eq!(
+            selection.remove_range(&Range::new(&document)).err(),
+            Some(DomException::NotFoundError)
+        );
+        selection.remove_range(&range).unwrap();
+        assert_eq!(selection.range_count(), 0);
+    }
+
+    #[test]
+    fn test_select_all_children() {
+        let document = parse("<div id=a><p>one</p><p>two</p></div>");
+        let selection = Node::get_selection(&document).unwrap();
+        let mut selection = selection.borrow_mut();
+
+        selection
+            .select_all_children(&Node::get_element_by_id(&document, "a").unwrap())
+            .unwrap();
+        assert_eq!(selection.to_string(), "onetwo");
+        assert_eq!(selection.focus().unwrap().offset, 2);
+    }
+
+    #[test]
+    fn test_get_selection() {
+        let document = parse("<p id=a>hello</p>");
+        let selection = Node::get_selection(&document).unwrap();
+        selection
+            .borrow_mut()
+            .collapse(Some(&text_of(&document, "a")), 1)
+            .unwrap();
+
+        // the document keeps the selection
+        let again = Node::get_selection(&document).unwrap();
+        assert!(Rc::ptr_eq(&selection, &again));
+        assert_eq!(again.borrow().range_count(), 1);
+
+        let p = Node::get_element_by_id(&document, "a").unwrap();
+        assert!(Node::get_selection(&p).is_none());
+    }
+}
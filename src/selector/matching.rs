@@ -0,0 +1,320 @@
+use super::*;
+use crate::element::Element;
+use crate::node::NodeKind;
+
+// https://drafts.csswg.org/selectors-4/#scoping-root
+#[derive(Clone, Debug, Default)]
+pub struct MatchingContext {
+    // the element `:scope` matches, the root element when unset
+    scope: Option<Rc<RefCell<Node>>>,
+}
+
+impl MatchingContext {
+    pub fn new() -> Self {
+        Self { scope: None }
+    }
+
+    pub fn with_scope(scope: &Rc<RefCell<Node>>) -> Self {
+        Self {
+            scope: Some(scope.clone()),
+        }
+    }
+}
+
+// https://drafts.csswg.org/selectors-4/#match-a-complex-selector-against-an-element
+//
+// Compounds are matched right to left, starting with the subject of the
+// selector and walking up or back through the tree for each combinator.
+pub fn matches_complex_selector(
+    selector: &ComplexSelector,
+    element: &Rc<RefCell<Node>>,
+    context: &MatchingContext,
+) -> bool {
+    match selector.compounds.len() {
+        0 => false,
+        len => matches_from(selector, len - 1, element, context),
+    }
+}
+
+fn matches_from(
+    selector: &ComplexSelector,
+    index: usize,
+    element: &Rc<RefCell<Node>>,
+    context: &MatchingContext,
+) -> bool {
+    if !matches_compound(&selector.compounds[index], element, context) {
+        return false;
+    }
+    if index == 0 {
+        return true;
+    }
+
+    match selector.combinators[index - 1] {
+        Combinator::Child => {
+            parent_element(element).is_some_and(|p| matches_from(selector, index - 1, &p, context))
+        }
+        Combinator::Descendant => Node::ancestors(element)
+            .take_while(|n| n.borrow().is_element())
+            .any(|n| matches_from(selector, index - 1, &n, context)),
+        Combinator::NextSibling => Node::preceding_siblings(element)
+            .find(|n| n.borrow().is_element())
+            .is_some_and(|n| matches_from(selector, index - 1, &n, context)),
+        Combinator::SubsequentSibling => Node::preceding_siblings(element)
+            .filter(|n| n.borrow().is_element())
+            .any(|n| matches_from(selector, index - 1, &n, context)),
+    }
+}
+
+fn matches_compound(
+    compound: &CompoundSelector,
+    element: &Rc<RefCell<Node>>,
+    context: &MatchingContext,
+) -> bool {
+    compound
+        .0
+        .iter()
+        .all(|s| matches_simple(s, element, context))
+}
+
+fn matches_simple(
+    selector: &SimpleSelector,
+    element: &Rc<RefCell<Node>>,
+    context: &MatchingContext,
+) -> bool {
+    match selector {
+        SimpleSelector::PseudoClass(p) => matches_pseudo_class(p, element, context),
+        // matched against the originating element
+        SimpleSelector::PseudoElement(_) => true,
+        _ => {
+            let node = element.borrow();
+            let e = match node.kind {
+                NodeKind::Element(ref e) => e,
+                _ => return false,
+            };
+            match selector {
+                // every element is in the HTML namespace, and no prefix is
+                // declared
+                SimpleSelector::Namespace(namespace) => *namespace == NamespaceConstraint::Any,
+                SimpleSelector::Universal => true,
+                SimpleSelector::Type(name) => *name == e.to_string(),
+                SimpleSelector::Id(id) => e.get_attribute("id") == Some(id.as_str()),
+                SimpleSelector::Class(class) => e
+                    .get_attribute("class")
+                    .is_some_and(|c| c.split_ascii_whitespace().any(|c| c == class)),
+                SimpleSelector::Attribute(attribute) => matches_attribute(attribute, e),
+                SimpleSelector::PseudoClass(_) | SimpleSelector::PseudoElement(_) => {
+                    unreachable!()
+                }
+            }
+        }
+    }
+}
+
+// https://drafts.csswg.org/selectors-4/#attribute-selectors
+fn matches_attribute(selector: &AttributeSelector, element: &Element) -> bool {
+    // attributes are in no namespace
+    if let Some(NamespaceConstraint::Prefix(_)) = selector.namespace {
+        return false;
+    }
+    let value = match element.get_attribute(&selector.name) {
+        Some(v) => v,
+        None => return false,
+    };
+    let (operator, expected) = match selector.matcher {
+        Some((operator, ref expected)) => (operator, expected),
+        None => return true,
+    };
+
+    let (value, expected) = if selector.case_insensitive {
+        (value.to_ascii_lowercase(), expected.to_ascii_lowercase())
+    } else {
+        (value.to_string(), expected.clone())
+    };
+
+    match operator {
+        AttributeOperator::Equal => value == expected,
+        AttributeOperator::Includes => {
+            !expected.is_empty()
+                && !expected.contains(char::is_whitespace)
+                && value.split_ascii_whitespace().any(|v| v == expected)
+        }
+        AttributeOperator::DashMatch => {
+            value == expected || value.starts_with(&format!("{}-", expected))
+        }
+        // an empty value never matches the substring operators
+        AttributeOperator::Prefix => !expected.is_empty() && value.starts_with(&expected),
+        AttributeOperator::Suffix => !expected.is_empty() && value.ends_with(&expected),
+        AttributeOperator::Substring => !expected.is_empty() && value.contains(&expected),
+    }
+}
+
+fn matches_pseudo_class(
+    pseudo_class: &PseudoClass,
+    element: &Rc<RefCell<Node>>,
+    context: &MatchingContext,
+) -> bool {
+    if !element.borrow().is_element() {
+        return false;
+    }
+
+    match pseudo_class {
+        PseudoClass::Not(list) => !list.matches(element, context),
+        PseudoClass::Is(list) | PseudoClass::Where(list) => list.matches(element, context),
+        PseudoClass::Has(relative) => relative.iter().any(|r| matches_relative(r, element)),
+        PseudoClass::NthChild(an_plus_b, of) => {
+            nth_index(element, false, |n| of_matches(of, n, context))
+                .is_some_and(|i| an_plus_b.matches(i))
+        }
+        PseudoClass::NthLastChild(an_plus_b, of) => {
+            nth_index(element, true, |n| of_matches(of, n, context))
+                .is_some_and(|i| an_plus_b.matches(i))
+        }
+        PseudoClass::NthOfType(an_plus_b) => nth_index(element, false, |n| same_type(n, element))
+            .is_some_and(|i| an_plus_b.matches(i)),
+        PseudoClass::NthLastOfType(an_plus_b) => {
+            nth_index(element, true, |n| same_type(n, element))
+                .is_some_and(|i| an_plus_b.matches(i))
+        }
+        PseudoClass::FirstChild => nth_index(element, false, |_| true) == Some(1),
+        PseudoClass::LastChild => nth_index(element, true, |_| true) == Some(1),
+        PseudoClass::OnlyChild => {
+            nth_index(element, false, |_| true) == Some(1)
+                && nth_index(element, true, |_| true) == Some(1)
+        }
+        PseudoClass::FirstOfType => nth_index(element, false, |n| same_type(n, element)) == Some(1),
+        PseudoClass::LastOfType => nth_index(element, true, |n| same_type(n, element)) == Some(1),
+        PseudoClass::OnlyOfType => {
+            nth_index(element, false, |n| same_type(n, element)) == Some(1)
+                && nth_index(element, true, |n| same_type(n, element)) == Some(1)
+        }
+        // https://drafts.csswg.org/selectors-4/#the-empty-pseudo
+        PseudoClass::Empty => Node::children(element).all(|n| match n.borrow().kind {
+            NodeKind::Element(_) => false,
+            NodeKind::Text(ref s) => s.is_empty(),
+            _ => true,
+        }),
+        PseudoClass::Root => is_root(element),
+        PseudoClass::Scope => match context.scope {
+            Some(ref scope) => Rc::ptr_eq(scope, element),
+            None => is_root(element),
+        },
+        // https://drafts.csswg.org/selectors-4/#the-hover-pseudo
+        PseudoClass::Hover => Node::connected_document(element)
+            .and_then(|d| Node::hovered_element(&d))
+            .is_some_and(|h| Node::inclusive_ancestors(&h).any(|a| Rc::ptr_eq(&a, element))),
+        PseudoClass::Lang(ranges) => {
+            // https://html.spec.whatwg.org/multipage/dom.html#language
+            //
+            // the `lang` of the nearest element that has one, or none
+            let lang =
+                Node::inclusive_ancestors(element).find_map(|a| Node::get_attribute(&a, "lang"));
+            lang.is_some_and(|lang| ranges.iter().any(|r| matches_language_range(&lang, r)))
+        }
+    }
+}
+
+// https://www.rfc-editor.org/rfc/rfc4647#section-3.3.2
+//
+// Extended filtering, case-insensitively: `de-*-DE` matches `de-Latn-DE`,
+// and `*-CH` any tag with a `CH` subtag. An empty range only matches an
+// empty language.
+fn matches_language_range(lang: &str, range: &str) -> bool {
+    if range.is_empty() {
+        return lang.is_empty();
+    }
+    let lang: Vec<String> = lang.split('-').map(|s| s.to_ascii_lowercase()).collect();
+    let range: Vec<String> = range.split('-').map(|s| s.to_ascii_lowercase()).collect();
+    if range[0] != "*" && range[0] != lang[0] {
+        return false;
+    }
+
+    let mut i = 1;
+    for subtag in range[1..].iter().filter(|s| *s != "*") {
+        loop {
+            match lang.get(i) {
+                None => return false,
+                Some(l) if l == subtag => break,
+                // a singleton starts an extension the range can't reach into
+                Some(l) if l.len() == 1 => return false,
+                Some(_) => i += 1,
+            }
+        }
+        i += 1;
+    }
+    true
+}
+
+fn of_matches(
+    of: &Option<SelectorList>,
+    element: &Rc<RefCell<Node>>,
+    context: &MatchingContext,
+) -> bool {
+    match of {
+        Some(list) => list.matches(element, context),
+        None => true,
+    }
+}
+
+// https://drafts.csswg.org/selectors-4/#relational
+//
+// The relative selector is matched as if it started with `:scope` followed
+// by its leading combinator, against the elements it could reach from the
+// anchor element until one matches.
+fn matches_relative(relative: &RelativeSelector, anchor: &Rc<RefCell<Node>>) -> bool {
+    let context = MatchingContext::with_scope(anchor);
+    let matches = |n: Rc<RefCell<Node>>| {
+        n.borrow().is_element() && matches_complex_selector(&relative.anchored, &n, &context)
+    };
+
+    match relative.combinator {
+        Combinator::Descendant | Combinator::Child => Node::descendants(anchor).any(matches),
+        Combinator::NextSibling | Combinator::SubsequentSibling => Node::following_siblings(anchor)
+            .flat_map(|n| Node::inclusive_descendants(&n))
+            .any(matches),
+    }
+}
+
+// The 1-based position of `element` among its element siblings that pass
+// `filter`, counting from the end when `from_end` is set. None when
+// `element` itself doesn't pass.
+fn nth_index(
+    element: &Rc<RefCell<Node>>,
+    from_end: bool,
+    filter: impl Fn(&Rc<RefCell<Node>>) -> bool,
+) -> Option<i32> {
+    if !filter(element) {
+        return None;
+    }
+
+    let siblings = if from_end {
+        Node::following_siblings(element)
+    } else {
+        Node::preceding_siblings(element)
+    };
+    let before = siblings
+        .filter(|n| n.borrow().is_element() && filter(n))
+        .count();
+    Some(before as i32 + 1)
+}
+
+fn same_type(a: &Rc<RefCell<Node>>, b: &Rc<RefCell<Node>>) -> bool {
+    match (&a.borrow().kind, &b.borrow().kind) {
+        (NodeKind::Element(a), NodeKind::Element(b)) => a.to_string() == b.to_string(),
+        _ => false,
+    }
+}
+
+fn parent_element(element: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+    element
+        .borrow()
+        .parent()
+        .filter(|p| p.borrow().is_element())
+}
+
+// https://drafts.csswg.org/selectors-4/#the-root-pseudo
+fn is_root(element: &Rc<RefCell<Node>>) -> bool {
+    element
+        .borrow()
+        .parent()
+        .is_some_and(|p| p.borrow().is_document())
+}
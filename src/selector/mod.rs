@@ -0,0 +1,533 @@
+mod matching;
+mod parser;
+
+pub use matching::{matches_complex_selector, MatchingContext};
+pub use parser::{parse_selector_list, SelectorParseError};
+
+use crate::node::{DomException, Node};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// https://drafts.csswg.org/selectors-4/#structure
+#[derive(Clone, Debug, PartialEq)]
+pub struct SelectorList(pub Vec<ComplexSelector>);
+
+// Compound selectors joined by combinators, stored left to right.
+// `combinators[i]` sits between `compounds[i]` and `compounds[i + 1]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComplexSelector {
+    pub compounds: Vec<CompoundSelector>,
+    pub combinators: Vec<Combinator>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompoundSelector(pub Vec<SimpleSelector>);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Combinator {
+    // whitespace
+    Descendant,
+    // >
+    Child,
+    // +
+    NextSibling,
+    // ~
+    SubsequentSibling,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SimpleSelector {
+    // the `ns|` before a type or universal selector
+    Namespace(NamespaceConstraint),
+    Universal,
+    Type(String),
+    Id(String),
+    Class(String),
+    Attribute(AttributeSelector),
+    PseudoClass(PseudoClass),
+    // only at the end of a selector
+    PseudoElement(PseudoElement),
+}
+
+// https://drafts.csswg.org/selectors-4/#type-nmsp
+//
+// There are no `@namespace` rules, so no prefix is ever declared. Elements
+// are all in the HTML namespace and attributes in none.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NamespaceConstraint {
+    // `*|`
+    Any,
+    // `|`
+    None,
+    // `ns|`
+    Prefix(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttributeSelector {
+    // None without a `|`
+    pub namespace: Option<NamespaceConstraint>,
+    pub name: String,
+    pub matcher: Option<(AttributeOperator, String)>,
+    pub case_insensitive: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AttributeOperator {
+    // =
+    Equal,
+    // ~=
+    Includes,
+    // |=
+    DashMatch,
+    // ^=
+    Prefix,
+    // $=
+    Suffix,
+    // *=
+    Substring,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PseudoClass {
+    Not(SelectorList),
+    Is(SelectorList),
+    Where(SelectorList),
+    Has(Vec<RelativeSelector>),
+    NthChild(AnPlusB, Option<SelectorList>),
+    NthLastChild(AnPlusB, Option<SelectorList>),
+    NthOfType(AnPlusB),
+    NthLastOfType(AnPlusB),
+    FirstChild,
+    LastChild,
+    OnlyChild,
+    FirstOfType,
+    LastOfType,
+    OnlyOfType,
+    Empty,
+    Root,
+    Scope,
+    Hover,
+    // https://drafts.csswg.org/selectors-4/#the-lang-pseudo
+    Lang(Vec<String>),
+}
+
+// https://drafts.csswg.org/css-pseudo-4/
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PseudoElement {
+    Marker,
+    Before,
+    After,
+    FirstLine,
+    FirstLetter,
+}
+
+impl PseudoElement {
+    pub const ALL: [PseudoElement; 5] = [
+        PseudoElement::Marker,
+        PseudoElement::Before,
+        PseudoElement::After,
+        PseudoElement::FirstLine,
+        PseudoElement::FirstLetter,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PseudoElement::Marker => "marker",
+            PseudoElement::Before => "before",
+            PseudoElement::After => "after",
+            PseudoElement::FirstLine => "first-line",
+            PseudoElement::FirstLetter => "first-letter",
+        }
+    }
+}
+
+// A selector inside `:has()`, anchored at the element being matched.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RelativeSelector {
+    pub combinator: Combinator,
+    pub selector: ComplexSelector,
+    // the selector starting with `:scope` and the combinator, matched
+    // against the elements the anchor reaches
+    anchored: ComplexSelector,
+}
+
+impl RelativeSelector {
+    pub fn new(combinator: Combinator, selector: ComplexSelector) -> Self {
+        let mut anchored = selector.clone();
+        anchored.compounds.insert(
+            0,
+            CompoundSelector(vec![SimpleSelector::PseudoClass(PseudoClass::Scope)]),
+        );
+        anchored.combinators.insert(0, combinator);
+        Self {
+            combinator,
+            selector,
+            anchored,
+        }
+    }
+}
+
+// https://drafts.csswg.org/css-syntax-3/#anb-microsyntax
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AnPlusB {
+    pub a: i32,
+    pub b: i32,
+}
+
+impl AnPlusB {
+    // Whether the 1-based `index` is `a*n + b` for some n >= 0.
+    pub fn matches(&self, index: i32) -> bool {
+        if self.a == 0 {
+            return index == self.b;
+        }
+        // in i64, as `index - b` overflows i32 for `b` near its bounds
+        let (a, n) = (self.a as i64, index as i64 - self.b as i64);
+        n % a == 0 && n / a >= 0
+    }
+}
+
+// https://drafts.csswg.org/selectors-4/#specificity-rules
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Specificity(pub u32, pub u32, pub u32);
+
+impl std::ops::Add for Specificity {
+    type Output = Specificity;
+
+    fn add(self, other: Specificity) -> Specificity {
+        Specificity(self.0 + other.0, self.1 + other.1, self.2 + other.2)
+    }
+}
+
+impl SelectorList {
+    pub fn parse(s: &str) -> Result<Self, SelectorParseError> {
+        parse_selector_list(s)
+    }
+
+    // the most specific selector of the list
+    fn max_specificity(&self) -> Specificity {
+        self.0
+            .iter()
+            .map(|s| s.specificity())
+            .max()
+            .unwrap_or_default()
+    }
+
+    // Selectors for pseudo-elements never match elements themselves; the
+    // cascade matches them against the originating element.
+    pub fn matches(&self, element: &Rc<RefCell<Node>>, context: &MatchingContext) -> bool {
+        self.0
+            .iter()
+            .filter(|s| s.pseudo_element().is_none())
+            .any(|s| matches_complex_selector(s, element, context))
+    }
+}
+
+impl ComplexSelector {
+    // the pseudo-element the selector ends with
+    pub fn pseudo_element(&self) -> Option<PseudoElement> {
+        self.compounds.last()?.0.iter().find_map(|s| match s {
+            SimpleSelector::PseudoElement(p) => Some(*p),
+            _ => None,
+        })
+    }
+
+    pub fn specificity(&self) -> Specificity {
+        self.compounds
+            .iter()
+            .flat_map(|c| c.0.iter())
+            .map(|s| s.specificity())
+            .fold(Specificity::default(), |a, b| a + b)
+    }
+}
+
+impl SimpleSelector {
+    fn specificity(&self) -> Specificity {
+        match self {
+            SimpleSelector::Namespace(_) | SimpleSelector::Universal => Specificity(0, 0, 0),
+            SimpleSelector::Type(_) | SimpleSelector::PseudoElement(_) => Specificity(0, 0, 1),
+            SimpleSelector::Id(_) => Specificity(1, 0, 0),
+            SimpleSelector::Class(_) | SimpleSelector::Attribute(_) => Specificity(0, 1, 0),
+            SimpleSelector::PseudoClass(p) => match p {
+                PseudoClass::Where(_) => Specificity(0, 0, 0),
+                PseudoClass::Not(list) | PseudoClass::Is(list) => list.max_specificity(),
+                PseudoClass::Has(relative) => relative
+                    .iter()
+                    .map(|r| r.selector.specificity())
+                    .max()
+                    .unwrap_or_default(),
+                PseudoClass::NthChild(_, Some(of)) | PseudoClass::NthLastChild(_, Some(of)) => {
+                    Specificity(0, 1, 0) + of.max_specificity()
+                }
+                _ => Specificity(0, 1, 0),
+            },
+        }
+    }
+}
+
+impl From<SelectorParseError> for DomException {
+    fn from(_: SelectorParseError) -> Self {
+        DomException::SyntaxError
+    }
+}
+
+impl Node {
+    // https://dom.spec.whatwg.org/#dom-parentnode-queryselector
+    pub fn query_selector(
+        node: &Rc<RefCell<Node>>,
+        selectors: &str,
+    ) -> Result<Option<Rc<RefCell<Node>>>, DomException> {
+        let list = SelectorList::parse(selectors)?;
+        let context = MatchingContext::with_scope(node);
+        Ok(Node::descendants(node).find(|n| n.borrow().is_element() && list.matches(n, &context)))
+    }
+
+    // https://dom.spec.whatwg.org/#dom-parentnode-queryselectorall
+    pub fn query_selector_all(
+        node: &Rc<RefCell<Node>>,
+        selectors: &str,
+    ) -> Result<Vec<Rc<RefCell<Node>>>, DomException> {
+        let list = SelectorList::parse(selectors)?;
+        let context = MatchingContext::with_scope(node);
+        Ok(Node::descendants(node)
+            .filter(|n| n.borrow().is_element() && list.matches(n, &context))
+            .collect())
+    }
+
+    // https://dom.spec.whatwg.org/#dom-element-matches
+    pub fn matches(element: &Rc<RefCell<Node>>, selectors: &str) -> Result<bool, DomException> {
+        let list = SelectorList::parse(selectors)?;
+        let context = MatchingContext::with_scope(element);
+        Ok(element.borrow().is_element() && list.matches(element, &context))
+    }
+
+    // https://dom.spec.whatwg.org/#dom-element-closest
+    pub fn closest(
+        element: &Rc<RefCell<Node>>,
+        selectors: &str,
+    ) -> Result<Option<Rc<RefCell<Node>>>, DomException> {
+        let list = SelectorList::parse(selectors)?;
+        let context = MatchingContext::with_scope(element);
+        Ok(Node::inclusive_ancestors(element)
+            .find(|n| n.borrow().is_element() && list.matches(n, &context)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::html_parser::parse;
+
+    fn ids(document: &Rc<RefCell<Node>>, selectors: &str) -> Vec<String> {
+        Node::query_selector_all(document, selectors)
+            .unwrap()
+            .iter()
+            .map(|n| Node::get_attribute(n, "id").unwrap_or_default())
+            .collect()
+    }
+
+    const PAGE: &str = "<div id=a class='box main'>\
+        <p id=b lang=en-US>one</p>\
+        <p id=c data-x='hello world'><span id=d class=note>two</span></p>\
+        <h1 id=e></h1>\
+        <span id=f class=note><!-- comment --></span>\
+        </div>\
+        <div id=g><h2 id=h>three</h2></div>";
+
+    #[test]
+    fn test_simple_selectors() {
+        let document = parse(PAGE);
+
+        assert_eq!(ids(&document, "p"), vec!["b", "c"]);
+        assert_eq!(ids(&document, "#c"), vec!["c"]);
+        assert_eq!(ids(&document, ".note"), vec!["d", "f"]);
+        assert_eq!(ids(&document, "div.box.main"), vec!["a"]);
+        assert_eq!(ids(&document, "span, h2"), vec!["d", "f", "h"]);
+        assert_eq!(ids(&document, "*").len(), 11);
+    }
+
+    #[test]
+    fn test_attribute_selectors() {
+        let document = parse(PAGE);
+
+        assert_eq!(ids(&document, "[lang]"), vec!["b"]);
+        assert_eq!(ids(&document, "[lang|=en]"), vec!["b"]);
+        assert_eq!(ids(&document, "[data-x~=world]"), vec!["c"]);
+        assert_eq!(ids(&document, "[data-x^='hell']"), vec!["c"]);
+        assert_eq!(ids(&document, "[data-x$=\"rld\"]"), vec!["c"]);
+        assert_eq!(ids(&document, "[data-x*=o\\ w]"), vec!["c"]);
+        assert_eq!(ids(&document, "[data-x=HELLO\\ WORLD i]"), vec!["c"]);
+        assert!(ids(&document, "[data-x=HELLO\\ WORLD]").is_empty());
+        assert!(ids(&document, "[data-x^='']").is_empty());
+    }
+
+    #[test]
+    fn test_namespaces() {
+        let document = parse(PAGE);
+
+        assert_eq!(ids(&document, "*|h1"), vec!["e"]);
+        assert_eq!(ids(&document, "div > *|*"), ids(&document, "div > *"));
+        // elements are in the HTML namespace, and no prefix is declared
+        assert!(ids(&document, "|h1").is_empty());
+        assert!(ids(&document, "svg|h1").is_empty());
+        assert_eq!(ids(&document, "[*|lang]"), vec!["b"]);
+        assert_eq!(ids(&document, "[|lang]"), vec!["b"]);
+        assert!(ids(&document, "[xml|lang]").is_empty());
+    }
+
+    #[test]
+    fn test_lang() {
+        let document = parse(
+            "<div lang=de-Latn-DE><p id=a>one</p><p id=b lang=EN-us>two</p></div>\
+             <p id=c lang=''>three</p><p id=d lang=de-DE-x-goethe>four</p>",
+        );
+
+        assert_eq!(ids(&document, "p:lang(de)"), vec!["a", "d"]);
+        assert_eq!(ids(&document, "p:lang(de-DE)"), vec!["a", "d"]);
+        assert_eq!(ids(&document, "p:lang('*-Latn')"), vec!["a"]);
+        assert_eq!(ids(&document, ":lang(en-US)"), vec!["b"]);
+        assert!(ids(&document, ":lang(en-GB)").is_empty());
+        assert_eq!(ids(&document, ":lang(\"\")"), vec!["c"]);
+        // a range can't reach past a singleton
+        assert!(ids(&document, ":lang(de-goethe)").is_empty());
+    }
+
+    #[test]
+    fn test_combinators() {
+        let document = parse(PAGE);
+
+        assert_eq!(ids(&document, "div span"), vec!["d", "f"]);
+        assert_eq!(ids(&document, "div > span"), vec!["f"]);
+        assert_eq!(ids(&document, "p + p"), vec!["c"]);
+        assert_eq!(ids(&document, "p ~ span"), vec!["f"]);
+        assert_eq!(ids(&document, "#a>p>span.note"), vec!["d"]);
+        assert_eq!(ids(&document, "div ~ div h2"), vec!["h"]);
+    }
+
+    #[test]
+    fn test_logical_pseudo_classes() {
+        let document = parse(PAGE);
+
+        assert_eq!(ids(&document, "span:not(#a span)"), Vec::<String>::new());
+        assert_eq!(ids(&document, "#a > :not(p, h1)"), vec!["f"]);
+        assert_eq!(ids(&document, ":is(h1, h2)"), vec!["e", "h"]);
+        // invalid entries in :is() and :where() are dropped
+        assert_eq!(ids(&document, ":where(h2, !!)"), vec!["h"]);
+        assert_eq!(ids(&document, "div:has(h2)"), vec!["g"]);
+        assert_eq!(ids(&document, "p:has(> span)"), vec!["c"]);
+        assert_eq!(ids(&document, "p:has(+ h1)"), vec!["c"]);
+        assert_eq!(ids(&document, "p:has(~ .note)"), vec!["b", "c"]);
+    }
+
+    #[test]
+    fn test_structural_pseudo_classes() {
+        let document = parse(PAGE);
+
+        assert_eq!(ids(&document, "#a > :first-child"), vec!["b"]);
+        assert_eq!(ids(&document, "#a > :last-child"), vec!["f"]);
+        // <html> is the only child of the document
+        assert_eq!(ids(&document, ":only-child"), vec!["", "d", "h"]);
+        assert_eq!(ids(&document, "#a > :nth-child(2n+1)"), vec!["b", "e"]);
+        assert_eq!(ids(&document, "#a > :nth-child(even)"), vec!["c", "f"]);
+        assert_eq!(ids(&document, "#a > :nth-child(-n + 2)"), vec!["b", "c"]);
+        assert_eq!(ids(&document, "#a > :nth-last-child(1)"), vec!["f"]);
+        // `index - b` overflows an i32, and larger integers are clamped
+        assert_eq!(
+            ids(&document, "#a > :nth-child(n-2147483647)"),
+            vec!["b", "c", "e", "f"]
+        );
+        assert_eq!(
+            ids(&document, "#a > :nth-child(-n+99999999999)"),
+            vec!["b", "c", "e", "f"]
+        );
+        assert_eq!(
+            ids(&document, "#a > :nth-child(2n-99999999999)"),
+            vec!["b", "e"]
+        );
+        assert_eq!(ids(&document, ":nth-child(1 of .note)"), vec!["d", "f"]);
+        assert_eq!(ids(&document, "#a > :nth-child(2 of p)"), vec!["c"]);
+        assert_eq!(ids(&document, "p:nth-of-type(2)"), vec!["c"]);
+        assert_eq!(ids(&document, "#a > :last-of-type"), vec!["c", "e", "f"]);
+        assert_eq!(ids(&document, "#a > :only-of-type"), vec!["e", "f"]);
+        // <head> has no children either
+        assert_eq!(ids(&document, ":empty"), vec!["", "e", "f"]);
+        assert_eq!(ids(&document, ":root").len(), 1);
+        assert_eq!(
+            Node::query_selector(&document, ":root")
+                .unwrap()
+                .map(|n| Node::outer_html(&n).starts_with("<html>")),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn test_specificity() {
+        let specificity = |s: &str| SelectorList::parse(s).unwrap().0[0].specificity();
+
+        assert_eq!(specificity("*"), Specificity(0, 0, 0));
+        assert_eq!(specificity("div p"), Specificity(0, 0, 2));
+        assert_eq!(specificity("#a .b[c]:first-child"), Specificity(1, 3, 0));
+        assert_eq!(specificity(":is(#a, p) span"), Specificity(1, 0, 1));
+        assert_eq!(specificity(":where(#a, p) span"), Specificity(0, 0, 1));
+        assert_eq!(specificity(":not(.a, .b.c)"), Specificity(0, 2, 0));
+        assert_eq!(specificity(":nth-child(2 of #a)"), Specificity(1, 1, 0));
+    }
+
+    #[test]
+    fn test_matches_and_closest() {
+        let document = parse(PAGE);
+        let d = Node::get_element_by_id(&document, "d").unwrap();
+
+        assert_eq!(Node::matches(&d, "p > .note"), Ok(true));
+        assert_eq!(Node::matches(&d, "h1 .note"), Ok(false));
+        assert_eq!(
+            Node::closest(&d, "div")
+                .unwrap()
+                .and_then(|n| Node::get_attribute(&n, "id")),
+            Some(String::from("a"))
+        );
+        assert_eq!(
+            Node::closest(&d, "span")
+                .unwrap()
+                .and_then(|n| Node::get_attribute(&n, "id")),
+            Some(String::from("d"))
+        );
+        assert!(Node::closest(&d, "h2").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_scope() {
+        let document = parse(PAGE);
+        let a = Node::get_element_by_id(&document, "a").unwrap();
+
+        let children: Vec<_> = Node::query_selector_all(&a, ":scope > span")
+            .unwrap()
+            .iter()
+            .map(|n| Node::get_attribute(n, "id").unwrap())
+            .collect();
+        assert_eq!(children, vec!["f"]);
+    }
+
+    #[test]
+    fn test_invalid_selectors() {
+        let document = parse(PAGE);
+
+        for selector in [
+            "",
+            "p >",
+            "[a=",
+            "#",
+            ":nth-child(x)",
+            ":unknown",
+            "a,,b",
+            "p!",
+        ] {
+            assert_eq!(
+                Node::query_selector(&document, selector).err(),
+                Some(DomException::SyntaxError),
+                "{}",
+                selector
+            );
+        }
+    }
+}
@@ -0,0 +1,762 @@
+use super::*;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SelectorParseError(pub String);
+
+// https://drafts.csswg.org/selectors-4/#parse-selector
+pub fn parse_selector_list(s: &str) -> Result<SelectorList, SelectorParseError> {
+    let mut parser = SelectorParser::new(s);
+    let list = parser.parse_selector_list()?;
+    parser.skip_whitespace();
+    if !parser.is_eof() {
+        return Err(parser.error("unexpected character"));
+    }
+    Ok(list)
+}
+
+struct SelectorParser {
+    input: Vec<char>,
+    pos: usize,
+    // how many functional pseudo-classes the parser is inside
+    depth: usize,
+}
+
+impl SelectorParser {
+    fn new(s: &str) -> Self {
+        Self {
+            input: s.chars().collect(),
+            pos: 0,
+            depth: 0,
+        }
+    }
+
+    fn error(&self, message: &str) -> SelectorParseError {
+        SelectorParseError(format!("{} at {}", message, self.pos))
+    }
+
+    fn is_eof(&self) -> bool {
+        self.pos >= self.input.len()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.input.get(self.pos + offset).copied()
+    }
+
+    fn consume_if(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), SelectorParseError> {
+        if self.consume_if(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", c)))
+        }
+    }
+
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.pos;
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r' | '\x0c')) {
+            self.pos += 1;
+        }
+        self.pos > start
+    }
+
+    fn parse_selector_list(&mut self) -> Result<SelectorList, SelectorParseError> {
+        let mut selectors = vec![self.parse_complex_selector()?];
+        while self.consume_if(',') {
+            selectors.push(self.parse_complex_selector()?);
+        }
+        Ok(SelectorList(selectors))
+    }
+
+    // https://drafts.csswg.org/selectors-4/#typedef-forgiving-selector-list
+    fn parse_forgiving_selector_list(&mut self) -> SelectorList {
+        let mut selectors = Vec::new();
+        loop {
+            let start = self.pos;
+            match self.parse_complex_selector() {
+                Ok(s) if matches!(self.peek(), Some(',' | ')')) => selectors.push(s),
+                _ => {
+                    // drop the broken selector up to the next top-level comma
+                    self.pos = start;
+                    self.skip_to_list_end();
+                }
+            }
+            if !self.consume_if(',') {
+                return SelectorList(selectors);
+            }
+        }
+    }
+
+    fn skip_to_list_end(&mut self) {
+        let mut depth = 0;
+        while let Some(c) = self.peek() {
+            match c {
+                '(' | '[' => depth += 1,
+                ')' | ']' if depth == 0 => return,
+                ')' | ']' => depth -= 1,
+                ',' if depth == 0 => return,
+                _ => {}
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn parse_complex_selector(&mut self) -> Result<ComplexSelector, SelectorParseError> {
+        self.skip_whitespace();
+        let mut compounds = vec![self.parse_compound_selector()?];
+        let mut combinators = Vec::new();
+
+        while let Some(combinator) = self.parse_combinator()? {
+            let last = compounds.last().unwrap();
+            if last
+                .0
+                .iter()
+                .any(|s| matches!(s, SimpleSelector::PseudoElement(_)))
+            {
+                return Err(self.error("a pseudo-element must end the selector"));
+            }
+            combinators.push(combinator);
+            compounds.push(self.parse_compound_selector()?);
+        }
+
+        Ok(ComplexSelector {
+            compounds,
+            combinators,
+        })
+    }
+
+    // None at the end of the selector
+    fn parse_combinator(&mut self) -> Result<Option<Combinator>, SelectorParseError> {
+        let had_whitespace = self.skip_whitespace();
+        let combinator = match self.peek() {
+            None | Some(',' | ')') => return Ok(None),
+            Some('>') => Combinator::Child,
+            Some('+') => Combinator::NextSibling,
+            Some('~') => Combinator::SubsequentSibling,
+            Some(_) if had_whitespace => return Ok(Some(Combinator::Descendant)),
+            Some(_) => return Err(self.error("unexpected character")),
+        };
+        self.pos += 1;
+        self.skip_whitespace();
+        Ok(Some(combinator))
+    }
+
+    fn parse_compound_selector(&mut self) -> Result<CompoundSelector, SelectorParseError> {
+        let mut selectors = self.parse_type_selector()?;
+
+        loop {
+            match self.peek() {
+                Some('#') => {
+                    self.pos += 1;
+                    selectors.push(SimpleSelector::Id(self.consume_ident()?));
+                }
+                Some('.') => {
+                    self.pos += 1;
+                    selectors.push(SimpleSelector::Class(self.consume_ident()?));
+                }
+                Some('[') => {
+                    self.pos += 1;
+                    selectors.push(SimpleSelector::Attribute(self.parse_attribute_selector()?));
+                }
+                Some(':') => {
+                    self.pos += 1;
+                    if let Some(pseudo_element) = self.parse_pseudo_element()? {
+                        selectors.push(SimpleSelector::PseudoElement(pseudo_element));
+                        break;
+                    }
+                    selectors.push(SimpleSelector::PseudoClass(self.parse_pseudo_class()?));
+                }
+                _ => break,
+            }
+        }
+
+        if selectors.is_empty() {
+            return Err(self.error("expected a selector"));
+        }
+        Ok(CompoundSelector(selectors))
+    }
+
+    // https://drafts.csswg.org/selectors-4/#type-selectors
+    //
+    // The type or universal selector starting a compound, with its
+    // namespace prefix if it has one.
+    fn parse_type_selector(&mut self) -> Result<Vec<SimpleSelector>, SelectorParseError> {
+        let mut selectors = Vec::new();
+        let start = self.pos;
+        match self.parse_namespace_prefix()? {
+            Some(namespace) => selectors.push(SimpleSelector::Namespace(namespace)),
+            None => self.pos = start,
+        }
+
+        if self.consume_if('*') {
+            selectors.push(SimpleSelector::Universal);
+        } else if self.starts_ident() {
+            selectors.push(SimpleSelector::Type(
+                self.consume_ident()?.to_ascii_lowercase(),
+            ));
+        } else if !selectors.is_empty() {
+            return Err(self.error("expected a type selector"));
+        }
+        Ok(selectors)
+    }
+
+    // https://drafts.csswg.org/selectors-4/#typedef-ns-prefix
+    //
+    // None, having consumed whatever it looked at, when there's no `|`
+    // (other than the `|=` operator).
+    fn parse_namespace_prefix(
+        &mut self,
+    ) -> Result<Option<NamespaceConstraint>, SelectorParseError> {
+        let namespace = if self.consume_if('*') {
+            NamespaceConstraint::Any
+        } else if self.starts_ident() {
+            NamespaceConstraint::Prefix(self.consume_ident()?)
+        } else {
+            NamespaceConstraint::None
+        };
+        if self.peek() != Some('|') || self.peek_at(1) == Some('=') {
+            return Ok(None);
+        }
+        self.pos += 1;
+        Ok(Some(namespace))
+    }
+
+    // https://drafts.csswg.org/selectors-4/#attribute-selectors
+    fn parse_attribute_selector(&mut self) -> Result<AttributeSelector, SelectorParseError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let namespace = self.parse_namespace_prefix()?;
+        if namespace.is_none() {
+            self.pos = start;
+        }
+        let name = self.consume_ident()?.to_ascii_lowercase();
+        self.skip_whitespace();
+
+        if self.consume_if(']') {
+            return Ok(AttributeSelector {
+                namespace,
+                name,
+                matcher: None,
+                case_insensitive: false,
+            });
+        }
+
+        let operator = match (self.peek(), self.peek_at(1)) {
+            (Some('='), _) => AttributeOperator::Equal,
+            (Some('~'), Some('=')) => AttributeOperator::Includes,
+            (Some('|'), Some('=')) => AttributeOperator::DashMatch,
+            (Some('^'), Some('=')) => AttributeOperator::Prefix,
+            (Some('$'), Some('=')) => AttributeOperator::Suffix,
+            (Some('*'), Some('=')) => AttributeOperator::Substring,
+            _ => return Err(self.error("expected an attribute operator")),
+        };
+        self.pos += if operator == AttributeOperator::Equal {
+            1
+        } else {
+            2
+        };
+        self.skip_whitespace();
+
+        let value = match self.peek() {
+            Some('"' | '\'') => self.consume_string()?,
+            _ => self.consume_ident()?,
+        };
+        self.skip_whitespace();
+
+        let mut case_insensitive = false;
+        if self.starts_ident() {
+            match self.consume_ident()?.to_ascii_lowercase().as_str() {
+                "i" => case_insensitive = true,
+                "s" => {}
+                _ => return Err(self.error("unknown attribute modifier")),
+            }
+            self.skip_whitespace();
+        }
+        self.expect(']')?;
+
+        Ok(AttributeSelector {
+            namespace,
+            name,
+            matcher: Some((operator, value)),
+            case_insensitive,
+        })
+    }
+
+    // https://drafts.csswg.org/selectors-4/#pseudo-elements
+    // None if the colon starts a pseudo-class instead
+    fn parse_pseudo_element(&mut self) -> Result<Option<PseudoElement>, SelectorParseError> {
+        let start = self.pos;
+        let double_colon = self.consume_if(':');
+        if !double_colon && !self.starts_ident() {
+            return Ok(None);
+        }
+        let name = self.consume_ident()?.to_ascii_lowercase();
+
+        let pseudo_element = match name.as_str() {
+            "before" => Some(PseudoElement::Before),
+            "after" => Some(PseudoElement::After),
+            "first-line" => Some(PseudoElement::FirstLine),
+            "first-letter" => Some(PseudoElement::FirstLetter),
+            "marker" if double_colon => Some(PseudoElement::Marker),
+            _ => None,
+        };
+        // only the legacy pseudo-elements keep the single-colon syntax
+        // https://drafts.csswg.org/selectors-4/#pseudo-element-syntax
+        if !double_colon && (pseudo_element.is_none() || self.peek() == Some('(')) {
+            self.pos = start;
+            return Ok(None);
+        }
+        match pseudo_element {
+            None => Err(self.error(&format!("unknown pseudo-element ::{}", name))),
+            Some(_) if self.depth > 0 => {
+                Err(self.error("pseudo-elements are not allowed inside pseudo-classes"))
+            }
+            Some(pseudo_element) => Ok(Some(pseudo_element)),
+        }
+    }
+
+    fn parse_pseudo_class(&mut self) -> Result<PseudoClass, SelectorParseError> {
+        let name = self.consume_ident()?.to_ascii_lowercase();
+
+        if !self.consume_if('(') {
+            return match name.as_str() {
+                "first-child" => Ok(PseudoClass::FirstChild),
+                "last-child" => Ok(PseudoClass::LastChild),
+                "only-child" => Ok(PseudoClass::OnlyChild),
+                "first-of-type" => Ok(PseudoClass::FirstOfType),
+                "last-of-type" => Ok(PseudoClass::LastOfType),
+                "only-of-type" => Ok(PseudoClass::OnlyOfType),
+                "empty" => Ok(PseudoClass::Empty),
+                "root" => Ok(PseudoClass::Root),
+                "scope" => Ok(PseudoClass::Scope),
+                "hover" => Ok(PseudoClass::Hover),
+                _ => Err(self.error(&format!("unknown pseudo-class :{}", name))),
+            };
+        }
+
+        self.depth += 1;
+        let pseudo_class = self.parse_pseudo_class_arguments(&name);
+        self.depth -= 1;
+        let pseudo_class = pseudo_class?;
+        self.skip_whitespace();
+        self.expect(')')?;
+        Ok(pseudo_class)
+    }
+
+    fn parse_pseudo_class_arguments(
+        &mut self,
+        name: &str,
+    ) -> Result<PseudoClass, SelectorParseError> {
+        Ok(match name {
+            "not" => PseudoClass::Not(self.parse_selector_list()?),
+            "is" | "matches" => PseudoClass::Is(self.parse_forgiving_selector_list()),
+            "where" => PseudoClass::Where(self.parse_forgiving_selector_list()),
+            "has" => PseudoClass::Has(self.parse_relative_selector_list()?),
+            "nth-child" | "nth-last-child" => {
+                let an_plus_b = self.parse_an_plus_b()?;
+                self.skip_whitespace();
+                let mut of = None;
+                if self.starts_ident() {
+                    if !self.consume_ident()?.eq_ignore_ascii_case("of") {
+                        return Err(self.error("expected 'of'"));
+                    }
+                    of = Some(self.parse_selector_list()?);
+                }
+                if name == "nth-child" {
+                    PseudoClass::NthChild(an_plus_b, of)
+                } else {
+                    PseudoClass::NthLastChild(an_plus_b, of)
+                }
+            }
+            "lang" => PseudoClass::Lang(self.parse_language_ranges()?),
+            "nth-of-type" => PseudoClass::NthOfType(self.parse_an_plus_b()?),
+            "nth-last-of-type" => PseudoClass::NthLastOfType(self.parse_an_plus_b()?),
+            _ => return Err(self.error(&format!("unknown pseudo-class :{}()", name))),
+        })
+    }
+
+    // https://drafts.csswg.org/selectors-4/#the-lang-pseudo
+    //
+    // Identifiers or strings, with `*` allowed as a subtag.
+    fn parse_language_ranges(&mut self) -> Result<Vec<String>, SelectorParseError> {
+        let mut ranges = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let range = match self.peek() {
+                Some('"' | '\'') => self.consume_string()?,
+                _ => {
+                    let range: String = self.input[self.pos..]
+                        .iter()
+                        .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '*'))
+                        .collect();
+                    if range.is_empty() {
+                        return Err(self.error("expected a language range"));
+                    }
+                    self.pos += range.chars().count();
+                    range
+                }
+            };
+            ranges.push(range);
+            self.skip_whitespace();
+            if !self.consume_if(',') {
+                return Ok(ranges);
+            }
+        }
+    }
+
+    // https://drafts.csswg.org/selectors-4/#typedef-relative-selector-list
+    fn parse_relative_selector_list(
+        &mut self,
+    ) -> Result<Vec<RelativeSelector>, SelectorParseError> {
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let combinator = match self.peek() {
+                Some('>') => Some(Combinator::Child),
+                Some('+') => Some(Combinator::NextSibling),
+                Some('~') => Some(Combinator::SubsequentSibling),
+                _ => None,
+            };
+            if combinator.is_some() {
+                self.pos += 1;
+            }
+            selectors.push(RelativeSelector::new(
+                combinator.unwrap_or(Combinator::Descendant),
+                self.parse_complex_selector()?,
+            ));
+            if !self.consume_if(',') {
+                return Ok(selectors);
+            }
+        }
+    }
+
+    // https://drafts.csswg.org/css-syntax-3/#anb-microsyntax
+    fn parse_an_plus_b(&mut self) -> Result<AnPlusB, SelectorParseError> {
+        self.skip_whitespace();
+
+        let word: String = self.input[self.pos..]
+            .iter()
+            .take_while(|c| c.is_ascii_alphabetic())
+            .collect();
+        if word.eq_ignore_ascii_case("odd") {
+            self.pos += 3;
+            return Ok(AnPlusB { a: 2, b: 1 });
+        }
+        if word.eq_ignore_ascii_case("even") {
+            self.pos += 4;
+            return Ok(AnPlusB { a: 2, b: 0 });
+        }
+
+        let sign = self.consume_sign().unwrap_or(1);
+        let number = self.consume_integer();
+
+        if matches!(self.peek(), Some('n' | 'N')) {
+            self.pos += 1;
+            let a = sign * number.unwrap_or(1);
+
+            self.skip_whitespace();
+            let b = match self.consume_sign() {
+                Some(b_sign) => {
+                    self.skip_whitespace();
+                    match self.consume_integer() {
+                        Some(b) => b_sign * b,
+                        None => return Err(self.error("expected an integer")),
+                    }
+                }
+                None => 0,
+            };
+            return Ok(AnPlusB { a, b });
+        }
+
+        match number {
+            Some(b) => Ok(AnPlusB { a: 0, b: sign * b }),
+            None => Err(self.error("expected An+B")),
+        }
+    }
+
+    fn consume_sign(&mut self) -> Option<i32> {
+        match self.peek() {
+            Some('+') => {
+                self.pos += 1;
+                Some(1)
+            }
+            Some('-') => {
+                self.pos += 1;
+                Some(-1)
+            }
+            _ => None,
+        }
+    }
+
+    fn consume_integer(&mut self) -> Option<i32> {
+        let digits: String = self.input[self.pos..]
+            .iter()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        if digits.is_empty() {
+            return None;
+        }
+        self.pos += digits.len();
+        // https://drafts.csswg.org/css-values-4/#numeric-ranges
+        //
+        // clamped to the range of an i32, which is all the digits can fail on
+        Some(digits.parse().unwrap_or(i32::MAX))
+    }
+
+    // https://drafts.csswg.org/css-syntax-3/#would-start-an-identifier
+    fn starts_ident(&self) -> bool {
+        let is_name_start = |c: Option<char>| {
+            c.is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || !c.is_ascii())
+        };
+        match self.peek() {
+            Some('-') => {
+                is_name_start(self.peek_at(1))
+                    || self.peek_at(1) == Some('-')
+                    || (self.peek_at(1) == Some('\\') && self.peek_at(2).is_some())
+            }
+            Some('\\') => self.peek_at(1).is_some(),
+            c => is_name_start(c),
+        }
+    }
+
+    fn consume_ident(&mut self) -> Result<String, SelectorParseError> {
+        if !self.starts_ident() {
+            return Err(self.error("expected an identifier"));
+        }
+
+        let mut ident = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || !c.is_ascii() {
+                ident.push(c);
+                self.pos += 1;
+            } else if c == '\\' && self.peek_at(1).is_some() {
+                self.pos += 1;
+                ident.push(self.consume_escape());
+            } else {
+                break;
+            }
+        }
+        Ok(ident)
+    }
+
+    // https://drafts.csswg.org/css-syntax-3/#consume-escaped-code-point
+    fn consume_escape(&mut self) -> char {
+        let hex: String = self.input[self.pos..]
+            .iter()
+            .take(6)
+            .take_while(|c| c.is_ascii_hexdigit())
+            .collect();
+        if hex.is_empty() {
+            let c = self.input[self.pos];
+            self.pos += 1;
+            return c;
+        }
+
+        self.pos += hex.len();
+        if matches!(self.peek(), Some(' ' | '\t' | '\n')) {
+            self.pos += 1;
+        }
+        u32::from_str_radix(&hex, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .filter(|c| *c != '\0')
+            .unwrap_or('\u{fffd}')
+    }
+
+    fn consume_string(&mut self) -> Result<String, SelectorParseError> {
+        let quote = self.input[self.pos];
+        self.pos += 1;
+
+        let mut s = String::new();
+        loop {
+            match self.peek() {
+                Some(c) if c == quote => {
+                    self.pos += 1;
+                    return Ok(s);
+                }
+                Some('\\') if self.peek_at(1).is_some() => {
+                    self.pos += 1;
+                    s.push(self.consume_escape());
+                }
+                Some('\n') | None => return Err(self.error("unterminated string")),
+                Some(c) => {
+                    s.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_complex_selector() {
+        let list = parse_selector_list("div > p.a#b, span").unwrap();
+
+        assert_eq!(
+            list,
+            SelectorList(vec![
+                ComplexSelector {
+                    compounds: vec![
+                        CompoundSelector(vec![SimpleSelector::Type(String::from("div"))]),
+                        CompoundSelector(vec![
+                            SimpleSelector::Type(String::from("p")),
+                            SimpleSelector::Class(String::from("a")),
+                            SimpleSelector::Id(String::from("b")),
+                        ]),
+                    ],
+                    combinators: vec![Combinator::Child],
+                },
+                ComplexSelector {
+                    compounds: vec![CompoundSelector(vec![SimpleSelector::Type(String::from(
+                        "span"
+                    ))])],
+                    combinators: vec![],
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_an_plus_b() {
+        let an_plus_b = |s: &str| {
+            let list = parse_selector_list(&format!(":nth-child({})", s)).unwrap();
+            match &list.0[0].compounds[0].0[0] {
+                SimpleSelector::PseudoClass(PseudoClass::NthChild(an_plus_b, _)) => *an_plus_b,
+                _ => panic!("not an nth-child selector"),
+            }
+        };
+
+        assert_eq!(an_plus_b("odd"), AnPlusB { a: 2, b: 1 });
+        assert_eq!(an_plus_b("EVEN"), AnPlusB { a: 2, b: 0 });
+        assert_eq!(an_plus_b("7"), AnPlusB { a: 0, b: 7 });
+        assert_eq!(an_plus_b("-n+3"), AnPlusB { a: -1, b: 3 });
+        assert_eq!(an_plus_b("+n"), AnPlusB { a: 1, b: 0 });
+        assert_eq!(an_plus_b(" 3n - 2 "), AnPlusB { a: 3, b: -2 });
+    }
+
+    #[test]
+    fn test_parse_pseudo_elements() {
+        let pseudo_element = |s: &str| {
+            parse_selector_list(s).map(|list| {
+                list.0
+                    .iter()
+                    .map(|s| s.pseudo_element())
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        assert_eq!(
+            pseudo_element("li::marker, p:first-child::before, a:after"),
+            Ok(vec![
+                Some(PseudoElement::Marker),
+                Some(PseudoElement::Before),
+                Some(PseudoElement::After),
+            ])
+        );
+        assert_eq!(
+            pseudo_element(":first-line, ::first-letter, p:hover"),
+            Ok(vec![
+                Some(PseudoElement::FirstLine),
+                Some(PseudoElement::FirstLetter),
+                None,
+            ])
+        );
+        assert_eq!(
+            parse_selector_list("p::before").unwrap().0[0].specificity(),
+            Specificity(0, 0, 2)
+        );
+
+        assert!(parse_selector_list(":marker").is_err());
+        assert!(parse_selector_list("::selection").is_err());
+        assert!(parse_selector_list("p::before span").is_err());
+        assert!(parse_selector_list("p::before.a").is_err());
+        assert!(parse_selector_list(":not(::before)").is_err());
+        assert!(parse_selector_list(":has(:after)").is_err());
+    }
+
+    #[test]
+    fn test_parse_escapes() {
+        let list = parse_selector_list(".a\\:b #\\31 23").unwrap();
+
+        assert_eq!(
+            list.0[0].compounds[0].0[0],
+            SimpleSelector::Class(String::from("a:b"))
+        );
+        assert_eq!(
+            list.0[0].compounds[1].0[0],
+            SimpleSelector::Id(String::from("123"))
+        );
+    }
+
+    #[test]
+    fn test_parse_namespaces() {
+        let compound =
+            |s: &str| parse_selector_list(s).map(|list| list.0[0].compounds[0].0.clone());
+
+        assert_eq!(
+            compound("*|*"),
+            Ok(vec![
+                SimpleSelector::Namespace(NamespaceConstraint::Any),
+                SimpleSelector::Universal,
+            ])
+        );
+        assert_eq!(
+            compound("|a"),
+            Ok(vec![
+                SimpleSelector::Namespace(NamespaceConstraint::None),
+                SimpleSelector::Type(String::from("a")),
+            ])
+        );
+        assert_eq!(
+            compound("svg|A.b"),
+            Ok(vec![
+                SimpleSelector::Namespace(NamespaceConstraint::Prefix(String::from("svg"))),
+                SimpleSelector::Type(String::from("a")),
+                SimpleSelector::Class(String::from("b")),
+            ])
+        );
+        assert_eq!(
+            compound("[*|lang|=en]"),
+            Ok(vec![SimpleSelector::Attribute(AttributeSelector {
+                namespace: Some(NamespaceConstraint::Any),
+                name: String::from("lang"),
+                matcher: Some((AttributeOperator::DashMatch, String::from("en"))),
+                case_insensitive: false,
+            })])
+        );
+        assert!(parse_selector_list("ns|").is_err());
+        assert!(parse_selector_list("*|.a").is_err());
+    }
+
+    #[test]
+    fn test_parse_lang() {
+        assert_eq!(
+            parse_selector_list(":lang(en, \"de-*-DE\", *-CH)")
+                .unwrap()
+                .0[0]
+                .compounds[0]
+                .0,
+            vec![SimpleSelector::PseudoClass(PseudoClass::Lang(vec![
+                String::from("en"),
+                String::from("de-*-DE"),
+                String::from("*-CH"),
+            ]))]
+        );
+        assert!(parse_selector_list(":lang()").is_err());
+        assert!(parse_selector_list(":lang(en,)").is_err());
+    }
+}
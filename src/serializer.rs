@@ -0,0 +1,200 @@
+use crate::element::Element;
+use crate::node::{Node, NodeKind};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// https://html.spec.whatwg.org/multipage/parsing.html#serialising-html-fragments
+impl Node {
+    // The markup of `node` itself and its descendants.
+    pub fn outer_html(node: &Rc<RefCell<Node>>) -> String {
+        let mut output = String::new();
+        match node.borrow().kind {
+            NodeKind::Document(_) | NodeKind::DocumentFragment => {}
+            _ => {
+                serialize_node(node, &mut output);
+                return output;
+            }
+        }
+        serialize_children(node, &mut output);
+        output
+    }
+
+    // The markup of the descendants of `node`.
+    pub fn inner_html(node: &Rc<RefCell<Node>>) -> String {
+        let mut output = String::new();
+        serialize_children(node, &mut output);
+        output
+    }
+}
+
+fn serialize_children(node: &Rc<RefCell<Node>>, output: &mut String) {
+    // void elements have no contents to serialize
+    if let NodeKind::Element(ref e) = node.borrow().kind {
+        if e.is_void() {
+            return;
+        }
+    }
+
+    // a template element serializes its template contents
+    let contents = node.borrow().template_contents();
+    let parent = contents.as_ref().unwrap_or(node);
+    for child in Node::children(parent) {
+        serialize_node(&child, output);
+    }
+}
+
+fn serialize_node(node: &Rc<RefCell<Node>>, output: &mut String) {
+    match node.borrow().kind {
+        NodeKind::Element(ref e) => {
+            let tag_name = e.to_string();
+            output.push('<');
+            output.push_str(&tag_name);
+            for attribute in e.attributes() {
+                output.push(' ');
+                output.push_str(attribute.name());
+                output.push_str("=\"");
+                output.push_str(&escape_string(attribute.value(), true));
+                output.push('"');
+            }
+            output.push('>');
+
+            if e.is_void() {
+                return;
+            }
+
+            serialize_children(node, output);
+
+            output.push_str("</");
+            output.push_str(&tag_name);
+            output.push('>');
+        }
+        NodeKind::Text(ref s) => {
+            let raw = node
+                .borrow()
+                .parent()
+                .is_some_and(|p| match p.borrow().kind {
+                    NodeKind::Element(ref e) => is_raw_text(e),
+                    _ => false,
+                });
+            if raw {
+                output.push_str(s);
+            } else {
+                output.push_str(&escape_string(s, false));
+            }
+        }
+        NodeKind::Comment(ref s) => {
+            output.push_str("<!--");
+            output.push_str(s);
+            output.push_str("-->");
+        }
+        NodeKind::DocumentType(ref name) => {
+            output.push_str("<!DOCTYPE ");
+            output.push_str(name);
+            output.push('>');
+        }
+        NodeKind::Document(_) | NodeKind::DocumentFragment => serialize_children(node, output),
+    }
+}
+
+// text inside these elements is emitted literally; `noscript` is left out
+// as scripting is disabled
+fn is_raw_text(element: &Element) -> bool {
+    matches!(
+        element.to_string().as_str(),
+        "style" | "script" | "xmp" | "iframe" | "noembed" | "noframes" | "plaintext"
+    )
+}
+
+// https://html.spec.whatwg.org/multipage/parsing.html#escapingString
+fn escape_string(s: &str, attribute_mode: bool) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '\u{a0}' => escaped.push_str("&nbsp;"),
+            '"' if attribute_mode => escaped.push_str("&quot;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::element::HtmlElementKind;
+    use crate::parser::html_parser::parse;
+
+    fn body(document: &Rc<RefCell<Node>>) -> Rc<RefCell<Node>> {
+        let html = document.borrow().last_child().unwrap();
+        let body = html.borrow().last_child().unwrap();
+        body
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let html = "<!DOCTYPE html><html><head><title>a &amp; b</title>\
+            <style>p > span { color: red; }</style></head>\
+            <body><!-- note --><div id=\"main\" class=\"a b\"><p>hello<br>\
+            <span>world</span></p><img src=\"x.png\"></div></body></html>";
+
+        assert_eq!(Node::outer_html(&parse(html)), html);
+    }
+
+    #[test]
+    fn test_escaping() {
+        let document = parse(
+            "<html><body><p title='say \"&lt;hi&gt;\"'>1 &lt; 2 &amp;&nbsp;3</p></body></html>",
+        );
+
+        assert_eq!(
+            Node::inner_html(&body(&document)),
+            "<p title=\"say &quot;&lt;hi&gt;&quot;\">1 &lt; 2 &amp;&nbsp;3</p>"
+        );
+    }
+
+    #[test]
+    fn test_outer_and_inner_html() {
+        let document = parse("<html><body><div><h1>title</h1><hr></div></body></html>");
+        let div = body(&document).borrow().first_child().unwrap();
+
+        assert_eq!(Node::outer_html(&div), "<div><h1>title</h1><hr></div>");
+        assert_eq!(Node::inner_html(&div), "<h1>title</h1><hr>");
+
+        let hr = div.borrow().last_child().unwrap();
+        assert_eq!(Node::outer_html(&hr), "<hr>");
+        assert_eq!(Node::inner_html(&hr), "");
+    }
+
+    #[test]
+    fn test_template_contents() {
+        let document = parse("<html><body><template><p>a</p></template></body></html>");
+        let template = body(&document).borrow().first_child().unwrap();
+
+        // the parser puts the markup into the template contents
+        assert!(!template.borrow().has_child_nodes());
+        let contents = template.borrow().template_contents().unwrap();
+        assert_eq!(Node::outer_html(&contents), "<p>a</p>");
+        assert_eq!(Node::inner_html(&template), "<p>a</p>");
+
+        let copy = Node::clone_node(&template, true);
+        assert_eq!(Node::outer_html(&copy), "<template><p>a</p></template>");
+    }
+
+    #[test]
+    fn test_serialize_after_mutation() {
+        let document = parse("<html><body><p>a</p></body></html>");
+        let body = body(&document);
+        let mut div = Element::new(HtmlElementKind::Div);
+        div.set_attribute("class", "x&y");
+        let div = Rc::new(RefCell::new(Node::new(NodeKind::Element(div))));
+        Node::prepend(&body, &div).unwrap();
+
+        assert_eq!(
+            Node::inner_html(&body),
+            "<div class=\"x&amp;y\"></div><p>a</p>"
+        );
+    }
+}
@@ -0,0 +1,296 @@
+use super::*;
+use crate::parser::css::CssToken;
+use crate::selector::{
+    parse_selector_list, Combinator, ComplexSelector, CompoundSelector, MatchingContext,
+    PseudoClass, SelectorList, SimpleSelector,
+};
+use std::rc::Weak;
+
+// https://drafts.csswg.org/css-cascade-5/#at-import
+//
+// `@import <url> [layer | layer(<name>)]? [supports(<condition>)]?
+// <media-query-list>?`
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ImportRule {
+    // as written, to be resolved against the sheet's URL
+    pub(crate) url: String,
+    // `Some(None)` for an anonymous layer
+    pub(crate) layer: Option<Option<String>>,
+    // whether the `supports()` condition holds, true without one
+    pub(crate) supported: bool,
+    pub(crate) media: MediaQueryList,
+}
+
+impl ImportRule {
+    pub(crate) fn parse(prelude: &[ComponentValue]) -> Option<ImportRule> {
+        let mut rest = prelude
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| !v.is_whitespace());
+        let url = match rest.next()? {
+            (_, ComponentValue::Token(CssToken::String(url) | CssToken::Url(url))) => url.clone(),
+            (_, ComponentValue::Function(name, arguments)) if name.eq_ignore_ascii_case("url") => {
+                match arguments.iter().find(|v| !v.is_whitespace()) {
+                    Some(ComponentValue::Token(CssToken::String(url))) => url.clone(),
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+
+        let mut layer = None;
+        let mut supported = true;
+        let mut media_start = prelude.len();
+        let mut next = rest.next();
+        if let Some((_, value)) = next {
+            match value {
+                ComponentValue::Token(CssToken::Ident(ident))
+                    if ident.eq_ignore_ascii_case("layer") =>
+                {
+                    layer = Some(None);
+                    next = rest.next();
+                }
+                ComponentValue::Function(name, arguments) if name.eq_ignore_ascii_case("layer") => {
+                    match parse_layer_names(arguments)?[..] {
+                        [ref name] => layer = Some(Some(name.clone())),
+                        _ => return None,
+                    }
+                    next = rest.next();
+                }
+                _ => {}
+            }
+        }
+        if let Some((_, ComponentValue::Function(name, arguments))) = next {
+            if name.eq_ignore_ascii_case("supports") {
+                // a bare declaration needs parens to be a condition
+                let condition = [ComponentValue::Block('(', arguments.clone())];
+                supported = supports_condition(arguments) || supports_condition(&condition);
+                next = rest.next();
+            }
+        }
+        if let Some((i, _)) = next {
+            media_start = i;
+        }
+
+        Some(ImportRule {
+            url,
+            layer,
+            supported,
+            media: MediaQueryList::parse(&prelude[media_start..]),
+        })
+    }
+}
+
+// https://drafts.csswg.org/css-cascade-5/#at-import
+//
+// The `@import` rules of a sheet that take effect, which are those before
+// any other rule but `@charset` and `@layer` statements.
+pub(crate) fn import_rules(rules: &[CssRule]) -> Vec<ImportRule> {
+    let mut imports = Vec::new();
+    for rule in rules.iter() {
+        match rule {
+            CssRule::At(rule) if rule.name == "import" => {
+                imports.extend(ImportRule::parse(&rule.prelude))
+            }
+            CssRule::At(rule) if rule.name == "charset" => {}
+            CssRule::At(rule) if rule.name == "layer" && rule.block.is_none() => {}
+            _ => break,
+        }
+    }
+    imports
+}
+
+// https://drafts.csswg.org/css-cascade-5/#typedef-layer-name
+//
+// The comma separated names of a `@layer` prelude, each dotted name as one
+// string.
+pub(crate) fn parse_layer_names(values: &[ComponentValue]) -> Option<Vec<String>> {
+    let mut names = Vec::new();
+    for name in values.split(|v| matches!(v, ComponentValue::Token(CssToken::Comma))) {
+        let start = name.iter().position(|v| !v.is_whitespace())?;
+        let end = name.iter().rposition(|v| !v.is_whitespace())? + 1;
+        let mut text = String::new();
+        for (i, value) in name[start..end].iter().enumerate() {
+            match value {
+                ComponentValue::Token(CssToken::Ident(ident)) if i % 2 == 0 => text.push_str(ident),
+                ComponentValue::Token(CssToken::Delim('.')) if i % 2 == 1 => text.push('.'),
+                _ => return None,
+            }
+        }
+        if text.ends_with('.') {
+            return None;
+        }
+        names.push(text);
+    }
+    Some(names)
+}
+
+// https://drafts.csswg.org/css-cascade-6/#scoped-styles
+//
+// `@scope (<start>)? [to (<end>)]?`. Without a start, the scoping root is
+// the parent element of the `<style>` the rule comes from.
+#[derive(Clone, Debug)]
+pub(crate) struct Scope {
+    start: Option<SelectorList>,
+    end: Option<SelectorList>,
+    owner_node: Weak<RefCell<Node>>,
+}
+
+impl Scope {
+    pub(crate) fn parse(
+        prelude: &[ComponentValue],
+        owner_node: Weak<RefCell<Node>>,
+    ) -> Option<Scope> {
+        let words: Vec<&ComponentValue> = prelude.iter().filter(|v| !v.is_whitespace()).collect();
+        let selectors = |value: &ComponentValue| match value {
+            ComponentValue::Block('(', contents) => {
+                parse_selector_list(&serialize_values(contents)).ok()
+            }
+            _ => None,
+        };
+        let is_to = |value: &ComponentValue| matches!(value, ComponentValue::Token(CssToken::Ident(ident)) if ident.eq_ignore_ascii_case("to"));
+        let (start, end) = match words[..] {
+            [] => (None, None),
+            [start] => (Some(selectors(start)?), None),
+            [to, end] if is_to(to) => (None, Some(selectors(end)?)),
+            [start, to, end] if is_to(to) => (Some(selectors(start)?), Some(selectors(end)?)),
+            _ => return None,
+        };
+        Some(Scope {
+            start,
+            end,
+            owner_node,
+        })
+    }
+
+    // the start and end selectors
+    pub(crate) fn selectors(&self) -> impl Iterator<Item = &SelectorList> {
+        self.start.iter().chain(self.end.iter())
+    }
+
+    // https://drafts.csswg.org/css-cascade-6/#scoping-root
+    //
+    // The scoping roots `element` is in scope of, nearest first, each with
+    // the number of generations up to it. Elements matching the end
+    // selector and their descendants are out of scope.
+    pub(crate) fn roots(&self, element: &Rc<RefCell<Node>>) -> Vec<(usize, Rc<RefCell<Node>>)> {
+        let ancestors: Vec<Rc<RefCell<Node>>> = std::iter::once(element.clone())
+            .chain(Node::ancestors(element))
+            .take_while(|n| n.borrow().is_element())
+            .collect();
+        let implicit_root = match self.start {
+            Some(_) => None,
+            None => self
+                .owner_node
+                .upgrade()
+                .and_then(|o| o.borrow().parent())
+                .filter(|p| p.borrow().is_element())
+                .or_else(|| ancestors.last().cloned()),
+        };
+
+        let mut roots = Vec::new();
+        for (i, root) in ancestors.iter().enumerate() {
+            let is_root = match (&self.start, &implicit_root) {
+                (Some(start), _) => start.matches(root, &MatchingContext::new()),
+                (None, Some(implicit)) => Rc::ptr_eq(implicit, root),
+                (None, None) => false,
+            };
+            if !is_root {
+                continue;
+            }
+            let context = MatchingContext::with_scope(root);
+            let out_of_scope = self
+                .end
+                .as_ref()
+                .is_some_and(|end| ancestors[..i].iter().any(|n| end.matches(n, &context)));
+            if !out_of_scope {
+                roots.push((i, root.clone()));
+            }
+        }
+        roots
+    }
+}
+
+// https://drafts.csswg.org/css-cascade-6/#scoped-rules
+//
+// A selector in a scoped rule that doesn't mention `:scope` is relative to
+// the scoping root, as if it started with `:where(:scope)` and a
+// descendant combinator.
+pub(crate) fn scope_selector(selector: &ComplexSelector) -> ComplexSelector {
+    if has_scope(selector) {
+        return selector.clone();
+    }
+    let scope = ComplexSelector {
+        compounds: vec![CompoundSelector(vec![SimpleSelector::PseudoClass(
+            PseudoClass::Scope,
+        )])],
+        combinators: Vec::new(),
+    };
+    let mut compounds = vec![CompoundSelector(vec![SimpleSelector::PseudoClass(
+        PseudoClass::Where(SelectorList(vec![scope])),
+    )])];
+    compounds.extend(selector.compounds.iter().cloned());
+    let mut combinators = vec![Combinator::Descendant];
+    combinators.extend(selector.combinators.iter().copied());
+    ComplexSelector {
+        compounds,
+        combinators,
+    }
+}
+
+fn has_scope(selector: &ComplexSelector) -> bool {
+    let in_list = |list: &SelectorList| list.0.iter().any(has_scope);
+    selector
+        .compounds
+        .iter()
+        .flat_map(|c| c.0.iter())
+        .any(|s| match s {
+            SimpleSelector::PseudoClass(p) => match p {
+                PseudoClass::Scope => true,
+                PseudoClass::Not(list) | PseudoClass::Is(list) | PseudoClass::Where(list) => {
+                    in_list(list)
+                }
+                PseudoClass::NthChild(_, Some(list)) | PseudoClass::NthLastChild(_, Some(list)) => {
+                    in_list(list)
+                }
+                PseudoClass::Has(relative) => relative.iter().any(|r| has_scope(&r.selector)),
+                _ => false,
+            },
+            _ => false,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::css::parse_component_values;
+
+    #[test]
+    fn test_parse_import() {
+        let import = |css| ImportRule::parse(&parse_component_values(css));
+        let rule = import("url(a.css) layer(base.reset) supports(display: grid) screen").unwrap();
+        assert_eq!(rule.url, "a.css");
+        assert_eq!(rule.layer, Some(Some(String::from("base.reset"))));
+        assert!(rule.supported);
+        assert_eq!(
+            rule.media,
+            MediaQueryList::parse(&parse_component_values("screen"))
+        );
+
+        let rule = import("\"b.css\" layer supports((color: nonsense))").unwrap();
+        assert_eq!(rule.layer, Some(None));
+        assert!(!rule.supported);
+        assert!(import("url(\"c.css\") print").is_some_and(|r| r.layer.is_none()));
+        assert!(import("c.css").is_none());
+        assert!(import("\"c.css\" layer(a, b)").is_none());
+    }
+
+    #[test]
+    fn test_parse_layer_names() {
+        let names = |css| parse_layer_names(&parse_component_values(css));
+        assert_eq!(names("a, b.c").unwrap(), vec!["a", "b.c"]);
+        assert!(names("a.").is_none());
+        assert!(names("a b").is_none());
+        assert!(names("a,").is_none());
+    }
+}
@@ -0,0 +1,769 @@
+use super::custom_properties::{
+    compute_custom_properties, has_references, is_custom_property, substitute,
+};
+use super::presentational_hints::presentational_hints;
+use super::stylist::StylistRule;
+use super::values::{compute_value, ComputeContext, MEDIUM_FONT_SIZE};
+use super::*;
+use crate::node::NodeKind;
+use crate::parser::css::{parse_component_values, parse_declaration_list, CssToken, Declaration};
+use crate::selector::{matches_complex_selector, MatchingContext, PseudoElement, Specificity};
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+
+// https://drafts.csswg.org/css-cascade-4/#cascading-origins
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Origin {
+    UserAgent,
+    User,
+    Author,
+}
+
+// https://drafts.csswg.org/css-cascade-4/#cascade-sort
+//
+// Declarations sort in ascending priority, so the last one for a property
+// wins.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct CascadePriority {
+    origin_and_importance: u8,
+    // https://drafts.csswg.org/css-cascade-5/#style-attr
+    //
+    // declarations from the `style` attribute beat any selector
+    inline: bool,
+    // https://drafts.csswg.org/css-cascade-5/#cascade-layering
+    layer: usize,
+    specificity: Specificity,
+    // https://drafts.csswg.org/css-cascade-6/#cascade-proximity
+    //
+    // the generations up to the scoping root, the nearest winning, and
+    // none for rules without a scope
+    proximity: Reverse<usize>,
+    order: usize,
+}
+
+// https://drafts.csswg.org/css-cascade-4/#cascade-origin
+//
+// Important declarations reverse the order of the origins.
+fn origin_and_importance(origin: Origin, important: bool) -> u8 {
+    match (important, origin) {
+        (false, Origin::UserAgent) => 0,
+        (false, Origin::User) => 1,
+        (false, Origin::Author) => 2,
+        (true, Origin::Author) => 3,
+        (true, Origin::User) => 4,
+        (true, Origin::UserAgent) => 5,
+    }
+}
+
+struct MatchedDeclaration<'a> {
+    priority: CascadePriority,
+    origin: Origin,
+    // the longhand the declaration sets, which differs from its own name
+    // for a shorthand left unexpanded for its `var()`s
+    name: &'a str,
+    declaration: &'a Declaration,
+}
+
+// https://drafts.csswg.org/css-cascade-4/#cascading
+//
+// Computes the style of `element` from the rules matching it and the style
+// of its parent.
+pub fn cascade(
+    stylist: &Stylist,
+    element: &Rc<RefCell<Node>>,
+    parent: Option<&ComputedStyle>,
+) -> ComputedStyle {
+    // https://html.spec.whatwg.org/multipage/dom.html#the-style-attribute
+    let (hints, inline) = match element.borrow().kind {
+        NodeKind::Element(ref e) => (
+            presentational_hints(e),
+            e.get_attribute("style")
+                .map(parse_declaration_list)
+                .unwrap_or_default(),
+        ),
+        _ => (Vec::new(), Vec::new()),
+    };
+    let matched = collect_matching_declarations(stylist, element, None, &hints, &inline);
+    compute_style(stylist, element, matched, parent)
+}
+
+// https://drafts.csswg.org/css-pseudo-4/#treelike
+//
+// Computes the style of a pseudo-element of `element` from the rules for it,
+// inheriting from `parent`: the style of `element`, or of its
+// `::first-line` for `::first-letter`. None when no rule matches, except for
+// `::marker`, which list items have anyway.
+pub fn cascade_pseudo_element(
+    stylist: &Stylist,
+    element: &Rc<RefCell<Node>>,
+    pseudo_element: PseudoElement,
+    parent: &ComputedStyle,
+) -> Option<ComputedStyle> {
+    let matched = collect_matching_declarations(stylist, element, Some(pseudo_element), &[], &[]);
+    if matched.is_empty() && pseudo_element != PseudoElement::Marker {
+        return None;
+    }
+    let mut style = compute_style(stylist, element, matched, Some(parent));
+    // https://drafts.csswg.org/css-content-3/#valdef-content-normal
+    //
+    // `normal` generates nothing for `::before` and `::after`
+    if matches!(pseudo_element, PseudoElement::Before | PseudoElement::After)
+        && style.get_text("content").as_deref() == Some("normal")
+    {
+        style.set("content", parse_component_values("none"));
+    }
+    Some(style)
+}
+
+// The styles of the pseudo-elements of `element` that rules match, and of
+// the `::marker` of a list item, in the order of `PseudoElement::ALL`.
+pub(crate) fn cascade_pseudo_elements(
+    stylist: &Stylist,
+    element: &Rc<RefCell<Node>>,
+    style: &ComputedStyle,
+) -> Vec<(PseudoElement, Rc<ComputedStyle>)> {
+    let mut styles: Vec<(PseudoElement, Rc<ComputedStyle>)> = Vec::new();
+    if style.display().is_none() {
+        return styles;
+    }
+    for pseudo_element in PseudoElement::ALL {
+        // https://drafts.csswg.org/css-lists-3/#marker-pseudo
+        let applies = match pseudo_element {
+            PseudoElement::Marker => style.display().is_list_item(),
+            _ => stylist.has_pseudo_element_rules(pseudo_element),
+        };
+        if !applies {
+            continue;
+        }
+        // https://drafts.csswg.org/css-pseudo-4/#first-text-line
+        let parent = match pseudo_element {
+            PseudoElement::FirstLetter => styles
+                .iter()
+                .find(|(p, _)| *p == PseudoElement::FirstLine)
+                .map_or(style, |(_, s)| s.as_ref()),
+            _ => style,
+        };
+        if let Some(s) = cascade_pseudo_element(stylist, element, pseudo_element, parent) {
+            styles.push((pseudo_element, Rc::new(s)));
+        }
+    }
+    styles
+}
+
+fn compute_style(
+    stylist: &Stylist,
+    element: &Rc<RefCell<Node>>,
+    mut matched: Vec<MatchedDeclaration>,
+    parent: Option<&ComputedStyle>,
+) -> ComputedStyle {
+    matched.sort_by_key(|m| m.priority);
+
+    let mut context = ComputeContext {
+        font_size: parent.map_or(MEDIUM_FONT_SIZE, |p| p.font_size()),
+        root_font_size: root_font_size(element),
+        viewport: stylist.viewport(),
+    };
+    let mut style = ComputedStyle::default();
+
+    // custom properties go before the properties that refer to them
+    let custom: BTreeSet<&str> = matched
+        .iter()
+        .map(|m| m.name)
+        .filter(|name| is_custom_property(name))
+        .collect();
+    let cascaded = custom
+        .into_iter()
+        .filter_map(|name| Some((name, cascaded_value(&matched, name)?.value.as_slice())))
+        .collect();
+    for (name, value) in compute_custom_properties(cascaded, parent) {
+        style.set(&name, value);
+    }
+
+    // `font-size` goes first, as the `em` of the other properties refer to it
+    let font_size = lookup("font-size").unwrap();
+    let properties =
+        std::iter::once(font_size).chain(PROPERTIES.iter().filter(|p| p.name != "font-size"));
+    for property in properties {
+        let value = cascaded_value(&matched, property.name)
+            .and_then(|d| specified_value(d, property, &style));
+        let value = match value {
+            Some(value) => compute(property, &value, parent, &context),
+            None if property.inherited => inherit(property, parent, &context),
+            None => initial(property, &context),
+        };
+        style.set(property.name, value);
+        if property.name == "font-size" {
+            context.font_size = style.font_size();
+        }
+    }
+
+    // https://drafts.csswg.org/css-backgrounds-3/#border-width
+    //
+    // A side whose style is `none` or `hidden` has no border to be wide.
+    for side in ["top", "right", "bottom", "left"] {
+        let border_style = style.get_text(&format!("border-{}-style", side));
+        if matches!(border_style.as_deref(), Some("none" | "hidden")) {
+            style.set(
+                &format!("border-{}-width", side),
+                parse_component_values("0px"),
+            );
+        }
+    }
+    style
+}
+
+// https://drafts.csswg.org/css-values-4/#rem
+//
+// The root element's `rem` refers to the initial font size.
+fn root_font_size(element: &Rc<RefCell<Node>>) -> f64 {
+    let root = Node::ancestors(element)
+        .filter(|a| a.borrow().is_element())
+        .last();
+    root.and_then(|r| Node::computed_style(&r))
+        .map_or(MEDIUM_FONT_SIZE, |s| s.font_size())
+}
+
+fn collect_matching_declarations<'a>(
+    stylist: &'a Stylist,
+    element: &Rc<RefCell<Node>>,
+    pseudo_element: Option<PseudoElement>,
+    hints: &'a [Declaration],
+    inline: &'a [Declaration],
+) -> Vec<MatchedDeclaration<'a>> {
+    let mut matched = Vec::new();
+    let mut order = 0;
+
+    // https://drafts.csswg.org/css-cascade-5/#preshint
+    //
+    // author declarations below every author rule
+    for declaration in hints.iter() {
+        for name in longhands(declaration) {
+            matched.push(MatchedDeclaration {
+                priority: CascadePriority {
+                    origin_and_importance: origin_and_importance(Origin::Author, false),
+                    inline: false,
+                    layer: 0,
+                    specificity: Specificity::default(),
+                    proximity: Reverse(usize::MAX),
+                    order,
+                },
+                origin: Origin::Author,
+                name,
+                declaration,
+            });
+        }
+        order += 1;
+    }
+
+    for StylistRule {
+        origin,
+        rule,
+        layer,
+        scope,
+        ..
+    } in stylist.rules()
+    {
+        // the most specific of the selectors that match, against the
+        // nearest scoping root any of them match under
+        let contexts = match scope {
+            Some(scope) => scope
+                .roots(element)
+                .into_iter()
+                .map(|(generations, root)| (generations, MatchingContext::with_scope(&root)))
+                .collect(),
+            None => vec![(usize::MAX, MatchingContext::new())],
+        };
+        let found = contexts.iter().find_map(|(generations, context)| {
+            let specificity = rule
+                .selectors
+                .0
+                .iter()
+                .filter(|s| s.pseudo_element() == pseudo_element)
+                .filter(|s| matches_complex_selector(s, element, context))
+                .map(|s| s.specificity())
+                .max()?;
+            Some((specificity, Reverse(*generations)))
+        });
+        let (specificity, proximity) = match found {
+            Some(found) => found,
+            None => continue,
+        };
+
+        for declaration in rule.declarations.iter() {
+            for name in longhands(declaration) {
+                matched.push(MatchedDeclaration {
+                    priority: CascadePriority {
+                        origin_and_importance: origin_and_importance(
+                            *origin,
+                            declaration.important,
+                        ),
+                        inline: false,
+                        layer: stylist.layer_priority(*layer, declaration.important),
+                        specificity,
+                        proximity,
+                        order,
+                    },
+                    origin: *origin,
+                    name,
+                    declaration,
+                });
+            }
+            order += 1;
+        }
+    }
+
+    for declaration in inline.iter() {
+        for name in longhands(declaration) {
+            matched.push(MatchedDeclaration {
+                priority: CascadePriority {
+                    origin_and_importance: origin_and_importance(
+                        Origin::Author,
+                        declaration.important,
+                    ),
+                    inline: true,
+                    layer: 0,
+                    specificity: Specificity::default(),
+                    proximity: Reverse(usize::MAX),
+                    order,
+                },
+                origin: Origin::Author,
+                name,
+                declaration,
+            });
+        }
+        order += 1;
+    }
+    matched
+}
+
+// the properties a declaration sets: the longhands of a shorthand that
+// still has `var()`s, or else its own
+fn longhands(declaration: &Declaration) -> Vec<&str> {
+    match lookup_shorthand(&declaration.name) {
+        Some(shorthand) => shorthand.longhands.to_vec(),
+        None => vec![declaration.name.as_str()],
+    }
+}
+
+// https://drafts.csswg.org/css-cascade-4/#cascaded-value
+//
+// `revert` hands the property to the best declaration from an earlier
+// origin, or leaves it without a cascaded value.
+//
+// https://drafts.csswg.org/css-cascade-5/#revert-layer
+//
+// `revert-layer` hands it to the best declaration from an earlier layer of
+// the same origin and importance, or else of an earlier origin. The `style`
+// attribute counts as a layer above all the others.
+fn cascaded_value<'a>(matched: &[MatchedDeclaration<'a>], name: &str) -> Option<&'a Declaration> {
+    let mut below: Option<Origin> = None;
+    let mut below_layer: Option<(u8, bool, usize)> = None;
+    for m in matched.iter().rev() {
+        let p = &m.priority;
+        let layer = (p.origin_and_importance, p.inline, p.layer);
+        if m.name != name
+            || below.is_some_and(|o| m.origin >= o)
+            || below_layer.is_some_and(|b| b.0 == layer.0 && (b.1, b.2) <= (layer.1, layer.2))
+        {
+            continue;
+        }
+        match css_wide_keyword(&m.declaration.value) {
+            Some("revert") => below = Some(m.origin),
+            Some("revert-layer") => below_layer = Some(layer),
+            _ => return Some(m.declaration),
+        }
+    }
+    None
+}
+
+// https://drafts.csswg.org/css-variables-2/#invalid-at-computed-value-time
+//
+// The value `declaration` gives the longhand `name`, with its `var()`s
+// substituted and then expanded if it's a shorthand. None when that fails
+// or gives a value the property doesn't take, which makes the declaration
+// act as `unset`.
+fn specified_value(
+    declaration: &Declaration,
+    property: &PropertyDef,
+    style: &ComputedStyle,
+) -> Option<Vec<ComponentValue>> {
+    if !has_references(&declaration.value) {
+        return Some(declaration.value.clone());
+    }
+    let value = substitute(&declaration.value, style)?;
+    match lookup_shorthand(&declaration.name) {
+        Some(shorthand) => shorthand
+            .expand(&value)?
+            .into_iter()
+            .find(|(longhand, _)| *longhand == property.name)
+            .map(|(_, value)| value),
+        None => property.parse(&value).then_some(value),
+    }
+}
+
+// https://drafts.csswg.org/css-values-4/#common-keywords
+pub(super) fn css_wide_keyword(value: &[ComponentValue]) -> Option<&'static str> {
+    let keyword = match value {
+        [ComponentValue::Token(CssToken::Ident(keyword))] => keyword,
+        _ => return None,
+    };
+    ["inherit", "initial", "unset", "revert", "revert-layer"]
+        .into_iter()
+        .find(|k| keyword.eq_ignore_ascii_case(k))
+}
+
+fn compute(
+    property: &PropertyDef,
+    value: &[ComponentValue],
+    parent: Option<&ComputedStyle>,
+    context: &ComputeContext,
+) -> Vec<ComponentValue> {
+    match css_wide_keyword(value) {
+        Some("inherit") => inherit(property, parent, context),
+        Some("initial") => initial(property, context),
+        Some(_) if property.inherited => inherit(property, parent, context),
+        Some(_) => initial(property, context),
+        // https://drafts.csswg.org/css-color-4/#resolving-other-colors
+        None if property.name == "color" && is_current_color(value) => {
+            inherit(property, parent, context)
+        }
+        None => compute_value(property.name, value, context),
+    }
+}
+
+fn is_current_color(value: &[ComponentValue]) -> bool {
+    matches!(value, [ComponentValue::Token(CssToken::Ident(keyword))]
+        if keyword.eq_ignore_ascii_case("currentcolor"))
+}
+
+// https://drafts.csswg.org/css-cascade-4/#inheriting
+//
+// The root element inherits initial values.
+fn inherit(
+    property: &PropertyDef,
+    parent: Option<&ComputedStyle>,
+    context: &ComputeContext,
+) -> Vec<ComponentValue> {
+    match parent.and_then(|p| p.get(property.name)) {
+        Some(value) => value.to_vec(),
+        None => initial(property, context),
+    }
+}
+
+fn initial(property: &PropertyDef, context: &ComputeContext) -> Vec<ComponentValue> {
+    compute_value(
+        property.name,
+        &parse_component_values(property.initial),
+        context,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::css::parse_stylesheet;
+    use crate::parser::html_parser::parse;
+
+    // the computed `property` of the element with the given id
+    fn computed(sheets: &[(Origin, &str)], html: &str, id: &str, property: &str) -> String {
+        let document = parse(html);
+        let mut stylist = Stylist::new();
+        for (origin, css) in sheets {
+            stylist.add_stylesheet(*origin, parse_stylesheet(css));
+        }
+        Node::resolve_styles(&document, &stylist);
+        let element = Node::get_element_by_id(&document, id).unwrap();
+        Node::computed_style(&element)
+            .unwrap()
+            .get_text(property)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_specificity_and_source_order() {
+        let html = "<p id=a class=x>x</p>";
+        let author = |css| computed(&[(Origin::Author, css)], html, "a", "color");
+
+        assert_eq!(
+            author("p { color: red } p { color: blue }"),
+            "rgb(0, 0, 255)"
+        );
+        assert_eq!(
+            author(".x { color: red } p { color: blue }"),
+            "rgb(255, 0, 0)"
+        );
+        assert_eq!(
+            author("#a { color: red } p.x { color: blue }"),
+            "rgb(255, 0, 0)"
+        );
+        // the most specific matching selector of a list counts
+        assert_eq!(
+            author("p, #a { color: red } .x { color: blue }"),
+            "rgb(255, 0, 0)"
+        );
+        assert_eq!(
+            author("p { color: red !important } #a { color: blue }"),
+            "rgb(255, 0, 0)"
+        );
+        assert_eq!(author("div { color: red }"), "rgb(0, 0, 0)");
+    }
+
+    #[test]
+    fn test_origins_and_importance() {
+        let html = "<p id=a>x</p>";
+        let ua = "p { color: red; display: block }";
+
+        assert_eq!(
+            computed(
+                &[
+                    (Origin::Author, "* { color: blue }"),
+                    (Origin::UserAgent, ua)
+                ],
+                html,
+                "a",
+                "color"
+            ),
+            "rgb(0, 0, 255)"
+        );
+        assert_eq!(
+            computed(
+                &[
+                    (Origin::UserAgent, "p { color: red !important }"),
+                    (Origin::Author, "#a { color: blue !important }"),
+                ],
+                html,
+                "a",
+                "color"
+            ),
+            "rgb(255, 0, 0)"
+        );
+        assert_eq!(
+            computed(
+                &[
+                    (Origin::User, "p { color: green !important }"),
+                    (Origin::Author, "#a { color: blue !important }"),
+                ],
+                html,
+                "a",
+                "color"
+            ),
+            "rgb(0, 128, 0)"
+        );
+        // revert falls back to the user agent's value
+        assert_eq!(
+            computed(
+                &[
+                    (Origin::UserAgent, ua),
+                    (
+                        Origin::Author,
+                        "p { display: inline } #a { display: revert }"
+                    ),
+                ],
+                html,
+                "a",
+                "display"
+            ),
+            "block"
+        );
+    }
+
+    #[test]
+    fn test_inheritance_and_initial_values() {
+        let html = "<div id=a><p id=b><span id=c>x</span></p></div>";
+        let css = "div { color: red; margin-left: 2px; font-size: 20px } \
+                   p { margin-left: inherit; font-size: initial } \
+                   span { color: unset; margin-left: unset }";
+        let get = |id, property| computed(&[(Origin::Author, css)], html, id, property);
+
+        assert_eq!(get("b", "color"), "rgb(255, 0, 0)");
+        assert_eq!(get("b", "margin-left"), "2px");
+        assert_eq!(get("b", "font-size"), "16px");
+        // unset inherits inherited properties and resets the others
+        assert_eq!(get("c", "color"), "rgb(255, 0, 0)");
+        assert_eq!(get("c", "margin-left"), "0px");
+        assert_eq!(get("c", "font-size"), "16px");
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        let html = "<p id=a>x</p>";
+        let get = |css, property| computed(&[(Origin::Author, css)], html, "a", property);
+
+        // a value the property doesn't take drops the declaration
+        let css = "p { color: red; margin-top: 2px } \
+                   p { color: 12px; margin-top: red; display: bogus }";
+        assert_eq!(get(css, "color"), "rgb(255, 0, 0)");
+        assert_eq!(get(css, "margin-top"), "2px");
+        assert_eq!(get(css, "display"), "inline");
+        assert_eq!(
+            get("p { margin: 1px red; margin-left: -3px }", "margin"),
+            "0px 0px 0px -3px"
+        );
+        // and one that is invalid only once substituted acts as `unset`
+        let css = "p { color: red } p { --c: 5px; color: var(--c); width: var(--c) }";
+        assert_eq!(get(css, "color"), "rgb(0, 0, 0)");
+        assert_eq!(get(css, "width"), "5px");
+    }
+
+    #[test]
+    fn test_shorthands() {
+        let html = "<p id=a style=\"margin-left: 3px\">x</p>";
+        let get = |css, property| computed(&[(Origin::Author, css)], html, "a", property);
+
+        assert_eq!(get("p { margin: 1px 2px }", "margin-right"), "2px");
+        // longhands from inline style beat the shorthand
+        assert_eq!(get("p { margin: 1px 2px }", "margin-left"), "3px");
+        assert_eq!(get("p { margin: 1px 2px }", "margin"), "1px 2px 1px 3px");
+        assert_eq!(
+            get(
+                "p { border-top: 1px solid; border-top-style: dashed }",
+                "border-top"
+            ),
+            "1px dashed"
+        );
+        // a shorthand with `var()` expands once it's substituted
+        let css = "p { --m: 4px 5px; padding: var(--m); margin: var(--missing) }";
+        assert_eq!(get(css, "padding"), "4px 5px");
+        assert_eq!(get(css, "margin-top"), "0px");
+        assert_eq!(
+            get("p { --w: 9px; padding: 1px var(--w) 2px a }", "padding-top"),
+            "0px"
+        );
+    }
+
+    #[test]
+    fn test_border_widths() {
+        let html = "<p id=a>x</p>";
+        let css = "p { border: 5px; border-top: 2px solid; border-right-style: hidden; \
+                   border-bottom: thin dotted }";
+        let get = |property| computed(&[(Origin::Author, css)], html, "a", property);
+
+        // a side only has a width while it has a style
+        assert_eq!(get("border-top-width"), "2px");
+        assert_eq!(get("border-right-width"), "0px");
+        assert_eq!(get("border-bottom-width"), "1px");
+        assert_eq!(get("border-left-width"), "0px");
+    }
+
+    #[test]
+    fn test_layers() {
+        let html = "<p id=a>x</p>";
+        let author = |css: &str| computed(&[(Origin::Author, css)], html, "a", "color");
+
+        // later layers win whatever the specificity, and unlayered rules
+        // beat them all
+        let layered = "@layer base, theme; \
+                       @layer theme { p { color: blue } } \
+                       @layer base { #a { color: red } }";
+        assert_eq!(author(layered), "rgb(0, 0, 255)");
+        assert_eq!(
+            author(&format!("{} p {{ color: green }}", layered)),
+            "rgb(0, 128, 0)"
+        );
+        // important declarations reverse the order
+        assert_eq!(
+            author(
+                "@layer base { p { color: red !important } } \
+                 @layer theme { p { color: blue !important } } \
+                 p { color: green !important }"
+            ),
+            "rgb(255, 0, 0)"
+        );
+        // a layer's own rules beat those of the layers nested in it
+        assert_eq!(
+            author("@layer a { @layer b { #a { color: red } } p { color: blue } }"),
+            "rgb(0, 0, 255)"
+        );
+        assert_eq!(
+            author(
+                "@layer a.b { p { color: red } } @layer c { p { color: green } } \
+                 @layer a { p { color: blue } }"
+            ),
+            "rgb(0, 128, 0)"
+        );
+        // anonymous layers are each their own
+        assert_eq!(
+            author("@layer { p { color: red } } @layer { p { color: blue } }"),
+            "rgb(0, 0, 255)"
+        );
+        // layers don't cross origins
+        assert_eq!(
+            computed(
+                &[
+                    (
+                        Origin::UserAgent,
+                        "@layer x { p { color: red !important } }"
+                    ),
+                    (Origin::Author, "@layer x { p { color: blue !important } }"),
+                ],
+                html,
+                "a",
+                "color"
+            ),
+            "rgb(255, 0, 0)"
+        );
+    }
+
+    #[test]
+    fn test_revert_layer() {
+        let html = "<p id=a style=\"color: revert-layer\">x</p>";
+        let author = |css: &str| computed(&[(Origin::Author, css)], html, "a", "color");
+
+        assert_eq!(
+            author("@layer a { p { color: red } } @layer b { p { color: blue } p { color: revert-layer } }"),
+            "rgb(255, 0, 0)"
+        );
+        // unlayered rules roll back to the last layer, and the style
+        // attribute to them
+        assert_eq!(
+            author("@layer a { p { color: red } } p { color: green } #a { color: revert-layer }"),
+            "rgb(255, 0, 0)"
+        );
+        assert_eq!(author("p { color: green }"), "rgb(0, 128, 0)");
+        // with no earlier layer it rolls back to the earlier origins
+        assert_eq!(
+            computed(
+                &[
+                    (Origin::User, "p { color: blue }"),
+                    (Origin::Author, "@layer a { p { color: revert-layer } }"),
+                ],
+                html,
+                "a",
+                "color"
+            ),
+            "rgb(0, 0, 255)"
+        );
+        assert_eq!(author("p { color: revert-layer }"), "rgb(0, 0, 0)");
+    }
+
+    #[test]
+    fn test_scope() {
+        let html = "<div class=card id=outer><p id=a>x</p>\
+                    <div class=card><p id=b>y</p><div class=content><p id=c>z</p></div></div></div>\
+                    <p id=d>w</p>";
+        let get = |css, id| computed(&[(Origin::Author, css)], html, id, "color");
+
+        // the nearest scoping root wins, before source order
+        let css = "@scope (.card) { p { color: red } } @scope (#outer) { p { color: blue } }";
+        assert_eq!(get(css, "a"), "rgb(0, 0, 255)");
+        assert_eq!(get(css, "b"), "rgb(255, 0, 0)");
+        assert_eq!(get(css, "d"), "rgb(0, 0, 0)");
+        // scoped rules beat unscoped ones of the same specificity
+        assert_eq!(
+            get(
+                "@scope (.card) { p { color: red } } p { color: orange }",
+                "a"
+            ),
+            "rgb(255, 0, 0)"
+        );
+        // the scoping limit and what's inside it are out of scope
+        let css = "@scope (.card) to (.content) { p { color: green } }";
+        assert_eq!(get(css, "b"), "rgb(0, 128, 0)");
+        assert_eq!(get(css, "c"), "rgb(0, 0, 0)");
+        // `:scope` is the scoping root
+        let css = "@scope (.card) { :scope > p { color: purple } }";
+        assert_eq!(get(css, "a"), "rgb(128, 0, 128)");
+        assert_eq!(get(css, "b"), "rgb(128, 0, 128)");
+        assert_eq!(get(css, "c"), "rgb(0, 0, 0)");
+    }
+}
@@ -0,0 +1,250 @@
+// https://drafts.csswg.org/css-counter-styles-3/#predefined-counters
+//
+// The counter styles `list-style-type`, `counter()` and `counters()` can
+// name. Each turns an integer into text, falling back to `decimal` for the
+// values out of its range.
+struct CounterStyle {
+    name: &'static str,
+    system: System,
+    // https://drafts.csswg.org/css-counter-styles-3/#counter-style-suffix
+    //
+    // what follows the representation in a marker
+    suffix: &'static str,
+}
+
+// https://drafts.csswg.org/css-counter-styles-3/#counter-style-system
+#[derive(Clone, Copy)]
+enum System {
+    Cyclic(&'static str),
+    // the symbols with the minimum number of them
+    Numeric(&'static str, usize),
+    Alphabetic(&'static str),
+    Additive(&'static [(i32, &'static str)]),
+}
+
+const UPPER_ROMAN: &[(i32, &str)] = &[
+    (1000, "M"),
+    (900, "CM"),
+    (500, "D"),
+    (400, "CD"),
+    (100, "C"),
+    (90, "XC"),
+    (50, "L"),
+    (40, "XL"),
+    (10, "X"),
+    (9, "IX"),
+    (5, "V"),
+    (4, "IV"),
+    (1, "I"),
+];
+
+const LOWER_ROMAN: &[(i32, &str)] = &[
+    (1000, "m"),
+    (900, "cm"),
+    (500, "d"),
+    (400, "cd"),
+    (100, "c"),
+    (90, "xc"),
+    (50, "l"),
+    (40, "xl"),
+    (10, "x"),
+    (9, "ix"),
+    (5, "v"),
+    (4, "iv"),
+    (1, "i"),
+];
+
+const fn style(name: &'static str, system: System, suffix: &'static str) -> CounterStyle {
+    CounterStyle {
+        name,
+        system,
+        suffix,
+    }
+}
+
+const COUNTER_STYLES: &[CounterStyle] = &[
+    style("decimal", System::Numeric("0123456789", 1), ". "),
+    style("decimal-leading-zero", System::Numeric("0123456789", 2), ". "),
+    style("lower-roman", System::Additive(LOWER_ROMAN), ". "),
+    style("upper-roman", System::Additive(UPPER_ROMAN), ". "),
+    style("lower-alpha", System::Alphabetic("abcdefghijklmnopqrstuvwxyz"), ". "),
+    style("lower-latin", System::Alphabetic("abcdefghijklmnopqrstuvwxyz"), ". "),
+    style("upper-alpha", System::Alphabetic("ABCDEFGHIJKLMNOPQRSTUVWXYZ"), ". "),
+    style("upper-latin", System::Alphabetic("ABCDEFGHIJKLMNOPQRSTUVWXYZ"), ". "),
+    style("lower-greek", System::Alphabetic("αβγδεζηθικλμνξοπρστυφχψω"), ". "),
+    // https://drafts.csswg.org/css-counter-styles-3/#simple-cjk
+    style("cjk-decimal", System::Numeric("〇一二三四五六七八九", 1), "、"),
+    style(
+        "hiragana",
+        System::Alphabetic(
+            "あいうえおかきくけこさしすせそたちつてとなにぬねのはひふへほまみむめもやゆよらりるれろわゐゑをん",
+        ),
+        "、",
+    ),
+    style(
+        "katakana",
+        System::Alphabetic(
+            "アイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワヰヱヲン",
+        ),
+        "、",
+    ),
+    style(
+        "hiragana-iroha",
+        System::Alphabetic(
+            "いろはにほへとちりぬるをわかよたれそつねならむうゐのおくやまけふこえてあさきゆめみしゑひもせす",
+        ),
+        "、",
+    ),
+    style(
+        "katakana-iroha",
+        System::Alphabetic(
+            "イロハニホヘトチリヌルヲワカヨタレソツネナラムウヰノオクヤマケフコエテアサキユメミシヱヒモセス",
+        ),
+        "、",
+    ),
+    style("disc", System::Cyclic("\u{2022}"), " "),
+    style("circle", System::Cyclic("\u{25E6}"), " "),
+    style("square", System::Cyclic("\u{25AA}"), " "),
+    style("disclosure-open", System::Cyclic("\u{25BE}"), " "),
+    style("disclosure-closed", System::Cyclic("\u{25B8}"), " "),
+];
+
+fn lookup(name: &str) -> &'static CounterStyle {
+    COUNTER_STYLES
+        .iter()
+        .find(|s| s.name.eq_ignore_ascii_case(name))
+        .unwrap_or(&COUNTER_STYLES[0])
+}
+
+// https://drafts.csswg.org/css-counter-styles-3/#generate-a-counter
+//
+// `value` in the counter style `name`, which is `decimal` for the styles not
+// known here and empty for `none`.
+pub fn format_counter(value: i32, name: &str) -> String {
+    if name.eq_ignore_ascii_case("none") {
+        return String::new();
+    }
+    let style = lookup(name);
+    represent(value, style.system)
+        .or_else(|| represent(value, COUNTER_STYLES[0].system))
+        .unwrap_or_default()
+}
+
+// the text after a counter in a list marker
+pub fn marker_suffix(name: &str) -> &'static str {
+    lookup(name).suffix
+}
+
+// None for a value out of the range of `system`
+fn represent(value: i32, system: System) -> Option<String> {
+    match system {
+        // https://drafts.csswg.org/css-counter-styles-3/#cyclic-system
+        System::Cyclic(symbols) => {
+            let symbols: Vec<char> = symbols.chars().collect();
+            let i = (value as i64 - 1).rem_euclid(symbols.len() as i64);
+            Some(symbols[i as usize].to_string())
+        }
+        // https://drafts.csswg.org/css-counter-styles-3/#numeric-system
+        System::Numeric(symbols, pad) => {
+            let symbols: Vec<char> = symbols.chars().collect();
+            let base = symbols.len() as u32;
+            let mut magnitude = value.unsigned_abs();
+            let mut digits = Vec::new();
+            loop {
+                digits.push(symbols[(magnitude % base) as usize]);
+                magnitude /= base;
+                if magnitude == 0 {
+                    break;
+                }
+            }
+            // https://drafts.csswg.org/css-counter-styles-3/#counter-style-pad
+            //
+            // the negative sign counts toward the padding
+            let negative = value < 0;
+            while digits.len() + (negative as usize) < pad {
+                digits.push(symbols[0]);
+            }
+            // https://drafts.csswg.org/css-counter-styles-3/#counter-style-negative
+            if negative {
+                digits.push('-');
+            }
+            Some(digits.into_iter().rev().collect())
+        }
+        // https://drafts.csswg.org/css-counter-styles-3/#alphabetic-system
+        System::Alphabetic(symbols) => {
+            if value < 1 {
+                return None;
+            }
+            let symbols: Vec<char> = symbols.chars().collect();
+            let base = symbols.len() as u32;
+            let mut value = value as u32;
+            let mut letters = Vec::new();
+            while value > 0 {
+                value -= 1;
+                letters.push(symbols[(value % base) as usize]);
+                value /= base;
+            }
+            Some(letters.into_iter().rev().collect())
+        }
+        // https://drafts.csswg.org/css-counter-styles-3/#additive-system
+        //
+        // Roman numerals go up to 3999.
+        System::Additive(symbols) => {
+            if !(1..=3999).contains(&value) {
+                return None;
+            }
+            let mut value = value;
+            let mut text = String::new();
+            for (weight, symbol) in symbols {
+                while value >= *weight {
+                    text.push_str(symbol);
+                    value -= weight;
+                }
+            }
+            Some(text)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_counter() {
+        let format = |values: &[i32], name| {
+            values
+                .iter()
+                .map(|v| format_counter(*v, name))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        assert_eq!(format(&[0, 7, -12, 305], "decimal"), "0 7 -12 305");
+        assert_eq!(format(&[3, 10, -4], "decimal-leading-zero"), "03 10 -4");
+        assert_eq!(
+            format(&[1, 4, 9, 14, 1994, 3999, 4000, 0], "lower-roman"),
+            "i iv ix xiv mcmxciv mmmcmxcix 4000 0"
+        );
+        assert_eq!(format(&[49], "UPPER-ROMAN"), "XLIX");
+        assert_eq!(
+            format(&[1, 26, 27, 52, 703, 0, -1], "lower-alpha"),
+            "a z aa az aaa 0 -1"
+        );
+        assert_eq!(format(&[28], "upper-latin"), "AB");
+        assert_eq!(format(&[1, 24, 25], "lower-greek"), "α ω αα");
+        assert_eq!(
+            format(&[0, 7, 10, 2024], "cjk-decimal"),
+            "〇 七 一〇 二〇二四"
+        );
+        assert_eq!(format(&[1, 2, 48, 49], "hiragana"), "あ い ん ああ");
+        assert_eq!(format(&[1, 47, 48], "katakana-iroha"), "イ ス イイ");
+        assert_eq!(format(&[1, 5], "disc"), "\u{2022} \u{2022}");
+        assert_eq!(format(&[5], "none"), "");
+        assert_eq!(format(&[5], "unknown"), "5");
+
+        assert_eq!(marker_suffix("decimal"), ". ");
+        assert_eq!(marker_suffix("hiragana"), "、");
+        assert_eq!(marker_suffix("square"), " ");
+    }
+}
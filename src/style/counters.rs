@@ -0,0 +1,450 @@
+use super::counter_styles::{format_counter, marker_suffix};
+use super::*;
+use crate::node::NodeKind;
+use crate::parser::css::CssToken;
+
+// https://drafts.csswg.org/css-lists-3/#auto-numbering
+//
+// The counters in scope at one point of a walk over the tree in order: each
+// with its value and the depth of the element that created it, which keeps
+// it for its following siblings and their descendants.
+#[derive(Clone, Debug, Default)]
+pub struct Counters {
+    counters: Vec<Counter>,
+}
+
+#[derive(Clone, Debug)]
+struct Counter {
+    name: String,
+    value: i32,
+    depth: usize,
+    // https://drafts.csswg.org/css-lists-3/#reversed-counter
+    reversed: bool,
+}
+
+// one `name [integer]?` of the counter properties, which `counter-reset`
+// can give as `reversed(name)`
+#[derive(Clone, Debug, PartialEq)]
+struct CounterChange {
+    name: String,
+    value: Option<i32>,
+    reversed: bool,
+}
+
+impl Counters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // https://drafts.csswg.org/css-lists-3/#counter-properties
+    //
+    // Applies the counter properties of `element`, or of one of its
+    // pseudo-elements, at `depth` of the tree: counters are reset, then
+    // incremented, then set.
+    pub fn update(
+        &mut self,
+        element: &Rc<RefCell<Node>>,
+        pseudo_element: Option<PseudoElement>,
+        depth: usize,
+        style: &ComputedStyle,
+    ) {
+        for change in counter_changes(style.get("counter-reset")) {
+            let value = match change.value {
+                Some(value) => value,
+                None if change.reversed => {
+                    reversed_initial_value(element, pseudo_element, style, &change.name)
+                }
+                None => 0,
+            };
+            self.instantiate(&change.name, value, depth, change.reversed);
+        }
+
+        let reversed_list = self.innermost("list-item").is_some_and(|c| c.reversed);
+        for (name, value) in increments(style, reversed_list) {
+            let counter = self.get_or_instantiate(&name, depth);
+            counter.value = counter.value.saturating_add(value);
+        }
+
+        for change in counter_changes(style.get("counter-set")) {
+            self.get_or_instantiate(&change.name, depth).value = change.value.unwrap_or(0);
+        }
+    }
+
+    // Drops the counters the children of an element at `depth` created,
+    // once the walk leaves it.
+    pub fn leave(&mut self, depth: usize) {
+        self.counters.retain(|c| c.depth <= depth);
+    }
+
+    // the value of the innermost counter named `name`, or 0 without one
+    pub fn value(&self, name: &str) -> i32 {
+        self.innermost(name).map_or(0, |c| c.value)
+    }
+
+    // the values of every counter named `name`, outermost first
+    pub fn values(&self, name: &str) -> Vec<i32> {
+        let values: Vec<i32> = self
+            .counters
+            .iter()
+            .filter(|c| c.name == name)
+            .map(|c| c.value)
+            .collect();
+        match values.is_empty() {
+            true => vec![0],
+            false => values,
+        }
+    }
+
+    fn innermost(&self, name: &str) -> Option<&Counter> {
+        self.counters.iter().rev().find(|c| c.name == name)
+    }
+
+    // https://drafts.csswg.org/css-lists-3/#instantiate-counter
+    //
+    // A counter replaces the one a previous sibling created.
+    fn instantiate(&mut self, name: &str, value: i32, depth: usize, reversed: bool) {
+        if let Some(last) = self.counters.iter().rposition(|c| c.name == name) {
+            if self.counters[last].depth == depth {
+                self.counters.remove(last);
+            }
+        }
+        self.counters.push(Counter {
+            name: name.to_string(),
+            value,
+            depth,
+            reversed,
+        });
+    }
+
+    // the counter to increment or set, created at 0 when there is none
+    fn get_or_instantiate(&mut self, name: &str, depth: usize) -> &mut Counter {
+        match self.counters.iter().rposition(|c| c.name == name) {
+            Some(i) => &mut self.counters[i],
+            None => {
+                self.instantiate(name, 0, depth, false);
+                self.counters.last_mut().unwrap()
+            }
+        }
+    }
+}
+
+// https://drafts.csswg.org/css-lists-3/#propdef-counter-reset
+//
+// The counters `counter-reset`, `counter-increment` or `counter-set` name,
+// each followed by an optional integer.
+fn counter_changes(value: Option<&[ComponentValue]>) -> Vec<CounterChange> {
+    let mut changes: Vec<CounterChange> = Vec::new();
+    for value in value
+        .unwrap_or_default()
+        .iter()
+        .filter(|v| !v.is_whitespace())
+    {
+        let change = |name: &str, reversed| CounterChange {
+            name: name.to_string(),
+            value: None,
+            reversed,
+        };
+        match value {
+            ComponentValue::Token(CssToken::Ident(name)) if !name.eq_ignore_ascii_case("none") => {
+                changes.push(change(name, false));
+            }
+            ComponentValue::Function(function, arguments)
+                if function.eq_ignore_ascii_case("reversed") =>
+            {
+                match arguments.iter().find(|v| !v.is_whitespace()) {
+                    Some(ComponentValue::Token(CssToken::Ident(name))) => {
+                        changes.push(change(name, true))
+                    }
+                    _ => return Vec::new(),
+                }
+            }
+            ComponentValue::Token(CssToken::Number {
+                value,
+                integer: true,
+            }) => match changes.last_mut() {
+                Some(change) if change.value.is_none() => change.value = Some(*value as i32),
+                _ => return Vec::new(),
+            },
+            _ => return Vec::new(),
+        }
+    }
+    changes
+}
+
+// https://drafts.csswg.org/css-lists-3/#declaring-a-list-item
+//
+// The counters `counter-increment` changes and by how much, with the
+// `list-item` counter of a list item going up by one, or down in a
+// reversed list, unless it says otherwise.
+fn increments(style: &ComputedStyle, reversed_list: bool) -> Vec<(String, i32)> {
+    let mut increments: Vec<(String, i32)> = counter_changes(style.get("counter-increment"))
+        .into_iter()
+        .filter(|c| !c.reversed)
+        .map(|c| (c.name, c.value.unwrap_or(1)))
+        .collect();
+    if style.display().is_list_item() && !increments.iter().any(|(n, _)| n == "list-item") {
+        increments.push((
+            String::from("list-item"),
+            if reversed_list { -1 } else { 1 },
+        ));
+    }
+    increments
+}
+
+// https://drafts.csswg.org/css-lists-3/#instantiate-counter
+//
+// The value a `reversed()` counter without one starts at: so that the
+// elements in its scope count down to 1, or to where one sets it.
+fn reversed_initial_value(
+    element: &Rc<RefCell<Node>>,
+    pseudo_element: Option<PseudoElement>,
+    style: &ComputedStyle,
+    name: &str,
+) -> i32 {
+    let mut count = ReversedCount {
+        name,
+        num: 0,
+        first: true,
+    };
+    // the counter of a pseudo-element reaches the children of its element
+    let _ = count.visit(style)
+        && match pseudo_element {
+            None => count.visit_contents(element) && count.visit_following_siblings(element),
+            Some(_) => {
+                count.visit_children(element)
+                    && count.visit_pseudo_element(element, PseudoElement::After)
+            }
+        };
+    count.num
+}
+
+struct ReversedCount<'a> {
+    name: &'a str,
+    num: i32,
+    first: bool,
+}
+
+impl ReversedCount<'_> {
+    // Counts what an element or pseudo-element increments the counter by.
+    // False when it sets the counter, which ends the count.
+    fn visit(&mut self, style: &ComputedStyle) -> bool {
+        let increment = increments(style, true)
+            .into_iter()
+            .find(|(n, _)| n == self.name)
+            .map(|(_, v)| v);
+        let set = counter_changes(style.get("counter-set"))
+            .into_iter()
+            .find(|c| c.name == self.name);
+        if increment.is_none() && set.is_none() {
+            return true;
+        }
+
+        let negated = increment.unwrap_or(0).saturating_neg();
+        if self.first {
+            self.num = self.num.saturating_add(negated);
+            self.first = false;
+        }
+        if let Some(set) = set {
+            self.num = self.num.saturating_add(set.value.unwrap_or(0));
+            return false;
+        }
+        self.num = self.num.saturating_add(negated);
+        true
+    }
+
+    fn visit_element(&mut self, element: &Rc<RefCell<Node>>) -> bool {
+        match Node::computed_style(element) {
+            Some(style) if !style.display().is_none() => {
+                self.visit(&style) && self.visit_contents(element)
+            }
+            _ => true,
+        }
+    }
+
+    fn visit_contents(&mut self, element: &Rc<RefCell<Node>>) -> bool {
+        self.visit_pseudo_element(element, PseudoElement::Marker)
+            && self.visit_pseudo_element(element, PseudoElement::Before)
+            && self.visit_children(element)
+            && self.visit_pseudo_element(element, PseudoElement::After)
+    }
+
+    fn visit_pseudo_element(
+        &mut self,
+        element: &Rc<RefCell<Node>>,
+        pseudo_element: PseudoElement,
+    ) -> bool {
+        match Node::pseudo_element_style(element, pseudo_element) {
+            Some(style) if generates_box(&style, pseudo_element) => self.visit(&style),
+            _ => true,
+        }
+    }
+
+    fn visit_children(&mut self, element: &Rc<RefCell<Node>>) -> bool {
+        match element.borrow().first_child() {
+            Some(child) => self.visit_siblings(Some(child)),
+            None => true,
+        }
+    }
+
+    fn visit_following_siblings(&mut self, element: &Rc<RefCell<Node>>) -> bool {
+        let next = element.borrow().next_sibling();
+        self.visit_siblings(next)
+    }
+
+    // an element that resets the counter takes itself and its following
+    // siblings out of the count
+    fn visit_siblings(&mut self, mut node: Option<Rc<RefCell<Node>>>) -> bool {
+        while let Some(n) = node {
+            if let Some(style) = Node::computed_style(&n) {
+                let resets = counter_changes(style.get("counter-reset"))
+                    .iter()
+                    .any(|c| c.name == self.name);
+                if resets && !style.display().is_none() {
+                    return true;
+                }
+            }
+            if !self.visit_element(&n) {
+                return false;
+            }
+            node = n.borrow().next_sibling();
+        }
+        true
+    }
+}
+
+// https://drafts.csswg.org/css-pseudo-4/#generated-content
+//
+// whether a `::marker`, `::before` or `::after` with `style` is there at
+// all, which `content` decides
+pub fn generates_box(style: &ComputedStyle, pseudo_element: PseudoElement) -> bool {
+    match style.get_text("content").as_deref() {
+        _ if style.display().is_none() => false,
+        None | Some("none") => false,
+        Some("normal") => pseudo_element == PseudoElement::Marker,
+        Some(_) => true,
+    }
+}
+
+// https://drafts.csswg.org/css-lists-3/#content-property
+//
+// The text of a `::marker` whose `content` is `normal`: its list item's
+// `list-style-type` as a symbol followed by a space, or as a number
+// followed by the suffix of the counter style.
+fn marker_text(style: &ComputedStyle, counters: &Counters) -> Option<String> {
+    let list_style_type = style.get("list-style-type")?;
+    let counter_style = match list_style_type.iter().find(|v| !v.is_whitespace())? {
+        ComponentValue::Token(CssToken::String(s)) => return Some(s.clone()),
+        ComponentValue::Token(CssToken::Ident(name)) => name,
+        _ => return None,
+    };
+    if counter_style.eq_ignore_ascii_case("none") {
+        return None;
+    }
+    Some(format!(
+        "{}{}",
+        format_counter(counters.value("list-item"), counter_style),
+        marker_suffix(counter_style)
+    ))
+}
+
+// https://drafts.csswg.org/css-content-3/#content-property
+//
+// The text a pseudo-element of `element` with `style` generates, with the
+// counters at its place in the tree. None when it generates no box.
+pub fn generated_content(
+    style: &ComputedStyle,
+    element: &Rc<RefCell<Node>>,
+    pseudo_element: PseudoElement,
+    counters: &Counters,
+) -> Option<String> {
+    let content = style.get("content")?;
+    let values: Vec<&ComponentValue> = content.iter().filter(|v| !v.is_whitespace()).collect();
+    match values[..] {
+        [ComponentValue::Token(CssToken::Ident(keyword))]
+            if keyword.eq_ignore_ascii_case("none") =>
+        {
+            return None
+        }
+        [ComponentValue::Token(CssToken::Ident(keyword))]
+            if keyword.eq_ignore_ascii_case("normal") =>
+        {
+            return match pseudo_element {
+                PseudoElement::Marker => marker_text(style, counters),
+                _ => None,
+            };
+        }
+        _ => {}
+    }
+
+    let mut text = String::new();
+    for value in values {
+        match value {
+            ComponentValue::Token(CssToken::String(s)) => text.push_str(s),
+            // the alternative text after `/` isn't shown
+            ComponentValue::Token(CssToken::Delim('/')) => break,
+            ComponentValue::Function(name, arguments) => {
+                text.push_str(&content_function(name, arguments, element, counters)?)
+            }
+            // https://drafts.csswg.org/css-content-3/#quote-values
+            //
+            // quotes aren't nested, so each is a plain double quote
+            ComponentValue::Token(CssToken::Ident(keyword)) => {
+                match keyword.to_ascii_lowercase().as_str() {
+                    "open-quote" => text.push('\u{201C}'),
+                    "close-quote" => text.push('\u{201D}'),
+                    "no-open-quote" | "no-close-quote" => {}
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        }
+    }
+    Some(text)
+}
+
+// `attr()`, `counter()` and `counters()`
+fn content_function(
+    name: &str,
+    arguments: &[ComponentValue],
+    element: &Rc<RefCell<Node>>,
+    counters: &Counters,
+) -> Option<String> {
+    let arguments: Vec<&ComponentValue> = arguments
+        .iter()
+        .filter(|v| !v.is_whitespace() && !matches!(v, ComponentValue::Token(CssToken::Comma)))
+        .collect();
+    let ident = |i: usize| match arguments.get(i) {
+        Some(ComponentValue::Token(CssToken::Ident(ident))) => Some(ident.as_str()),
+        _ => None,
+    };
+
+    match name.to_ascii_lowercase().as_str() {
+        // https://drafts.csswg.org/css-values-5/#attr-notation
+        "attr" => {
+            let name = ident(0)?.to_ascii_lowercase();
+            Some(match element.borrow().kind {
+                NodeKind::Element(ref e) => e.get_attribute(&name).unwrap_or_default().to_string(),
+                _ => String::new(),
+            })
+        }
+        // https://drafts.csswg.org/css-lists-3/#counter-functions
+        "counter" => {
+            let counter_style = ident(1).unwrap_or("decimal");
+            Some(format_counter(counters.value(ident(0)?), counter_style))
+        }
+        "counters" => {
+            let separator = match arguments.get(1) {
+                Some(ComponentValue::Token(CssToken::String(s))) => s,
+                _ => return None,
+            };
+            let counter_style = ident(2).unwrap_or("decimal");
+            let values: Vec<String> = counters
+                .values(ident(0)?)
+                .into_iter()
+                .map(|v| format_counter(v, counter_style))
+                .collect();
+            Some(values.join(separator))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,302 @@
+use super::cascade::css_wide_keyword;
+use super::*;
+use crate::parser::css::CssToken;
+use std::collections::BTreeSet;
+
+// https://drafts.csswg.org/css-variables-2/#custom-property
+pub fn is_custom_property(name: &str) -> bool {
+    name.starts_with("--")
+}
+
+// whether `value` has a `var()` anywhere in it
+pub fn has_references(value: &[ComponentValue]) -> bool {
+    value.iter().any(|v| match v {
+        ComponentValue::Function(name, arguments) => {
+            name.eq_ignore_ascii_case("var") || has_references(arguments)
+        }
+        ComponentValue::Block(_, values) => has_references(values),
+        ComponentValue::Token(_) => false,
+    })
+}
+
+// https://drafts.csswg.org/css-variables-2/#long-variables
+//
+// The most component values a substitution may give, counting those nested
+// in functions and blocks, so that `var()`s referring to each other twice
+// over can't double the value at every step.
+const MAX_SUBSTITUTED_LENGTH: usize = 1 << 16;
+
+// https://drafts.csswg.org/css-variables-2/#substitute-a-var
+//
+// Replaces every `var()` in `value` with what `lookup` finds for its custom
+// property, or else with its fallback. None when a `var()` has neither, or
+// the result is longer than `MAX_SUBSTITUTED_LENGTH`, which makes the
+// declaration invalid at computed-value time.
+pub fn substitute_references(
+    value: &[ComponentValue],
+    lookup: &mut dyn FnMut(&str) -> Option<Vec<ComponentValue>>,
+) -> Option<Vec<ComponentValue>> {
+    let mut result = Vec::new();
+    let mut total = 0;
+    for v in value.iter() {
+        let start = result.len();
+        match v {
+            ComponentValue::Function(name, arguments) if name.eq_ignore_ascii_case("var") => {
+                let (property, fallback) = parse_var(arguments)?;
+                let substituted = match lookup(property) {
+                    Some(value) => value,
+                    None => substitute_references(fallback?, lookup)?,
+                };
+                result.extend(substituted);
+            }
+            ComponentValue::Function(name, arguments) => result.push(ComponentValue::Function(
+                name.clone(),
+                substitute_references(arguments, lookup)?,
+            )),
+            ComponentValue::Block(open, values) => result.push(ComponentValue::Block(
+                *open,
+                substitute_references(values, lookup)?,
+            )),
+            ComponentValue::Token(_) => result.push(v.clone()),
+        }
+        total += length(&result[start..]);
+        if total > MAX_SUBSTITUTED_LENGTH {
+            return None;
+        }
+    }
+    Some(result)
+}
+
+// the number of component values, with those inside functions and blocks
+fn length(values: &[ComponentValue]) -> usize {
+    values
+        .iter()
+        .map(|v| match v {
+            ComponentValue::Function(_, values) | ComponentValue::Block(_, values) => {
+                1 + length(values)
+            }
+            ComponentValue::Token(_) => 1,
+        })
+        .sum()
+}
+
+// the value of a declaration with its `var()`s replaced by the custom
+// properties of `style`
+pub(crate) fn substitute(
+    value: &[ComponentValue],
+    style: &ComputedStyle,
+) -> Option<Vec<ComponentValue>> {
+    let value = substitute_references(value, &mut |name| style.get(name).map(|v| v.to_vec()))?;
+    Some(trim(&value).to_vec())
+}
+
+// https://drafts.csswg.org/css-variables-2/#funcdef-var
+//
+// The custom property name of `var()` and its fallback, which may be empty.
+fn parse_var(arguments: &[ComponentValue]) -> Option<(&str, Option<&[ComponentValue]>)> {
+    let mut rest = arguments.iter().skip_while(|v| v.is_whitespace());
+    let name = match rest.next()? {
+        ComponentValue::Token(CssToken::Ident(name)) if is_custom_property(name) => name,
+        _ => return None,
+    };
+
+    let comma = arguments
+        .iter()
+        .position(|v| matches!(v, ComponentValue::Token(CssToken::Comma)));
+    let before_comma = &arguments[..comma.unwrap_or(arguments.len())];
+    if before_comma.iter().filter(|v| !v.is_whitespace()).count() != 1 {
+        return None;
+    }
+    let fallback = comma.map(|i| trim(&arguments[i + 1..]));
+    Some((name, fallback))
+}
+
+fn trim(values: &[ComponentValue]) -> &[ComponentValue] {
+    let start = values
+        .iter()
+        .position(|v| !v.is_whitespace())
+        .unwrap_or(values.len());
+    let end = values
+        .iter()
+        .rposition(|v| !v.is_whitespace())
+        .map_or(start, |i| i + 1);
+    &values[start..end]
+}
+
+// https://drafts.csswg.org/css-variables-2/#cycles
+//
+// Resolves the custom properties declared on an element, following their
+// references depth first. The properties of a cycle are all invalid.
+struct Resolver<'a> {
+    declared: BTreeMap<&'a str, &'a [ComponentValue]>,
+    parent: Option<&'a ComputedStyle>,
+    computed: BTreeMap<String, Option<Vec<ComponentValue>>>,
+    stack: Vec<String>,
+    cyclic: BTreeSet<String>,
+}
+
+impl Resolver<'_> {
+    fn resolve(&mut self, name: &str) -> Option<Vec<ComponentValue>> {
+        if let Some(value) = self.computed.get(name) {
+            return value.clone();
+        }
+        let declared = match self.declared.get(name) {
+            Some(value) => *value,
+            None => return self.parent.and_then(|p| p.get(name)).map(|v| v.to_vec()),
+        };
+        if let Some(i) = self.stack.iter().position(|n| n == name) {
+            self.cyclic.extend(self.stack[i..].iter().cloned());
+            return None;
+        }
+
+        self.stack.push(name.to_string());
+        let value = substitute_references(declared, &mut |n| self.resolve(n));
+        self.stack.pop();
+
+        let value = match self.cyclic.contains(name) {
+            true => None,
+            false => value.map(|v| trim(&v).to_vec()),
+        };
+        self.computed.insert(name.to_string(), value.clone());
+        value
+    }
+}
+
+// https://drafts.csswg.org/css-variables-2/#cascading-variables
+//
+// The computed custom properties of an element from the cascaded value of
+// each one declared on it. They all inherit; one that is `initial` or
+// invalid has the guaranteed-invalid value and is left out.
+pub(crate) fn compute_custom_properties<'a>(
+    cascaded: BTreeMap<&'a str, &'a [ComponentValue]>,
+    parent: Option<&'a ComputedStyle>,
+) -> BTreeMap<String, Vec<ComponentValue>> {
+    let mut properties: BTreeMap<String, Vec<ComponentValue>> = parent
+        .map(|p| {
+            p.custom_properties()
+                .map(|(name, value)| (name.to_string(), value.to_vec()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut declared = BTreeMap::new();
+    for (name, value) in cascaded {
+        match css_wide_keyword(value) {
+            Some("initial") => {
+                properties.remove(name);
+            }
+            // `inherit`, `unset` and a `revert` or `revert-layer` without
+            // anything to revert to all keep the parent's value
+            Some(_) => {}
+            None => {
+                declared.insert(name, value);
+            }
+        }
+    }
+
+    let mut resolver = Resolver {
+        declared,
+        parent,
+        computed: BTreeMap::new(),
+        stack: Vec::new(),
+        cyclic: BTreeSet::new(),
+    };
+    let names: Vec<&str> = resolver.declared.keys().copied().collect();
+    for name in names {
+        match resolver.resolve(name) {
+            Some(value) => properties.insert(name.to_string(), value),
+            None => properties.remove(name),
+        };
+    }
+    properties
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::css::parse_stylesheet;
+    use crate::parser::html_parser::parse;
+
+    fn style(css: &str, html: &str, id: &str) -> Rc<ComputedStyle> {
+        let document = parse(html);
+        let mut stylist = Stylist::new();
+        stylist.add_stylesheet(Origin::Author, parse_stylesheet(css));
+        Node::resolve_styles(&document, &stylist);
+        let element = Node::get_element_by_id(&document, id).unwrap();
+        Node::computed_style(&element).unwrap()
+    }
+
+    #[test]
+    fn test_inheritance_and_substitution() {
+        let html = "<div id=a><p id=b>x</p></div>";
+        let css = "div { --Main: 10px; --color: blue; --empty:; --gap: calc(var(--Main) * 2) } \
+                   p { --color: initial; margin-top: var(--gap); padding-left: var(--Main) var(--empty); \
+                       color: var(--color, var(--fallback, green)) }";
+        let b = style(css, html, "b");
+
+        // names are case-sensitive and values keep their specified form
+        assert_eq!(b.get_text("--Main").unwrap(), "10px");
+        assert!(b.get("--main").is_none());
+        assert_eq!(b.get_text("--gap").unwrap(), "calc(10px * 2)");
+        assert_eq!(b.get_text("--empty").unwrap(), "");
+        assert!(b.get("--color").is_none());
+        assert_eq!(b.get_text("margin-top").unwrap(), "20px");
+        assert_eq!(b.get_text("padding-left").unwrap(), "10px");
+        assert_eq!(b.get_text("color").unwrap(), "rgb(0, 128, 0)");
+        assert_eq!(
+            b.custom_properties().map(|(n, _)| n).collect::<Vec<_>>(),
+            vec!["--Main", "--empty", "--gap"]
+        );
+    }
+
+    #[test]
+    fn test_cycles() {
+        let html = "<p id=a>x</p>";
+        let css = "p { --a: var(--b); --b: var(--a, 1px); --c: var(--c); \
+                   --d: var(--a, 2px); --e: 3px; --f: var(--e) var(--e) }";
+        let a = style(css, html, "a");
+
+        // a fallback doesn't save a property in a cycle
+        assert!(a.get("--a").is_none());
+        assert!(a.get("--b").is_none());
+        assert!(a.get("--c").is_none());
+        // but properties referring to one take their fallback
+        assert_eq!(a.get_text("--d").unwrap(), "2px");
+        assert_eq!(a.get_text("--f").unwrap(), "3px 3px");
+    }
+
+    #[test]
+    fn test_long_substitutions() {
+        // each property is twice the one before, so `--p40` would have 2^40
+        // words
+        let mut css = String::from("p { --p0: 1px; ");
+        for i in 1..=40 {
+            css.push_str(&format!("--p{}: var(--p{}) var(--p{}); ", i, i - 1, i - 1));
+        }
+        css.push_str("margin-top: var(--p40, 2px); margin-left: var(--p1) }");
+        let a = style(&css, "<p id=a>x</p>", "a");
+
+        assert_eq!(a.get_text("--p2").unwrap(), "1px 1px 1px 1px");
+        assert!(a.get("--p15").is_some());
+        assert!(a.get("--p16").is_none());
+        assert!(a.get("--p40").is_none());
+        // a property referring to one that is too long is invalid too
+        assert_eq!(a.get_text("margin-top").unwrap(), "2px");
+        assert_eq!(a.get_text("margin-left").unwrap(), "0px");
+    }
+
+    #[test]
+    fn test_invalid_at_computed_value_time() {
+        let html = "<div id=a><p id=b>x</p></div>";
+        let css = "div { color: red; margin-left: 5px } \
+                   p { color: blue; color: var(--missing); margin-left: var(--missing); \
+                       padding-top: var(--missing, 4px); margin-top: var(bad) }";
+        let b = style(css, html, "b");
+
+        // the declaration wins the cascade and then acts as `unset`
+        assert_eq!(b.get_text("color").unwrap(), "rgb(255, 0, 0)");
+        assert_eq!(b.get_text("margin-left").unwrap(), "0px");
+        assert_eq!(b.get_text("padding-top").unwrap(), "4px");
+        assert_eq!(b.get_text("margin-top").unwrap(), "0px");
+    }
+}
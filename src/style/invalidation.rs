@@ -0,0 +1,675 @@
+use super::cascade::cascade_pseudo_elements;
+use super::presentational_hints::is_hint_attribute;
+use super::*;
+use crate::node::NodeKind;
+use crate::selector::{Combinator, ComplexSelector, PseudoClass, SelectorList, SimpleSelector};
+use std::collections::{BTreeSet, HashMap};
+use std::rc::Weak;
+
+// What a node needs once the tree changed under it, set by the mutation
+// methods on `Node` and cleared by the next `update_style`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum RestyleHint {
+    #[default]
+    None,
+    // the element itself, and its descendants if its style changes
+    Element,
+    // the element and all of its descendants
+    Subtree,
+}
+
+// How far a change to an element can reach, given where in the selectors
+// the thing that changed appears.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Invalidation {
+    // in the subject compound: the element
+    Element,
+    // left of a descendant or child combinator: the element and its
+    // descendants
+    Subtree,
+    // left of a sibling combinator: the siblings of the element and their
+    // descendants
+    Siblings,
+    // inside `:has()`: anything
+    Document,
+}
+
+// what a selector can depend on that changes without elements being
+// inserted or removed
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum InvalidationKey {
+    Id(String),
+    Class(String),
+    Attribute(String),
+    Hover,
+    // `:empty`, depending on the children of the element
+    Empty,
+    // `:nth-child()` and the like, depending on the siblings of the element
+    Structural,
+}
+
+// Everything the selectors of a stylist depend on, with the furthest each
+// one can reach.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct InvalidationMap {
+    keys: HashMap<InvalidationKey, Invalidation>,
+    // whether any selector has `+` or `~`, so inserting and removing
+    // elements changes what their following siblings match
+    sibling_combinators: bool,
+    // whether any selector has `:has()`, which any change can affect
+    relative_selectors: bool,
+}
+
+// The work the last `update_style` did, to tell how much a change cost.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StyleUpdateStats {
+    // whether every element was restyled, as the style sheets or the device
+    // changed
+    pub full_restyle: bool,
+    // the elements changes marked for restyle since the update before
+    pub invalidated: usize,
+    // the elements whose style was computed again
+    pub restyled: usize,
+}
+
+// What the document keeps between style updates.
+#[derive(Debug, Default)]
+pub(crate) struct InvalidationState {
+    // https://drafts.csswg.org/selectors-4/#the-hover-pseudo
+    pub(crate) hover: Option<Weak<RefCell<Node>>>,
+    // the elements marked for restyle since the last update
+    pub(crate) invalidated: usize,
+    pub(crate) stats: StyleUpdateStats,
+}
+
+impl InvalidationMap {
+    pub(crate) fn add_selector_list(&mut self, list: &SelectorList) {
+        for selector in list.0.iter() {
+            self.add_selector(selector, Invalidation::Element);
+        }
+    }
+
+    // https://drafts.csswg.org/css-cascade-6/#scope-limits
+    //
+    // Whether an element is a scoping root or limit changes which of its
+    // descendants are in scope.
+    pub(crate) fn add_scope_selector_list(&mut self, list: &SelectorList) {
+        for selector in list.0.iter() {
+            self.add_selector(selector, Invalidation::Subtree);
+        }
+    }
+
+    // `at_least` is how far the selector this one is nested in reaches
+    fn add_selector(&mut self, selector: &ComplexSelector, at_least: Invalidation) {
+        for (i, compound) in selector.compounds.iter().enumerate() {
+            let combinators = &selector.combinators[i..];
+            let is_sibling = |c: &Combinator| {
+                matches!(c, Combinator::NextSibling | Combinator::SubsequentSibling)
+            };
+            let invalidation = match combinators {
+                [] => Invalidation::Element,
+                _ if combinators.iter().any(is_sibling) => Invalidation::Siblings,
+                _ => Invalidation::Subtree,
+            };
+            self.sibling_combinators |= combinators.iter().any(is_sibling);
+            for simple in compound.0.iter() {
+                self.add_simple_selector(simple, invalidation.max(at_least));
+            }
+        }
+    }
+
+    fn add_simple_selector(&mut self, selector: &SimpleSelector, mut invalidation: Invalidation) {
+        let key = match selector {
+            SimpleSelector::Namespace(_)
+            | SimpleSelector::Universal
+            | SimpleSelector::Type(_)
+            | SimpleSelector::PseudoElement(_) => return,
+            SimpleSelector::Id(id) => InvalidationKey::Id(id.clone()),
+            SimpleSelector::Class(class) => InvalidationKey::Class(class.clone()),
+            SimpleSelector::Attribute(attribute) => {
+                InvalidationKey::Attribute(attribute.name.to_ascii_lowercase())
+            }
+            SimpleSelector::PseudoClass(pseudo_class) => match pseudo_class {
+                PseudoClass::Not(list) | PseudoClass::Is(list) | PseudoClass::Where(list) => {
+                    for selector in list.0.iter() {
+                        self.add_selector(selector, invalidation);
+                    }
+                    return;
+                }
+                // https://drafts.csswg.org/selectors-4/#relational
+                PseudoClass::Has(relative) => {
+                    self.relative_selectors = true;
+                    for r in relative.iter() {
+                        self.add_selector(&r.selector, Invalidation::Document);
+                    }
+                    return;
+                }
+                // what the siblings match changes the position of the element
+                PseudoClass::NthChild(_, of) | PseudoClass::NthLastChild(_, of) => {
+                    for selector in of.iter().flat_map(|list| list.0.iter()) {
+                        self.add_selector(selector, invalidation.max(Invalidation::Siblings));
+                    }
+                    InvalidationKey::Structural
+                }
+                PseudoClass::NthOfType(_)
+                | PseudoClass::NthLastOfType(_)
+                | PseudoClass::FirstChild
+                | PseudoClass::LastChild
+                | PseudoClass::OnlyChild
+                | PseudoClass::FirstOfType
+                | PseudoClass::LastOfType
+                | PseudoClass::OnlyOfType => InvalidationKey::Structural,
+                PseudoClass::Empty => InvalidationKey::Empty,
+                PseudoClass::Hover => InvalidationKey::Hover,
+                // the language comes from the nearest `lang` attribute up
+                // the tree
+                PseudoClass::Lang(_) => {
+                    invalidation = invalidation.max(Invalidation::Subtree);
+                    InvalidationKey::Attribute(String::from("lang"))
+                }
+                PseudoClass::Root | PseudoClass::Scope => return,
+            },
+        };
+        let entry = self.keys.entry(key).or_insert(invalidation);
+        *entry = (*entry).max(invalidation);
+    }
+
+    fn get(&self, key: &InvalidationKey) -> Option<Invalidation> {
+        self.keys.get(key).copied()
+    }
+}
+
+impl Node {
+    // the stylist styles were last resolved with, if they were
+    fn stylist_of(document: &Rc<RefCell<Node>>) -> Option<Rc<Stylist>> {
+        match document.borrow().kind {
+            NodeKind::Document(ref d) => d.stylist.clone(),
+            _ => None,
+        }
+    }
+
+    // https://dom.spec.whatwg.org/#concept-element-attributes-change
+    //
+    // Marks what a change of the attribute `name` of `element` can restyle:
+    // the classes added or removed, the old and new ids, or the attribute.
+    pub(crate) fn invalidate_attribute(
+        document: &Rc<RefCell<Node>>,
+        element: &Rc<RefCell<Node>>,
+        name: &str,
+        old_value: Option<&str>,
+        value: Option<&str>,
+    ) {
+        let stylist = match Self::stylist_of(document) {
+            Some(stylist) => stylist,
+            None => return,
+        };
+        let map = stylist.invalidation_map();
+
+        // https://html.spec.whatwg.org/multipage/dom.html#the-style-attribute
+        // https://html.spec.whatwg.org/multipage/rendering.html#presentational-hints
+        let hint = match element.borrow().kind {
+            NodeKind::Element(ref e) => is_hint_attribute(e, name),
+            _ => false,
+        };
+        if name == "style" || hint {
+            Self::mark_for_restyle(document, element, RestyleHint::Element);
+        }
+        let mut keys = vec![InvalidationKey::Attribute(name.to_string())];
+        match name {
+            "id" => keys.extend(
+                [old_value, value]
+                    .into_iter()
+                    .flatten()
+                    .map(|id| InvalidationKey::Id(id.to_string())),
+            ),
+            "class" => {
+                let classes = |value: Option<&str>| -> BTreeSet<String> {
+                    value
+                        .unwrap_or_default()
+                        .split_ascii_whitespace()
+                        .map(|c| c.to_string())
+                        .collect()
+                };
+                let (old, new) = (classes(old_value), classes(value));
+                keys.extend(
+                    old.symmetric_difference(&new)
+                        .map(|c| InvalidationKey::Class(c.clone())),
+                );
+            }
+            _ => {}
+        }
+        for key in keys.iter() {
+            if let Some(invalidation) = map.get(key) {
+                Self::invalidate(document, element, invalidation);
+            }
+        }
+    }
+
+    // https://dom.spec.whatwg.org/#concept-node-insert
+    //
+    // An inserted node is styled along with its descendants, and may change
+    // what its parent and siblings match.
+    pub(crate) fn invalidate_insertion(document: &Rc<RefCell<Node>>, node: &Rc<RefCell<Node>>) {
+        if Self::stylist_of(document).is_none() {
+            return;
+        }
+        Self::mark_for_restyle(document, node, RestyleHint::Subtree);
+        if let Some(parent) = node.borrow().parent() {
+            let next_sibling = node.borrow().next_sibling();
+            Self::invalidate_children_changed(document, &parent, next_sibling.as_ref());
+        }
+    }
+
+    // https://dom.spec.whatwg.org/#concept-node-remove
+    pub(crate) fn invalidate_removal(
+        document: &Rc<RefCell<Node>>,
+        parent: &Rc<RefCell<Node>>,
+        next_sibling: Option<&Rc<RefCell<Node>>>,
+    ) {
+        if Self::stylist_of(document).is_some() {
+            Self::invalidate_children_changed(document, parent, next_sibling);
+        }
+    }
+
+    // https://dom.spec.whatwg.org/#concept-cd-replace
+    //
+    // Text going empty or not changes whether its parent is `:empty`.
+    pub(crate) fn invalidate_character_data(
+        document: &Rc<RefCell<Node>>,
+        node: &Rc<RefCell<Node>>,
+    ) {
+        let stylist = match Self::stylist_of(document) {
+            Some(stylist) => stylist,
+            None => return,
+        };
+        if let Some(parent) = node.borrow().parent() {
+            if let Some(invalidation) = stylist.invalidation_map().get(&InvalidationKey::Empty) {
+                Self::invalidate(document, &parent, invalidation);
+            }
+        }
+    }
+
+    // The children of `parent` changed just before `next_sibling`: whether
+    // it's `:empty`, the positions of its children, and what the siblings
+    // after the change follow.
+    fn invalidate_children_changed(
+        document: &Rc<RefCell<Node>>,
+        parent: &Rc<RefCell<Node>>,
+        next_sibling: Option<&Rc<RefCell<Node>>>,
+    ) {
+        let stylist = match Self::stylist_of(document) {
+            Some(stylist) => stylist,
+            None => return,
+        };
+        let map = stylist.invalidation_map();
+        if map.relative_selectors {
+            Self::invalidate(document, parent, Invalidation::Document);
+            return;
+        }
+        if let Some(invalidation) = map.get(&InvalidationKey::Empty) {
+            Self::invalidate(document, parent, invalidation);
+        }
+        if let Some(invalidation) = map.get(&InvalidationKey::Structural) {
+            for child in Node::children(parent).filter(|c| c.borrow().is_element()) {
+                Self::invalidate(document, &child, invalidation);
+            }
+        }
+        if let (true, Some(next_sibling)) = (map.sibling_combinators, next_sibling) {
+            let following = std::iter::once(next_sibling.clone())
+                .chain(Node::following_siblings(next_sibling))
+                .filter(|n| n.borrow().is_element());
+            for sibling in following {
+                Self::mark_for_restyle(document, &sibling, RestyleHint::Subtree);
+            }
+        }
+    }
+
+    // https://drafts.csswg.org/selectors-4/#the-hover-pseudo
+    //
+    // The element the pointer is over, which along with its ancestors
+    // matches `:hover`.
+    pub fn hovered_element(document: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+        match document.borrow().kind {
+            NodeKind::Document(ref d) => d.invalidation.hover.as_ref().and_then(|h| h.upgrade()),
+            _ => None,
+        }
+    }
+
+    // Moves the pointer over `element`, restyling the elements that start
+    // or stop matching `:hover`.
+    pub fn set_hovered_element(document: &Rc<RefCell<Node>>, element: Option<&Rc<RefCell<Node>>>) {
+        let chain = |element: Option<Rc<RefCell<Node>>>| -> Vec<Rc<RefCell<Node>>> {
+            element
+                .map(|e| {
+                    Node::inclusive_ancestors(&e)
+                        .filter(|n| n.borrow().is_element())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let old_chain = chain(Self::hovered_element(document));
+        let new_chain = chain(element.cloned());
+        if let NodeKind::Document(ref mut d) = document.borrow_mut().kind {
+            d.invalidation.hover = element.map(Rc::downgrade);
+        }
+
+        let invalidation = match Self::stylist_of(document) {
+            Some(stylist) => stylist.invalidation_map().get(&InvalidationKey::Hover),
+            None => return,
+        };
+        let invalidation = match invalidation {
+            Some(invalidation) => invalidation,
+            None => return,
+        };
+        let contains = |chain: &[Rc<RefCell<Node>>], node: &Rc<RefCell<Node>>| {
+            chain.iter().any(|n| Rc::ptr_eq(n, node))
+        };
+        for node in old_chain.iter() {
+            if !contains(&new_chain, node) {
+                Self::invalidate(document, node, invalidation);
+            }
+        }
+        for node in new_chain.iter() {
+            if !contains(&old_chain, node) {
+                Self::invalidate(document, node, invalidation);
+            }
+        }
+    }
+
+    fn invalidate(
+        document: &Rc<RefCell<Node>>,
+        element: &Rc<RefCell<Node>>,
+        invalidation: Invalidation,
+    ) {
+        let parent = element
+            .borrow()
+            .parent()
+            .filter(|p| p.borrow().is_element());
+        match (invalidation, parent) {
+            (Invalidation::Element, _) => {
+                Self::mark_for_restyle(document, element, RestyleHint::Element)
+            }
+            (Invalidation::Subtree, _) | (Invalidation::Siblings, None) => {
+                Self::mark_for_restyle(document, element, RestyleHint::Subtree)
+            }
+            (Invalidation::Siblings, Some(parent)) => {
+                Self::mark_for_restyle(document, &parent, RestyleHint::Subtree)
+            }
+            (Invalidation::Document, _) => {
+                if let Some(root) = Self::document_element(document) {
+                    Self::mark_for_restyle(document, &root, RestyleHint::Subtree);
+                }
+            }
+        }
+    }
+
+    // sets the hint of `node` and flags its ancestors so `update_style`
+    // finds it
+    fn mark_for_restyle(document: &Rc<RefCell<Node>>, node: &Rc<RefCell<Node>>, hint: RestyleHint) {
+        {
+            let mut n = node.borrow_mut();
+            if n.restyle_hint >= hint {
+                return;
+            }
+            n.restyle_hint = hint;
+        }
+        if node.borrow().is_element() {
+            if let NodeKind::Document(ref mut d) = document.borrow_mut().kind {
+                d.invalidation.invalidated += 1;
+            }
+        }
+        for ancestor in Node::ancestors(node) {
+            let mut a = ancestor.borrow_mut();
+            if a.dirty_descendants {
+                break;
+            }
+            a.dirty_descendants = true;
+        }
+    }
+
+    // whether a change marked anything for restyle since the last update
+    pub(crate) fn has_dirty_styles(document: &Rc<RefCell<Node>>) -> bool {
+        let d = document.borrow();
+        d.dirty_descendants || d.restyle_hint != RestyleHint::None
+    }
+
+    pub fn style_update_stats(document: &Rc<RefCell<Node>>) -> StyleUpdateStats {
+        match document.borrow().kind {
+            NodeKind::Document(ref d) => d.invalidation.stats,
+            _ => StyleUpdateStats::default(),
+        }
+    }
+
+    // Computes the styles of the nodes marked for restyle and of those below
+    // them, parents before children. `force` is what the parent of `node`
+    // asks of it: an element whose style changed restyles its children,
+    // which only go further if theirs change too.
+    pub(crate) fn restyle(
+        node: &Rc<RefCell<Node>>,
+        stylist: &Stylist,
+        parent_style: Option<&ComputedStyle>,
+        force: RestyleHint,
+        stats: &mut StyleUpdateStats,
+    ) {
+        let (hint, dirty_descendants) = {
+            let mut n = node.borrow_mut();
+            let hint = std::mem::take(&mut n.restyle_hint).max(force);
+            (hint, std::mem::take(&mut n.dirty_descendants))
+        };
+        let mut force_children = match hint {
+            RestyleHint::Subtree => RestyleHint::Subtree,
+            _ => RestyleHint::None,
+        };
+
+        if hint != RestyleHint::None && node.borrow().is_element() {
+            let old_style = Node::computed_style(node);
+            let style = cascade(stylist, node, parent_style);
+            stats.restyled += 1;
+            if old_style.as_deref() != Some(&style) {
+                force_children = force_children.max(RestyleHint::Element);
+            }
+            // https://drafts.csswg.org/css-values-4/#rem
+            //
+            // every `rem` refers to the font size of the root element
+            let is_root = node
+                .borrow()
+                .parent()
+                .is_some_and(|p| p.borrow().is_document());
+            if is_root && old_style.is_some_and(|s| s.font_size() != style.font_size()) {
+                force_children = RestyleHint::Subtree;
+            }
+            let pseudo_element_styles = cascade_pseudo_elements(stylist, node, &style);
+            let mut n = node.borrow_mut();
+            n.computed_style = Some(Rc::new(style));
+            n.pseudo_element_styles = pseudo_element_styles;
+        }
+
+        if force_children == RestyleHint::None && !dirty_descendants {
+            return;
+        }
+        let style = Node::computed_style(node);
+        for child in Node::children(node) {
+            Self::restyle(&child, stylist, style.as_deref(), force_children, stats);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::element::{Element, HtmlElementKind};
+    use crate::parser::html_parser::parse;
+
+    fn by_id(document: &Rc<RefCell<Node>>, id: &str) -> Rc<RefCell<Node>> {
+        Node::get_element_by_id(document, id).unwrap()
+    }
+
+    fn color(document: &Rc<RefCell<Node>>, id: &str) -> String {
+        Node::computed_style(&by_id(document, id))
+            .unwrap()
+            .get_text("color")
+            .unwrap()
+    }
+
+    // the number of elements restyled by the next update
+    fn restyled(document: &Rc<RefCell<Node>>) -> usize {
+        Node::update_style(document);
+        let stats = Node::style_update_stats(document);
+        assert!(!stats.full_restyle);
+        stats.restyled
+    }
+
+    // a list of ten items in a section
+    fn list(css: &str) -> Rc<RefCell<Node>> {
+        let items: String = (0..10).map(|i| format!("<p id=p{}>{}</p>", i, i)).collect();
+        let document = parse(&format!(
+            "<style>{}</style><div id=list>{}</div><div id=other></div>",
+            css, items
+        ));
+        Node::update_style(&document);
+        assert!(Node::style_update_stats(&document).full_restyle);
+        document
+    }
+
+    #[test]
+    fn test_attribute_invalidation() {
+        let document =
+            list(".on { color: red } .dark p { color: white } #other[data-x] { color: blue }");
+
+        // a class in the subject restyles the element alone
+        Node::set_attribute(&by_id(&document, "p3"), "class", "on");
+        assert_eq!(restyled(&document), 1);
+        assert_eq!(color(&document, "p3"), "rgb(255, 0, 0)");
+
+        // classes no selector mentions restyle nothing
+        Node::set_attribute(&by_id(&document, "p3"), "class", "on unused");
+        Node::set_attribute(&by_id(&document, "p4"), "title", "x");
+        assert_eq!(restyled(&document), 0);
+        assert!(!Node::needs_style_update(&document));
+
+        // one left of a descendant combinator restyles the subtree
+        Node::set_attribute(&by_id(&document, "list"), "class", "dark");
+        assert_eq!(restyled(&document), 11);
+        assert_eq!(color(&document, "p0"), "rgb(255, 255, 255)");
+
+        Node::set_attribute(&by_id(&document, "other"), "data-x", "");
+        Node::set_attribute(&by_id(&document, "p5"), "style", "color: green");
+        assert_eq!(restyled(&document), 2);
+        assert_eq!(color(&document, "other"), "rgb(0, 0, 255)");
+        assert_eq!(color(&document, "p5"), "rgb(0, 128, 0)");
+        Node::remove_attribute(&by_id(&document, "other"), "data-x");
+        assert_eq!(restyled(&document), 1);
+        assert_eq!(color(&document, "other"), "rgb(0, 0, 0)");
+    }
+
+    #[test]
+    fn test_presentational_hint_invalidation() {
+        let document = parse("<ol id=list><li id=a>x</li></ol><ul id=bullets><li id=b>y</li></ul>");
+        Node::update_style(&document);
+        let get = |id, property| {
+            Node::computed_style(&by_id(&document, id))
+                .unwrap()
+                .get_text(property)
+                .unwrap()
+        };
+
+        Node::set_attribute(&by_id(&document, "list"), "start", "4");
+        Node::set_attribute(&by_id(&document, "list"), "type", "i");
+        Node::set_attribute(&by_id(&document, "a"), "value", "7");
+        Node::set_attribute(&by_id(&document, "bullets"), "type", "square");
+        assert_eq!(restyled(&document), 4);
+        assert_eq!(get("list", "counter-reset"), "list-item 3");
+        assert_eq!(get("a", "list-style-type"), "lower-roman");
+        assert_eq!(get("a", "counter-set"), "list-item 7");
+        assert_eq!(get("b", "list-style-type"), "square");
+
+        // the list, then its item as the list's style changed
+        Node::set_attribute(&by_id(&document, "list"), "reversed", "");
+        assert_eq!(restyled(&document), 2);
+        assert_eq!(get("list", "counter-reset"), "reversed(list-item) 5");
+        // `value` only counts on list items
+        Node::set_attribute(&by_id(&document, "list"), "value", "1");
+        assert_eq!(restyled(&document), 0);
+    }
+
+    #[test]
+    fn test_sibling_and_structural_invalidation() {
+        let document = list(".on + p { color: red }");
+        Node::set_attribute(&by_id(&document, "p3"), "class", "on");
+        // the parent and its subtree, as a sibling combinator follows
+        assert_eq!(restyled(&document), 11);
+        assert_eq!(color(&document, "p4"), "rgb(255, 0, 0)");
+
+        // inserting restyles the new element and the siblings after it
+        let p = by_id(&document, "p8");
+        let new = Node::clone_node(&by_id(&document, "p0"), true);
+        Node::remove_attribute(&new, "id");
+        Node::insert_before(&by_id(&document, "list"), &new, Some(&p)).unwrap();
+        assert_eq!(restyled(&document), 3);
+
+        let document = list("p:nth-child(odd) { color: red } div:empty { color: blue }");
+        assert_eq!(color(&document, "p0"), "rgb(255, 0, 0)");
+        // the siblings left, and the parent for `:empty`
+        Node::detach(&by_id(&document, "p0"));
+        assert_eq!(restyled(&document), 10);
+        assert_eq!(color(&document, "p1"), "rgb(255, 0, 0)");
+        assert_eq!(color(&document, "p2"), "rgb(0, 0, 0)");
+        // text going in changes `:empty`
+        assert_eq!(color(&document, "other"), "rgb(0, 0, 255)");
+        let text = Rc::new(RefCell::new(Node::new(NodeKind::Text(String::from("x")))));
+        Node::append_child_node(&by_id(&document, "other"), &text).unwrap();
+        assert_eq!(restyled(&document), 1);
+        assert_eq!(color(&document, "other"), "rgb(0, 0, 0)");
+    }
+
+    #[test]
+    fn test_hover_invalidation() {
+        let document = list("div:hover > p { color: red } div > p:hover { color: blue }");
+        let p = by_id(&document, "p2");
+        Node::set_hovered_element(&document, Some(&p));
+        // `div:hover` is left of a combinator, so the subtree of each
+        // element in the chain up from the hovered one, which is all of them
+        assert!(Node::needs_style_update(&document));
+        assert_eq!(restyled(&document), 16);
+        assert_eq!(color(&document, "p2"), "rgb(0, 0, 255)");
+        assert_eq!(color(&document, "p1"), "rgb(255, 0, 0)");
+
+        // moving within the list restyles the two items
+        Node::set_hovered_element(&document, Some(&by_id(&document, "p3")));
+        assert_eq!(restyled(&document), 2);
+        assert_eq!(color(&document, "p2"), "rgb(255, 0, 0)");
+
+        Node::set_hovered_element(&document, None);
+        Node::update_style(&document);
+        assert_eq!(color(&document, "p3"), "rgb(0, 0, 0)");
+        assert!(Node::hovered_element(&document).is_none());
+    }
+
+    #[test]
+    fn test_inherited_changes() {
+        let document = parse(
+            "<style>.big { font-size: 20px } p { margin-top: 1em } .x { color: red }</style>\
+             <div id=a><p id=b><span id=c>x</span></p></div><div id=d><p>y</p></div>",
+        );
+        Node::update_style(&document);
+
+        // children follow a style change down, and stop where theirs don't
+        Node::set_attribute(&by_id(&document, "a"), "class", "big");
+        assert_eq!(restyled(&document), 3);
+        let style = Node::computed_style(&by_id(&document, "b")).unwrap();
+        assert_eq!(style.get_text("margin-top").unwrap(), "20px");
+
+        // a change that leaves the style as it was stops there
+        Node::set_attribute(&by_id(&document, "d"), "class", "x");
+        Node::set_attribute(&by_id(&document, "d"), "class", "");
+        assert_eq!(restyled(&document), 1);
+
+        // new sheets restyle everything
+        let style = Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new(
+            HtmlElementKind::Style,
+        )))));
+        Node::append_child_node(&Node::head(&document).unwrap(), &style).unwrap();
+        Node::update_style(&document);
+        assert!(Node::style_update_stats(&document).full_restyle);
+    }
+}
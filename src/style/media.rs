@@ -0,0 +1,605 @@
+use super::values::{ComputeContext, Length};
+use super::*;
+use crate::parser::css::CssToken;
+
+// https://drafts.csswg.org/mediaqueries-5/#prefers-color-scheme
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorScheme {
+    #[default]
+    Light,
+    Dark,
+}
+
+// https://drafts.csswg.org/mediaqueries-4/#media
+//
+// What media queries are evaluated against: the viewport, the number of
+// device pixels per CSS px and the color scheme the user prefers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Device {
+    pub viewport: Viewport,
+    pub device_pixel_ratio: f64,
+    pub color_scheme: ColorScheme,
+}
+
+impl Default for Device {
+    fn default() -> Self {
+        Self {
+            viewport: Viewport::default(),
+            device_pixel_ratio: 1.0,
+            color_scheme: ColorScheme::default(),
+        }
+    }
+}
+
+// https://drafts.csswg.org/mediaqueries-4/#mq-list
+//
+// Matches when any of its queries does, or always when it's empty.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MediaQueryList {
+    queries: Vec<MediaQuery>,
+}
+
+// https://drafts.csswg.org/mediaqueries-4/#media-query
+//
+// A query that fails to parse becomes `not all`.
+#[derive(Clone, Debug, PartialEq)]
+struct MediaQuery {
+    negated: bool,
+    // lowercased; `all` when left out
+    media_type: String,
+    condition: Option<MediaCondition>,
+}
+
+// https://drafts.csswg.org/mediaqueries-4/#media-condition
+#[derive(Clone, Debug, PartialEq)]
+enum MediaCondition {
+    Feature(MediaFeature),
+    Not(Box<MediaCondition>),
+    And(Vec<MediaCondition>),
+    Or(Vec<MediaCondition>),
+    // https://drafts.csswg.org/mediaqueries-4/#typedef-general-enclosed
+    //
+    // Syntax reserved for the future, which is neither true nor false.
+    Unknown,
+}
+
+// https://drafts.csswg.org/mediaqueries-4/#mq-features
+//
+// `(name)`, or the name compared to values. `min-` and `max-` features and
+// ranges like `(400px < width <= 800px)` all become comparisons with the
+// name on the left.
+#[derive(Clone, Debug, PartialEq)]
+struct MediaFeature {
+    name: String,
+    comparisons: Vec<(Comparison, MediaValue)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Comparison {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum MediaValue {
+    Number(f64),
+    Length(Length),
+    // https://drafts.csswg.org/mediaqueries-4/#values
+    Ratio(f64),
+    // in dppx
+    Resolution(f64),
+    // lowercased
+    Ident(String),
+}
+
+impl MediaQueryList {
+    // https://drafts.csswg.org/mediaqueries-4/#parse-a-media-query-list
+    pub fn parse(values: &[ComponentValue]) -> MediaQueryList {
+        let queries = match values.iter().all(|v| v.is_whitespace()) {
+            true => Vec::new(),
+            false => values
+                .split(|v| matches!(v, ComponentValue::Token(CssToken::Comma)))
+                .map(|query| {
+                    MediaQuery::parse(&words(query)).unwrap_or_else(|| MediaQuery {
+                        negated: true,
+                        media_type: String::from("all"),
+                        condition: None,
+                    })
+                })
+                .collect(),
+        };
+        MediaQueryList { queries }
+    }
+
+    pub fn matches(&self, device: &Device) -> bool {
+        self.queries.is_empty() || self.queries.iter().any(|q| q.matches(device))
+    }
+}
+
+fn words(values: &[ComponentValue]) -> Vec<&ComponentValue> {
+    values.iter().filter(|v| !v.is_whitespace()).collect()
+}
+
+fn ident(value: &ComponentValue) -> Option<String> {
+    match value {
+        ComponentValue::Token(CssToken::Ident(ident)) => Some(ident.to_ascii_lowercase()),
+        _ => None,
+    }
+}
+
+impl MediaQuery {
+    // https://drafts.csswg.org/mediaqueries-4/#typedef-media-query
+    fn parse(words: &[&ComponentValue]) -> Option<MediaQuery> {
+        if !matches!(
+            words.first(),
+            Some(ComponentValue::Token(CssToken::Ident(_)))
+        ) {
+            return Some(MediaQuery {
+                negated: false,
+                media_type: String::from("all"),
+                condition: Some(MediaCondition::parse(words, true)?),
+            });
+        }
+        // `not (width > 0)` is a condition, `not screen` a negated type
+        if ident(words[0]).as_deref() == Some("not")
+            && !matches!(
+                words.get(1),
+                Some(ComponentValue::Token(CssToken::Ident(_)))
+            )
+        {
+            return Some(MediaQuery {
+                negated: false,
+                media_type: String::from("all"),
+                condition: Some(MediaCondition::parse(words, true)?),
+            });
+        }
+
+        let mut i = 0;
+        let qualifier = ident(words[0]).filter(|q| q == "not" || q == "only");
+        if qualifier.is_some() {
+            i += 1;
+        }
+        let media_type = ident(words.get(i)?)?;
+        if matches!(media_type.as_str(), "not" | "and" | "or" | "only" | "layer") {
+            return None;
+        }
+        i += 1;
+
+        let condition = match words.get(i) {
+            None => None,
+            Some(word) if ident(word).as_deref() == Some("and") => {
+                Some(MediaCondition::parse(&words[i + 1..], false)?)
+            }
+            Some(_) => return None,
+        };
+        Some(MediaQuery {
+            negated: qualifier.as_deref() == Some("not"),
+            media_type,
+            condition,
+        })
+    }
+
+    // https://drafts.csswg.org/mediaqueries-4/#evaluating
+    //
+    // `not` doesn't turn an unknown result into a match.
+    fn matches(&self, device: &Device) -> bool {
+        // https://drafts.csswg.org/mediaqueries-4/#media-types
+        let result = match self.media_type.as_str() {
+            "all" | "screen" => self
+                .condition
+                .as_ref()
+                .map_or(Some(true), |c| c.evaluate(device)),
+            _ => Some(false),
+        };
+        match result {
+            Some(result) => result != self.negated,
+            None => false,
+        }
+    }
+}
+
+impl MediaCondition {
+    // https://drafts.csswg.org/mediaqueries-4/#typedef-media-condition
+    //
+    // `or` isn't allowed after a media type.
+    fn parse(words: &[&ComponentValue], allow_or: bool) -> Option<MediaCondition> {
+        let first = words.first()?;
+        if ident(first).as_deref() == Some("not") {
+            return match words[1..] {
+                [condition] => Some(MediaCondition::Not(Box::new(Self::parse_in_parens(
+                    condition,
+                )?))),
+                _ => None,
+            };
+        }
+
+        let mut conditions = vec![Self::parse_in_parens(first)?];
+        let mut combinator = None;
+        for pair in words[1..].chunks(2) {
+            let word = ident(pair[0])?;
+            if !matches!(word.as_str(), "and" | "or") || combinator.is_some_and(|c| c != word) {
+                return None;
+            }
+            conditions.push(Self::parse_in_parens(pair.get(1)?)?);
+            combinator = Some(word);
+        }
+        match combinator.as_deref() {
+            None => conditions.pop(),
+            Some("and") => Some(MediaCondition::And(conditions)),
+            _ if allow_or => Some(MediaCondition::Or(conditions)),
+            _ => None,
+        }
+    }
+
+    // https://drafts.csswg.org/mediaqueries-4/#typedef-media-in-parens
+    fn parse_in_parens(value: &ComponentValue) -> Option<MediaCondition> {
+        match value {
+            ComponentValue::Block('(', contents) => {
+                if let Some(feature) = MediaFeature::parse(contents) {
+                    return Some(MediaCondition::Feature(feature));
+                }
+                Some(Self::parse(&words(contents), true).unwrap_or(MediaCondition::Unknown))
+            }
+            ComponentValue::Function(_, _) => Some(MediaCondition::Unknown),
+            _ => None,
+        }
+    }
+
+    // three-valued, with None for unknown
+    fn evaluate(&self, device: &Device) -> Option<bool> {
+        match self {
+            MediaCondition::Feature(feature) => feature.evaluate(device),
+            MediaCondition::Not(condition) => condition.evaluate(device).map(|r| !r),
+            MediaCondition::And(conditions) => {
+                let results: Vec<Option<bool>> =
+                    conditions.iter().map(|c| c.evaluate(device)).collect();
+                match results.contains(&Some(false)) {
+                    true => Some(false),
+                    false => results
+                        .into_iter()
+                        .collect::<Option<Vec<_>>>()
+                        .map(|_| true),
+                }
+            }
+            MediaCondition::Or(conditions) => {
+                let results: Vec<Option<bool>> =
+                    conditions.iter().map(|c| c.evaluate(device)).collect();
+                match results.contains(&Some(true)) {
+                    true => Some(true),
+                    false => results
+                        .into_iter()
+                        .collect::<Option<Vec<_>>>()
+                        .map(|_| false),
+                }
+            }
+            MediaCondition::Unknown => None,
+        }
+    }
+}
+
+// the features with values that can be compared, and so have `min-` and
+// `max-` forms and ranges
+const RANGE_FEATURES: &[&str] = &[
+    "width",
+    "height",
+    "aspect-ratio",
+    "resolution",
+    "color",
+    "color-index",
+    "monochrome",
+];
+
+impl MediaFeature {
+    // https://drafts.csswg.org/mediaqueries-4/#typedef-media-feature
+    fn parse(contents: &[ComponentValue]) -> Option<MediaFeature> {
+        let contents = words(contents);
+        match contents[..] {
+            // `(name)`
+            [name] => Some(MediaFeature {
+                name: ident(name)?,
+                comparisons: Vec::new(),
+            }),
+            // `(name: value)`
+            [name, ComponentValue::Token(CssToken::Colon), ref value @ ..] => {
+                let name = ident(name)?;
+                let value = MediaValue::parse(value)?;
+                let (comparison, name) =
+                    match (name.strip_prefix("min-"), name.strip_prefix("max-")) {
+                        (Some(name), _) if RANGE_FEATURES.contains(&name) => (Comparison::Ge, name),
+                        (_, Some(name)) if RANGE_FEATURES.contains(&name) => (Comparison::Le, name),
+                        _ => (Comparison::Eq, name.as_str()),
+                    };
+                Some(MediaFeature {
+                    name: name.to_string(),
+                    comparisons: vec![(comparison, value)],
+                })
+            }
+            _ => Self::parse_range(&contents),
+        }
+    }
+
+    // https://drafts.csswg.org/mediaqueries-4/#typedef-mf-range
+    fn parse_range(contents: &[&ComponentValue]) -> Option<MediaFeature> {
+        // the operands between `<`, `<=`, `>`, `>=` and `=`
+        let mut operands: Vec<Vec<&ComponentValue>> = vec![Vec::new()];
+        let mut operators = Vec::new();
+        let mut i = 0;
+        while i < contents.len() {
+            let delim = |i: usize| match contents.get(i) {
+                Some(ComponentValue::Token(CssToken::Delim(c))) => Some(*c),
+                _ => None,
+            };
+            let or_equal = delim(i + 1) == Some('=');
+            let operator = match delim(i) {
+                Some('<') if or_equal => Comparison::Le,
+                Some('<') => Comparison::Lt,
+                Some('>') if or_equal => Comparison::Ge,
+                Some('>') => Comparison::Gt,
+                Some('=') => Comparison::Eq,
+                _ => {
+                    operands.last_mut()?.push(contents[i]);
+                    i += 1;
+                    continue;
+                }
+            };
+            i += if or_equal && operator != Comparison::Eq {
+                2
+            } else {
+                1
+            };
+            operators.push(operator);
+            operands.push(Vec::new());
+        }
+
+        let is_name = |operand: &[&ComponentValue]| match operand {
+            [name] => ident(name).filter(|n| RANGE_FEATURES.contains(&n.as_str())),
+            _ => None,
+        };
+        let flip = |c: Comparison| match c {
+            Comparison::Lt => Comparison::Gt,
+            Comparison::Le => Comparison::Ge,
+            Comparison::Eq => Comparison::Eq,
+            Comparison::Ge => Comparison::Le,
+            Comparison::Gt => Comparison::Lt,
+        };
+        let (name, comparisons) = match (&operands[..], &operators[..]) {
+            // `name < value`
+            ([name, value], [c]) if is_name(name).is_some() => {
+                (is_name(name)?, vec![(*c, MediaValue::parse(value)?)])
+            }
+            // `value < name`
+            ([value, name], [c]) => (is_name(name)?, vec![(flip(*c), MediaValue::parse(value)?)]),
+            // `value < name < value`, both pointing the same way
+            ([low, name, high], [a, b]) => {
+                let ascending = |c: &Comparison| matches!(c, Comparison::Lt | Comparison::Le);
+                let descending = |c: &Comparison| matches!(c, Comparison::Gt | Comparison::Ge);
+                if !(ascending(a) && ascending(b) || descending(a) && descending(b)) {
+                    return None;
+                }
+                (
+                    is_name(name)?,
+                    vec![
+                        (flip(*a), MediaValue::parse(low)?),
+                        (*b, MediaValue::parse(high)?),
+                    ],
+                )
+            }
+            _ => return None,
+        };
+        Some(MediaFeature { name, comparisons })
+    }
+
+    // https://drafts.csswg.org/mediaqueries-5/#mq-features
+    //
+    // Unknown for features this engine doesn't know.
+    fn evaluate(&self, device: &Device) -> Option<bool> {
+        let viewport = device.viewport;
+        let actual = match self.name.as_str() {
+            "width" => MediaValue::Length(Length::Px(viewport.width)),
+            "height" => MediaValue::Length(Length::Px(viewport.height)),
+            "aspect-ratio" => MediaValue::Ratio(viewport.width / viewport.height),
+            "resolution" => MediaValue::Resolution(device.device_pixel_ratio),
+            // https://drafts.csswg.org/mediaqueries-4/#orientation
+            "orientation" => {
+                MediaValue::Ident(String::from(match viewport.height >= viewport.width {
+                    true => "portrait",
+                    false => "landscape",
+                }))
+            }
+            "prefers-color-scheme" => MediaValue::Ident(String::from(match device.color_scheme {
+                ColorScheme::Light => "light",
+                ColorScheme::Dark => "dark",
+            })),
+            // https://drafts.csswg.org/mediaqueries-4/#mf-colors
+            //
+            // The window is on a screen with 8 bits per color component and
+            // no color lookup table.
+            "color" => MediaValue::Number(8.0),
+            "color-index" | "monochrome" => MediaValue::Number(0.0),
+            // https://drafts.csswg.org/mediaqueries-4/#mf-interaction
+            //
+            // and used with a mouse.
+            "hover" | "any-hover" => MediaValue::Ident(String::from("hover")),
+            "pointer" | "any-pointer" => MediaValue::Ident(String::from("fine")),
+            _ => return None,
+        };
+
+        // https://drafts.csswg.org/mediaqueries-4/#mq-boolean-context
+        if self.comparisons.is_empty() {
+            return Some(match actual {
+                MediaValue::Ident(_) => true,
+                value => value.to_f64(device) != Some(0.0),
+            });
+        }
+
+        let mut result = true;
+        for (comparison, expected) in self.comparisons.iter() {
+            let matched = match (&actual, expected) {
+                (MediaValue::Ident(a), MediaValue::Ident(b)) => {
+                    *comparison == Comparison::Eq && a == b
+                }
+                _ => {
+                    if !actual.is_comparable_with(expected) {
+                        return Some(false);
+                    }
+                    let (a, b) = (actual.to_f64(device)?, expected.to_f64(device)?);
+                    match comparison {
+                        Comparison::Lt => a < b,
+                        Comparison::Le => a <= b,
+                        Comparison::Eq => a == b,
+                        Comparison::Ge => a >= b,
+                        Comparison::Gt => a > b,
+                    }
+                }
+            };
+            result &= matched;
+        }
+        Some(result)
+    }
+}
+
+impl MediaValue {
+    // https://drafts.csswg.org/mediaqueries-4/#mf-value
+    fn parse(value: &[&ComponentValue]) -> Option<MediaValue> {
+        let number = |value: &ComponentValue| match value {
+            ComponentValue::Token(CssToken::Number { value, .. }) => Some(*value),
+            _ => None,
+        };
+        match value {
+            // https://drafts.csswg.org/css-values-4/#ratio-value
+            [a, ComponentValue::Token(CssToken::Delim('/')), b] => {
+                Some(MediaValue::Ratio(number(a)? / number(b)?))
+            }
+            [ComponentValue::Token(CssToken::Dimension { value, unit, .. })] => {
+                // https://drafts.csswg.org/css-values-4/#resolution
+                let dppx = match unit.to_ascii_lowercase().as_str() {
+                    "dppx" | "x" => Some(*value),
+                    "dpi" => Some(value / 96.0),
+                    "dpcm" => Some(value * 2.54 / 96.0),
+                    _ => None,
+                };
+                match dppx {
+                    Some(dppx) => Some(MediaValue::Resolution(dppx)),
+                    None => Length::new(*value, unit).map(MediaValue::Length),
+                }
+            }
+            [value] => match number(value) {
+                Some(n) => Some(MediaValue::Number(n)),
+                None => ident(value).map(MediaValue::Ident),
+            },
+            _ => None,
+        }
+    }
+
+    fn is_comparable_with(&self, other: &MediaValue) -> bool {
+        match (self, other) {
+            // a unitless zero is a length, and a number a ratio over 1
+            (MediaValue::Length(_), MediaValue::Number(n)) => *n == 0.0,
+            (MediaValue::Ratio(_), MediaValue::Number(_)) => true,
+            (a, b) => std::mem::discriminant(a) == std::mem::discriminant(b),
+        }
+    }
+
+    // https://drafts.csswg.org/mediaqueries-4/#units
+    //
+    // Relative lengths are based on the initial font size.
+    fn to_f64(&self, device: &Device) -> Option<f64> {
+        match self {
+            MediaValue::Number(n) | MediaValue::Ratio(n) | MediaValue::Resolution(n) => Some(*n),
+            MediaValue::Length(length) => Some(length.to_px(&ComputeContext {
+                viewport: device.viewport,
+                ..ComputeContext::default()
+            })),
+            MediaValue::Ident(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::css::parse_component_values;
+
+    fn matches(query: &str, width: f64, height: f64) -> bool {
+        let device = Device {
+            viewport: Viewport { width, height },
+            device_pixel_ratio: 2.0,
+            color_scheme: ColorScheme::Dark,
+        };
+        MediaQueryList::parse(&parse_component_values(query)).matches(&device)
+    }
+
+    #[test]
+    fn test_media_types_and_lists() {
+        assert!(matches("", 800.0, 600.0));
+        assert!(matches("all", 800.0, 600.0));
+        assert!(matches("only screen", 800.0, 600.0));
+        assert!(!matches("print", 800.0, 600.0));
+        assert!(matches("not print", 800.0, 600.0));
+        assert!(matches("print, screen", 800.0, 600.0));
+        // an invalid query is `not all`, but leaves the others alone
+        assert!(!matches("screen and", 800.0, 600.0));
+        assert!(matches("screen (width), all", 800.0, 600.0));
+        assert!(!matches("screen and (width) or (height)", 800.0, 600.0));
+    }
+
+    #[test]
+    fn test_media_features() {
+        assert!(matches("(min-width: 600px)", 800.0, 600.0));
+        assert!(!matches("(max-width: 600px)", 800.0, 600.0));
+        assert!(matches("screen and (max-width: 50em)", 800.0, 600.0));
+        assert!(matches(
+            "(width >= 800px) and (height < 601px)",
+            800.0,
+            600.0
+        ));
+        assert!(matches("(400px < width <= 800px)", 800.0, 600.0));
+        assert!(!matches("(400px < width < 800px)", 800.0, 600.0));
+        assert!(matches("(1000px > width)", 800.0, 600.0));
+        assert!(!matches("(400px < width > 800px)", 800.0, 600.0));
+        assert!(matches("(orientation: landscape)", 800.0, 600.0));
+        assert!(matches("(orientation: portrait)", 600.0, 800.0));
+        assert!(matches("(aspect-ratio: 4/3)", 800.0, 600.0));
+        assert!(matches("(min-aspect-ratio: 1)", 800.0, 600.0));
+        assert!(matches("(prefers-color-scheme: dark)", 800.0, 600.0));
+        assert!(!matches("(prefers-color-scheme: light)", 800.0, 600.0));
+        assert!(matches("(resolution: 2dppx)", 800.0, 600.0));
+        assert!(matches("(min-resolution: 144dpi)", 800.0, 600.0));
+        assert!(matches("(width)", 800.0, 600.0));
+        assert!(!matches("(width)", 0.0, 600.0));
+        assert!(!matches("(width: red)", 800.0, 600.0));
+        assert!(matches("(color)", 800.0, 600.0));
+        assert!(matches("(min-color: 8)", 800.0, 600.0));
+        assert!(!matches("(color-index)", 800.0, 600.0));
+        assert!(!matches("(monochrome)", 800.0, 600.0));
+        assert!(matches("not all and (monochrome)", 800.0, 600.0));
+        assert!(matches("(hover: hover) and (pointer: fine)", 800.0, 600.0));
+        assert!(!matches("(any-pointer: coarse)", 800.0, 600.0));
+    }
+
+    #[test]
+    fn test_media_conditions() {
+        assert!(matches("not (max-width: 600px)", 800.0, 600.0));
+        assert!(matches(
+            "(max-width: 600px) or (orientation: landscape)",
+            800.0,
+            600.0
+        ));
+        assert!(matches(
+            "((width > 1px) and (not (height > 1000px)))",
+            800.0,
+            600.0
+        ));
+        assert!(!matches("not screen and (width > 1px)", 800.0, 600.0));
+        // mixing `and` and `or` without parens is invalid
+        assert!(!matches("(width) and (height) or (width)", 800.0, 600.0));
+        // unknown features and syntax are neither true nor false
+        assert!(!matches("(inverted-colors: none)", 800.0, 600.0));
+        assert!(!matches("not (inverted-colors: none)", 800.0, 600.0));
+        assert!(matches("(inverted-colors: none) or (width)", 800.0, 600.0));
+        assert!(!matches("not (foo bar)", 800.0, 600.0));
+    }
+}
@@ -0,0 +1,268 @@
+mod at_rules;
+mod cascade;
+mod counter_styles;
+mod counters;
+mod custom_properties;
+mod invalidation;
+mod media;
+mod presentational_hints;
+mod properties;
+mod shorthands;
+mod stylesheet_list;
+mod stylist;
+mod supports;
+pub mod values;
+
+pub use cascade::{cascade, cascade_pseudo_element, Origin};
+pub use counter_styles::{format_counter, marker_suffix};
+pub use counters::{generated_content, generates_box, Counters};
+pub use custom_properties::{has_references, is_custom_property, substitute_references};
+pub use invalidation::StyleUpdateStats;
+pub(crate) use invalidation::{InvalidationState, RestyleHint};
+pub use media::{ColorScheme, Device, MediaQueryList};
+pub use properties::{lookup, PropertyDef, PROPERTIES};
+pub use shorthands::{
+    collapse_shorthands, expand_declaration, lookup_shorthand, ShorthandDef, SHORTHANDS,
+};
+pub use stylesheet_list::CssStyleSheet;
+pub(crate) use stylesheet_list::StyleSheetList;
+pub use stylist::Stylist;
+pub use supports::supports_condition;
+
+use crate::node::Node;
+use crate::parser::css::{
+    parse_stylesheet, serialize_values, ComponentValue, CssRule, Declaration, StyleSheet,
+};
+use crate::selector::PseudoElement;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::rc::Rc;
+use values::{Color, Display, LengthPercentage, MEDIUM_FONT_SIZE};
+
+// https://html.spec.whatwg.org/multipage/rendering.html
+const USER_AGENT_CSS: &str = include_str!("ua.css");
+
+pub fn user_agent_stylesheet() -> StyleSheet {
+    parse_stylesheet(USER_AGENT_CSS)
+}
+
+// https://drafts.csswg.org/css2/#viewport
+//
+// The size of the area the document is shown in, in CSS px.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            width: 800.0,
+            height: 600.0,
+        }
+    }
+}
+
+// https://drafts.csswg.org/css-cascade-4/#computed
+//
+// The value of every known property for one element, and of the custom
+// properties it has.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ComputedStyle {
+    properties: BTreeMap<String, Vec<ComponentValue>>,
+}
+
+impl ComputedStyle {
+    pub fn get(&self, name: &str) -> Option<&[ComponentValue]> {
+        self.properties.get(name).map(|v| v.as_slice())
+    }
+
+    // the serialized value of a property, or of a shorthand when its
+    // longhands can be written as one
+    pub fn get_text(&self, name: &str) -> Option<String> {
+        if let Some(value) = self.get(name) {
+            return Some(serialize_values(value));
+        }
+        let shorthand = lookup_shorthand(name)?;
+        let values: Option<Vec<&[ComponentValue]>> =
+            shorthand.longhands.iter().map(|l| self.get(l)).collect();
+        shorthand.serialize(&values?)
+    }
+
+    // https://drafts.csswg.org/css-variables-2/#custom-property
+    pub fn custom_properties(&self) -> impl Iterator<Item = (&str, &[ComponentValue])> {
+        self.properties
+            .iter()
+            .filter(|(name, _)| is_custom_property(name))
+            .map(|(name, value)| (name.as_str(), value.as_slice()))
+    }
+
+    // a length or percentage value, such as `width` or `margin-top`
+    pub fn length_percentage(&self, name: &str) -> Option<LengthPercentage> {
+        self.get(name).and_then(LengthPercentage::parse)
+    }
+
+    pub fn color(&self, name: &str) -> Option<Color> {
+        self.get(name).and_then(Color::parse)
+    }
+
+    pub fn display(&self) -> Display {
+        self.get("display")
+            .and_then(Display::parse)
+            .unwrap_or_default()
+    }
+
+    // the computed `font-size` in px
+    pub fn font_size(&self) -> f64 {
+        match self.length_percentage("font-size") {
+            Some(LengthPercentage::Length(px)) => px,
+            _ => MEDIUM_FONT_SIZE,
+        }
+    }
+
+    pub(crate) fn set(&mut self, name: &str, value: Vec<ComponentValue>) {
+        self.properties.insert(name.to_string(), value);
+    }
+}
+
+// one `name: value;` line per property, sorted by name, with longhands
+// collapsed into a shorthand where they can be, in place of the first
+impl fmt::Display for ComputedStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let declarations: Vec<Declaration> = self
+            .properties
+            .iter()
+            .map(|(name, value)| Declaration {
+                name: name.clone(),
+                value: value.clone(),
+                important: false,
+            })
+            .collect();
+        for declaration in collapse_shorthands(&declarations) {
+            writeln!(f, "{};", declaration)?;
+        }
+        Ok(())
+    }
+}
+
+impl Node {
+    // the style computed by the last `resolve_styles` covering `node`
+    pub fn computed_style(node: &Rc<RefCell<Node>>) -> Option<Rc<ComputedStyle>> {
+        node.borrow().computed_style.clone()
+    }
+
+    // the style of a pseudo-element of `node`, if it has one
+    pub fn pseudo_element_style(
+        node: &Rc<RefCell<Node>>,
+        pseudo_element: PseudoElement,
+    ) -> Option<Rc<ComputedStyle>> {
+        node.borrow()
+            .pseudo_element_styles
+            .iter()
+            .find(|(p, _)| *p == pseudo_element)
+            .map(|(_, style)| style.clone())
+    }
+
+    // Computes the style of every element in `root`'s subtree, parents
+    // before children so each can inherit from the one above it.
+    pub fn resolve_styles(root: &Rc<RefCell<Node>>, stylist: &Stylist) {
+        let parent_style = root
+            .borrow()
+            .parent()
+            .and_then(|p| Node::computed_style(&p));
+        let mut stats = StyleUpdateStats::default();
+        Node::restyle(
+            root,
+            stylist,
+            parent_style.as_deref(),
+            RestyleHint::Subtree,
+            &mut stats,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::css::CssRule;
+    use crate::parser::html_parser::parse;
+
+    #[test]
+    fn test_resolve_styles() {
+        let document = parse("<div id=a><p id=b>x</p></div>");
+        let mut stylist = Stylist::new();
+        stylist.add_stylesheet(
+            Origin::Author,
+            parse_stylesheet("div { color: red; display: block } p { margin-top: 1em }"),
+        );
+        Node::resolve_styles(&document, &stylist);
+
+        let a = Node::get_element_by_id(&document, "a").unwrap();
+        let b = Node::get_element_by_id(&document, "b").unwrap();
+        let text = b.borrow().first_child().unwrap();
+        assert!(Node::computed_style(&text).is_none());
+
+        let style = Node::computed_style(&b).unwrap();
+        assert_eq!(style.get_text("color").unwrap(), "rgb(255, 0, 0)");
+        assert_eq!(style.get_text("display").unwrap(), "inline");
+        assert_eq!(style.get_text("margin-top").unwrap(), "16px");
+        assert_eq!(
+            Node::computed_style(&a)
+                .unwrap()
+                .get_text("display")
+                .unwrap(),
+            "block"
+        );
+
+        assert_eq!(style.get_text("margin").unwrap(), "16px 0px 0px");
+        // without a style the border has no width
+        assert_eq!(style.get_text("border").unwrap(), "0px");
+
+        // every known property is present, in a shorthand where it fits
+        let dump = style.to_string();
+        let expanded = crate::parser::css::parse_declaration_list(&dump);
+        assert_eq!(expanded.len(), PROPERTIES.len());
+        assert!(PROPERTIES
+            .iter()
+            .all(|p| expanded.iter().any(|d| d.name == p.name)));
+        assert!(dump.contains("\ncolor: rgb(255, 0, 0);\n"));
+        assert!(dump.contains("\nmargin: 16px 0px 0px;\n"));
+    }
+
+    #[test]
+    fn test_user_agent_stylesheet() {
+        // every rule of the sheet parses
+        let rules = USER_AGENT_CSS.matches('{').count();
+        let sheet = user_agent_stylesheet();
+        assert_eq!(sheet.rules.len(), rules);
+        assert!(sheet.rules.iter().all(|r| matches!(r, CssRule::Style(_))));
+
+        let document = parse(
+            "<html><head><title>t</title></head><body>\
+             <h1 id=h>x</h1><p id=p>y<span id=s>z</span></p><div id=d hidden></div>",
+        );
+        let mut stylist = Stylist::with_user_agent_stylesheet();
+        stylist.add_stylesheet(Origin::Author, parse_stylesheet("p { margin-top: 0 }"));
+        Node::resolve_styles(&document, &stylist);
+
+        let get = |element: Option<Rc<RefCell<Node>>>, property| {
+            Node::computed_style(&element.unwrap())
+                .unwrap()
+                .get_text(property)
+                .unwrap()
+        };
+        let by_id = |id| Node::get_element_by_id(&document, id);
+        assert_eq!(get(Node::head(&document), "display"), "none");
+        assert_eq!(get(Node::body(&document), "margin-left"), "8px");
+        assert_eq!(get(by_id("h"), "display"), "block");
+        assert_eq!(get(by_id("h"), "font-size"), "32px");
+        assert_eq!(get(by_id("h"), "font-weight"), "bold");
+        assert_eq!(get(by_id("s"), "display"), "inline");
+        assert_eq!(get(by_id("d"), "display"), "none");
+        // author rules win over the user agent's
+        assert_eq!(get(by_id("p"), "margin-top"), "0px");
+        assert_eq!(get(by_id("p"), "margin-bottom"), "16px");
+    }
+}
@@ -0,0 +1,115 @@
+use crate::element::{Element, HtmlElementKind};
+use crate::parser::css::{parse_declaration_list, Declaration};
+
+// https://html.spec.whatwg.org/multipage/rendering.html#presentational-hints
+//
+// The declarations the attributes of an element stand for, which come before
+// the author's own rules.
+pub(super) fn presentational_hints(element: &Element) -> Vec<Declaration> {
+    let mut css = String::new();
+
+    // https://html.spec.whatwg.org/multipage/rendering.html#lists
+    match element.kind() {
+        HtmlElementKind::Ol => {
+            let reversed = element.get_attribute("reversed").is_some();
+            let start = element.get_attribute("start").and_then(parse_integer);
+            match (reversed, start) {
+                (true, Some(start)) => css.push_str(&format!(
+                    "counter-reset: reversed(list-item) {};",
+                    start.saturating_add(1)
+                )),
+                (true, None) => css.push_str("counter-reset: reversed(list-item);"),
+                (false, Some(start)) => css.push_str(&format!(
+                    "counter-reset: list-item {};",
+                    start.saturating_sub(1)
+                )),
+                (false, None) => {}
+            }
+            if let Some(kind) = element.get_attribute("type").and_then(ordered_list_type) {
+                css.push_str(&format!("list-style-type: {};", kind));
+            }
+        }
+        HtmlElementKind::Ul => {
+            if let Some(kind) = element.get_attribute("type").and_then(unordered_list_type) {
+                css.push_str(&format!("list-style-type: {};", kind));
+            }
+        }
+        HtmlElementKind::Li => {
+            if let Some(value) = element.get_attribute("value").and_then(parse_integer) {
+                css.push_str(&format!("counter-set: list-item {};", value));
+            }
+            let kind = element
+                .get_attribute("type")
+                .and_then(|t| ordered_list_type(t).or_else(|| unordered_list_type(t)));
+            if let Some(kind) = kind {
+                css.push_str(&format!("list-style-type: {};", kind));
+            }
+        }
+        _ => {}
+    }
+
+    parse_declaration_list(&css)
+}
+
+// whether the attribute `name` of `element` feeds its presentational hints
+pub(super) fn is_hint_attribute(element: &Element, name: &str) -> bool {
+    match element.kind() {
+        HtmlElementKind::Ol => matches!(name, "start" | "reversed" | "type"),
+        HtmlElementKind::Ul => name == "type",
+        HtmlElementKind::Li => matches!(name, "value" | "type"),
+        _ => false,
+    }
+}
+
+// `type` of `ol` and `li`, which is case-sensitive
+fn ordered_list_type(value: &str) -> Option<&'static str> {
+    match value {
+        "1" => Some("decimal"),
+        "a" => Some("lower-alpha"),
+        "A" => Some("upper-alpha"),
+        "i" => Some("lower-roman"),
+        "I" => Some("upper-roman"),
+        _ => None,
+    }
+}
+
+// `type` of `ul` and `li`
+fn unordered_list_type(value: &str) -> Option<&'static str> {
+    match value.to_ascii_lowercase().as_str() {
+        "none" => Some("none"),
+        "disc" => Some("disc"),
+        "circle" => Some("circle"),
+        "square" => Some("square"),
+        _ => None,
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/common-microsyntaxes.html#rules-for-parsing-integers
+//
+// Leading white space and a sign, then digits up to the first that isn't.
+fn parse_integer(value: &str) -> Option<i32> {
+    let value = value.trim_start_matches(['\t', '\n', '\x0C', '\r', ' ']);
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let digits: String = value.chars().take_while(|c| c.is_ascii_digit()).collect();
+    let magnitude: i64 = digits.parse().ok()?;
+    let value = if negative { -magnitude } else { magnitude };
+    i32::try_from(value).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_integer() {
+        assert_eq!(parse_integer("  12abc"), Some(12));
+        assert_eq!(parse_integer("-3"), Some(-3));
+        assert_eq!(parse_integer("+7"), Some(7));
+        assert_eq!(parse_integer("x1"), None);
+        assert_eq!(parse_integer("-"), None);
+        assert_eq!(parse_integer("99999999999"), None);
+    }
+}
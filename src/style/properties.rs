@@ -0,0 +1,846 @@
+use super::cascade::css_wide_keyword;
+use super::values::{CalcKind, CalcNode, Color, Display, Length};
+use crate::parser::css::{ComponentValue, CssToken};
+
+// The longhand properties the style engine knows, with whether they are
+// inherited, their initial value and what values they take.
+//
+// https://drafts.csswg.org/css-cascade-4/#inherited-property
+// https://drafts.csswg.org/css-cascade-4/#initial-value
+pub struct PropertyDef {
+    pub name: &'static str,
+    pub inherited: bool,
+    pub initial: &'static str,
+    // https://drafts.csswg.org/css-values-4/#value-defs
+    parse: fn(&[ComponentValue]) -> bool,
+}
+
+const fn property(
+    name: &'static str,
+    inherited: bool,
+    initial: &'static str,
+    parse: fn(&[ComponentValue]) -> bool,
+) -> PropertyDef {
+    PropertyDef {
+        name,
+        inherited,
+        initial,
+        parse,
+    }
+}
+
+const BORDER_COLLAPSE: &[&str] = &["separate", "collapse"];
+const FLOATS: &[&str] = &["none", "left", "right", "inline-start", "inline-end"];
+const OVERFLOWS: &[&str] = &["visible", "hidden", "clip", "scroll", "auto"];
+
+pub const PROPERTIES: &[PropertyDef] = &[
+    property("background-attachment", false, "scroll", |v| {
+        list(v, |layer| keyword(layer, &["scroll", "fixed", "local"]))
+    }),
+    property("background-clip", false, "border-box", |v| {
+        list(v, |layer| keyword(layer, BACKGROUND_BOXES))
+    }),
+    property("background-color", false, "transparent", |v| {
+        single(v, is_color)
+    }),
+    property("background-image", false, "none", |v| {
+        list(v, |layer| single(layer, is_image_or_none))
+    }),
+    property("background-origin", false, "padding-box", |v| {
+        list(v, |layer| keyword(layer, BACKGROUND_BOXES))
+    }),
+    property("background-position-x", false, "0%", |v| {
+        list(v, |layer| position(layer, &["left", "right"]))
+    }),
+    property("background-position-y", false, "0%", |v| {
+        list(v, |layer| position(layer, &["top", "bottom"]))
+    }),
+    property("background-repeat", false, "repeat", |v| {
+        list(v, background_repeat)
+    }),
+    property("background-size", false, "auto", |v| {
+        list(v, background_size)
+    }),
+    property("border-bottom-color", false, "currentcolor", |v| {
+        single(v, is_color)
+    }),
+    property("border-bottom-style", false, "none", |v| {
+        single(v, is_line_style)
+    }),
+    property("border-bottom-width", false, "medium", line_width),
+    property("border-collapse", true, "separate", |v| {
+        keyword(v, BORDER_COLLAPSE)
+    }),
+    property("border-left-color", false, "currentcolor", |v| {
+        single(v, is_color)
+    }),
+    property("border-left-style", false, "none", |v| {
+        single(v, is_line_style)
+    }),
+    property("border-left-width", false, "medium", line_width),
+    property("border-right-color", false, "currentcolor", |v| {
+        single(v, is_color)
+    }),
+    property("border-right-style", false, "none", |v| {
+        single(v, is_line_style)
+    }),
+    property("border-right-width", false, "medium", line_width),
+    property("border-spacing", true, "0", |v| {
+        repeated(v, 2, |w| is_length(w) && is_non_negative(w))
+    }),
+    property("border-top-color", false, "currentcolor", |v| {
+        single(v, is_color)
+    }),
+    property("border-top-style", false, "none", |v| {
+        single(v, is_line_style)
+    }),
+    property("border-top-width", false, "medium", line_width),
+    property("bottom", false, "auto", length_percentage_or_auto),
+    property("box-sizing", false, "content-box", |v| {
+        keyword(v, &["content-box", "border-box"])
+    }),
+    property("caption-side", true, "top", |v| {
+        keyword(v, &["top", "bottom"])
+    }),
+    property("clear", false, "none", |v| {
+        keyword(
+            v,
+            &[
+                "none",
+                "left",
+                "right",
+                "both",
+                "inline-start",
+                "inline-end",
+            ],
+        )
+    }),
+    property("color", true, "canvastext", |v| single(v, is_color)),
+    property("column-gap", false, "normal", gap),
+    property("content", false, "normal", content),
+    property("counter-increment", false, "none", |v| counters(v, false)),
+    property("counter-reset", false, "none", |v| counters(v, true)),
+    property("counter-set", false, "none", |v| counters(v, false)),
+    property("cursor", true, "auto", cursor),
+    property("direction", true, "ltr", |v| keyword(v, &["ltr", "rtl"])),
+    property("display", false, "inline", |v| Display::parse(v).is_some()),
+    property("flex-basis", false, "auto", |v| {
+        single(v, |w| {
+            is_non_negative_length_percentage(w) || is_keyword(w, &["auto", "content"])
+        })
+    }),
+    property("flex-direction", false, "row", |v| {
+        keyword(v, &["row", "row-reverse", "column", "column-reverse"])
+    }),
+    property("flex-grow", false, "0", non_negative_number),
+    property("flex-shrink", false, "1", non_negative_number),
+    property("flex-wrap", false, "nowrap", |v| {
+        keyword(v, &["nowrap", "wrap", "wrap-reverse"])
+    }),
+    property("float", false, "none", |v| keyword(v, FLOATS)),
+    property("font-family", true, "serif", font_family),
+    property("font-size", true, "medium", |v| {
+        single(v, |w| {
+            is_non_negative_length_percentage(w) || is_keyword(w, FONT_SIZES)
+        })
+    }),
+    property("font-stretch", true, "normal", |v| {
+        single(v, |w| {
+            is_keyword(w, &["normal"])
+                || is_keyword(w, FONT_STRETCHES)
+                || matches!(w, ComponentValue::Token(CssToken::Percentage(p)) if *p >= 0.0)
+        })
+    }),
+    property("font-style", true, "normal", font_style),
+    property("font-variant-caps", true, "normal", |v| {
+        keyword(
+            v,
+            &[
+                "normal",
+                "small-caps",
+                "all-small-caps",
+                "petite-caps",
+                "all-petite-caps",
+                "unicase",
+                "titling-caps",
+            ],
+        )
+    }),
+    property("font-weight", true, "normal", |v| {
+        single(v, |w| is_keyword(w, &["normal"]) || is_font_weight(w))
+    }),
+    property("grid-template-areas", false, "none", |v| {
+        keyword(v, &["none"])
+            || words(v)
+                .iter()
+                .all(|w| matches!(w, ComponentValue::Token(CssToken::String(_))))
+    }),
+    property("grid-template-columns", false, "none", track_list),
+    property("grid-template-rows", false, "none", track_list),
+    property("height", false, "auto", |v| size(v, "auto")),
+    property("left", false, "auto", length_percentage_or_auto),
+    property("letter-spacing", true, "normal", |v| {
+        single(v, |w| is_length_percentage(w) || is_keyword(w, &["normal"]))
+    }),
+    property("line-height", true, "normal", |v| {
+        single(v, |w| {
+            is_keyword(w, &["normal"])
+                || ((is_number(w) || is_length_percentage(w)) && is_non_negative(w))
+        })
+    }),
+    property("list-style-image", true, "none", |v| {
+        single(v, is_image_or_none)
+    }),
+    property("list-style-position", true, "outside", |v| {
+        keyword(v, &["inside", "outside"])
+    }),
+    property("list-style-type", true, "disc", |v| {
+        single(v, |w| {
+            matches!(
+                w,
+                ComponentValue::Token(CssToken::Ident(_) | CssToken::String(_))
+            )
+        })
+    }),
+    property("margin-bottom", false, "0", length_percentage_or_auto),
+    property("margin-left", false, "0", length_percentage_or_auto),
+    property("margin-right", false, "0", length_percentage_or_auto),
+    property("margin-top", false, "0", length_percentage_or_auto),
+    property("max-height", false, "none", |v| size(v, "none")),
+    property("max-width", false, "none", |v| size(v, "none")),
+    property("min-height", false, "auto", |v| size(v, "auto")),
+    property("min-width", false, "auto", |v| size(v, "auto")),
+    property("opacity", false, "1", |v| {
+        single(v, |w| {
+            is_number(w) || matches!(w, ComponentValue::Token(CssToken::Percentage(_)))
+        })
+    }),
+    property("overflow-x", false, "visible", |v| keyword(v, OVERFLOWS)),
+    property("overflow-y", false, "visible", |v| keyword(v, OVERFLOWS)),
+    property("padding-bottom", false, "0", |v| {
+        single(v, is_non_negative_length_percentage)
+    }),
+    property("padding-left", false, "0", |v| {
+        single(v, is_non_negative_length_percentage)
+    }),
+    property("padding-right", false, "0", |v| {
+        single(v, is_non_negative_length_percentage)
+    }),
+    property("padding-top", false, "0", |v| {
+        single(v, is_non_negative_length_percentage)
+    }),
+    property("position", false, "static", |v| {
+        keyword(v, &["static", "relative", "absolute", "sticky", "fixed"])
+    }),
+    property("right", false, "auto", length_percentage_or_auto),
+    property("row-gap", false, "normal", gap),
+    property("table-layout", false, "auto", |v| {
+        keyword(v, &["auto", "fixed"])
+    }),
+    property("text-align", true, "start", |v| {
+        keyword(
+            v,
+            &[
+                "start",
+                "end",
+                "left",
+                "right",
+                "center",
+                "justify",
+                "match-parent",
+            ],
+        )
+    }),
+    property("text-decoration-color", false, "currentcolor", |v| {
+        single(v, is_color)
+    }),
+    property("text-decoration-line", false, "none", |v| {
+        keyword(v, &["none"]) || keywords(v, TEXT_DECORATION_LINES)
+    }),
+    property("text-decoration-style", false, "solid", |v| {
+        keyword(v, TEXT_DECORATION_STYLES)
+    }),
+    property("text-indent", true, "0", |v| {
+        single(v, is_length_percentage)
+    }),
+    property("text-transform", true, "none", |v| {
+        keyword(v, &["none"])
+            || (keywords(
+                v,
+                &[
+                    "capitalize",
+                    "uppercase",
+                    "lowercase",
+                    "full-width",
+                    "full-size-kana",
+                ],
+            ) && words(v)
+                .iter()
+                .filter(|w| is_keyword(w, &["capitalize", "uppercase", "lowercase"]))
+                .count()
+                <= 1)
+    }),
+    property("top", false, "auto", length_percentage_or_auto),
+    property("vertical-align", false, "baseline", |v| {
+        single(v, |w| {
+            is_length_percentage(w)
+                || is_keyword(
+                    w,
+                    &[
+                        "baseline",
+                        "sub",
+                        "super",
+                        "text-top",
+                        "text-bottom",
+                        "middle",
+                        "top",
+                        "bottom",
+                    ],
+                )
+        })
+    }),
+    property("visibility", true, "visible", |v| {
+        keyword(v, &["visible", "hidden", "collapse"])
+    }),
+    property("white-space", true, "normal", |v| {
+        keyword(
+            v,
+            &[
+                "normal",
+                "pre",
+                "nowrap",
+                "pre-wrap",
+                "break-spaces",
+                "pre-line",
+            ],
+        )
+    }),
+    property("width", false, "auto", |v| size(v, "auto")),
+    property("word-spacing", true, "normal", |v| {
+        single(v, |w| is_length_percentage(w) || is_keyword(w, &["normal"]))
+    }),
+    property("z-index", false, "auto", |v| {
+        single(v, |w| is_integer(w) || is_keyword(w, &["auto"]))
+    }),
+];
+
+pub fn lookup(name: &str) -> Option<&'static PropertyDef> {
+    PROPERTIES.iter().find(|p| p.name == name)
+}
+
+impl PropertyDef {
+    // https://drafts.csswg.org/css-syntax-3/#parse-grammar
+    //
+    // Whether `value` is one the property takes. The CSS-wide keywords are
+    // valid for every property.
+    pub fn parse(&self, value: &[ComponentValue]) -> bool {
+        css_wide_keyword(value).is_some() || (self.parse)(value)
+    }
+}
+
+pub(super) const BACKGROUND_REPEATS: &[&str] = &["repeat", "space", "round", "no-repeat"];
+pub(super) const BACKGROUND_BOXES: &[&str] = &["border-box", "padding-box", "content-box"];
+pub(super) const FONT_SIZES: &[&str] = &[
+    "xx-small",
+    "x-small",
+    "small",
+    "medium",
+    "large",
+    "x-large",
+    "xx-large",
+    "xxx-large",
+    "larger",
+    "smaller",
+];
+pub(super) const FONT_STRETCHES: &[&str] = &[
+    "ultra-condensed",
+    "extra-condensed",
+    "condensed",
+    "semi-condensed",
+    "semi-expanded",
+    "expanded",
+    "extra-expanded",
+    "ultra-expanded",
+];
+pub(super) const TEXT_DECORATION_LINES: &[&str] =
+    &["underline", "overline", "line-through", "blink"];
+pub(super) const TEXT_DECORATION_STYLES: &[&str] = &["solid", "double", "dotted", "dashed", "wavy"];
+
+// a single word that `valid` accepts
+fn single(value: &[ComponentValue], valid: impl Fn(&ComponentValue) -> bool) -> bool {
+    matches!(words(value)[..], [word] if valid(word))
+}
+
+fn keyword(value: &[ComponentValue], keywords: &[&str]) -> bool {
+    single(value, |w| is_keyword(w, keywords))
+}
+
+// https://drafts.csswg.org/css-values-4/#comb-any
+//
+// one or more of the keywords, each at most once
+fn keywords(value: &[ComponentValue], keywords: &[&str]) -> bool {
+    let words = words(value);
+    !words.is_empty()
+        && words
+            .iter()
+            .enumerate()
+            .all(|(i, w)| is_keyword(w, keywords) && !words[..i].iter().any(|earlier| earlier == w))
+}
+
+// https://drafts.csswg.org/css-values-4/#mult-num-range
+//
+// one to `max` words that `valid` accepts
+fn repeated(value: &[ComponentValue], max: usize, valid: impl Fn(&ComponentValue) -> bool) -> bool {
+    let words = words(value);
+    (1..=max).contains(&words.len()) && words.into_iter().all(valid)
+}
+
+// https://drafts.csswg.org/css-values-4/#mult-comma
+fn list(value: &[ComponentValue], valid: impl Fn(&[ComponentValue]) -> bool) -> bool {
+    split_list(value)
+        .into_iter()
+        .all(|item| !item.is_empty() && valid(item))
+}
+
+// https://drafts.csswg.org/css-values-4/#numeric-ranges
+//
+// Math functions are clamped to the range once computed instead.
+fn is_non_negative(value: &ComponentValue) -> bool {
+    match value {
+        ComponentValue::Token(
+            CssToken::Number { value, .. }
+            | CssToken::Percentage(value)
+            | CssToken::Dimension { value, .. },
+        ) => *value >= 0.0,
+        _ => true,
+    }
+}
+
+fn is_non_negative_length_percentage(value: &ComponentValue) -> bool {
+    is_length_percentage(value) && is_non_negative(value)
+}
+
+fn is_integer(value: &ComponentValue) -> bool {
+    matches!(
+        value,
+        ComponentValue::Token(CssToken::Number { integer: true, .. })
+    )
+}
+
+fn is_image_or_none(value: &ComponentValue) -> bool {
+    is_image(value) || is_keyword(value, &["none"])
+}
+
+// https://drafts.csswg.org/css-fonts-4/#font-weight-absolute-values
+pub(super) fn is_font_weight(value: &ComponentValue) -> bool {
+    match value {
+        ComponentValue::Token(CssToken::Number { value, .. }) => (1.0..=1000.0).contains(value),
+        value => is_keyword(value, &["bold", "bolder", "lighter"]),
+    }
+}
+
+fn non_negative_number(value: &[ComponentValue]) -> bool {
+    single(value, |w| is_number(w) && is_non_negative(w))
+}
+
+fn line_width(value: &[ComponentValue]) -> bool {
+    single(value, |w| is_line_width(w) && is_non_negative(w))
+}
+
+fn length_percentage_or_auto(value: &[ComponentValue]) -> bool {
+    single(value, |w| {
+        is_length_percentage(w) || is_keyword(w, &["auto"])
+    })
+}
+
+// https://drafts.csswg.org/css-sizing-3/#sizing-values
+//
+// `keyword` is `auto` for the sizes and minimums, `none` for the maximums.
+fn size(value: &[ComponentValue], keyword: &str) -> bool {
+    single(value, |w| {
+        is_non_negative_length_percentage(w)
+            || is_keyword(w, &[keyword, "min-content", "max-content", "fit-content"])
+    })
+}
+
+// https://drafts.csswg.org/css-align-3/#column-row-gap
+fn gap(value: &[ComponentValue]) -> bool {
+    single(value, |w| {
+        is_non_negative_length_percentage(w) || is_keyword(w, &["normal"])
+    })
+}
+
+// https://drafts.csswg.org/css-backgrounds-3/#background-position-longhands
+//
+// A position along one axis: `center`, an offset, or an offset from one of
+// the `edges`.
+fn position(value: &[ComponentValue], edges: &[&str]) -> bool {
+    match words(value)[..] {
+        [word] => {
+            is_length_percentage(word) || is_keyword(word, edges) || is_keyword(word, &["center"])
+        }
+        [edge, offset] => is_keyword(edge, edges) && is_length_percentage(offset),
+        _ => false,
+    }
+}
+
+// https://drafts.csswg.org/css-backgrounds-3/#background-repeat
+fn background_repeat(value: &[ComponentValue]) -> bool {
+    match words(value)[..] {
+        [word] => {
+            is_keyword(word, &["repeat-x", "repeat-y"]) || is_keyword(word, BACKGROUND_REPEATS)
+        }
+        [x, y] => is_keyword(x, BACKGROUND_REPEATS) && is_keyword(y, BACKGROUND_REPEATS),
+        _ => false,
+    }
+}
+
+// https://drafts.csswg.org/css-backgrounds-3/#background-size
+fn background_size(value: &[ComponentValue]) -> bool {
+    keyword(value, &["cover", "contain"])
+        || repeated(value, 2, |w| {
+            is_non_negative_length_percentage(w) || is_keyword(w, &["auto"])
+        })
+}
+
+// https://drafts.csswg.org/css-content-3/#content-property
+fn content(value: &[ComponentValue]) -> bool {
+    if keyword(value, &["normal", "none"]) {
+        return true;
+    }
+    // the content, then its alternative text after a `/`
+    let words = words(value);
+    let slash = words
+        .iter()
+        .position(|w| matches!(w, ComponentValue::Token(CssToken::Delim('/'))));
+    let (content, alternative) = match slash {
+        Some(slash) => (&words[..slash], &words[slash + 1..]),
+        None => (&words[..], &[][..]),
+    };
+    let is_text = |w: &&ComponentValue| match w {
+        ComponentValue::Token(CssToken::String(_)) => true,
+        ComponentValue::Function(name, _) => {
+            matches!(
+                name.to_ascii_lowercase().as_str(),
+                "attr" | "counter" | "counters"
+            )
+        }
+        _ => false,
+    };
+    !content.is_empty()
+        && content.iter().all(|w| {
+            is_text(w)
+                || is_image(w)
+                || is_keyword(
+                    w,
+                    &[
+                        "open-quote",
+                        "close-quote",
+                        "no-open-quote",
+                        "no-close-quote",
+                    ],
+                )
+        })
+        && alternative.iter().all(is_text)
+        && (slash.is_none() || !alternative.is_empty())
+}
+
+// https://drafts.csswg.org/css-lists-3/#propdef-counter-reset
+//
+// `none`, or counter names each followed by an optional integer; only
+// `counter-reset` may reverse a counter.
+fn counters(value: &[ComponentValue], reset: bool) -> bool {
+    if keyword(value, &["none"]) {
+        return true;
+    }
+    let words = words(value);
+    let is_name = |w: &ComponentValue| match w {
+        ComponentValue::Token(CssToken::Ident(name)) => {
+            css_wide_keyword(std::slice::from_ref(w)).is_none()
+                && !name.eq_ignore_ascii_case("none")
+        }
+        ComponentValue::Function(name, arguments) if reset => {
+            name.eq_ignore_ascii_case("reversed")
+                && matches!(
+                    self::words(arguments)[..],
+                    [ComponentValue::Token(CssToken::Ident(_))]
+                )
+        }
+        _ => false,
+    };
+    !words.is_empty()
+        && words
+            .iter()
+            .enumerate()
+            .all(|(i, w)| is_name(w) || (is_integer(w) && i > 0 && is_name(words[i - 1])))
+}
+
+// https://drafts.csswg.org/css-ui-4/#cursor
+//
+// Images to try, then the keyword to fall back to.
+fn cursor(value: &[ComponentValue]) -> bool {
+    let items = split_list(value);
+    let (fallback, images) = match items.split_last() {
+        Some(split) => split,
+        None => return false,
+    };
+    images.iter().all(|image| {
+        matches!(words(image)[..], [w, ..] if is_image(w))
+            && words(image)[1..].iter().all(|w| is_number(w))
+    }) && keyword(
+        fallback,
+        &[
+            "auto",
+            "default",
+            "none",
+            "context-menu",
+            "help",
+            "pointer",
+            "progress",
+            "wait",
+            "cell",
+            "crosshair",
+            "text",
+            "vertical-text",
+            "alias",
+            "copy",
+            "move",
+            "no-drop",
+            "not-allowed",
+            "grab",
+            "grabbing",
+            "e-resize",
+            "n-resize",
+            "ne-resize",
+            "nw-resize",
+            "s-resize",
+            "se-resize",
+            "sw-resize",
+            "w-resize",
+            "ew-resize",
+            "ns-resize",
+            "nesw-resize",
+            "nwse-resize",
+            "col-resize",
+            "row-resize",
+            "all-scroll",
+            "zoom-in",
+            "zoom-out",
+        ],
+    )
+}
+
+// https://drafts.csswg.org/css-fonts-4/#font-family-prop
+//
+// Each family is a string or a sequence of identifiers.
+pub(super) fn font_family(value: &[ComponentValue]) -> bool {
+    list(value, |family| match words(family)[..] {
+        [ComponentValue::Token(CssToken::String(_))] => true,
+        ref words => words
+            .iter()
+            .all(|w| matches!(w, ComponentValue::Token(CssToken::Ident(_)))),
+    })
+}
+
+// https://drafts.csswg.org/css-fonts-4/#font-style-prop
+fn font_style(value: &[ComponentValue]) -> bool {
+    match words(value)[..] {
+        [word] => is_keyword(word, &["normal", "italic", "oblique"]),
+        [oblique, ComponentValue::Token(CssToken::Dimension { value, unit, .. })] => {
+            is_keyword(oblique, &["oblique"])
+                && (-90.0..=90.0).contains(value)
+                && ["deg", "grad", "rad", "turn"]
+                    .iter()
+                    .any(|u| unit.eq_ignore_ascii_case(u))
+        }
+        _ => false,
+    }
+}
+
+// https://drafts.csswg.org/css-grid-2/#track-sizing
+//
+// `none`, or track sizes with the names of the lines between them in
+// brackets.
+fn track_list(value: &[ComponentValue]) -> bool {
+    if keyword(value, &["none"]) {
+        return true;
+    }
+    let words = words(value);
+    let is_track = |w: &ComponentValue| match w {
+        ComponentValue::Token(CssToken::Dimension { value, unit, .. })
+            if unit.eq_ignore_ascii_case("fr") =>
+        {
+            *value >= 0.0
+        }
+        ComponentValue::Function(name, _) => {
+            matches!(
+                name.to_ascii_lowercase().as_str(),
+                "repeat" | "minmax" | "fit-content"
+            ) || is_non_negative_length_percentage(w)
+        }
+        w => {
+            is_non_negative_length_percentage(w)
+                || is_keyword(w, &["auto", "min-content", "max-content"])
+        }
+    };
+    let is_line_names = |w: &ComponentValue| match w {
+        ComponentValue::Block('[', names) => self::words(names)
+            .iter()
+            .all(|n| matches!(n, ComponentValue::Token(CssToken::Ident(_)))),
+        _ => false,
+    };
+    words.iter().any(|w| is_track(w)) && words.iter().all(|w| is_track(w) || is_line_names(w))
+}
+
+pub(super) fn words(value: &[ComponentValue]) -> Vec<&ComponentValue> {
+    value.iter().filter(|v| !v.is_whitespace()).collect()
+}
+
+pub(super) fn trim(values: &[ComponentValue]) -> &[ComponentValue] {
+    let start = values
+        .iter()
+        .position(|v| !v.is_whitespace())
+        .unwrap_or(values.len());
+    let end = values
+        .iter()
+        .rposition(|v| !v.is_whitespace())
+        .map_or(start, |i| i + 1);
+    &values[start..end]
+}
+
+pub(super) fn is_comma(value: &ComponentValue) -> bool {
+    matches!(value, ComponentValue::Token(CssToken::Comma))
+}
+
+// the items of a comma separated list
+pub(super) fn split_list(value: &[ComponentValue]) -> Vec<&[ComponentValue]> {
+    value.split(is_comma).map(trim).collect()
+}
+
+pub(super) fn is_keyword(value: &ComponentValue, keywords: &[&str]) -> bool {
+    match value {
+        ComponentValue::Token(CssToken::Ident(ident)) => {
+            keywords.iter().any(|k| ident.eq_ignore_ascii_case(k))
+        }
+        _ => false,
+    }
+}
+
+pub(super) fn is_number(value: &ComponentValue) -> bool {
+    matches!(value, ComponentValue::Token(CssToken::Number { .. }))
+}
+
+pub(super) fn is_length(value: &ComponentValue) -> bool {
+    match value {
+        ComponentValue::Function(name, arguments) => CalcNode::parse_function(name, arguments)
+            .is_some_and(|c| c.kind() == Some(CalcKind::Length)),
+        value => Length::parse(value).is_some(),
+    }
+}
+
+pub(super) fn is_length_percentage(value: &ComponentValue) -> bool {
+    match value {
+        ComponentValue::Token(CssToken::Percentage(_)) => true,
+        ComponentValue::Function(name, arguments) => CalcNode::parse_function(name, arguments)
+            .is_some_and(|c| c.kind() != Some(CalcKind::Number)),
+        value => Length::parse(value).is_some(),
+    }
+}
+
+pub(super) fn is_color(value: &ComponentValue) -> bool {
+    Color::parse(std::slice::from_ref(value)).is_some()
+}
+
+// https://drafts.csswg.org/css-images-3/#typedef-image
+pub(super) fn is_image(value: &ComponentValue) -> bool {
+    match value {
+        ComponentValue::Token(CssToken::Url(_)) => true,
+        ComponentValue::Function(name, _) => {
+            let name = name.to_ascii_lowercase();
+            name == "url" || name == "image-set" || name.ends_with("gradient")
+        }
+        _ => false,
+    }
+}
+
+// https://drafts.csswg.org/css-backgrounds-3/#typedef-line-width
+pub(super) fn is_line_width(value: &ComponentValue) -> bool {
+    is_length(value) || is_keyword(value, &["thin", "medium", "thick"])
+}
+
+// https://drafts.csswg.org/css-backgrounds-3/#typedef-line-style
+pub(super) fn is_line_style(value: &ComponentValue) -> bool {
+    is_keyword(
+        value,
+        &[
+            "none", "hidden", "dotted", "dashed", "solid", "double", "groove", "ridge", "inset",
+            "outset",
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::css::parse_component_values;
+
+    fn parse(name: &str, css: &str) -> bool {
+        lookup(name).unwrap().parse(&parse_component_values(css))
+    }
+
+    #[test]
+    fn test_initial_values_parse() {
+        for property in PROPERTIES {
+            assert!(
+                property.parse(&parse_component_values(property.initial)),
+                "{}",
+                property.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!(parse("color", "red"));
+        assert!(parse("color", "inherit"));
+        assert!(!parse("color", "12px"));
+        assert!(!parse("color", "red blue"));
+        assert!(parse("margin-top", "-1em"));
+        assert!(parse("margin-top", "calc(10% - 2px)"));
+        assert!(!parse("margin-top", "red"));
+        assert!(!parse("padding-left", "-1px"));
+        assert!(!parse("width", "10"));
+        assert!(parse("display", "inline list-item"));
+        assert!(!parse("display", "bogus"));
+        assert!(!parse("border-top-width", "10%"));
+        assert!(parse("border-top-style", "dashed"));
+        assert!(parse("background-position-x", "right 10px, center"));
+        assert!(!parse("background-position-x", "top"));
+        assert!(parse("background-size", "cover, 10px auto"));
+        assert!(!parse("background-size", "1px 2px 3px"));
+        assert!(parse("content", "\"a\" counter(x) / \"alt\""));
+        assert!(!parse("content", "\"a\" /"));
+        assert!(parse("counter-reset", "reversed(a) 3 b"));
+        assert!(!parse("counter-increment", "reversed(a)"));
+        assert!(!parse("counter-set", "3 a"));
+        assert!(parse(
+            "font-family",
+            "\"Helvetica Neue\", Times New Roman, serif"
+        ));
+        assert!(!parse("font-family", "a, , b"));
+        assert!(parse("font-style", "oblique 10deg"));
+        assert!(!parse("font-weight", "0"));
+        assert!(parse(
+            "grid-template-columns",
+            "[a] 1fr [b] minmax(10px, 1fr)"
+        ));
+        assert!(!parse("grid-template-columns", "[a]"));
+        assert!(parse("text-decoration-line", "underline overline"));
+        assert!(!parse("text-decoration-line", "underline underline"));
+        assert!(!parse("text-transform", "uppercase lowercase"));
+        assert!(parse("z-index", "-2"));
+        assert!(!parse("z-index", "1.5"));
+        assert!(parse("cursor", "url(a.png) 4 4, pointer"));
+        assert!(!parse("cursor", "url(a.png)"));
+    }
+}
@@ -0,0 +1,1360 @@
+use super::cascade::css_wide_keyword;
+use super::properties::{
+    is_color, is_comma, is_font_weight, is_image, is_keyword, is_length_percentage, is_line_style,
+    is_line_width, is_number, split_list, trim, words, BACKGROUND_BOXES, BACKGROUND_REPEATS,
+    FONT_SIZES, FONT_STRETCHES, TEXT_DECORATION_LINES, TEXT_DECORATION_STYLES,
+};
+use super::values::{compute_value, ComputeContext};
+use super::*;
+use crate::parser::css::{parse_component_values, CssToken, Declaration};
+
+type Values = Vec<ComponentValue>;
+
+// https://drafts.csswg.org/css-cascade-4/#shorthand-property
+//
+// A shorthand with the longhands it sets, a parser giving the value of
+// each longhand in order, and a serializer for the reverse.
+pub struct ShorthandDef {
+    pub name: &'static str,
+    pub longhands: &'static [&'static str],
+    expand: fn(&[ComponentValue]) -> Option<Vec<Values>>,
+    serialize: fn(&[&[ComponentValue]]) -> Option<String>,
+}
+
+const fn shorthand(
+    name: &'static str,
+    longhands: &'static [&'static str],
+    expand: fn(&[ComponentValue]) -> Option<Vec<Values>>,
+    serialize: fn(&[&[ComponentValue]]) -> Option<String>,
+) -> ShorthandDef {
+    ShorthandDef {
+        name,
+        longhands,
+        expand,
+        serialize,
+    }
+}
+
+const BORDER_TOP: &[&str] = &["border-top-width", "border-top-style", "border-top-color"];
+const BORDER_RIGHT: &[&str] = &[
+    "border-right-width",
+    "border-right-style",
+    "border-right-color",
+];
+const BORDER_BOTTOM: &[&str] = &[
+    "border-bottom-width",
+    "border-bottom-style",
+    "border-bottom-color",
+];
+const BORDER_LEFT: &[&str] = &[
+    "border-left-width",
+    "border-left-style",
+    "border-left-color",
+];
+
+pub const SHORTHANDS: &[ShorthandDef] = &[
+    shorthand(
+        "background",
+        &[
+            "background-image",
+            "background-position-x",
+            "background-position-y",
+            "background-size",
+            "background-repeat",
+            "background-attachment",
+            "background-origin",
+            "background-clip",
+            "background-color",
+        ],
+        expand_background,
+        serialize_background,
+    ),
+    shorthand(
+        "border",
+        &[
+            "border-top-width",
+            "border-top-style",
+            "border-top-color",
+            "border-right-width",
+            "border-right-style",
+            "border-right-color",
+            "border-bottom-width",
+            "border-bottom-style",
+            "border-bottom-color",
+            "border-left-width",
+            "border-left-style",
+            "border-left-color",
+        ],
+        expand_border,
+        serialize_border,
+    ),
+    shorthand(
+        "border-bottom",
+        BORDER_BOTTOM,
+        expand_border_side,
+        serialize_border_side,
+    ),
+    shorthand(
+        "border-color",
+        &[
+            "border-top-color",
+            "border-right-color",
+            "border-bottom-color",
+            "border-left-color",
+        ],
+        |value| expand_box(value, is_color),
+        serialize_box,
+    ),
+    shorthand(
+        "border-left",
+        BORDER_LEFT,
+        expand_border_side,
+        serialize_border_side,
+    ),
+    shorthand(
+        "border-right",
+        BORDER_RIGHT,
+        expand_border_side,
+        serialize_border_side,
+    ),
+    shorthand(
+        "border-style",
+        &[
+            "border-top-style",
+            "border-right-style",
+            "border-bottom-style",
+            "border-left-style",
+        ],
+        |value| expand_box(value, is_line_style),
+        serialize_box,
+    ),
+    shorthand(
+        "border-top",
+        BORDER_TOP,
+        expand_border_side,
+        serialize_border_side,
+    ),
+    shorthand(
+        "border-width",
+        &[
+            "border-top-width",
+            "border-right-width",
+            "border-bottom-width",
+            "border-left-width",
+        ],
+        |value| expand_box(value, is_line_width),
+        serialize_box,
+    ),
+    shorthand(
+        "flex",
+        &["flex-grow", "flex-shrink", "flex-basis"],
+        expand_flex,
+        |values| Some(join_texts(values)),
+    ),
+    shorthand(
+        "flex-flow",
+        &["flex-direction", "flex-wrap"],
+        |value| {
+            expand_any_order(
+                value,
+                &["flex-direction", "flex-wrap"],
+                &[
+                    |v| is_keyword(v, &["row", "row-reverse", "column", "column-reverse"]),
+                    |v| is_keyword(v, &["nowrap", "wrap", "wrap-reverse"]),
+                ],
+            )
+        },
+        |values| {
+            Some(serialize_any_order(
+                &["flex-direction", "flex-wrap"],
+                values,
+            ))
+        },
+    ),
+    shorthand(
+        "font",
+        &[
+            "font-style",
+            "font-variant-caps",
+            "font-weight",
+            "font-stretch",
+            "font-size",
+            "line-height",
+            "font-family",
+        ],
+        expand_font,
+        serialize_font,
+    ),
+    shorthand(
+        "gap",
+        &["row-gap", "column-gap"],
+        |value| {
+            expand_pair(value, |v| {
+                is_length_percentage(v) || is_keyword(v, &["normal"])
+            })
+        },
+        serialize_pair,
+    ),
+    shorthand(
+        "grid-template",
+        &[
+            "grid-template-rows",
+            "grid-template-columns",
+            "grid-template-areas",
+        ],
+        expand_grid_template,
+        serialize_grid_template,
+    ),
+    shorthand(
+        "inset",
+        &["top", "right", "bottom", "left"],
+        |value| {
+            expand_box(value, |v| {
+                is_length_percentage(v) || is_keyword(v, &["auto"])
+            })
+        },
+        serialize_box,
+    ),
+    shorthand(
+        "list-style",
+        &["list-style-position", "list-style-image", "list-style-type"],
+        expand_list_style,
+        serialize_list_style,
+    ),
+    shorthand(
+        "margin",
+        &["margin-top", "margin-right", "margin-bottom", "margin-left"],
+        |value| {
+            expand_box(value, |v| {
+                is_length_percentage(v) || is_keyword(v, &["auto"])
+            })
+        },
+        serialize_box,
+    ),
+    shorthand(
+        "overflow",
+        &["overflow-x", "overflow-y"],
+        |value| {
+            expand_pair(value, |v| {
+                is_keyword(v, &["visible", "hidden", "clip", "scroll", "auto"])
+            })
+        },
+        serialize_pair,
+    ),
+    shorthand(
+        "padding",
+        &[
+            "padding-top",
+            "padding-right",
+            "padding-bottom",
+            "padding-left",
+        ],
+        |value| expand_box(value, is_length_percentage),
+        serialize_box,
+    ),
+    shorthand(
+        "text-decoration",
+        &[
+            "text-decoration-line",
+            "text-decoration-style",
+            "text-decoration-color",
+        ],
+        expand_text_decoration,
+        |values| {
+            let names = [
+                "text-decoration-line",
+                "text-decoration-style",
+                "text-decoration-color",
+            ];
+            Some(serialize_any_order(&names, values))
+        },
+    ),
+];
+
+pub fn lookup_shorthand(name: &str) -> Option<&'static ShorthandDef> {
+    SHORTHANDS.iter().find(|s| s.name == name)
+}
+
+impl ShorthandDef {
+    // The longhands with their values, or None if `value` doesn't parse or
+    // gives a longhand a value it doesn't take. A CSS-wide keyword goes to
+    // every longhand.
+    pub fn expand(&self, value: &[ComponentValue]) -> Option<Vec<(&'static str, Values)>> {
+        let values = match css_wide_keyword(value) {
+            Some(_) => vec![value.to_vec(); self.longhands.len()],
+            None => (self.expand)(value)?,
+        };
+        let longhands: Vec<(&'static str, Values)> =
+            self.longhands.iter().copied().zip(values).collect();
+        longhands
+            .iter()
+            .all(|(name, value)| lookup(name).is_none_or(|p| p.parse(value)))
+            .then_some(longhands)
+    }
+
+    // https://drafts.csswg.org/cssom/#serialize-a-css-value
+    //
+    // The shorthand value for the values of the longhands, in order, if
+    // they can be written as one.
+    pub fn serialize(&self, values: &[&[ComponentValue]]) -> Option<String> {
+        let keywords: Vec<Option<&str>> = values.iter().map(|v| css_wide_keyword(v)).collect();
+        if let Some(keyword) = keywords[0] {
+            if keywords.iter().all(|k| *k == Some(keyword)) {
+                return Some(keyword.to_string());
+            }
+        }
+        if keywords.iter().any(|k| k.is_some()) || values.iter().any(|v| has_references(v)) {
+            return None;
+        }
+        (self.serialize)(values)
+    }
+}
+
+// Replaces a shorthand declaration with the declarations of its longhands,
+// or drops it when its value doesn't parse, and drops a longhand
+// declaration with a value the property doesn't take. One with `var()` is
+// kept as it is, to be checked once the variables are substituted.
+pub fn expand_declaration(declaration: Declaration) -> Vec<Declaration> {
+    if has_references(&declaration.value) {
+        return vec![declaration];
+    }
+    let shorthand = match lookup_shorthand(&declaration.name) {
+        Some(shorthand) => shorthand,
+        None => match lookup(&declaration.name) {
+            Some(property) if !property.parse(&declaration.value) => return Vec::new(),
+            _ => return vec![declaration],
+        },
+    };
+    shorthand
+        .expand(&declaration.value)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, value)| Declaration {
+            name: name.to_string(),
+            value,
+            important: declaration.important,
+        })
+        .collect()
+}
+
+// https://drafts.csswg.org/cssom/#serialize-a-css-declaration-block
+//
+// The declarations with the longhands of a shorthand replaced by it, where
+// they all have the same importance and can be written as one.
+pub fn collapse_shorthands(declarations: &[Declaration]) -> Vec<Declaration> {
+    // only the declaration that wins for each property is kept
+    let mut done: Vec<bool> = declarations
+        .iter()
+        .enumerate()
+        .map(|(i, d)| {
+            declarations[i + 1..]
+                .iter()
+                .any(|later| later.name == d.name && (later.important || !d.important))
+        })
+        .collect();
+    let mut result = Vec::new();
+
+    for (i, declaration) in declarations.iter().enumerate() {
+        if done[i] {
+            continue;
+        }
+        // the shorthands with the most longhands are preferred
+        let mut shorthands: Vec<&ShorthandDef> = SHORTHANDS
+            .iter()
+            .filter(|s| s.longhands.contains(&declaration.name.as_str()))
+            .collect();
+        shorthands.sort_by_key(|s| std::cmp::Reverse(s.longhands.len()));
+
+        for shorthand in shorthands {
+            let found: Option<Vec<usize>> = shorthand
+                .longhands
+                .iter()
+                .map(|name| {
+                    (0..declarations.len()).rev().find(|&j| {
+                        !done[j]
+                            && declarations[j].name == *name
+                            && declarations[j].important == declaration.important
+                    })
+                })
+                .collect();
+            let found = match found {
+                Some(found) => found,
+                None => continue,
+            };
+            let values: Vec<&[ComponentValue]> = found
+                .iter()
+                .map(|&j| declarations[j].value.as_slice())
+                .collect();
+            if let Some(text) = shorthand.serialize(&values) {
+                found.iter().for_each(|&j| done[j] = true);
+                result.push(Declaration {
+                    name: shorthand.name.to_string(),
+                    value: parse_component_values(&text),
+                    important: declaration.important,
+                });
+                break;
+            }
+        }
+
+        if !done[i] {
+            done[i] = true;
+            result.push(declaration.clone());
+        }
+    }
+    result
+}
+
+// the words as one value, separated by whitespace
+fn join(words: &[&ComponentValue]) -> Values {
+    let mut values = Vec::new();
+    for (i, word) in words.iter().enumerate() {
+        if i > 0 {
+            values.push(ComponentValue::Token(CssToken::Whitespace));
+        }
+        values.push((*word).clone());
+    }
+    values
+}
+
+fn join_texts(values: &[&[ComponentValue]]) -> String {
+    values
+        .iter()
+        .map(|v| serialize_values(v))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn is_slash(value: &ComponentValue) -> bool {
+    matches!(value, ComponentValue::Token(CssToken::Delim('/')))
+}
+
+fn initial_value(name: &str) -> Values {
+    parse_component_values(lookup(name).map_or("", |p| p.initial))
+}
+
+// Whether `value` is the initial value of the longhand, as specified or as
+// computed.
+fn is_initial(name: &str, value: &[ComponentValue]) -> bool {
+    let initial = initial_value(name);
+    let context = ComputeContext::default();
+    serialize_values(value) == serialize_values(&initial)
+        || serialize_values(&compute_value(name, value, &context))
+            == serialize_values(&compute_value(name, &initial, &context))
+}
+
+// https://drafts.csswg.org/css-values-4/#comb-any
+//
+// Components that may each appear once in any order; those left out take
+// their initial value.
+fn expand_any_order(
+    value: &[ComponentValue],
+    longhands: &[&str],
+    valid: &[fn(&ComponentValue) -> bool],
+) -> Option<Vec<Values>> {
+    let mut parts: Vec<Option<Values>> = vec![None; longhands.len()];
+    for word in words(value) {
+        let i = (0..longhands.len()).find(|&i| parts[i].is_none() && valid[i](word))?;
+        parts[i] = Some(vec![word.clone()]);
+    }
+    Some(
+        parts
+            .into_iter()
+            .zip(longhands)
+            .map(|(part, name)| part.unwrap_or_else(|| initial_value(name)))
+            .collect(),
+    )
+}
+
+// the components that aren't initial, or the first one if they all are
+fn serialize_any_order(longhands: &[&str], values: &[&[ComponentValue]]) -> String {
+    let parts: Vec<String> = longhands
+        .iter()
+        .zip(values)
+        .filter(|(name, value)| !is_initial(name, value))
+        .map(|(_, value)| serialize_values(value))
+        .collect();
+    match parts.is_empty() {
+        true => serialize_values(values[0]),
+        false => parts.join(" "),
+    }
+}
+
+// https://drafts.csswg.org/css-box-4/#margin-shorthand
+//
+// One to four values for the top, right, bottom and left sides.
+fn expand_box(value: &[ComponentValue], valid: fn(&ComponentValue) -> bool) -> Option<Vec<Values>> {
+    let words = words(value);
+    if words.is_empty() || words.len() > 4 || !words.iter().all(|w| valid(w)) {
+        return None;
+    }
+    let sides = match words.len() {
+        1 => [0, 0, 0, 0],
+        2 => [0, 1, 0, 1],
+        3 => [0, 1, 2, 1],
+        _ => [0, 1, 2, 3],
+    };
+    Some(sides.iter().map(|&i| vec![words[i].clone()]).collect())
+}
+
+// https://drafts.csswg.org/cssom/#serialize-a-css-value
+fn serialize_box(values: &[&[ComponentValue]]) -> Option<String> {
+    let mut sides: Vec<String> = values.iter().map(|v| serialize_values(v)).collect();
+    if sides[3] == sides[1] {
+        sides.pop();
+        if sides[2] == sides[0] {
+            sides.pop();
+            if sides[1] == sides[0] {
+                sides.pop();
+            }
+        }
+    }
+    Some(sides.join(" "))
+}
+
+// one or two values, the second defaulting to the first
+fn expand_pair(
+    value: &[ComponentValue],
+    valid: fn(&ComponentValue) -> bool,
+) -> Option<Vec<Values>> {
+    match words(value)[..] {
+        [first] if valid(first) => Some(vec![vec![first.clone()], vec![first.clone()]]),
+        [first, second] if valid(first) && valid(second) => {
+            Some(vec![vec![first.clone()], vec![second.clone()]])
+        }
+        _ => None,
+    }
+}
+
+fn serialize_pair(values: &[&[ComponentValue]]) -> Option<String> {
+    match values[0] == values[1] {
+        true => Some(serialize_values(values[0])),
+        false => Some(join_texts(values)),
+    }
+}
+
+// https://drafts.csswg.org/css-backgrounds-3/#border-shorthands
+fn expand_border_side(value: &[ComponentValue]) -> Option<Vec<Values>> {
+    expand_any_order(value, BORDER_TOP, &[is_line_width, is_line_style, is_color])
+}
+
+fn serialize_border_side(values: &[&[ComponentValue]]) -> Option<String> {
+    Some(serialize_any_order(BORDER_TOP, values))
+}
+
+fn expand_border(value: &[ComponentValue]) -> Option<Vec<Values>> {
+    let side = expand_border_side(value)?;
+    Some((0..4).flat_map(|_| side.clone()).collect())
+}
+
+// only when all four sides are the same
+fn serialize_border(values: &[&[ComponentValue]]) -> Option<String> {
+    let sides: Vec<&[&[ComponentValue]]> = values.chunks(3).collect();
+    match sides.iter().all(|side| *side == sides[0]) {
+        true => serialize_border_side(sides[0]),
+        false => None,
+    }
+}
+
+// https://drafts.csswg.org/css-backgrounds-3/#background
+//
+// Each longhand but `background-color` gets a comma separated list with an
+// item per layer; only the final layer may have a color.
+fn expand_background(value: &[ComponentValue]) -> Option<Vec<Values>> {
+    let layers: Vec<&[ComponentValue]> = value.split(is_comma).collect();
+    let mut lists: Vec<Vec<Values>> = vec![Vec::new(); 8];
+    let mut color = None;
+    for (i, layer) in layers.iter().enumerate() {
+        let (values, layer_color) = expand_background_layer(layer, i == layers.len() - 1)?;
+        for (list, value) in lists.iter_mut().zip(values) {
+            list.push(value);
+        }
+        color = layer_color;
+    }
+
+    let mut values: Vec<Values> = lists
+        .into_iter()
+        .map(|items| {
+            let mut list = Vec::new();
+            for (i, item) in items.into_iter().enumerate() {
+                if i > 0 {
+                    list.push(ComponentValue::Token(CssToken::Comma));
+                    list.push(ComponentValue::Token(CssToken::Whitespace));
+                }
+                list.extend(item);
+            }
+            list
+        })
+        .collect();
+    values.push(color.unwrap_or_else(|| initial_value("background-color")));
+    Some(values)
+}
+
+// the values of one layer, without the color, and its color
+fn expand_background_layer(
+    layer: &[ComponentValue],
+    last: bool,
+) -> Option<(Vec<Values>, Option<Values>)> {
+    let words = words(layer);
+    if words.is_empty() {
+        return None;
+    }
+
+    let mut image = None;
+    let mut position = None;
+    let mut size = None;
+    let mut repeat = None;
+    let mut attachment = None;
+    let mut boxes = Vec::new();
+    let mut color = None;
+    let mut i = 0;
+    while i < words.len() {
+        let word = words[i];
+        let start = i;
+        i += 1;
+        if image.is_none() && (is_image(word) || is_keyword(word, &["none"])) {
+            image = Some(vec![word.clone()]);
+        } else if position.is_none() && is_position(word) {
+            while i < words.len() && i - start < 4 && is_position(words[i]) {
+                i += 1;
+            }
+            position = Some(split_position(&words[start..i])?);
+
+            // a size may follow the position after a `/`
+            if words.get(i).is_some_and(|w| is_slash(w)) {
+                i += 1;
+                let start = i;
+                if words
+                    .get(i)
+                    .is_some_and(|w| is_keyword(w, &["cover", "contain"]))
+                {
+                    i += 1;
+                } else {
+                    while i < words.len()
+                        && i - start < 2
+                        && (is_length_percentage(words[i]) || is_keyword(words[i], &["auto"]))
+                    {
+                        i += 1;
+                    }
+                }
+                if i == start {
+                    return None;
+                }
+                size = Some(join(&words[start..i]));
+            }
+        } else if repeat.is_none() && is_keyword(word, &["repeat-x", "repeat-y"]) {
+            repeat = Some(vec![word.clone()]);
+        } else if repeat.is_none() && is_keyword(word, BACKGROUND_REPEATS) {
+            if words
+                .get(i)
+                .is_some_and(|w| is_keyword(w, BACKGROUND_REPEATS))
+            {
+                i += 1;
+            }
+            repeat = Some(join(&words[start..i]));
+        } else if attachment.is_none() && is_keyword(word, &["scroll", "fixed", "local"]) {
+            attachment = Some(vec![word.clone()]);
+        } else if boxes.len() < 2 && is_keyword(word, BACKGROUND_BOXES) {
+            boxes.push(word);
+        } else if last && color.is_none() && is_color(word) {
+            color = Some(vec![word.clone()]);
+        } else {
+            return None;
+        }
+    }
+
+    let (x, y) = position.unwrap_or_else(|| {
+        (
+            initial_value("background-position-x"),
+            initial_value("background-position-y"),
+        )
+    });
+    // a single box sets both the origin and the clip
+    let (origin, clip) = match boxes[..] {
+        [] => (
+            initial_value("background-origin"),
+            initial_value("background-clip"),
+        ),
+        [both] => (vec![both.clone()], vec![both.clone()]),
+        [origin, clip, ..] => (vec![origin.clone()], vec![clip.clone()]),
+    };
+    let values = vec![
+        image.unwrap_or_else(|| initial_value("background-image")),
+        x,
+        y,
+        size.unwrap_or_else(|| initial_value("background-size")),
+        repeat.unwrap_or_else(|| initial_value("background-repeat")),
+        attachment.unwrap_or_else(|| initial_value("background-attachment")),
+        origin,
+        clip,
+    ];
+    Some((values, color))
+}
+
+fn is_position(value: &ComponentValue) -> bool {
+    is_length_percentage(value) || is_keyword(value, &["left", "right", "top", "bottom", "center"])
+}
+
+// https://drafts.csswg.org/css-backgrounds-3/#typedef-bg-position
+//
+// The horizontal and vertical parts of a position.
+fn split_position(words: &[&ComponentValue]) -> Option<(Values, Values)> {
+    let horizontal = |v: &ComponentValue| is_keyword(v, &["left", "right"]);
+    let vertical = |v: &ComponentValue| is_keyword(v, &["top", "bottom"]);
+    let center = || parse_component_values("center");
+
+    match words[..] {
+        [a] if vertical(a) => Some((center(), vec![a.clone()])),
+        [a] => Some((vec![a.clone()], center())),
+        [a, b] if vertical(a) || horizontal(b) => {
+            if vertical(b) || horizontal(a) {
+                return None;
+            }
+            Some((vec![b.clone()], vec![a.clone()]))
+        }
+        [a, b] => Some((vec![a.clone()], vec![b.clone()])),
+        // an edge keyword with an offset for one side or both
+        _ => {
+            let mut groups: Vec<Vec<&ComponentValue>> = Vec::new();
+            for word in words {
+                if is_keyword(word, &["left", "right", "top", "bottom", "center"]) {
+                    groups.push(vec![word]);
+                } else {
+                    match groups.last_mut() {
+                        Some(group) if group.len() == 1 && !is_keyword(group[0], &["center"]) => {
+                            group.push(word)
+                        }
+                        _ => return None,
+                    }
+                }
+            }
+            match &groups[..] {
+                [a, b] if vertical(a[0]) && !vertical(b[0]) => Some((join(b), join(a))),
+                [a, b] if !horizontal(b[0]) && !vertical(a[0]) => Some((join(a), join(b))),
+                _ => None,
+            }
+        }
+    }
+}
+
+// the layers with their initial parts left out
+fn serialize_background(values: &[&[ComponentValue]]) -> Option<String> {
+    let lists: Vec<Vec<&[ComponentValue]>> = values[..8].iter().map(|v| split_list(v)).collect();
+    let count = lists[0].len();
+    if lists.iter().any(|list| list.len() != count) {
+        return None;
+    }
+
+    let layers: Vec<String> = (0..count)
+        .map(|i| {
+            let text = |j: usize| serialize_values(lists[j][i]);
+            let mut parts = Vec::new();
+            if text(0) != "none" {
+                parts.push(text(0));
+            }
+            if text(1) != "0%" || text(2) != "0%" || text(3) != "auto" {
+                parts.push(format!("{} {}", text(1), text(2)));
+                if text(3) != "auto" {
+                    parts.push(format!("/ {}", text(3)));
+                }
+            }
+            if text(4) != "repeat" {
+                parts.push(text(4));
+            }
+            if text(5) != "scroll" {
+                parts.push(text(5));
+            }
+            if text(6) != "padding-box" || text(7) != "border-box" {
+                parts.push(text(6));
+                if text(7) != text(6) {
+                    parts.push(text(7));
+                }
+            }
+            if i == count - 1 && !is_initial("background-color", values[8]) {
+                parts.push(serialize_values(values[8]));
+            }
+            match parts.is_empty() {
+                true => String::from("none"),
+                false => parts.join(" "),
+            }
+        })
+        .collect();
+    Some(layers.join(", "))
+}
+
+// https://drafts.csswg.org/css-fonts-4/#font-prop
+//
+// System fonts aren't supported.
+fn expand_font(value: &[ComponentValue]) -> Option<Vec<Values>> {
+    let words: Vec<(usize, &ComponentValue)> = value
+        .iter()
+        .enumerate()
+        .filter(|(_, v)| !v.is_whitespace())
+        .collect();
+
+    // style, variant, weight and stretch, in any order; `normal` counts
+    // for any of them
+    let mut parts: [Option<Values>; 4] = Default::default();
+    let mut i = 0;
+    while i < words.len() && i < 4 {
+        let word = words[i].1;
+        let part = if is_keyword(word, &["normal"]) {
+            None
+        } else if parts[0].is_none() && is_keyword(word, &["italic", "oblique"]) {
+            Some(0)
+        } else if parts[1].is_none() && is_keyword(word, &["small-caps"]) {
+            Some(1)
+        } else if parts[2].is_none() && is_font_weight(word) {
+            Some(2)
+        } else if parts[3].is_none() && is_keyword(word, FONT_STRETCHES) {
+            Some(3)
+        } else {
+            break;
+        };
+        if let Some(part) = part {
+            parts[part] = Some(vec![word.clone()]);
+        }
+        i += 1;
+    }
+
+    let size = words.get(i)?.1;
+    let is_size_keyword = is_keyword(size, FONT_SIZES);
+    if !is_length_percentage(size) && !is_size_keyword {
+        return None;
+    }
+    i += 1;
+
+    let mut line_height = initial_value("line-height");
+    if words.get(i).is_some_and(|(_, w)| is_slash(w)) {
+        let height = words.get(i + 1)?.1;
+        if !is_number(height) && !is_length_percentage(height) && !is_keyword(height, &["normal"]) {
+            return None;
+        }
+        line_height = vec![height.clone()];
+        i += 2;
+    }
+
+    let family = trim(&value[words.get(i)?.0..]);
+    if split_list(family).iter().any(|f| f.is_empty()) {
+        return None;
+    }
+
+    let [style, variant, weight, stretch] = parts;
+    let normal = || parse_component_values("normal");
+    Some(vec![
+        style.unwrap_or_else(normal),
+        variant.unwrap_or_else(normal),
+        weight.unwrap_or_else(normal),
+        stretch.unwrap_or_else(normal),
+        vec![size.clone()],
+        line_height,
+        family.to_vec(),
+    ])
+}
+
+fn serialize_font(values: &[&[ComponentValue]]) -> Option<String> {
+    let text = |i: usize| serialize_values(values[i]);
+    if !matches!(text(1).as_str(), "normal" | "small-caps") {
+        return None;
+    }
+
+    let mut parts: Vec<String> = (0..4).map(text).filter(|t| t != "normal").collect();
+    match text(5).as_str() {
+        "normal" => parts.push(text(4)),
+        height => parts.push(format!("{}/{}", text(4), height)),
+    }
+    parts.push(text(6));
+    Some(parts.join(" "))
+}
+
+// https://drafts.csswg.org/css-flexbox-1/#flex-property
+fn expand_flex(value: &[ComponentValue]) -> Option<Vec<Values>> {
+    let words = words(value);
+    match words[..] {
+        [word] if is_keyword(word, &["none"]) => {
+            return Some(vec![
+                parse_component_values("0"),
+                parse_component_values("0"),
+                parse_component_values("auto"),
+            ])
+        }
+        [word] if is_keyword(word, &["auto"]) => {
+            return Some(vec![
+                parse_component_values("1"),
+                parse_component_values("1"),
+                parse_component_values("auto"),
+            ])
+        }
+        [] => return None,
+        _ => {}
+    }
+
+    // the shrink factor has to follow the grow factor
+    let mut grow = None;
+    let mut shrink = None;
+    let mut basis = None;
+    for (i, word) in words.iter().enumerate() {
+        if grow.is_none() && is_number(word) {
+            grow = Some((i, *word));
+        } else if shrink.is_none() && is_number(word) && grow.is_some_and(|(g, _)| g + 1 == i) {
+            shrink = Some(*word);
+        } else if basis.is_none()
+            && (is_length_percentage(word) || is_keyword(word, &["auto", "content"]))
+        {
+            basis = Some(*word);
+        } else {
+            return None;
+        }
+    }
+
+    // a basis left out is 0
+    Some(vec![
+        grow.map_or_else(|| parse_component_values("1"), |(_, g)| vec![g.clone()]),
+        shrink.map_or_else(|| parse_component_values("1"), |s| vec![s.clone()]),
+        basis.map_or_else(|| parse_component_values("0%"), |b| vec![b.clone()]),
+    ])
+}
+
+// https://drafts.csswg.org/css-grid-2/#explicit-grid-shorthand
+fn expand_grid_template(value: &[ComponentValue]) -> Option<Vec<Values>> {
+    let words = words(value);
+    if let [word] = words[..] {
+        if is_keyword(word, &["none"]) {
+            return Some(vec![initial_value("grid-template-rows"); 3]);
+        }
+    }
+
+    let slash = words.iter().position(|w| is_slash(w));
+    let (rows, columns) = match slash {
+        Some(i) => (&words[..i], Some(&words[i + 1..])),
+        None => (&words[..], None),
+    };
+    let is_string = |v: &ComponentValue| matches!(v, ComponentValue::Token(CssToken::String(_)));
+
+    // `<rows> / <columns>`
+    if !rows.iter().any(|w| is_string(w)) {
+        let columns = columns?;
+        if rows.is_empty() || columns.is_empty() {
+            return None;
+        }
+        return Some(vec![
+            join(rows),
+            join(columns),
+            initial_value("grid-template-areas"),
+        ]);
+    }
+
+    // rows of `[names]? "areas" <track-size>? [names]?`, whose sizes
+    // default to auto and whose adjacent line names merge
+    let mut areas = Vec::new();
+    let mut tracks: Vec<ComponentValue> = Vec::new();
+    let mut row_has_size = true;
+    for word in rows {
+        match word {
+            ComponentValue::Token(CssToken::String(_)) => {
+                if !row_has_size {
+                    tracks.push(ComponentValue::Token(CssToken::Ident(String::from("auto"))));
+                }
+                areas.push(*word);
+                row_has_size = false;
+            }
+            ComponentValue::Block('[', names) => match tracks.last_mut() {
+                Some(ComponentValue::Block('[', last)) if row_has_size => {
+                    last.push(ComponentValue::Token(CssToken::Whitespace));
+                    last.extend(names.iter().cloned());
+                }
+                _ if !row_has_size => {
+                    tracks.push(ComponentValue::Token(CssToken::Ident(String::from("auto"))));
+                    row_has_size = true;
+                    tracks.push((*word).clone());
+                }
+                _ => tracks.push((*word).clone()),
+            },
+            size if !row_has_size && !areas.is_empty() => {
+                tracks.push((*size).clone());
+                row_has_size = true;
+            }
+            _ => return None,
+        }
+    }
+    if !row_has_size {
+        tracks.push(ComponentValue::Token(CssToken::Ident(String::from("auto"))));
+    }
+    let tracks: Vec<&ComponentValue> = tracks.iter().collect();
+
+    let columns = match columns {
+        Some([]) => return None,
+        Some(columns) => join(columns),
+        None => initial_value("grid-template-columns"),
+    };
+    Some(vec![join(&tracks), columns, join(&areas)])
+}
+
+fn serialize_grid_template(values: &[&[ComponentValue]]) -> Option<String> {
+    let (rows, columns, areas) = (values[0], values[1], values[2]);
+    if serialize_values(areas) == "none" {
+        return match serialize_values(rows) == "none" && serialize_values(columns) == "none" {
+            true => Some(String::from("none")),
+            false => Some(format!(
+                "{} / {}",
+                serialize_values(rows),
+                serialize_values(columns)
+            )),
+        };
+    }
+
+    // each area string with the size of its row
+    let strings = words(areas);
+    let mut parts = Vec::new();
+    let mut count = 0;
+    for word in words(rows) {
+        match word {
+            ComponentValue::Block('[', _) => parts.push(word.to_string()),
+            size => {
+                parts.push(strings.get(count)?.to_string());
+                if !is_keyword(size, &["auto"]) {
+                    parts.push(size.to_string());
+                }
+                count += 1;
+            }
+        }
+    }
+    if count != strings.len() {
+        return None;
+    }
+    if serialize_values(columns) != "none" {
+        parts.push(format!("/ {}", serialize_values(columns)));
+    }
+    Some(parts.join(" "))
+}
+
+// https://drafts.csswg.org/css-lists-3/#list-style-property
+//
+// `none` sets whichever of the image and the type are otherwise left out.
+fn expand_list_style(value: &[ComponentValue]) -> Option<Vec<Values>> {
+    let mut position = None;
+    let mut image = None;
+    let mut kind = None;
+    let mut nones = 0;
+    for word in words(value) {
+        if position.is_none() && is_keyword(word, &["inside", "outside"]) {
+            position = Some(vec![word.clone()]);
+        } else if is_keyword(word, &["none"]) {
+            nones += 1;
+        } else if image.is_none() && is_image(word) {
+            image = Some(vec![word.clone()]);
+        } else if kind.is_none()
+            && matches!(
+                word,
+                ComponentValue::Token(CssToken::Ident(_) | CssToken::String(_))
+            )
+        {
+            kind = Some(vec![word.clone()]);
+        } else {
+            return None;
+        }
+    }
+
+    let none = || parse_component_values("none");
+    match (nones, &image, &kind) {
+        (0, _, _) => {}
+        (1, None, Some(_)) => image = Some(none()),
+        (1, Some(_), None) => kind = Some(none()),
+        (1 | 2, None, None) => {
+            image = Some(none());
+            kind = Some(none());
+        }
+        _ => return None,
+    }
+    Some(vec![
+        position.unwrap_or_else(|| initial_value("list-style-position")),
+        image.unwrap_or_else(|| initial_value("list-style-image")),
+        kind.unwrap_or_else(|| initial_value("list-style-type")),
+    ])
+}
+
+fn serialize_list_style(values: &[&[ComponentValue]]) -> Option<String> {
+    let names = ["list-style-position", "list-style-image", "list-style-type"];
+    // `none` alone is both the image and the type
+    if serialize_values(values[1]) == "none" && serialize_values(values[2]) == "none" {
+        return match is_initial(names[0], values[0]) {
+            true => Some(String::from("none")),
+            false => Some(format!("{} none", serialize_values(values[0]))),
+        };
+    }
+    Some(serialize_any_order(&names, values))
+}
+
+// https://drafts.csswg.org/css-text-decor-3/#text-decoration-property
+fn expand_text_decoration(value: &[ComponentValue]) -> Option<Vec<Values>> {
+    let mut lines: Vec<&ComponentValue> = Vec::new();
+    let mut rest = Vec::new();
+    for word in words(value) {
+        let duplicate = lines
+            .iter()
+            .any(|l| l.to_string().eq_ignore_ascii_case(&word.to_string()));
+        let none = lines.iter().any(|l| is_keyword(l, &["none"]));
+        if (is_keyword(word, TEXT_DECORATION_LINES) && !duplicate && !none)
+            || (is_keyword(word, &["none"]) && lines.is_empty())
+        {
+            lines.push(word);
+        } else {
+            rest.push(word.clone());
+        }
+    }
+
+    let mut values = expand_any_order(
+        &rest,
+        &["text-decoration-style", "text-decoration-color"],
+        &[|v| is_keyword(v, TEXT_DECORATION_STYLES), is_color],
+    )?;
+    let line = match lines.is_empty() {
+        true => initial_value("text-decoration-line"),
+        false => join(&lines),
+    };
+    values.insert(0, line);
+    Some(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::css::{parse_declaration_list, parse_stylesheet};
+
+    // the longhands a shorthand declaration expands to
+    fn expand(css: &str) -> Vec<String> {
+        parse_declaration_list(css)
+            .iter()
+            .map(|d| d.to_string())
+            .collect()
+    }
+
+    // the declarations serialized back, with shorthands where they fit
+    fn round_trip(css: &str) -> String {
+        collapse_shorthands(&parse_declaration_list(css))
+            .iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    #[test]
+    fn test_box_shorthands() {
+        assert_eq!(
+            expand("margin: 1px auto"),
+            vec![
+                "margin-top: 1px",
+                "margin-right: auto",
+                "margin-bottom: 1px",
+                "margin-left: auto",
+            ]
+        );
+        assert_eq!(
+            expand("padding: 1px 2px 3px !important"),
+            vec![
+                "padding-top: 1px !important",
+                "padding-right: 2px !important",
+                "padding-bottom: 3px !important",
+                "padding-left: 2px !important",
+            ]
+        );
+        assert_eq!(expand("inset: 0")[3], "left: 0");
+        assert_eq!(expand("margin: inherit")[2], "margin-bottom: inherit");
+        // invalid values drop the declaration
+        assert!(expand("margin: 1px 2px 3px 4px 5px").is_empty());
+        assert!(expand("padding: auto").is_empty());
+        assert!(expand("border-style: solid 1px").is_empty());
+
+        assert_eq!(round_trip("margin: 1px 2px 1px 2px"), "margin: 1px 2px");
+        assert_eq!(round_trip("margin: 1px 2px 3px"), "margin: 1px 2px 3px");
+        assert_eq!(round_trip("margin: 0 0 0 0"), "margin: 0");
+        assert_eq!(
+            round_trip("gap: 1px 2px; overflow: hidden"),
+            "gap: 1px 2px; overflow: hidden"
+        );
+        // longhands with different importance stay apart
+        assert_eq!(
+            round_trip("margin: 0; margin-left: 1px !important"),
+            "margin-top: 0; margin-right: 0; margin-bottom: 0; margin-left: 1px !important"
+        );
+        assert_eq!(
+            round_trip("margin-top: 0; color: red; margin-right: 0; margin-bottom: 0"),
+            "margin-top: 0; color: red; margin-right: 0; margin-bottom: 0"
+        );
+    }
+
+    #[test]
+    fn test_border() {
+        assert_eq!(
+            expand("border-top: red 2px"),
+            vec![
+                "border-top-width: 2px",
+                "border-top-style: none",
+                "border-top-color: red",
+            ]
+        );
+        assert_eq!(expand("border: 1px solid blue").len(), 12);
+        assert!(expand("border: 1px 2px").is_empty());
+
+        assert_eq!(
+            round_trip("border: 1px solid blue"),
+            "border: 1px solid blue"
+        );
+        assert_eq!(round_trip("border: solid"), "border: solid");
+        assert_eq!(
+            round_trip("border: 1px solid; border-left-color: red"),
+            "border-width: 1px; border-style: solid; \
+             border-color: currentcolor currentcolor currentcolor red"
+        );
+        assert_eq!(round_trip("border-width: 1px 2px"), "border-width: 1px 2px");
+    }
+
+    #[test]
+    fn test_background() {
+        assert_eq!(
+            expand("background: url(a.png) right 10px top / 50% no-repeat fixed content-box red"),
+            vec![
+                "background-image: url(\"a.png\")",
+                "background-position-x: right 10px",
+                "background-position-y: top",
+                "background-size: 50%",
+                "background-repeat: no-repeat",
+                "background-attachment: fixed",
+                "background-origin: content-box",
+                "background-clip: content-box",
+                "background-color: red",
+            ]
+        );
+        assert_eq!(
+            expand("background: top left, center / cover")[1..3],
+            [
+                "background-position-x: left, center",
+                "background-position-y: top, center",
+            ]
+        );
+        // only the final layer may have a color
+        assert!(expand("background: red, url(a.png)").is_empty());
+        assert!(expand("background: left left").is_empty());
+
+        assert_eq!(round_trip("background: red"), "background: red");
+        assert_eq!(round_trip("background: none"), "background: none");
+        assert_eq!(
+            round_trip("background: url(a.png) 0% 0% / auto 10px repeat-x, blue"),
+            "background: url(\"a.png\") 0% 0% / auto 10px repeat-x, blue"
+        );
+        assert_eq!(
+            round_trip("background: padding-box content-box"),
+            "background: padding-box content-box"
+        );
+    }
+
+    #[test]
+    fn test_font() {
+        assert_eq!(
+            expand("font: italic bold 12px/1.5 \"Helvetica Neue\", sans-serif"),
+            vec![
+                "font-style: italic",
+                "font-variant-caps: normal",
+                "font-weight: bold",
+                "font-stretch: normal",
+                "font-size: 12px",
+                "line-height: 1.5",
+                "font-family: \"Helvetica Neue\", sans-serif",
+            ]
+        );
+        assert_eq!(
+            expand("font: normal small-caps 700 condensed large serif")[2],
+            "font-weight: 700"
+        );
+        assert!(expand("font: 12px").is_empty());
+        assert!(expand("font: bold serif").is_empty());
+        assert!(expand("font: 12px serif,").is_empty());
+
+        assert_eq!(
+            round_trip("font: 700 12px/20px serif"),
+            "font: 700 12px/20px serif"
+        );
+        assert_eq!(
+            round_trip("font: medium monospace"),
+            "font: medium monospace"
+        );
+    }
+
+    #[test]
+    fn test_flex_and_grid() {
+        let flex = |css| round_trip(&format!("flex: {}", css));
+        assert_eq!(flex("1"), "flex: 1 1 0%");
+        assert_eq!(flex("2 3"), "flex: 2 3 0%");
+        assert_eq!(flex("10px"), "flex: 1 1 10px");
+        assert_eq!(flex("auto 2"), "flex: 2 1 auto");
+        assert_eq!(flex("none"), "flex: 0 0 auto");
+        assert!(expand("flex: 1 10px 2").is_empty());
+        assert_eq!(
+            round_trip("flex-flow: wrap column"),
+            "flex-flow: column wrap"
+        );
+        assert_eq!(round_trip("flex-flow: row"), "flex-flow: row");
+
+        assert_eq!(
+            expand("grid-template: [top] \"a a\" 40px [mid] \"b c\" [end] / 1fr 2fr"),
+            vec![
+                "grid-template-rows: [top] 40px [mid] auto [end]",
+                "grid-template-columns: 1fr 2fr",
+                "grid-template-areas: \"a a\" \"b c\"",
+            ]
+        );
+        assert_eq!(
+            round_trip("grid-template: \"a\" 1fr \"b\" / auto"),
+            "grid-template: \"a\" 1fr \"b\" / auto"
+        );
+        assert_eq!(
+            round_trip("grid-template: 100px 1fr / 50px"),
+            "grid-template: 100px 1fr / 50px"
+        );
+        assert_eq!(round_trip("grid-template: none"), "grid-template: none");
+        assert!(expand("grid-template: 100px").is_empty());
+    }
+
+    #[test]
+    fn test_list_style_and_text_decoration() {
+        assert_eq!(
+            expand("list-style: none inside"),
+            vec![
+                "list-style-position: inside",
+                "list-style-image: none",
+                "list-style-type: none",
+            ]
+        );
+        assert_eq!(
+            expand("list-style: square none")[1],
+            "list-style-image: none"
+        );
+        assert!(expand("list-style: none none none").is_empty());
+        assert_eq!(round_trip("list-style: none"), "list-style: none");
+        assert_eq!(
+            round_trip("list-style: square inside"),
+            "list-style: inside square"
+        );
+
+        assert_eq!(
+            expand("text-decoration: underline overline wavy red")[0],
+            "text-decoration-line: underline overline"
+        );
+        assert!(expand("text-decoration: underline none").is_empty());
+        assert_eq!(
+            round_trip("text-decoration: red underline"),
+            "text-decoration: underline red"
+        );
+        assert_eq!(round_trip("text-decoration: none"), "text-decoration: none");
+    }
+
+    #[test]
+    fn test_variables_and_keywords() {
+        // shorthands with `var()` are kept until the variables are known
+        assert_eq!(expand("margin: var(--m) 0"), vec!["margin: var(--m) 0"]);
+        assert_eq!(round_trip("border: unset"), "border: unset");
+        assert_eq!(
+            parse_stylesheet("p { margin: 0 auto; color: red }").to_string(),
+            "p { margin: 0 auto; color: red; }\n"
+        );
+    }
+}
@@ -0,0 +1,710 @@
+use super::at_rules::import_rules;
+use super::*;
+use crate::element::HtmlElementKind;
+use crate::loader::{resolve_url, ResourceLoader};
+use crate::node::NodeKind;
+use crate::parser::css::parse_component_values;
+use std::collections::HashMap;
+use std::rc::Weak;
+use std::time::SystemTime;
+
+// https://drafts.csswg.org/cssom/#css-style-sheets
+//
+// A style sheet of the document, from a `<style>` or `<link>` element, or
+// imported by another sheet.
+#[derive(Debug)]
+pub struct CssStyleSheet {
+    owner_node: Weak<RefCell<Node>>,
+    href: Option<String>,
+    // the text of a `<style>` or the URL of a `<link>`, to reuse the parsed
+    // sheet while it stays the same
+    source: String,
+    // the loader's stamp of `href` when it was loaded
+    modified: Option<SystemTime>,
+    // https://html.spec.whatwg.org/multipage/semantics.html#attr-style-media
+    //
+    // the `media` attribute of the owner node, which the whole sheet only
+    // applies under
+    media: Rc<MediaQueryList>,
+    sheet: Rc<StyleSheet>,
+    // the sheet of each `@import` rule, None when it isn't loaded
+    imports: Vec<Option<Rc<CssStyleSheet>>>,
+}
+
+impl CssStyleSheet {
+    pub fn owner_node(&self) -> Option<Rc<RefCell<Node>>> {
+        self.owner_node.upgrade()
+    }
+
+    // the resolved URL of a `<link>` sheet
+    pub fn href(&self) -> Option<&str> {
+        self.href.as_deref()
+    }
+
+    pub fn sheet(&self) -> &Rc<StyleSheet> {
+        &self.sheet
+    }
+
+    // https://drafts.csswg.org/cssom/#dom-stylesheet-media
+    pub fn media(&self) -> &Rc<MediaQueryList> {
+        &self.media
+    }
+
+    // https://drafts.csswg.org/cssom/#cssimportrule
+    pub fn imports(&self) -> &[Option<Rc<CssStyleSheet>>] {
+        &self.imports
+    }
+
+    pub(crate) fn owner_node_weak(&self) -> Weak<RefCell<Node>> {
+        self.owner_node.clone()
+    }
+
+    // whether the loader has a newer version of this sheet or of one it
+    // imports
+    fn is_stale(&self, loader: &dyn ResourceLoader) -> bool {
+        self.href
+            .as_ref()
+            .is_some_and(|href| loader.modified(href) != self.modified)
+            || self.imports.iter().flatten().any(|s| s.is_stale(loader))
+    }
+}
+
+// The style sheet list of a document and the version it was collected at.
+#[derive(Debug, Default)]
+pub(crate) struct StyleSheetList {
+    pub(crate) sheets: Vec<Rc<CssStyleSheet>>,
+    pub(crate) version: Option<u64>,
+    // the URLs of `<link>` sheets that failed to load, with the loader's
+    // stamp at the time, so they are only requested again once it changes
+    pub(crate) failed_loads: HashMap<String, Option<SystemTime>>,
+}
+
+// where a sheet comes from, found while walking the tree
+enum SheetSource {
+    Style(String),
+    Link(String),
+}
+
+impl Node {
+    // https://drafts.csswg.org/cssom/#dom-documentorshadowroot-stylesheets
+    //
+    // The sheets of the `<style>` and `<link rel=stylesheet>` elements in
+    // the document, in tree order. The list is collected again after the
+    // tree changes, reusing the sheets whose text or URL stayed the same
+    // and whose files didn't change since they were loaded.
+    pub fn style_sheets(document: &Rc<RefCell<Node>>) -> Vec<Rc<CssStyleSheet>> {
+        let (version, cached, loader, mut failed) = match document.borrow().kind {
+            NodeKind::Document(ref d) => {
+                if d.style_sheets.version == Some(d.version()) {
+                    return d.style_sheets.sheets.clone();
+                }
+                (
+                    d.version(),
+                    d.style_sheets.sheets.clone(),
+                    d.resource_loader(),
+                    d.style_sheets.failed_loads.clone(),
+                )
+            }
+            _ => return Vec::new(),
+        };
+        let base_url = match document.borrow().kind {
+            NodeKind::Document(ref d) => d.base_url().to_string(),
+            _ => unreachable!(),
+        };
+
+        let mut sheets = Vec::new();
+        for node in Node::descendants(document) {
+            let (source, media) = match sheet_source(&node, &base_url) {
+                Some(source) => source,
+                None => continue,
+            };
+            let (href, text) = match source {
+                SheetSource::Style(ref text) => (None, text),
+                SheetSource::Link(ref url) => (Some(url.clone()), url),
+            };
+
+            let reused = cached.iter().find(|s| {
+                s.owner_node().is_some_and(|o| Rc::ptr_eq(&o, &node))
+                    && s.href == href
+                    && s.source == *text
+                    && !s.is_stale(loader.as_ref())
+            });
+            if let Some(sheet) = reused {
+                // the same sheet under a new `media` attribute
+                let sheet = match *sheet.media == media {
+                    true => sheet.clone(),
+                    false => Rc::new(CssStyleSheet {
+                        owner_node: sheet.owner_node.clone(),
+                        href: sheet.href.clone(),
+                        source: sheet.source.clone(),
+                        modified: sheet.modified,
+                        media: Rc::new(media),
+                        sheet: sheet.sheet.clone(),
+                        imports: sheet.imports.clone(),
+                    }),
+                };
+                sheets.push(sheet);
+                continue;
+            }
+
+            let modified = href.as_ref().and_then(|url| loader.modified(url));
+            let (css, url) = match source {
+                SheetSource::Style(ref text) => (text.clone(), base_url.as_str()),
+                // https://html.spec.whatwg.org/multipage/links.html#link-type-stylesheet
+                //
+                // A sheet that fails to load is left out, and isn't
+                // requested again until the loader says it changed.
+                SheetSource::Link(ref url) if failed.get(url) == Some(&modified) => continue,
+                SheetSource::Link(ref url) => match loader.load(url) {
+                    Ok(css) => {
+                        failed.remove(url);
+                        (css, url.as_str())
+                    }
+                    Err(_) => {
+                        failed.insert(url.clone(), modified);
+                        continue;
+                    }
+                },
+            };
+            let sheet = Rc::new(parse_stylesheet(&css));
+            let mut loading = href.iter().cloned().collect();
+            sheets.push(Rc::new(CssStyleSheet {
+                owner_node: Rc::downgrade(&node),
+                href,
+                source: text.clone(),
+                modified,
+                media: Rc::new(media),
+                imports: load_imports(&sheet, url, loader.as_ref(), &mut loading),
+                sheet,
+            }));
+        }
+
+        if let NodeKind::Document(ref mut d) = document.borrow_mut().kind {
+            *d.style_sheets = StyleSheetList {
+                sheets: sheets.clone(),
+                version: Some(version),
+                failed_loads: failed,
+            };
+        }
+        sheets
+    }
+
+    // Drops the sheets loaded so far, and the record of those that failed,
+    // so the next `update_style` loads them all again, e.g. when the page
+    // is reloaded.
+    pub fn reload_style_sheets(document: &Rc<RefCell<Node>>) {
+        if let NodeKind::Document(ref mut d) = document.borrow_mut().kind {
+            *d.style_sheets = StyleSheetList::default();
+            d.style_version = None;
+        }
+    }
+
+    // whether the tree changed since styles were last resolved by
+    // `update_style`, or the device changed in a way that matters to them
+    pub fn needs_style_update(document: &Rc<RefCell<Node>>) -> bool {
+        match document.borrow().kind {
+            NodeKind::Document(ref d) => {
+                d.style_version != Some(d.version())
+                    || d.stylist
+                        .as_ref()
+                        .is_some_and(|s| s.is_affected_by(&d.device))
+                    || Self::has_dirty_styles(document)
+            }
+            _ => false,
+        }
+    }
+
+    // Resolves the styles of the document from the user agent sheet and the
+    // document's own sheets, if anything changed since the last time. While
+    // the sheets stay the same, only what changes to the tree marked is
+    // restyled.
+    pub fn update_style(document: &Rc<RefCell<Node>>) {
+        if !Self::needs_style_update(document) {
+            return;
+        }
+
+        let sheets = Self::style_sheets(document);
+        let device = Self::device(document);
+        let (previous, invalidated) = match document.borrow().kind {
+            NodeKind::Document(ref d) => (d.stylist.clone(), d.invalidation.invalidated),
+            _ => return,
+        };
+        let mut stats = StyleUpdateStats {
+            invalidated,
+            ..StyleUpdateStats::default()
+        };
+
+        let reusable =
+            previous.filter(|s| s.has_stylesheets(&sheets) && !s.is_affected_by(&device));
+        let stylist = match reusable {
+            Some(stylist) => {
+                // the device may still have changed in ways no rule minds
+                let stylist = match stylist.device() == device {
+                    true => stylist,
+                    false => {
+                        let mut stylist = (*stylist).clone();
+                        stylist.set_device(device);
+                        Rc::new(stylist)
+                    }
+                };
+                Self::restyle(document, &stylist, None, RestyleHint::None, &mut stats);
+                stylist
+            }
+            None => {
+                let mut stylist = Stylist::with_user_agent_stylesheet();
+                stylist.set_device(device);
+                for sheet in sheets.iter() {
+                    stylist.add_css_stylesheet(Origin::Author, sheet);
+                }
+                Self::restyle(document, &stylist, None, RestyleHint::Subtree, &mut stats);
+                stats.full_restyle = true;
+                Rc::new(stylist)
+            }
+        };
+
+        if let NodeKind::Document(ref mut d) = document.borrow_mut().kind {
+            d.style_version = Some(d.version());
+            d.stylist = Some(stylist);
+            d.invalidation.stats = stats;
+            d.invalidation.invalidated = 0;
+        }
+    }
+
+    pub fn device(document: &Rc<RefCell<Node>>) -> Device {
+        match document.borrow().kind {
+            NodeKind::Document(ref d) => *d.device,
+            _ => Device::default(),
+        }
+    }
+
+    // https://drafts.csswg.org/cssom-view/#resizing-viewports
+    //
+    // Styles are resolved again by the next `update_style` only if the
+    // change crosses a media query breakpoint or resizes viewport-relative
+    // lengths.
+    pub fn set_device(document: &Rc<RefCell<Node>>, device: Device) {
+        if let NodeKind::Document(ref mut d) = document.borrow_mut().kind {
+            *d.device = device;
+        }
+    }
+
+    pub fn set_viewport(document: &Rc<RefCell<Node>>, viewport: Viewport) {
+        let device = Device {
+            viewport,
+            ..Self::device(document)
+        };
+        Self::set_device(document, device);
+    }
+}
+
+// https://drafts.csswg.org/css-cascade-5/#fetch-an-import
+//
+// Loads the sheets a sheet imports, and theirs, resolving their URLs
+// against the URL of the sheet. `loading` has the URLs of the sheets
+// importing this one, which aren't loaded again, so an import cycle ends.
+fn load_imports(
+    sheet: &StyleSheet,
+    base_url: &str,
+    loader: &dyn ResourceLoader,
+    loading: &mut Vec<String>,
+) -> Vec<Option<Rc<CssStyleSheet>>> {
+    let mut imports = Vec::new();
+    for import in import_rules(&sheet.rules) {
+        let url = resolve_url(base_url, &import.url);
+        if !import.supported || loading.contains(&url) {
+            imports.push(None);
+            continue;
+        }
+        let modified = loader.modified(&url);
+        let css = match loader.load(&url) {
+            Ok(css) => css,
+            Err(_) => {
+                imports.push(None);
+                continue;
+            }
+        };
+        let imported = Rc::new(parse_stylesheet(&css));
+        loading.push(url.clone());
+        let nested = load_imports(&imported, &url, loader, loading);
+        loading.pop();
+        imports.push(Some(Rc::new(CssStyleSheet {
+            owner_node: Weak::new(),
+            href: Some(url.clone()),
+            source: url,
+            modified,
+            media: Rc::default(),
+            sheet: imported,
+            imports: nested,
+        })));
+    }
+    imports
+}
+
+// where the sheet of `node` comes from, and the media it applies to
+fn sheet_source(node: &Rc<RefCell<Node>>, base_url: &str) -> Option<(SheetSource, MediaQueryList)> {
+    let (source, media) = match node.borrow().kind {
+        NodeKind::Element(ref e) => {
+            // https://html.spec.whatwg.org/multipage/semantics.html#update-a-style-block
+            if !matches!(e.get_attribute("type"), None | Some(""))
+                && !e
+                    .get_attribute("type")
+                    .is_some_and(|t| t.eq_ignore_ascii_case("text/css"))
+            {
+                return None;
+            }
+            let source = match e.kind() {
+                HtmlElementKind::Style => SheetSource::Style(String::new()),
+                HtmlElementKind::Link => {
+                    let is_stylesheet = e.get_attribute("rel").is_some_and(|rel| {
+                        rel.split_ascii_whitespace()
+                            .any(|r| r.eq_ignore_ascii_case("stylesheet"))
+                    });
+                    match e.get_attribute("href") {
+                        Some(href) if is_stylesheet && !href.is_empty() => {
+                            SheetSource::Link(resolve_url(base_url, href))
+                        }
+                        _ => return None,
+                    }
+                }
+                _ => return None,
+            };
+            let media = e.get_attribute("media").unwrap_or_default();
+            (
+                source,
+                MediaQueryList::parse(&parse_component_values(media)),
+            )
+        }
+        _ => return None,
+    };
+
+    let source = match source {
+        // https://html.spec.whatwg.org/multipage/dom.html#child-text-content
+        SheetSource::Style(_) => SheetSource::Style(
+            Node::children(node)
+                .filter_map(|c| match c.borrow().kind {
+                    NodeKind::Text(ref s) => Some(s.clone()),
+                    _ => None,
+                })
+                .collect(),
+        ),
+        link => link,
+    };
+    Some((source, media))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::{LoadError, ResourceLoader};
+    use crate::parser::html_parser::parse;
+
+    // serves the sheets under `file:///site/` from memory and counts the
+    // loads; `edits` replaces a sheet's text, stamped with its revision
+    #[derive(Debug, Default)]
+    struct TestLoader {
+        loads: RefCell<Vec<String>>,
+        edits: RefCell<HashMap<String, (String, u64)>>,
+    }
+
+    impl TestLoader {
+        fn edit(&self, url: &str, css: &str) {
+            let mut edits = self.edits.borrow_mut();
+            let revision = edits.get(url).map_or(1, |(_, r)| r + 1);
+            edits.insert(url.to_string(), (css.to_string(), revision));
+        }
+    }
+
+    impl ResourceLoader for TestLoader {
+        fn load(&self, url: &str) -> Result<String, LoadError> {
+            self.loads.borrow_mut().push(url.to_string());
+            if let Some((css, _)) = self.edits.borrow().get(url) {
+                return Ok(css.clone());
+            }
+            match url {
+                "file:///site/a.css" => Ok(String::from("p { color: green }")),
+                "file:///site/main.css" => Ok(String::from(
+                    "@import 'css/reset.css' layer(reset); @import url(print.css) print; \
+                     @import 'a.css' supports(color: nonsense); @import 'cycle.css'; \
+                     #b { color: blue }",
+                )),
+                "file:///site/css/reset.css" => Ok(String::from(
+                    "@import 'base.css'; p { color: gray } #a { color: red !important }",
+                )),
+                "file:///site/css/base.css" => Ok(String::from("p { color: black }")),
+                "file:///site/print.css" => Ok(String::from("p { color: white }")),
+                "file:///site/cycle.css" => {
+                    Ok(String::from("@import 'main.css'; #c { color: purple }"))
+                }
+                _ => Err(LoadError::Io(String::from("not found"))),
+            }
+        }
+
+        fn modified(&self, url: &str) -> Option<SystemTime> {
+            let revision = self.edits.borrow().get(url)?.1;
+            Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(revision))
+        }
+    }
+
+    fn color(document: &Rc<RefCell<Node>>, id: &str) -> String {
+        let element = Node::get_element_by_id(document, id).unwrap();
+        Node::computed_style(&element)
+            .unwrap()
+            .get_text("color")
+            .unwrap()
+    }
+
+    #[test]
+    fn test_style_elements_and_attributes() {
+        let document = parse(
+            "<head><style>p { color: red } #b { color: blue }</style>\
+             <style type=text/plain>p { color: gray }</style></head>\
+             <body><p id=a>x</p><p id=b style='color: orange'>y</p>\
+             <p id=c style='color: orange'>z</p></body>",
+        );
+        // inline styles beat id selectors, but not important declarations
+        let style = Node::get_elements_by_tag_name(&document, "style")
+            .item(0)
+            .unwrap();
+        let text = style.borrow().first_child().unwrap();
+        Node::set_data(&text, "p { color: red } #c { color: blue !important }");
+
+        assert_eq!(Node::style_sheets(&document).len(), 1);
+        assert!(Node::needs_style_update(&document));
+        Node::update_style(&document);
+        assert!(!Node::needs_style_update(&document));
+        assert_eq!(color(&document, "a"), "rgb(255, 0, 0)");
+        assert_eq!(color(&document, "b"), "rgb(255, 165, 0)");
+        assert_eq!(color(&document, "c"), "rgb(0, 0, 255)");
+
+        // changing the style attribute invalidates style
+        let a = Node::get_element_by_id(&document, "a").unwrap();
+        Node::set_attribute(&a, "style", "color: purple");
+        assert!(Node::needs_style_update(&document));
+        Node::update_style(&document);
+        assert_eq!(color(&document, "a"), "rgb(128, 0, 128)");
+
+        // and so does removing a sheet
+        Node::detach(&style);
+        Node::update_style(&document);
+        assert!(Node::style_sheets(&document).is_empty());
+        assert_eq!(color(&document, "c"), "rgb(255, 165, 0)");
+    }
+
+    #[test]
+    fn test_link_elements() {
+        let document = parse(
+            "<head><link rel=stylesheet href=a.css>\
+             <link rel=stylesheet href=missing.css><link rel=icon href=a.css>\
+             </head><body><p id=a>x</p></body>",
+        );
+        let loader = Rc::new(TestLoader::default());
+        if let NodeKind::Document(ref mut d) = document.borrow_mut().kind {
+            d.set_url("file:///site/index.html");
+            d.set_resource_loader(loader.clone());
+        }
+
+        Node::update_style(&document);
+        assert_eq!(color(&document, "a"), "rgb(0, 128, 0)");
+        let sheets = Node::style_sheets(&document);
+        assert_eq!(sheets.len(), 1);
+        assert_eq!(sheets[0].href(), Some("file:///site/a.css"));
+
+        // sheets are loaded once while their link stays the same, and so
+        // are the ones that fail
+        let p = Node::get_element_by_id(&document, "a").unwrap();
+        Node::set_attribute(&p, "class", "x");
+        Node::update_style(&document);
+        assert_eq!(
+            *loader.loads.borrow(),
+            vec!["file:///site/a.css", "file:///site/missing.css"]
+        );
+
+        // a new loader is asked again
+        let loader = Rc::new(TestLoader::default());
+        if let NodeKind::Document(ref mut d) = document.borrow_mut().kind {
+            d.set_resource_loader(loader.clone());
+        }
+        Node::update_style(&document);
+        assert_eq!(*loader.loads.borrow(), vec!["file:///site/missing.css"]);
+    }
+
+    #[test]
+    fn test_reloading_sheets() {
+        let document = parse(
+            "<head><link rel=stylesheet href=a.css><link rel=stylesheet href=missing.css>\
+             <link rel=stylesheet href=main.css></head><body><p id=a>x</p><p id=b>y</p></body>",
+        );
+        let loader = Rc::new(TestLoader::default());
+        if let NodeKind::Document(ref mut d) = document.borrow_mut().kind {
+            d.set_url("file:///site/index.html");
+            d.set_resource_loader(loader.clone());
+        }
+        Node::update_style(&document);
+        assert_eq!(Node::style_sheets(&document).len(), 2);
+        assert_eq!(color(&document, "b"), "rgb(0, 0, 255)");
+
+        // sheets the loader says changed are loaded again when the list is
+        // next collected, even those that failed or are only imported
+        loader.edit("file:///site/missing.css", "#a { color: orange }");
+        loader.edit("file:///site/css/base.css", "#b { color: teal !important }");
+        let loads = loader.loads.borrow().len();
+        let p = Node::get_element_by_id(&document, "a").unwrap();
+        Node::set_attribute(&p, "class", "x");
+        Node::update_style(&document);
+        assert_eq!(Node::style_sheets(&document).len(), 3);
+        assert_eq!(color(&document, "a"), "rgb(255, 0, 0)");
+        assert_eq!(color(&document, "b"), "rgb(0, 128, 128)");
+        assert!(!loader.loads.borrow()[loads..].contains(&String::from("file:///site/a.css")));
+
+        // and reloading loads them all
+        let loads = loader.loads.borrow().len();
+        Node::reload_style_sheets(&document);
+        Node::update_style(&document);
+        assert!(loader.loads.borrow()[loads..].contains(&String::from("file:///site/a.css")));
+    }
+
+    #[test]
+    fn test_media_breakpoints() {
+        let document = parse(
+            "<style>p { color: red } \
+             @media (min-width: 600px) { p { color: green } } \
+             @media (prefers-color-scheme: dark) { @supports (display: grid) { p { color: white } } }\
+             </style><p id=a>x</p>",
+        );
+        Node::update_style(&document);
+        assert_eq!(color(&document, "a"), "rgb(0, 128, 0)");
+
+        // resizing within a range keeps the styles
+        Node::set_viewport(
+            &document,
+            Viewport {
+                width: 700.0,
+                height: 500.0,
+            },
+        );
+        assert!(!Node::needs_style_update(&document));
+
+        // crossing a breakpoint resolves them again
+        Node::set_viewport(
+            &document,
+            Viewport {
+                width: 500.0,
+                height: 500.0,
+            },
+        );
+        assert!(Node::needs_style_update(&document));
+        Node::update_style(&document);
+        assert_eq!(color(&document, "a"), "rgb(255, 0, 0)");
+
+        let device = Device {
+            color_scheme: ColorScheme::Dark,
+            ..Node::device(&document)
+        };
+        Node::set_device(&document, device);
+        Node::update_style(&document);
+        assert_eq!(color(&document, "a"), "rgb(255, 255, 255)");
+    }
+
+    #[test]
+    fn test_media_attribute() {
+        let document = parse(
+            "<link rel=stylesheet href=a.css media='(max-width: 600px)'>\
+             <style media='screen and (min-width: 600px)'>p { color: blue }</style>\
+             <style media=print>p { color: white }</style><p id=a>x</p>",
+        );
+        if let NodeKind::Document(ref mut d) = document.borrow_mut().kind {
+            d.set_url("file:///site/index.html");
+            d.set_resource_loader(Rc::new(TestLoader::default()));
+        }
+        Node::update_style(&document);
+        assert_eq!(color(&document, "a"), "rgb(0, 0, 255)");
+
+        // resizing across the breakpoint switches the sheets
+        Node::set_viewport(
+            &document,
+            Viewport {
+                width: 500.0,
+                height: 500.0,
+            },
+        );
+        assert!(Node::needs_style_update(&document));
+        Node::update_style(&document);
+        assert_eq!(color(&document, "a"), "rgb(0, 128, 0)");
+
+        // and so does changing the attribute
+        let print = Node::get_elements_by_tag_name(&document, "style")
+            .item(1)
+            .unwrap();
+        Node::set_attribute(&print, "media", "all");
+        Node::update_style(&document);
+        assert_eq!(color(&document, "a"), "rgb(255, 255, 255)");
+        assert_eq!(
+            **Node::style_sheets(&document)[2].media(),
+            MediaQueryList::parse(&parse_component_values("all"))
+        );
+    }
+
+    #[test]
+    fn test_viewport_units() {
+        let document = parse("<style>p { width: 10vw }</style><p id=a>x</p>");
+        Node::update_style(&document);
+        let width = || {
+            let element = Node::get_element_by_id(&document, "a").unwrap();
+            Node::computed_style(&element)
+                .unwrap()
+                .get_text("width")
+                .unwrap()
+        };
+        assert_eq!(width(), "80px");
+
+        Node::set_viewport(
+            &document,
+            Viewport {
+                width: 1000.0,
+                height: 600.0,
+            },
+        );
+        assert!(Node::needs_style_update(&document));
+        Node::update_style(&document);
+        assert_eq!(width(), "100px");
+    }
+
+    #[test]
+    fn test_imports() {
+        let document = parse(
+            "<link rel=stylesheet href=main.css>\
+             <style>@import 'print.css' screen; #a { color: orange }</style>\
+             <p id=a>x</p><p id=b>y</p><p id=c>z</p><p id=d>w</p>",
+        );
+        let loader = Rc::new(TestLoader::default());
+        if let NodeKind::Document(ref mut d) = document.borrow_mut().kind {
+            d.set_url("file:///site/index.html");
+            d.set_resource_loader(loader.clone());
+        }
+
+        Node::update_style(&document);
+        // the imported layer loses to unlayered rules but for its
+        // important declarations
+        assert_eq!(color(&document, "a"), "rgb(255, 0, 0)");
+        assert_eq!(color(&document, "b"), "rgb(0, 0, 255)");
+        assert_eq!(color(&document, "c"), "rgb(128, 0, 128)");
+        assert_eq!(color(&document, "d"), "rgb(255, 255, 255)");
+
+        let sheets = Node::style_sheets(&document);
+        let imports = sheets[0].imports();
+        assert_eq!(imports.len(), 4);
+        let reset = imports[0].as_ref().unwrap();
+        assert_eq!(reset.href(), Some("file:///site/css/reset.css"));
+        assert_eq!(
+            reset.imports()[0].as_ref().unwrap().href(),
+            Some("file:///site/css/base.css")
+        );
+        // unsupported imports aren't loaded, and cycles end
+        assert!(imports[2].is_none());
+        assert!(imports[3].as_ref().unwrap().imports()[0].is_none());
+        assert!(!loader
+            .loads
+            .borrow()
+            .contains(&String::from("file:///site/a.css")));
+    }
+}
@@ -0,0 +1,386 @@
+use super::at_rules::{parse_layer_names, scope_selector, ImportRule, Scope};
+use super::invalidation::InvalidationMap;
+use super::*;
+use crate::parser::css::{consume_rule_list, CssToken, StyleRule};
+use crate::selector::{PseudoElement, SelectorList};
+use std::collections::BTreeSet;
+use std::rc::Weak;
+
+// The style sheets taking part in the cascade, each with its origin, and
+// the device media queries and viewport-relative lengths refer to.
+#[derive(Clone, Debug, Default)]
+pub struct Stylist {
+    sheets: Vec<(Origin, Rc<StyleSheet>)>,
+    // the `media` of each sheet, empty for those not from an element
+    sheet_media: Vec<Rc<MediaQueryList>>,
+    // the style rules of the sheets in order, with the conditional rules,
+    // layers and imports around them unwrapped
+    rules: Vec<StylistRule>,
+    // https://drafts.csswg.org/css-cascade-5/#layer-ordering
+    //
+    // Every layer of each origin in the order it first appeared, with the
+    // unlayered rules as the layer named "", and where each goes in the
+    // cascade.
+    layers: Vec<(Origin, String)>,
+    layer_order: Vec<usize>,
+    anonymous_layers: usize,
+    // whether any declaration uses `vw`, `vh`, `vmin` or `vmax`
+    uses_viewport_units: bool,
+    device: Device,
+    // what the selectors depend on, to tell what a change to the tree
+    // restyles
+    invalidation_map: InvalidationMap,
+    // the pseudo-elements some selector ends with, which are the only ones
+    // given a style
+    pseudo_elements: BTreeSet<PseudoElement>,
+}
+
+// https://drafts.csswg.org/css-conditional-3/#processing
+//
+// A style rule with the `@media` lists it's nested in, which all have to
+// match for it to apply, its layer and its scope. `@supports` rules don't
+// change with the device, so those whose condition fails are left out
+// instead.
+#[derive(Clone, Debug)]
+pub(crate) struct StylistRule {
+    pub(crate) origin: Origin,
+    pub(crate) rule: StyleRule,
+    pub(crate) media: Vec<Rc<MediaQueryList>>,
+    pub(crate) layer: usize,
+    pub(crate) scope: Option<Rc<Scope>>,
+}
+
+// what the rules of a sheet or block are nested in
+#[derive(Clone)]
+struct Nesting {
+    origin: Origin,
+    media: Vec<Rc<MediaQueryList>>,
+    layer: String,
+    scope: Option<Rc<Scope>>,
+    // the `<style>` or `<link>` the sheet comes from
+    owner_node: Weak<RefCell<Node>>,
+}
+
+impl Stylist {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // a stylist starting with the built-in user agent style sheet
+    pub fn with_user_agent_stylesheet() -> Self {
+        let mut stylist = Self::new();
+        stylist.add_stylesheet(Origin::UserAgent, user_agent_stylesheet());
+        stylist
+    }
+
+    // Sheets added later come later in source order. Their `@import` rules
+    // are left out, as there is nothing to load them with.
+    pub fn add_stylesheet(&mut self, origin: Origin, sheet: impl Into<Rc<StyleSheet>>) {
+        let sheet = sheet.into();
+        self.add_rules(&sheet.rules, &[], &Nesting::new(origin, Weak::new()));
+        self.sheets.push((origin, sheet));
+        self.sheet_media.push(Rc::default());
+        self.order_layers();
+    }
+
+    // a sheet of the document, with the sheets it imports
+    pub fn add_css_stylesheet(&mut self, origin: Origin, sheet: &CssStyleSheet) {
+        let mut nesting = Nesting::new(origin, sheet.owner_node_weak());
+        nesting.media.push(sheet.media().clone());
+        self.add_rules(&sheet.sheet().rules, sheet.imports(), &nesting);
+        self.sheets.push((origin, sheet.sheet().clone()));
+        self.sheet_media.push(sheet.media().clone());
+        self.order_layers();
+    }
+
+    // `imports` has the loaded sheet of each `@import` that takes effect
+    fn add_rules(
+        &mut self,
+        rules: &[CssRule],
+        imports: &[Option<Rc<CssStyleSheet>>],
+        nesting: &Nesting,
+    ) {
+        // the root layer holds the unlayered rules
+        self.declare_layer(nesting.origin, &nesting.layer);
+        let mut imports = imports.iter();
+        let mut imports_allowed = true;
+
+        for rule in rules.iter() {
+            let at_rule = match rule {
+                CssRule::Style(rule) => {
+                    imports_allowed = false;
+                    self.add_style_rule(rule, nesting);
+                    continue;
+                }
+                CssRule::At(rule) => rule,
+            };
+            let is_statement = matches!(at_rule.name.as_str(), "charset" | "import")
+                || at_rule.name == "layer" && at_rule.block.is_none();
+            imports_allowed &= is_statement;
+
+            match (at_rule.name.as_str(), &at_rule.block) {
+                ("import", _) if imports_allowed => {
+                    let import = match ImportRule::parse(&at_rule.prelude) {
+                        Some(import) => import,
+                        None => continue,
+                    };
+                    let sheet = match imports.next() {
+                        Some(Some(sheet)) if import.supported => sheet,
+                        _ => continue,
+                    };
+                    let mut nesting = nesting.clone();
+                    nesting.media.push(Rc::new(import.media));
+                    if let Some(name) = import.layer {
+                        nesting.layer = self.nested_layer(nesting.origin, &nesting.layer, name);
+                    }
+                    self.add_rules(&sheet.sheet().rules, sheet.imports(), &nesting);
+                }
+                // https://drafts.csswg.org/css-cascade-5/#layer-empty
+                ("layer", None) => {
+                    for name in parse_layer_names(&at_rule.prelude).unwrap_or_default() {
+                        self.nested_layer(nesting.origin, &nesting.layer, Some(name));
+                    }
+                }
+                // https://drafts.csswg.org/css-cascade-5/#layer-block
+                ("layer", Some(block)) => {
+                    let name = match parse_layer_names(&at_rule.prelude).as_deref() {
+                        Some([name]) => Some(name.clone()),
+                        _ if at_rule.prelude.is_empty() => None,
+                        _ => continue,
+                    };
+                    let mut nesting = nesting.clone();
+                    nesting.layer = self.nested_layer(nesting.origin, &nesting.layer, name);
+                    self.add_rules(&consume_rule_list(block), &[], &nesting);
+                }
+                // https://drafts.csswg.org/css-conditional-3/#at-media
+                ("media", Some(block)) => {
+                    let mut nesting = nesting.clone();
+                    nesting
+                        .media
+                        .push(Rc::new(MediaQueryList::parse(&at_rule.prelude)));
+                    self.add_rules(&consume_rule_list(block), &[], &nesting);
+                }
+                ("supports", Some(block)) if supports_condition(&at_rule.prelude) => {
+                    self.add_rules(&consume_rule_list(block), &[], nesting);
+                }
+                // https://drafts.csswg.org/css-cascade-6/#scope-atrule
+                ("scope", Some(block)) => {
+                    let scope = match Scope::parse(&at_rule.prelude, nesting.owner_node.clone()) {
+                        Some(scope) => scope,
+                        None => continue,
+                    };
+                    for list in scope.selectors() {
+                        self.invalidation_map.add_scope_selector_list(list);
+                    }
+                    let mut nesting = nesting.clone();
+                    nesting.scope = Some(Rc::new(scope));
+                    self.add_rules(&consume_rule_list(block), &[], &nesting);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn add_style_rule(&mut self, rule: &StyleRule, nesting: &Nesting) {
+        self.uses_viewport_units |= rule
+            .declarations
+            .iter()
+            .any(|d| uses_viewport_units(&d.value));
+        self.invalidation_map.add_selector_list(&rule.selectors);
+        self.pseudo_elements
+            .extend(rule.selectors.0.iter().filter_map(|s| s.pseudo_element()));
+        let mut rule = rule.clone();
+        if nesting.scope.is_some() {
+            rule.selectors = SelectorList(rule.selectors.0.iter().map(scope_selector).collect());
+        }
+        let layer = self.declare_layer(nesting.origin, &nesting.layer);
+        self.rules.push(StylistRule {
+            origin: nesting.origin,
+            rule,
+            media: nesting.media.clone(),
+            layer,
+            scope: nesting.scope.clone(),
+        });
+    }
+
+    // the full name of a layer inside `parent`, declared along with the
+    // layers it's nested in; None for a new anonymous layer
+    fn nested_layer(&mut self, origin: Origin, parent: &str, name: Option<String>) -> String {
+        let name = name.unwrap_or_else(|| {
+            // a name no `@layer` rule can refer to
+            self.anonymous_layers += 1;
+            format!("<anonymous {}>", self.anonymous_layers)
+        });
+        let full_name = match parent.is_empty() {
+            true => name,
+            false => format!("{}.{}", parent, name),
+        };
+        self.declare_layer(origin, &full_name);
+        full_name
+    }
+
+    fn declare_layer(&mut self, origin: Origin, name: &str) -> usize {
+        let mut prefix = String::new();
+        for part in name.split('.').filter(|p| !p.is_empty()) {
+            if !prefix.is_empty() {
+                prefix.push('.');
+            }
+            prefix.push_str(part);
+            self.layer_index(origin, &prefix);
+        }
+        self.layer_index(origin, name)
+    }
+
+    fn layer_index(&mut self, origin: Origin, name: &str) -> usize {
+        match self
+            .layers
+            .iter()
+            .position(|(o, n)| *o == origin && n == name)
+        {
+            Some(i) => i,
+            None => {
+                self.layers.push((origin, name.to_string()));
+                self.layers.len() - 1
+            }
+        }
+    }
+
+    // https://drafts.csswg.org/css-cascade-5/#layer-ordering
+    //
+    // Sibling layers go in the order they first appeared, each after the
+    // layers nested in it, and the unlayered rules last.
+    fn order_layers(&mut self) {
+        let keys: Vec<Vec<usize>> = self
+            .layers
+            .iter()
+            .map(|(origin, name)| {
+                let mut key = Vec::new();
+                let mut prefix = String::new();
+                for part in name.split('.').filter(|p| !p.is_empty()) {
+                    if !prefix.is_empty() {
+                        prefix.push('.');
+                    }
+                    prefix.push_str(part);
+                    let index = self
+                        .layers
+                        .iter()
+                        .position(|(o, n)| o == origin && *n == prefix);
+                    key.push(index.unwrap_or(usize::MAX));
+                }
+                key.push(usize::MAX);
+                key
+            })
+            .collect();
+        let mut sorted: Vec<usize> = (0..self.layers.len()).collect();
+        sorted.sort_by_key(|&i| &keys[i]);
+        self.layer_order = vec![0; self.layers.len()];
+        for (order, i) in sorted.into_iter().enumerate() {
+            self.layer_order[i] = order;
+        }
+    }
+
+    // https://drafts.csswg.org/css-cascade-5/#cascade-layering
+    //
+    // Where a layer goes among the others, higher winning. Important
+    // declarations reverse the order of the layers.
+    pub(crate) fn layer_priority(&self, layer: usize, important: bool) -> usize {
+        match important {
+            true => self.layers.len() - self.layer_order[layer],
+            false => self.layer_order[layer],
+        }
+    }
+
+    pub fn stylesheets(&self) -> &[(Origin, Rc<StyleSheet>)] {
+        &self.sheets
+    }
+
+    // whether the author sheets are `sheets`, unchanged and under the same
+    // media
+    pub(crate) fn has_stylesheets(&self, sheets: &[Rc<CssStyleSheet>]) -> bool {
+        let author: Vec<(&Rc<StyleSheet>, &Rc<MediaQueryList>)> = self
+            .sheets
+            .iter()
+            .zip(self.sheet_media.iter())
+            .filter(|((origin, _), _)| *origin == Origin::Author)
+            .map(|((_, sheet), media)| (sheet, media))
+            .collect();
+        author.len() == sheets.len()
+            && author
+                .iter()
+                .zip(sheets.iter())
+                .all(|((a, media), b)| Rc::ptr_eq(a, b.sheet()) && Rc::ptr_eq(media, b.media()))
+    }
+
+    pub(crate) fn invalidation_map(&self) -> &InvalidationMap {
+        &self.invalidation_map
+    }
+
+    pub fn has_pseudo_element_rules(&self, pseudo_element: PseudoElement) -> bool {
+        self.pseudo_elements.contains(&pseudo_element)
+    }
+
+    // the style rules that apply on the current device
+    pub(crate) fn rules(&self) -> impl Iterator<Item = &StylistRule> {
+        self.rules
+            .iter()
+            .filter(|r| r.media.iter().all(|m| m.matches(&self.device)))
+    }
+
+    pub fn device(&self) -> Device {
+        self.device
+    }
+
+    pub fn set_device(&mut self, device: Device) {
+        self.device = device;
+    }
+
+    pub fn viewport(&self) -> Viewport {
+        self.device.viewport
+    }
+
+    pub fn set_viewport(&mut self, viewport: Viewport) {
+        self.device.viewport = viewport;
+    }
+
+    // Whether styles resolved for the current device could come out
+    // different on `device`: when a media query turns out differently, or
+    // the viewport changes size under viewport-relative lengths.
+    pub fn is_affected_by(&self, device: &Device) -> bool {
+        if *device == self.device {
+            return false;
+        }
+        if self.uses_viewport_units && device.viewport != self.device.viewport {
+            return true;
+        }
+        self.rules
+            .iter()
+            .flat_map(|r| r.media.iter())
+            .any(|m| m.matches(&self.device) != m.matches(device))
+    }
+}
+
+impl Nesting {
+    fn new(origin: Origin, owner_node: Weak<RefCell<Node>>) -> Self {
+        Self {
+            origin,
+            media: Vec::new(),
+            layer: String::new(),
+            scope: None,
+            owner_node,
+        }
+    }
+}
+
+// https://drafts.csswg.org/css-values-4/#viewport-relative-lengths
+fn uses_viewport_units(value: &[ComponentValue]) -> bool {
+    value.iter().any(|v| match v {
+        ComponentValue::Token(CssToken::Dimension { unit, .. }) => matches!(
+            unit.to_ascii_lowercase().as_str(),
+            "vw" | "vh" | "vmin" | "vmax"
+        ),
+        ComponentValue::Function(_, values) | ComponentValue::Block(_, values) => {
+            uses_viewport_units(values)
+        }
+        ComponentValue::Token(_) => false,
+    })
+}
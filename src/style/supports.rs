@@ -0,0 +1,130 @@
+use super::*;
+use crate::parser::css::{consume_declaration_list, CssToken, Declaration};
+use crate::selector::parse_selector_list;
+
+// https://drafts.csswg.org/css-conditional-3/#at-supports
+//
+// Whether the condition of an `@supports` rule holds. One that doesn't
+// parse never does.
+pub fn supports_condition(values: &[ComponentValue]) -> bool {
+    let words: Vec<&ComponentValue> = values.iter().filter(|v| !v.is_whitespace()).collect();
+    evaluate(&words).unwrap_or(false)
+}
+
+// https://drafts.csswg.org/css-conditional-3/#typedef-supports-condition
+fn evaluate(words: &[&ComponentValue]) -> Option<bool> {
+    let keyword = |value: &ComponentValue| match value {
+        ComponentValue::Token(CssToken::Ident(ident)) => Some(ident.to_ascii_lowercase()),
+        _ => None,
+    };
+
+    let first = words.first()?;
+    if keyword(first).as_deref() == Some("not") {
+        return match words[1..] {
+            [condition] => Some(!evaluate_in_parens(condition)?),
+            _ => None,
+        };
+    }
+
+    // `and` and `or` can't be mixed without parens
+    let mut results = vec![evaluate_in_parens(first)?];
+    let mut combinator = None;
+    for pair in words[1..].chunks(2) {
+        let word = keyword(pair[0])?;
+        if !matches!(word.as_str(), "and" | "or") || combinator.is_some_and(|c| c != word) {
+            return None;
+        }
+        results.push(evaluate_in_parens(pair.get(1)?)?);
+        combinator = Some(word);
+    }
+    match combinator.as_deref() {
+        Some("or") => Some(results.contains(&true)),
+        _ => Some(results.iter().all(|r| *r)),
+    }
+}
+
+// https://drafts.csswg.org/css-conditional-4/#typedef-supports-in-parens
+fn evaluate_in_parens(value: &ComponentValue) -> Option<bool> {
+    match value {
+        ComponentValue::Block('(', contents) => {
+            let words: Vec<&ComponentValue> =
+                contents.iter().filter(|v| !v.is_whitespace()).collect();
+            let is_declaration = matches!(
+                words[..],
+                [
+                    ComponentValue::Token(CssToken::Ident(_)),
+                    ComponentValue::Token(CssToken::Colon),
+                    ..
+                ]
+            );
+            match is_declaration {
+                true => {
+                    let declarations = consume_declaration_list(contents);
+                    Some(!declarations.is_empty() && declarations.iter().all(supports_declaration))
+                }
+                // a nested condition, or anything else, which is false
+                false => Some(evaluate(&words).unwrap_or(false)),
+            }
+        }
+        // https://drafts.csswg.org/css-conditional-4/#dfn-support-selector
+        ComponentValue::Function(name, arguments) if name.eq_ignore_ascii_case("selector") => {
+            Some(parse_selector_list(&serialize_values(arguments)).is_ok())
+        }
+        // https://drafts.csswg.org/css-conditional-3/#typedef-general-enclosed
+        ComponentValue::Function(_, _) => Some(false),
+        _ => None,
+    }
+}
+
+// https://drafts.csswg.org/css-conditional-3/#dfn-support
+//
+// Shorthands are checked as they expand, which the declaration list has
+// already done, so only longhands and custom properties are left. Values
+// with `var()` are taken as supported.
+fn supports_declaration(declaration: &Declaration) -> bool {
+    let name = declaration.name.as_str();
+    if is_custom_property(name) || lookup_shorthand(name).is_some() {
+        return true;
+    }
+    match lookup(name) {
+        Some(property) => has_references(&declaration.value) || property.parse(&declaration.value),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::css::parse_component_values;
+
+    fn supports(condition: &str) -> bool {
+        supports_condition(&parse_component_values(condition))
+    }
+
+    #[test]
+    fn test_supports_condition() {
+        assert!(supports("(display: grid)"));
+        assert!(supports("(color: rgb(0 0 0 / 50%))"));
+        assert!(!supports("(color: nonsense)"));
+        assert!(!supports("(display: bogus)"));
+        assert!(!supports("(width: red)"));
+        assert!(supports("(width: calc(100% - 1em))"));
+        assert!(!supports("(padding-left: -1px)"));
+        assert!(!supports("(unknown-property: 1px)"));
+        assert!(supports("(margin: 0 auto)"));
+        assert!(!supports("(margin: 1px 2px 3px 4px 5px)"));
+        assert!(supports("(--anything: { ; })"));
+        assert!(supports("(color: var(--x))"));
+        assert!(supports("not (unknown: 1px)"));
+        assert!(supports("(display: flex) and (not (foo: bar))"));
+        assert!(supports(
+            "(foo: bar) or ((display: block) and (color: red))"
+        ));
+        assert!(supports("selector(div > p:first-child)"));
+        assert!(!supports("selector(div >>> p)"));
+        // mixed combinators and unknown syntax never match
+        assert!(!supports("(color: red) and (color: red) or (color: red)"));
+        assert!(!supports("font-tech(color-COLRv1)"));
+        assert!(!supports("display: block"));
+    }
+}
@@ -0,0 +1,459 @@
+use super::*;
+use std::fmt;
+
+// https://drafts.csswg.org/css-values-4/#calc-syntax
+//
+// The tree of a math function. Subtraction is kept as the sum of a negated
+// value and division as a product with an inverted factor.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CalcNode {
+    Number(f64),
+    Percentage(f64),
+    Length(Length),
+    Sum(Vec<CalcNode>),
+    Product(Vec<CalcNode>),
+    Invert(Box<CalcNode>),
+    Min(Vec<CalcNode>),
+    Max(Vec<CalcNode>),
+    Clamp(Box<CalcNode>, Box<CalcNode>, Box<CalcNode>),
+}
+
+// https://drafts.csswg.org/css-values-4/#calc-type-checking
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CalcKind {
+    Number,
+    Length,
+    Percentage,
+    LengthPercentage,
+}
+
+impl CalcKind {
+    // the type of a sum of the two, if they can be added
+    fn add(self, other: CalcKind) -> Option<CalcKind> {
+        match (self, other) {
+            (a, b) if a == b => Some(a),
+            (CalcKind::Number, _) | (_, CalcKind::Number) => None,
+            _ => Some(CalcKind::LengthPercentage),
+        }
+    }
+}
+
+impl CalcNode {
+    // `calc()`, `min()`, `max()` or `clamp()` with its arguments, if they
+    // are valid and of a consistent type
+    pub fn parse_function(name: &str, arguments: &[ComponentValue]) -> Option<CalcNode> {
+        let node = match name.to_ascii_lowercase().as_str() {
+            "calc" => parse_sum(arguments)?,
+            "min" => CalcNode::Min(parse_arguments(arguments)?),
+            "max" => CalcNode::Max(parse_arguments(arguments)?),
+            "clamp" => {
+                let mut arguments = parse_arguments(arguments)?;
+                if arguments.len() != 3 {
+                    return None;
+                }
+                let max = arguments.pop()?;
+                let value = arguments.pop()?;
+                let min = arguments.pop()?;
+                CalcNode::Clamp(Box::new(min), Box::new(value), Box::new(max))
+            }
+            _ => return None,
+        };
+        node.kind()?;
+        Some(node)
+    }
+
+    // the type the expression resolves to, or None if it mixes types in a
+    // way the spec doesn't allow
+    pub fn kind(&self) -> Option<CalcKind> {
+        match self {
+            CalcNode::Number(_) => Some(CalcKind::Number),
+            CalcNode::Percentage(_) => Some(CalcKind::Percentage),
+            CalcNode::Length(_) => Some(CalcKind::Length),
+            CalcNode::Sum(nodes) | CalcNode::Min(nodes) | CalcNode::Max(nodes) => {
+                let mut kinds = nodes.iter().map(|n| n.kind());
+                let first = kinds.next()??;
+                kinds.try_fold(first, |kind, other| kind.add(other?))
+            }
+            CalcNode::Clamp(min, value, max) => min.kind()?.add(value.kind()?)?.add(max.kind()?),
+            // at most one factor may have a unit
+            CalcNode::Product(factors) => {
+                let mut kind = CalcKind::Number;
+                for factor in factors.iter() {
+                    match factor.kind()? {
+                        CalcKind::Number => {}
+                        other if kind == CalcKind::Number => kind = other,
+                        _ => return None,
+                    }
+                }
+                Some(kind)
+            }
+            // dividing by anything but a number isn't supported
+            CalcNode::Invert(node) => match node.kind()? {
+                CalcKind::Number => Some(CalcKind::Number),
+                _ => None,
+            },
+        }
+    }
+
+    // https://drafts.csswg.org/css-values-4/#calc-simplification
+    //
+    // Turns lengths into px and folds what can be folded. Percentages are
+    // resolved against `basis` when it is known.
+    pub fn simplify(&self, context: &ComputeContext, basis: Option<f64>) -> CalcNode {
+        match self {
+            CalcNode::Number(_) => self.clone(),
+            CalcNode::Percentage(p) => match basis {
+                Some(basis) => CalcNode::Length(Length::Px(p * basis / 100.0)),
+                None => self.clone(),
+            },
+            CalcNode::Length(length) => CalcNode::Length(Length::Px(length.to_px(context))),
+            CalcNode::Sum(nodes) => {
+                let mut number = None;
+                let mut percentage = None;
+                let mut px = None;
+                let mut rest = Vec::new();
+                for node in nodes.iter().map(|n| n.simplify(context, basis)) {
+                    let nodes = match node {
+                        CalcNode::Sum(nodes) => nodes,
+                        node => vec![node],
+                    };
+                    for node in nodes {
+                        match node {
+                            CalcNode::Number(n) => *number.get_or_insert(0.0) += n,
+                            CalcNode::Percentage(p) => *percentage.get_or_insert(0.0) += p,
+                            CalcNode::Length(Length::Px(v)) => *px.get_or_insert(0.0) += v,
+                            node => rest.push(node),
+                        }
+                    }
+                }
+
+                // https://drafts.csswg.org/css-values-4/#sort-a-calculations-children
+                let mut sum: Vec<CalcNode> = number.map(CalcNode::Number).into_iter().collect();
+                sum.extend(percentage.map(CalcNode::Percentage));
+                sum.extend(px.map(|v| CalcNode::Length(Length::Px(v))));
+                sum.extend(rest);
+                if sum.len() == 1 {
+                    sum.pop().unwrap()
+                } else {
+                    CalcNode::Sum(sum)
+                }
+            }
+            CalcNode::Product(factors) => {
+                let mut scale = 1.0;
+                let mut rest = Vec::new();
+                for factor in factors.iter().map(|f| f.simplify(context, basis)) {
+                    match factor {
+                        CalcNode::Number(n) => scale *= n,
+                        CalcNode::Invert(node) => match *node {
+                            CalcNode::Number(n) => scale /= n,
+                            node => rest.push(CalcNode::Invert(Box::new(node))),
+                        },
+                        factor => rest.push(factor),
+                    }
+                }
+                match rest.len() {
+                    0 => CalcNode::Number(scale),
+                    1 => match rest.pop().unwrap() {
+                        CalcNode::Percentage(p) => CalcNode::Percentage(p * scale),
+                        CalcNode::Length(Length::Px(v)) => CalcNode::Length(Length::Px(v * scale)),
+                        // a scale distributes over a sum
+                        CalcNode::Sum(nodes) => CalcNode::Sum(
+                            nodes
+                                .into_iter()
+                                .map(|n| CalcNode::Product(vec![CalcNode::Number(scale), n]))
+                                .collect(),
+                        )
+                        .simplify(context, basis),
+                        node if scale == 1.0 => node,
+                        node => CalcNode::Product(vec![CalcNode::Number(scale), node]),
+                    },
+                    _ => {
+                        rest.insert(0, CalcNode::Number(scale));
+                        CalcNode::Product(rest)
+                    }
+                }
+            }
+            CalcNode::Invert(node) => match node.simplify(context, basis) {
+                CalcNode::Number(n) => CalcNode::Number(1.0 / n),
+                node => CalcNode::Invert(Box::new(node)),
+            },
+            CalcNode::Min(nodes) => fold(nodes, context, basis, f64::min, CalcNode::Min),
+            CalcNode::Max(nodes) => fold(nodes, context, basis, f64::max, CalcNode::Max),
+            CalcNode::Clamp(min, value, max) => {
+                let min = min.simplify(context, basis);
+                let value = value.simplify(context, basis);
+                let max = max.simplify(context, basis);
+                match (leaf(&min), leaf(&value), leaf(&max)) {
+                    // the minimum wins over the maximum
+                    (Some((a, min)), Some((b, value)), Some((c, max))) if a == b && b == c => {
+                        with_value(a, value.min(max).max(min))
+                    }
+                    _ => CalcNode::Clamp(Box::new(min), Box::new(value), Box::new(max)),
+                }
+            }
+        }
+    }
+
+    // https://drafts.csswg.org/css-values-4/#serialize-a-math-function
+    pub fn to_css(&self) -> String {
+        match self {
+            CalcNode::Number(_) | CalcNode::Percentage(_) | CalcNode::Length(_) => self.to_string(),
+            CalcNode::Min(_) | CalcNode::Max(_) | CalcNode::Clamp(..) => self.to_string(),
+            _ => format!("calc({})", self),
+        }
+    }
+}
+
+// https://drafts.csswg.org/css-values-4/#calc-syntax
+fn parse_sum(values: &[ComponentValue]) -> Option<CalcNode> {
+    let mut sum = Vec::new();
+    let mut product = Vec::new();
+    let mut negate = false;
+    let mut invert = false;
+    let mut expect_operand = true;
+
+    for (i, value) in values.iter().enumerate() {
+        match value {
+            ComponentValue::Token(CssToken::Whitespace) => {}
+            // `+` and `-` need whitespace on both sides
+            ComponentValue::Token(CssToken::Delim(c @ ('+' | '-'))) if !expect_operand => {
+                let spaced = |v: Option<&ComponentValue>| v.is_some_and(|v| v.is_whitespace());
+                if !spaced(values.get(i.wrapping_sub(1))) || !spaced(values.get(i + 1)) {
+                    return None;
+                }
+                sum.push(end_product(&mut product, negate));
+                negate = *c == '-';
+                expect_operand = true;
+            }
+            ComponentValue::Token(CssToken::Delim(c @ ('*' | '/'))) if !expect_operand => {
+                invert = *c == '/';
+                expect_operand = true;
+            }
+            value if expect_operand => {
+                let node = parse_value(value)?;
+                product.push(match invert {
+                    true => CalcNode::Invert(Box::new(node)),
+                    false => node,
+                });
+                invert = false;
+                expect_operand = false;
+            }
+            _ => return None,
+        }
+    }
+    if expect_operand {
+        return None;
+    }
+
+    sum.push(end_product(&mut product, negate));
+    Some(match sum.len() {
+        1 => sum.pop().unwrap(),
+        _ => CalcNode::Sum(sum),
+    })
+}
+
+fn end_product(product: &mut Vec<CalcNode>, negate: bool) -> CalcNode {
+    let mut factors = std::mem::take(product);
+    if negate {
+        factors.insert(0, CalcNode::Number(-1.0));
+    }
+    match factors.len() {
+        1 => factors.pop().unwrap(),
+        _ => CalcNode::Product(factors),
+    }
+}
+
+// comma separated sums, as in `min()`
+fn parse_arguments(values: &[ComponentValue]) -> Option<Vec<CalcNode>> {
+    values
+        .split(|v| matches!(v, ComponentValue::Token(CssToken::Comma)))
+        .map(parse_sum)
+        .collect()
+}
+
+fn parse_value(value: &ComponentValue) -> Option<CalcNode> {
+    match value {
+        ComponentValue::Token(CssToken::Number { value, .. }) => Some(CalcNode::Number(*value)),
+        ComponentValue::Token(CssToken::Percentage(p)) => Some(CalcNode::Percentage(*p)),
+        ComponentValue::Token(CssToken::Dimension { value, unit, .. }) => {
+            Length::new(*value, unit).map(CalcNode::Length)
+        }
+        // https://drafts.csswg.org/css-values-4/#calc-constants
+        ComponentValue::Token(CssToken::Ident(name)) => match name.to_ascii_lowercase().as_str() {
+            "pi" => Some(CalcNode::Number(std::f64::consts::PI)),
+            "e" => Some(CalcNode::Number(std::f64::consts::E)),
+            _ => None,
+        },
+        ComponentValue::Block('(', values) => parse_sum(values),
+        ComponentValue::Function(name, arguments) => CalcNode::parse_function(name, arguments),
+        _ => None,
+    }
+}
+
+// the kind and value of a number, a percentage or a px length
+fn leaf(node: &CalcNode) -> Option<(CalcKind, f64)> {
+    match node {
+        CalcNode::Number(n) => Some((CalcKind::Number, *n)),
+        CalcNode::Percentage(p) => Some((CalcKind::Percentage, *p)),
+        CalcNode::Length(Length::Px(v)) => Some((CalcKind::Length, *v)),
+        _ => None,
+    }
+}
+
+fn with_value(kind: CalcKind, value: f64) -> CalcNode {
+    match kind {
+        CalcKind::Number => CalcNode::Number(value),
+        CalcKind::Percentage => CalcNode::Percentage(value),
+        _ => CalcNode::Length(Length::Px(value)),
+    }
+}
+
+// `min()` or `max()`, folded when all the arguments have the same unit
+fn fold(
+    nodes: &[CalcNode],
+    context: &ComputeContext,
+    basis: Option<f64>,
+    f: fn(f64, f64) -> f64,
+    function: fn(Vec<CalcNode>) -> CalcNode,
+) -> CalcNode {
+    let nodes: Vec<CalcNode> = nodes.iter().map(|n| n.simplify(context, basis)).collect();
+    let leaves: Option<Vec<(CalcKind, f64)>> = nodes.iter().map(leaf).collect();
+    match leaves {
+        Some(leaves) if leaves.iter().all(|(kind, _)| *kind == leaves[0].0) => {
+            let value = leaves.iter().map(|(_, v)| *v).reduce(f).unwrap();
+            with_value(leaves[0].0, value)
+        }
+        _ => function(nodes),
+    }
+}
+
+fn write_arguments(f: &mut fmt::Formatter<'_>, name: &str, nodes: &[&CalcNode]) -> fmt::Result {
+    write!(f, "{}(", name)?;
+    for (i, node) in nodes.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", node)?;
+    }
+    write!(f, ")")
+}
+
+// the expression inside `calc()`
+impl fmt::Display for CalcNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CalcNode::Number(n) => write!(f, "{}", round(*n)),
+            CalcNode::Percentage(p) => write!(f, "{}%", round(*p)),
+            CalcNode::Length(length) => write!(f, "{}", length),
+            CalcNode::Sum(nodes) => {
+                for (i, node) in nodes.iter().enumerate() {
+                    let negated = match node {
+                        CalcNode::Number(n) if *n < 0.0 => Some(CalcNode::Number(-n)),
+                        CalcNode::Percentage(p) if *p < 0.0 => Some(CalcNode::Percentage(-p)),
+                        CalcNode::Length(Length::Px(v)) if *v < 0.0 => {
+                            Some(CalcNode::Length(Length::Px(-v)))
+                        }
+                        _ => None,
+                    };
+                    match (i, negated) {
+                        (0, _) => write!(f, "{}", node)?,
+                        (_, Some(node)) => write!(f, " - {}", node)?,
+                        (_, None) => write!(f, " + {}", node)?,
+                    }
+                }
+                Ok(())
+            }
+            CalcNode::Product(factors) => {
+                for (i, factor) in factors.iter().enumerate() {
+                    let (operator, factor) = match factor {
+                        CalcNode::Invert(node) => (" / ", node.as_ref()),
+                        factor => (" * ", factor),
+                    };
+                    if i > 0 {
+                        write!(f, "{}", operator)?;
+                    }
+                    match factor {
+                        CalcNode::Sum(_) => write!(f, "({})", factor)?,
+                        factor => write!(f, "{}", factor)?,
+                    }
+                }
+                Ok(())
+            }
+            CalcNode::Invert(node) => write!(f, "1 / {}", node),
+            CalcNode::Min(nodes) => write_arguments(f, "min", &nodes.iter().collect::<Vec<_>>()),
+            CalcNode::Max(nodes) => write_arguments(f, "max", &nodes.iter().collect::<Vec<_>>()),
+            CalcNode::Clamp(min, value, max) => write_arguments(f, "clamp", &[min, value, max]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::css::parse_component_values;
+
+    fn calc(css: &str) -> Option<CalcNode> {
+        match parse_component_values(css).as_slice() {
+            [ComponentValue::Function(name, arguments)] => {
+                CalcNode::parse_function(name, arguments)
+            }
+            _ => None,
+        }
+    }
+
+    fn computed(css: &str, basis: Option<f64>) -> String {
+        let context = ComputeContext {
+            font_size: 20.0,
+            ..ComputeContext::default()
+        };
+        calc(css).unwrap().simplify(&context, basis).to_css()
+    }
+
+    #[test]
+    fn test_parse_and_type_check() {
+        assert_eq!(
+            calc("calc(1px + 2 * 3px)").unwrap().kind(),
+            Some(CalcKind::Length)
+        );
+        assert_eq!(
+            calc("calc(50% - 1em)").unwrap().kind(),
+            Some(CalcKind::LengthPercentage)
+        );
+        assert_eq!(
+            calc("calc((1 + 2) / 4)").unwrap().kind(),
+            Some(CalcKind::Number)
+        );
+        // `+` and `-` need whitespace around them
+        assert_eq!(calc("calc(1px -2px)"), None);
+        assert_eq!(calc("calc(1px+2px)"), None);
+        // a number can't be added to a length, nor two lengths multiplied
+        assert_eq!(calc("calc(1px + 2)"), None);
+        assert_eq!(calc("calc(1px * 2px)"), None);
+        assert_eq!(calc("calc(1 / 2px)"), None);
+        assert_eq!(calc("calc(1px 2px)"), None);
+        assert_eq!(calc("clamp(1px, 2px)"), None);
+        assert_eq!(calc("calc()"), None);
+    }
+
+    #[test]
+    fn test_simplify_and_serialize() {
+        assert_eq!(computed("calc(1px + 2 * 3px)", None), "7px");
+        assert_eq!(computed("calc(2em - 10px)", None), "30px");
+        assert_eq!(computed("calc(10px + 50% + 1em)", None), "calc(50% + 30px)");
+        assert_eq!(computed("calc(100% - 10px)", None), "calc(100% - 10px)");
+        assert_eq!(computed("calc(100% - 10px)", Some(200.0)), "190px");
+        assert_eq!(computed("calc(2 * (50% - 1px))", None), "calc(100% - 2px)");
+        assert_eq!(computed("calc(10vw / 4)", None), "20px");
+        assert_eq!(computed("min(10px, 2em, 3rem)", None), "10px");
+        assert_eq!(computed("max(10px, 50%)", None), "max(10px, 50%)");
+        assert_eq!(computed("max(10px, 50%)", Some(100.0)), "50px");
+        assert_eq!(computed("clamp(10px, 1px, 20px)", None), "10px");
+        // the minimum wins when it is above the maximum
+        assert_eq!(computed("clamp(30px, 1px, 20px)", None), "30px");
+        assert_eq!(computed("calc(1px * pi)", None), "3.141593px");
+        assert_eq!(
+            computed("calc(min(50%, 10px) * 2)", None),
+            "calc(2 * min(50%, 10px))"
+        );
+    }
+}
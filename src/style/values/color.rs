@@ -0,0 +1,1100 @@
+use super::*;
+use std::fmt;
+
+// https://drafts.csswg.org/css-color-4/#color-type
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Color {
+    // https://drafts.csswg.org/css-color-4/#currentcolor-color
+    CurrentColor,
+    Absolute(AbsoluteColor),
+}
+
+// https://drafts.csswg.org/css-color-4/#interpolation-space
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorSpace {
+    Srgb,
+    SrgbLinear,
+    Hsl,
+    Hwb,
+    Lab,
+    Lch,
+    Oklab,
+    Oklch,
+}
+
+// A color in one color space. sRGB channels, saturation, lightness,
+// whiteness and blackness go from 0 to 1; the others keep the ranges of
+// their CSS functions. Missing (`none`) components are NaN.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AbsoluteColor {
+    pub space: ColorSpace,
+    pub components: [f64; 3],
+    pub alpha: f64,
+    // hex, named, `rgb()`, `hsl()` and `hwb()` colors serialize as `rgb()`
+    legacy: bool,
+}
+
+impl AbsoluteColor {
+    pub fn new(space: ColorSpace, components: [f64; 3], alpha: f64) -> Self {
+        Self {
+            space,
+            components,
+            alpha,
+            legacy: false,
+        }
+    }
+
+    // a legacy sRGB color from 0-255 channels
+    pub fn rgb(r: u8, g: u8, b: u8, alpha: f64) -> Self {
+        Self::legacy(
+            ColorSpace::Srgb,
+            [r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0],
+            alpha,
+        )
+    }
+
+    fn legacy(space: ColorSpace, components: [f64; 3], alpha: f64) -> Self {
+        Self {
+            legacy: true,
+            ..Self::new(space, components, alpha)
+        }
+        .to_space(ColorSpace::Srgb)
+    }
+
+    // https://drafts.csswg.org/css-color-4/#color-conversion
+    pub fn to_space(&self, space: ColorSpace) -> AbsoluteColor {
+        if self.space == space {
+            return *self;
+        }
+        // the sRGB forms are converted directly, so that `hsl()` colors
+        // keep their exact channels
+        let components = match (self.space, space) {
+            (ColorSpace::Hsl | ColorSpace::Hwb, _) | (_, ColorSpace::Hsl | ColorSpace::Hwb) => {
+                let c = self.components.map(or_zero);
+                let srgb = match self.space {
+                    ColorSpace::Hsl => hsl_to_srgb(c),
+                    ColorSpace::Hwb => hwb_to_srgb(c),
+                    _ => self.to_space(ColorSpace::Srgb).components,
+                };
+                match space {
+                    ColorSpace::Hsl => srgb_to_hsl(srgb),
+                    ColorSpace::Hwb => srgb_to_hwb(srgb),
+                    space => {
+                        AbsoluteColor::new(ColorSpace::Srgb, srgb, self.alpha)
+                            .to_space(space)
+                            .components
+                    }
+                }
+            }
+            _ => from_xyz_d65(space, to_xyz_d65(self.space, self.components)),
+        };
+        AbsoluteColor {
+            space,
+            components,
+            alpha: self.alpha,
+            legacy: self.legacy && space == ColorSpace::Srgb,
+        }
+    }
+
+    // https://drafts.csswg.org/css-color-4/#gamut-mapping
+    //
+    // The 8-bit sRGB channels and alpha to paint with. Colors outside sRGB
+    // are clipped rather than gamut mapped.
+    pub fn to_rgba8(&self) -> [u8; 4] {
+        let [r, g, b] = self.to_space(ColorSpace::Srgb).components;
+        let channel = |c: f64| (or_zero(c).clamp(0.0, 1.0) * 255.0).round() as u8;
+        [channel(r), channel(g), channel(b), channel(self.alpha)]
+    }
+}
+
+impl Color {
+    pub fn parse(values: &[ComponentValue]) -> Option<Color> {
+        let mut values = values.iter().filter(|v| !v.is_whitespace());
+        let value = values.next()?;
+        if values.next().is_some() {
+            return None;
+        }
+        parse_color(value)
+    }
+
+    // https://drafts.csswg.org/css-color-4/#resolving-color-values
+    //
+    // The used color, with `currentcolor` taking the value of `color`.
+    pub fn resolve(&self, current_color: &AbsoluteColor) -> AbsoluteColor {
+        match self {
+            Color::CurrentColor => *current_color,
+            Color::Absolute(color) => *color,
+        }
+    }
+}
+
+fn parse_color(value: &ComponentValue) -> Option<Color> {
+    let color = match value {
+        // https://drafts.csswg.org/css-color-4/#hex-notation
+        ComponentValue::Token(CssToken::Hash { value, .. }) => {
+            let digits: Vec<u8> = value
+                .chars()
+                .map(|c| c.to_digit(16).map(|d| d as u8))
+                .collect::<Option<_>>()?;
+            let channels: Vec<u8> = match digits.len() {
+                3 | 4 => digits.iter().map(|d| d * 17).collect(),
+                6 | 8 => digits.chunks(2).map(|d| d[0] * 16 + d[1]).collect(),
+                _ => return None,
+            };
+            let alpha = channels.get(3).map_or(1.0, |a| *a as f64 / 255.0);
+            AbsoluteColor::rgb(channels[0], channels[1], channels[2], alpha)
+        }
+        ComponentValue::Token(CssToken::Ident(name)) => {
+            let name = name.to_ascii_lowercase();
+            match name.as_str() {
+                "currentcolor" => return Some(Color::CurrentColor),
+                "transparent" => AbsoluteColor::rgb(0, 0, 0, 0.0),
+                name => {
+                    let rgb = NAMED_COLORS
+                        .iter()
+                        .chain(SYSTEM_COLORS.iter())
+                        .find(|(n, _)| *n == name)?
+                        .1;
+                    AbsoluteColor::rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 1.0)
+                }
+            }
+        }
+        ComponentValue::Function(name, arguments) => match name.to_ascii_lowercase().as_str() {
+            "color-mix" => return parse_color_mix(arguments),
+            name => parse_color_function(name, arguments)?,
+        },
+        _ => return None,
+    };
+    Some(Color::Absolute(color))
+}
+
+// the three channels and the alpha of a color function, which are either
+// space separated with a `/ alpha`, or comma separated for the legacy
+// syntax of `rgb()` and `hsl()`
+fn channels(
+    arguments: &[ComponentValue],
+    legacy: bool,
+) -> Option<(Vec<&ComponentValue>, Option<&ComponentValue>)> {
+    let values: Vec<&ComponentValue> = arguments.iter().filter(|v| !v.is_whitespace()).collect();
+    let is_comma = |v: &&ComponentValue| matches!(v, ComponentValue::Token(CssToken::Comma));
+
+    if values.iter().any(is_comma) {
+        if !legacy {
+            return None;
+        }
+        let mut channels = Vec::new();
+        for (i, value) in values.iter().enumerate() {
+            if (i % 2 == 1) != is_comma(value) {
+                return None;
+            }
+            if i % 2 == 0 {
+                channels.push(*value);
+            }
+        }
+        // `none` is only allowed in the modern syntax
+        let is_none = |v: &&ComponentValue| match v {
+            ComponentValue::Token(CssToken::Ident(s)) => s.eq_ignore_ascii_case("none"),
+            _ => false,
+        };
+        if values.len().is_multiple_of(2) || channels.iter().any(is_none) {
+            return None;
+        }
+        let alpha = match channels.len() {
+            3 => None,
+            4 => channels.pop(),
+            _ => return None,
+        };
+        return Some((channels, alpha));
+    }
+
+    match values
+        .iter()
+        .position(|v| matches!(v, ComponentValue::Token(CssToken::Delim('/'))))
+    {
+        Some(3) if values.len() == 5 => Some((values[..3].to_vec(), Some(values[4]))),
+        None if values.len() == 3 => Some((values, None)),
+        _ => None,
+    }
+}
+
+// A number or percentage channel, with 100% being `percent`; `none` is NaN.
+fn channel(value: &ComponentValue, percent: f64) -> Option<f64> {
+    match value {
+        ComponentValue::Token(CssToken::Number { value, .. }) => Some(*value),
+        ComponentValue::Token(CssToken::Percentage(p)) => Some(p / 100.0 * percent),
+        ComponentValue::Token(CssToken::Ident(s)) if s.eq_ignore_ascii_case("none") => {
+            Some(f64::NAN)
+        }
+        _ => None,
+    }
+}
+
+// https://drafts.csswg.org/css-values-4/#angles
+//
+// A hue in degrees.
+fn hue(value: &ComponentValue) -> Option<f64> {
+    match value {
+        ComponentValue::Token(CssToken::Dimension { value, unit, .. }) => {
+            let degrees = match unit.to_ascii_lowercase().as_str() {
+                "deg" => *value,
+                "grad" => value * 0.9,
+                "rad" => value.to_degrees(),
+                "turn" => value * 360.0,
+                _ => return None,
+            };
+            Some(degrees)
+        }
+        ComponentValue::Token(CssToken::Percentage(_)) => None,
+        value => channel(value, 0.0),
+    }
+}
+
+fn alpha(value: Option<&ComponentValue>) -> Option<f64> {
+    match value {
+        Some(value) => channel(value, 1.0).map(|a| if a.is_nan() { a } else { a.clamp(0.0, 1.0) }),
+        None => Some(1.0),
+    }
+}
+
+// https://drafts.csswg.org/css-color-4/#rgb-functions
+// https://drafts.csswg.org/css-color-4/#the-hsl-notation
+// https://drafts.csswg.org/css-color-4/#the-hwb-notation
+// https://drafts.csswg.org/css-color-4/#specifying-lab-lch
+// https://drafts.csswg.org/css-color-4/#specifying-oklab-oklch
+// https://drafts.csswg.org/css-color-4/#color-function
+fn parse_color_function(name: &str, arguments: &[ComponentValue]) -> Option<AbsoluteColor> {
+    if name == "color" {
+        let (space, rest) = match arguments.iter().position(|v| !v.is_whitespace()) {
+            Some(i) => (&arguments[i], &arguments[i + 1..]),
+            None => return None,
+        };
+        let space = match space {
+            ComponentValue::Token(CssToken::Ident(s)) if s.eq_ignore_ascii_case("srgb") => {
+                ColorSpace::Srgb
+            }
+            ComponentValue::Token(CssToken::Ident(s)) if s.eq_ignore_ascii_case("srgb-linear") => {
+                ColorSpace::SrgbLinear
+            }
+            _ => return None,
+        };
+        let (c, a) = channels(rest, false)?;
+        let components = [
+            channel(c[0], 1.0)?,
+            channel(c[1], 1.0)?,
+            channel(c[2], 1.0)?,
+        ];
+        return Some(AbsoluteColor::new(space, components, alpha(a)?));
+    }
+
+    let legacy = matches!(name, "rgb" | "rgba" | "hsl" | "hsla");
+    let (c, a) = channels(arguments, legacy)?;
+    let a = alpha(a)?;
+    let color = match name {
+        "rgb" | "rgba" => {
+            let c = [
+                channel(c[0], 255.0)?,
+                channel(c[1], 255.0)?,
+                channel(c[2], 255.0)?,
+            ];
+            AbsoluteColor::legacy(
+                ColorSpace::Srgb,
+                c.map(|c| {
+                    if c.is_nan() {
+                        c
+                    } else {
+                        c.clamp(0.0, 255.0) / 255.0
+                    }
+                }),
+                a,
+            )
+        }
+        "hsl" | "hsla" => AbsoluteColor::legacy(
+            ColorSpace::Hsl,
+            [
+                hue(c[0])?,
+                channel(c[1], 100.0)? / 100.0,
+                channel(c[2], 100.0)? / 100.0,
+            ],
+            a,
+        ),
+        "hwb" => AbsoluteColor::legacy(
+            ColorSpace::Hwb,
+            [
+                hue(c[0])?,
+                channel(c[1], 100.0)? / 100.0,
+                channel(c[2], 100.0)? / 100.0,
+            ],
+            a,
+        ),
+        "lab" => AbsoluteColor::new(
+            ColorSpace::Lab,
+            [
+                channel(c[0], 100.0)?.max(0.0),
+                channel(c[1], 125.0)?,
+                channel(c[2], 125.0)?,
+            ],
+            a,
+        ),
+        "lch" => AbsoluteColor::new(
+            ColorSpace::Lch,
+            [
+                channel(c[0], 100.0)?.max(0.0),
+                channel(c[1], 150.0)?.max(0.0),
+                hue(c[2])?,
+            ],
+            a,
+        ),
+        "oklab" => AbsoluteColor::new(
+            ColorSpace::Oklab,
+            [
+                channel(c[0], 1.0)?.max(0.0),
+                channel(c[1], 0.4)?,
+                channel(c[2], 0.4)?,
+            ],
+            a,
+        ),
+        "oklch" => AbsoluteColor::new(
+            ColorSpace::Oklch,
+            [
+                channel(c[0], 1.0)?.max(0.0),
+                channel(c[1], 0.4)?.max(0.0),
+                hue(c[2])?,
+            ],
+            a,
+        ),
+        _ => return None,
+    };
+    Some(color)
+}
+
+// https://drafts.csswg.org/css-color-4/#hue-interpolation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum HueInterpolation {
+    Shorter,
+    Longer,
+    Increasing,
+    Decreasing,
+}
+
+// https://drafts.csswg.org/css-color-5/#color-mix
+//
+// Mixing `currentcolor` isn't supported, as the mix is done when parsing.
+fn parse_color_mix(arguments: &[ComponentValue]) -> Option<Color> {
+    let mut parts = arguments.split(|v| matches!(v, ComponentValue::Token(CssToken::Comma)));
+    let method: Vec<String> = parts
+        .next()?
+        .iter()
+        .filter(|v| !v.is_whitespace())
+        .map(|v| match v {
+            ComponentValue::Token(CssToken::Ident(s)) => Some(s.to_ascii_lowercase()),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    let (first, second) = (parts.next()?, parts.next()?);
+    if parts.next().is_some() {
+        return None;
+    }
+
+    let space = match method.get(1).map(|s| s.as_str()) {
+        Some("srgb") => ColorSpace::Srgb,
+        Some("srgb-linear") => ColorSpace::SrgbLinear,
+        Some("hsl") => ColorSpace::Hsl,
+        Some("hwb") => ColorSpace::Hwb,
+        Some("lab") => ColorSpace::Lab,
+        Some("lch") => ColorSpace::Lch,
+        Some("oklab") => ColorSpace::Oklab,
+        Some("oklch") => ColorSpace::Oklch,
+        _ => return None,
+    };
+    let polar = matches!(
+        space,
+        ColorSpace::Hsl | ColorSpace::Hwb | ColorSpace::Lch | ColorSpace::Oklch
+    );
+    let hue_interpolation = match method[2..].iter().map(|s| s.as_str()).collect::<Vec<_>>()[..] {
+        _ if method[0] != "in" => return None,
+        [] => HueInterpolation::Shorter,
+        [method, "hue"] if polar => match method {
+            "shorter" => HueInterpolation::Shorter,
+            "longer" => HueInterpolation::Longer,
+            "increasing" => HueInterpolation::Increasing,
+            "decreasing" => HueInterpolation::Decreasing,
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    let (c1, p1) = mix_item(first)?;
+    let (c2, p2) = mix_item(second)?;
+    let (c1, c2) = match (c1, c2) {
+        (Color::Absolute(c1), Color::Absolute(c2)) => (c1, c2),
+        _ => return None,
+    };
+
+    // https://drafts.csswg.org/css-color-5/#color-mix-percent-norm
+    let (p1, p2) = match (p1, p2) {
+        (None, None) => (50.0, 50.0),
+        (Some(p1), None) => (p1, 100.0 - p1),
+        (None, Some(p2)) => (100.0 - p2, p2),
+        (Some(p1), Some(p2)) => (p1, p2),
+    };
+    let total = p1 + p2;
+    if total <= 0.0 {
+        return None;
+    }
+    let multiplier = (total / 100.0).min(1.0);
+
+    let mut color = mix(space, hue_interpolation, &c1, &c2, p2 / total);
+    color.alpha *= multiplier;
+    // mixes in the legacy spaces serialize as `rgb()`
+    if matches!(space, ColorSpace::Hsl | ColorSpace::Hwb) {
+        color.legacy = true;
+        color = color.to_space(ColorSpace::Srgb);
+    }
+    Some(Color::Absolute(color))
+}
+
+// a color and an optional percentage, in either order
+fn mix_item(values: &[ComponentValue]) -> Option<(Color, Option<f64>)> {
+    let values: Vec<&ComponentValue> = values.iter().filter(|v| !v.is_whitespace()).collect();
+    let percentage = |v: &ComponentValue| match v {
+        ComponentValue::Token(CssToken::Percentage(p)) if (0.0..=100.0).contains(p) => Some(*p),
+        _ => None,
+    };
+    match values[..] {
+        [color] => Some((parse_color(color)?, None)),
+        [color, p] | [p, color] if percentage(p).is_some() => {
+            Some((parse_color(color)?, percentage(p)))
+        }
+        _ => None,
+    }
+}
+
+// https://drafts.csswg.org/css-color-4/#interpolation
+fn mix(
+    space: ColorSpace,
+    hue_interpolation: HueInterpolation,
+    from: &AbsoluteColor,
+    to: &AbsoluteColor,
+    t: f64,
+) -> AbsoluteColor {
+    let mut a = from.to_space(space);
+    let mut b = to.to_space(space);
+    let hue_index = match space {
+        ColorSpace::Hsl | ColorSpace::Hwb => Some(0),
+        ColorSpace::Lch | ColorSpace::Oklch => Some(2),
+        _ => None,
+    };
+
+    // https://drafts.csswg.org/css-color-4/#interpolation-missing
+    for i in 0..3 {
+        if a.components[i].is_nan() {
+            a.components[i] = b.components[i];
+        } else if b.components[i].is_nan() {
+            b.components[i] = a.components[i];
+        }
+    }
+    if a.alpha.is_nan() {
+        a.alpha = b.alpha;
+    } else if b.alpha.is_nan() {
+        b.alpha = a.alpha;
+    }
+
+    // https://drafts.csswg.org/css-color-4/#hue-interpolation
+    if let Some(h) = hue_index {
+        let (h1, h2) = (
+            a.components[h].rem_euclid(360.0),
+            b.components[h].rem_euclid(360.0),
+        );
+        let delta = h2 - h1;
+        let h2 = match hue_interpolation {
+            HueInterpolation::Shorter if delta > 180.0 => h2 - 360.0,
+            HueInterpolation::Shorter if delta < -180.0 => h2 + 360.0,
+            HueInterpolation::Longer if (0.0..180.0).contains(&delta) && delta != 0.0 => h2 - 360.0,
+            HueInterpolation::Longer if (-180.0..0.0).contains(&delta) => h2 + 360.0,
+            HueInterpolation::Increasing if h2 < h1 => h2 + 360.0,
+            HueInterpolation::Decreasing if h2 > h1 => h2 - 360.0,
+            _ => h2,
+        };
+        a.components[h] = h1;
+        b.components[h] = h2;
+    }
+
+    // https://drafts.csswg.org/css-color-4/#interpolation-alpha
+    let alpha = or_zero(a.alpha) + (or_zero(b.alpha) - or_zero(a.alpha)) * t;
+    let components = [0, 1, 2].map(|i| {
+        let (x, y) = (a.components[i], b.components[i]);
+        if Some(i) == hue_index {
+            (x + (y - x) * t).rem_euclid(360.0)
+        } else if alpha == 0.0 {
+            x + (y - x) * t
+        } else {
+            (x * or_zero(a.alpha) + (y * or_zero(b.alpha) - x * or_zero(a.alpha)) * t) / alpha
+        }
+    });
+    AbsoluteColor::new(space, components, alpha)
+}
+
+fn or_zero(value: f64) -> f64 {
+    if value.is_nan() {
+        0.0
+    } else {
+        value
+    }
+}
+
+type Matrix = [[f64; 3]; 3];
+
+fn multiply(m: &Matrix, v: [f64; 3]) -> [f64; 3] {
+    [0, 1, 2].map(|i| m[i][0] * v[0] + m[i][1] * v[1] + m[i][2] * v[2])
+}
+
+// https://drafts.csswg.org/css-color-4/#color-conversion-code
+const LINEAR_SRGB_TO_XYZ: Matrix = [
+    [0.41239079926595934, 0.357584339383878, 0.1804807884018343],
+    [0.21263900587151027, 0.715168678767756, 0.07219231536073371],
+    [0.01933081871559182, 0.11919477979462598, 0.9505321522496607],
+];
+const XYZ_TO_LINEAR_SRGB: Matrix = [
+    [3.2409699419045226, -1.537383177570094, -0.4986107602930034],
+    [-0.9692436362808796, 1.8759675015077202, 0.04155505740717559],
+    [
+        0.05563007969699366,
+        -0.20397695888897652,
+        1.0569715142428786,
+    ],
+];
+const D65_TO_D50: Matrix = [
+    [
+        1.0479297925449969,
+        0.022946870601609652,
+        -0.05019226628920524,
+    ],
+    [
+        0.02962780877005599,
+        0.9904344267538799,
+        -0.017073799063418826,
+    ],
+    [
+        -0.009243040646204504,
+        0.015055191490298152,
+        0.7518742814281371,
+    ],
+];
+const D50_TO_D65: Matrix = [
+    [0.955473421488075, -0.02309845494876471, 0.06325924320057072],
+    [
+        -0.0283697093338637,
+        1.0099953980813041,
+        0.021041441191917323,
+    ],
+    [
+        0.012314014864481998,
+        -0.020507649298898964,
+        1.330365926242124,
+    ],
+];
+const XYZ_TO_LMS: Matrix = [
+    [0.819022437996703, 0.3619062600528904, -0.1288737815209879],
+    [0.0329836539323885, 0.9292868615863434, 0.0361446663506424],
+    [0.0481771893596242, 0.2642395317527308, 0.6335478284694309],
+];
+const LMS_TO_XYZ: Matrix = [
+    [1.2268798758459243, -0.5578149944602171, 0.2813910456659647],
+    [-0.0405757452148008, 1.112286803280317, -0.0717110580655164],
+    [-0.0763729366746601, -0.4214933324022432, 1.5869240198367816],
+];
+const LMS_TO_OKLAB: Matrix = [
+    [0.210454268309314, 0.7936177747023054, -0.0040720430116193],
+    [1.9779985324311684, -2.42859224204858, 0.450593709617411],
+    [0.0259040424655478, 0.7827717124575296, -0.8086757549230774],
+];
+const OKLAB_TO_LMS: Matrix = [
+    [1.0, 0.3963377773761749, 0.2158037573099136],
+    [1.0, -0.1055613458156586, -0.0638541728258133],
+    [1.0, -0.0894841775298119, -1.2914855480194092],
+];
+const D50_WHITE: [f64; 3] = [0.3457 / 0.3585, 1.0, (1.0 - 0.3457 - 0.3585) / 0.3585];
+const LAB_KAPPA: f64 = 24389.0 / 27.0;
+const LAB_EPSILON: f64 = 216.0 / 24389.0;
+
+fn to_xyz_d65(space: ColorSpace, c: [f64; 3]) -> [f64; 3] {
+    let c = c.map(or_zero);
+    match space {
+        ColorSpace::Srgb => multiply(&LINEAR_SRGB_TO_XYZ, c.map(srgb_to_linear)),
+        ColorSpace::SrgbLinear => multiply(&LINEAR_SRGB_TO_XYZ, c),
+        ColorSpace::Hsl => to_xyz_d65(ColorSpace::Srgb, hsl_to_srgb(c)),
+        ColorSpace::Hwb => to_xyz_d65(ColorSpace::Srgb, hwb_to_srgb(c)),
+        ColorSpace::Lab => {
+            let [l, a, b] = c;
+            let f1 = (l + 16.0) / 116.0;
+            let f0 = a / 500.0 + f1;
+            let f2 = f1 - b / 200.0;
+            let inverse = |f: f64| match f.powi(3) {
+                cube if cube > LAB_EPSILON => cube,
+                _ => (116.0 * f - 16.0) / LAB_KAPPA,
+            };
+            let y = match l > LAB_KAPPA * LAB_EPSILON {
+                true => f1.powi(3),
+                false => l / LAB_KAPPA,
+            };
+            let xyz = [inverse(f0) * D50_WHITE[0], y, inverse(f2) * D50_WHITE[2]];
+            multiply(&D50_TO_D65, xyz)
+        }
+        ColorSpace::Lch => to_xyz_d65(ColorSpace::Lab, polar_to_rectangular(c)),
+        ColorSpace::Oklab => {
+            let lms = multiply(&OKLAB_TO_LMS, c).map(|v| v.powi(3));
+            multiply(&LMS_TO_XYZ, lms)
+        }
+        ColorSpace::Oklch => to_xyz_d65(ColorSpace::Oklab, polar_to_rectangular(c)),
+    }
+}
+
+fn from_xyz_d65(space: ColorSpace, xyz: [f64; 3]) -> [f64; 3] {
+    match space {
+        ColorSpace::Srgb => multiply(&XYZ_TO_LINEAR_SRGB, xyz).map(linear_to_srgb),
+        ColorSpace::SrgbLinear => multiply(&XYZ_TO_LINEAR_SRGB, xyz),
+        ColorSpace::Hsl => srgb_to_hsl(from_xyz_d65(ColorSpace::Srgb, xyz)),
+        ColorSpace::Hwb => srgb_to_hwb(from_xyz_d65(ColorSpace::Srgb, xyz)),
+        ColorSpace::Lab => {
+            let xyz = multiply(&D65_TO_D50, xyz);
+            let f = [0, 1, 2].map(|i| match xyz[i] / D50_WHITE[i] {
+                v if v > LAB_EPSILON => v.cbrt(),
+                v => (LAB_KAPPA * v + 16.0) / 116.0,
+            });
+            [
+                116.0 * f[1] - 16.0,
+                500.0 * (f[0] - f[1]),
+                200.0 * (f[1] - f[2]),
+            ]
+        }
+        ColorSpace::Lch => rectangular_to_polar(from_xyz_d65(ColorSpace::Lab, xyz), 0.0015),
+        ColorSpace::Oklab => {
+            let lms = multiply(&XYZ_TO_LMS, xyz).map(f64::cbrt);
+            multiply(&LMS_TO_OKLAB, lms)
+        }
+        ColorSpace::Oklch => rectangular_to_polar(from_xyz_d65(ColorSpace::Oklab, xyz), 0.000004),
+    }
+}
+
+fn srgb_to_linear(c: f64) -> f64 {
+    match c.abs() {
+        abs if abs <= 0.04045 => c / 12.92,
+        abs => c.signum() * ((abs + 0.055) / 1.055).powf(2.4),
+    }
+}
+
+fn linear_to_srgb(c: f64) -> f64 {
+    match c.abs() {
+        abs if abs > 0.0031308 => c.signum() * (1.055 * abs.powf(1.0 / 2.4) - 0.055),
+        _ => 12.92 * c,
+    }
+}
+
+// https://drafts.csswg.org/css-color-4/#hsl-to-rgb
+fn hsl_to_srgb([h, s, l]: [f64; 3]) -> [f64; 3] {
+    let h = h.rem_euclid(360.0);
+    let f = |n: f64| {
+        let k = (n + h / 30.0) % 12.0;
+        let a = s * l.min(1.0 - l);
+        l - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+    };
+    [f(0.0), f(8.0), f(4.0)]
+}
+
+// https://drafts.csswg.org/css-color-4/#rgb-to-hsl
+fn srgb_to_hsl([r, g, b]: [f64; 3]) -> [f64; 3] {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let light = (min + max) / 2.0;
+    let d = max - min;
+    if d == 0.0 {
+        // the hue of a gray is powerless
+        return [f64::NAN, 0.0, light];
+    }
+    let sat = match light {
+        0.0 | 1.0 => 0.0,
+        light => (max - light) / light.min(1.0 - light),
+    };
+    let hue = if max == r {
+        (g - b) / d + if g < b { 6.0 } else { 0.0 }
+    } else if max == g {
+        (b - r) / d + 2.0
+    } else {
+        (r - g) / d + 4.0
+    };
+    [hue * 60.0, sat, light]
+}
+
+// https://drafts.csswg.org/css-color-4/#rgb-to-hwb
+fn srgb_to_hwb([r, g, b]: [f64; 3]) -> [f64; 3] {
+    let hue = srgb_to_hsl([r, g, b])[0];
+    [hue, r.min(g).min(b), 1.0 - r.max(g).max(b)]
+}
+
+// https://drafts.csswg.org/css-color-4/#hwb-to-rgb
+fn hwb_to_srgb([h, w, b]: [f64; 3]) -> [f64; 3] {
+    if w + b >= 1.0 {
+        let gray = w / (w + b);
+        return [gray; 3];
+    }
+    hsl_to_srgb([h, 1.0, 0.5]).map(|c| c * (1.0 - w - b) + w)
+}
+
+fn polar_to_rectangular([l, c, h]: [f64; 3]) -> [f64; 3] {
+    let h = h.to_radians();
+    [l, c * h.cos(), c * h.sin()]
+}
+
+// the hue of a color with chroma below `epsilon` is powerless
+fn rectangular_to_polar([l, a, b]: [f64; 3], epsilon: f64) -> [f64; 3] {
+    let c = (a * a + b * b).sqrt();
+    let h = match c < epsilon {
+        true => f64::NAN,
+        false => b.atan2(a).to_degrees().rem_euclid(360.0),
+    };
+    [l, c, h]
+}
+
+// https://drafts.csswg.org/css-color-4/#serializing-color-values
+impl fmt::Display for AbsoluteColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.legacy {
+            let [r, g, b, _] = self.to_rgba8();
+            // the alpha with the fewest digits that keeps its 8-bit value
+            let a8 = (or_zero(self.alpha).clamp(0.0, 1.0) * 255.0).round();
+            let mut alpha = (a8 / 255.0 * 100.0).round() / 100.0;
+            if (alpha * 255.0).round() != a8 {
+                alpha = (a8 / 255.0 * 1000.0).round() / 1000.0;
+            }
+            return match alpha {
+                1.0 => write!(f, "rgb({}, {}, {})", r, g, b),
+                alpha => write!(f, "rgba({}, {}, {}, {})", r, g, b, alpha),
+            };
+        }
+
+        let component = |c: f64| match c.is_nan() {
+            true => String::from("none"),
+            false => round(c).to_string(),
+        };
+        let [c0, c1, c2] = self.components.map(component);
+        match self.space {
+            ColorSpace::Srgb => write!(f, "color(srgb {} {} {}", c0, c1, c2)?,
+            ColorSpace::SrgbLinear => write!(f, "color(srgb-linear {} {} {}", c0, c1, c2)?,
+            ColorSpace::Lab => write!(f, "lab({} {} {}", c0, c1, c2)?,
+            ColorSpace::Lch => write!(f, "lch({} {} {}", c0, c1, c2)?,
+            ColorSpace::Oklab => write!(f, "oklab({} {} {}", c0, c1, c2)?,
+            ColorSpace::Oklch => write!(f, "oklch({} {} {}", c0, c1, c2)?,
+            // only made by color-mix(), which turns them into sRGB
+            ColorSpace::Hsl | ColorSpace::Hwb => {
+                let color = AbsoluteColor {
+                    legacy: true,
+                    ..self.to_space(ColorSpace::Srgb)
+                };
+                return write!(f, "{}", color);
+            }
+        }
+        if self.alpha != 1.0 {
+            write!(f, " / {}", component(self.alpha))?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Color::CurrentColor => write!(f, "currentcolor"),
+            Color::Absolute(color) => write!(f, "{}", color),
+        }
+    }
+}
+
+// https://drafts.csswg.org/css-color-4/#css-system-colors
+//
+// Computed to fixed light-scheme values.
+const SYSTEM_COLORS: &[(&str, u32)] = &[
+    ("accentcolor", 0x0075ff),
+    ("accentcolortext", 0xffffff),
+    ("activetext", 0xff0000),
+    ("buttonborder", 0x767676),
+    ("buttonface", 0xefefef),
+    ("buttontext", 0x000000),
+    ("canvas", 0xffffff),
+    ("canvastext", 0x000000),
+    ("field", 0xffffff),
+    ("fieldtext", 0x000000),
+    ("graytext", 0x808080),
+    ("highlight", 0x3390ff),
+    ("highlighttext", 0xffffff),
+    ("linktext", 0x0000ee),
+    ("mark", 0xffff00),
+    ("marktext", 0x000000),
+    ("selecteditem", 0x3390ff),
+    ("selecteditemtext", 0xffffff),
+    ("visitedtext", 0x551a8b),
+];
+
+// https://drafts.csswg.org/css-color-4/#named-colors
+const NAMED_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::css::parse_component_values;
+
+    fn color(css: &str) -> String {
+        Color::parse(&parse_component_values(css))
+            .unwrap()
+            .to_string()
+    }
+
+    fn rgba8(css: &str) -> [u8; 4] {
+        match Color::parse(&parse_component_values(css)).unwrap() {
+            Color::Absolute(color) => color.to_rgba8(),
+            Color::CurrentColor => panic!("currentcolor"),
+        }
+    }
+
+    fn invalid(css: &str) -> bool {
+        Color::parse(&parse_component_values(css)).is_none()
+    }
+
+    #[test]
+    fn test_legacy_colors() {
+        assert_eq!(color("#f00"), "rgb(255, 0, 0)");
+        assert_eq!(color("#ff000080"), "rgba(255, 0, 0, 0.5)");
+        assert_eq!(color("#0f08"), "rgba(0, 255, 0, 0.533)");
+        assert_eq!(color("RebeccaPurple"), "rgb(102, 51, 153)");
+        assert_eq!(color("transparent"), "rgba(0, 0, 0, 0)");
+        assert_eq!(color("CanvasText"), "rgb(0, 0, 0)");
+        assert_eq!(color("currentColor"), "currentcolor");
+        assert_eq!(color("rgb(255, 128, 0)"), "rgb(255, 128, 0)");
+        assert_eq!(color("rgba(100%, 0%, 0%, 25%)"), "rgba(255, 0, 0, 0.25)");
+        assert_eq!(color("rgb(0 0 255 / 0.5)"), "rgba(0, 0, 255, 0.5)");
+        assert_eq!(color("rgb(300 -1 none)"), "rgb(255, 0, 0)");
+        assert_eq!(color("hsl(120deg 100% 50%)"), "rgb(0, 255, 0)");
+        assert_eq!(color("hsla(240, 100%, 50%, 0.5)"), "rgba(0, 0, 255, 0.5)");
+        assert_eq!(color("hsl(0.5turn 100% 25%)"), "rgb(0, 128, 128)");
+        assert_eq!(color("hwb(0 0% 0%)"), "rgb(255, 0, 0)");
+        assert_eq!(color("hwb(90 60% 60%)"), "rgb(128, 128, 128)");
+
+        assert!(invalid("#ff00f"));
+        assert!(invalid("notacolor"));
+        assert!(invalid("rgb(1, 2)"));
+        assert!(invalid("rgb(1, 2, none)"));
+        assert!(invalid("rgb(1 2, 3)"));
+        assert!(invalid("hwb(0, 0%, 0%)"));
+        assert!(invalid("red blue"));
+    }
+
+    #[test]
+    fn test_lab_and_oklch() {
+        assert_eq!(color("lab(50% 40 -20)"), "lab(50 40 -20)");
+        assert_eq!(color("lch(50 30 400deg / 50%)"), "lch(50 30 400 / 0.5)");
+        assert_eq!(color("oklab(40% 0.1 none)"), "oklab(0.4 0.1 none)");
+        assert_eq!(color("oklch(0.7 50% 180)"), "oklch(0.7 0.2 180)");
+        assert!(invalid("lab(50, 40, -20)"));
+
+        // the same colors as in sRGB
+        assert_eq!(rgba8("oklch(0.627955 0.257683 29.2339)"), [255, 0, 0, 255]);
+        assert_eq!(rgba8("lab(54.2917 80.8125 69.8851)"), [255, 0, 0, 255]);
+        assert_eq!(rgba8("lch(100 0 0)"), [255, 255, 255, 255]);
+        assert_eq!(rgba8("oklab(0 0 0 / 0.5)"), [0, 0, 0, 128]);
+        assert_eq!(
+            rgba8("color(srgb-linear 0.2158605 0.2158605 0.2158605)"),
+            [128, 128, 128, 255]
+        );
+    }
+
+    #[test]
+    fn test_color_mix() {
+        assert_eq!(
+            color("color-mix(in srgb, red, blue)"),
+            "color(srgb 0.5 0 0.5)"
+        );
+        assert_eq!(
+            color("color-mix(in srgb, red 25%, blue)"),
+            "color(srgb 0.25 0 0.75)"
+        );
+        // percentages below 100% in total make the mix transparent
+        assert_eq!(
+            color("color-mix(in srgb, 30% red, blue 20%)"),
+            "color(srgb 0.6 0 0.4 / 0.5)"
+        );
+        // premultiplied alpha: a transparent color has no say in the hue
+        assert_eq!(
+            color("color-mix(in srgb, transparent, blue)"),
+            "color(srgb 0 0 1 / 0.5)"
+        );
+        assert_eq!(color("color-mix(in hsl, red, lime)"), "rgb(255, 255, 0)");
+        // magenta is 60 degrees from red going down, 300 going up
+        assert_eq!(
+            color("color-mix(in hsl increasing hue, red, magenta)"),
+            "rgb(0, 255, 128)"
+        );
+        assert_eq!(color("color-mix(in hsl, red, magenta)"), "rgb(255, 0, 128)");
+        assert_eq!(
+            rgba8("color-mix(in oklab, white, black 0%)"),
+            [255, 255, 255, 255]
+        );
+        assert_eq!(
+            rgba8("color-mix(in lch, black, white)"),
+            [119, 119, 119, 255]
+        );
+
+        assert!(invalid("color-mix(in srgb, red)"));
+        assert!(invalid("color-mix(srgb, red, blue)"));
+        assert!(invalid("color-mix(in srgb longer hue, red, blue)"));
+        assert!(invalid("color-mix(in srgb, red 0%, blue 0%)"));
+        assert!(invalid("color-mix(in srgb, currentcolor, blue)"));
+    }
+}
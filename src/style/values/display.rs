@@ -0,0 +1,151 @@
+use super::*;
+
+// https://drafts.csswg.org/css-display-3/#the-display-properties
+//
+// What box an element generates, as far as the box tree needs to know.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Display {
+    pub outside: DisplayOutside,
+    // https://drafts.csswg.org/css-display-3/#list-items
+    pub list_item: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DisplayOutside {
+    // https://drafts.csswg.org/css-display-3/#box-generation
+    None,
+    Contents,
+    #[default]
+    Inline,
+    Block,
+}
+
+const MULTI_KEYWORDS: &[&str] = &[
+    "block",
+    "inline",
+    "run-in",
+    "flow",
+    "flow-root",
+    "table",
+    "flex",
+    "grid",
+    "ruby",
+    "list-item",
+];
+
+const SINGLE_KEYWORDS: &[&str] = &[
+    "block",
+    "inline",
+    "run-in",
+    "flow",
+    "flow-root",
+    "table",
+    "flex",
+    "grid",
+    "ruby",
+    "list-item",
+    "contents",
+    "none",
+    "inline-block",
+    "inline-table",
+    "inline-flex",
+    "inline-grid",
+    "table-row-group",
+    "table-header-group",
+    "table-footer-group",
+    "table-row",
+    "table-cell",
+    "table-column-group",
+    "table-column",
+    "table-caption",
+    "ruby-base",
+    "ruby-text",
+    "ruby-base-container",
+    "ruby-text-container",
+];
+
+impl Display {
+    // both the single keywords and the multi-keyword syntax, with table
+    // parts, flex and grid containers taken as blocks
+    pub fn parse(values: &[ComponentValue]) -> Option<Display> {
+        let mut keywords = Vec::new();
+        for value in values.iter().filter(|v| !v.is_whitespace()) {
+            match value {
+                ComponentValue::Token(CssToken::Ident(k)) => keywords.push(k.to_ascii_lowercase()),
+                _ => return None,
+            }
+        }
+        let has = |keyword: &str| keywords.iter().any(|k| k == keyword);
+
+        // https://drafts.csswg.org/css-display-3/#the-display-properties
+        //
+        // a single keyword, or up to an outer type, an inner type and
+        // `list-item`, each at most once
+        let valid = match &keywords[..] {
+            [keyword] => SINGLE_KEYWORDS.contains(&keyword.as_str()),
+            multiple => {
+                multiple.len() <= 3
+                    && multiple.iter().enumerate().all(|(i, k)| {
+                        MULTI_KEYWORDS.contains(&k.as_str()) && !multiple[..i].contains(k)
+                    })
+            }
+        };
+        let outside = if !valid {
+            return None;
+        } else if has("none") {
+            DisplayOutside::None
+        } else if has("contents") {
+            DisplayOutside::Contents
+        } else if keywords
+            .iter()
+            .any(|k| k == "inline" || k.starts_with("inline-") || k.starts_with("ruby"))
+        {
+            DisplayOutside::Inline
+        } else {
+            DisplayOutside::Block
+        };
+        Some(Display {
+            outside,
+            list_item: has("list-item"),
+        })
+    }
+
+    pub fn is_none(&self) -> bool {
+        self.outside == DisplayOutside::None
+    }
+
+    pub fn is_block_level(&self) -> bool {
+        self.outside == DisplayOutside::Block
+    }
+
+    pub fn is_list_item(&self) -> bool {
+        self.list_item && !self.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_display() {
+        let display = |s| Display::parse(&parse_component_values(s)).unwrap();
+
+        assert_eq!(
+            display("none"),
+            Display {
+                outside: DisplayOutside::None,
+                list_item: false,
+            }
+        );
+        assert!(display("list-item").is_block_level());
+        assert!(display("list-item").is_list_item());
+        assert!(display("inline list-item").is_list_item());
+        assert!(!display("inline list-item").is_block_level());
+        assert!(!display("inline-block").is_block_level());
+        assert!(display("flex").is_block_level());
+        assert!(display("block flow-root").is_block_level());
+        assert_eq!(display("contents").outside, DisplayOutside::Contents);
+        assert!(Display::parse(&parse_component_values("1px")).is_none());
+    }
+}
@@ -0,0 +1,190 @@
+use super::*;
+use std::fmt;
+
+// https://drafts.csswg.org/css-values-4/#lengths
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Length {
+    // absolute units are kept in px
+    Px(f64),
+    Em(f64),
+    Ex(f64),
+    Ch(f64),
+    Rem(f64),
+    Vw(f64),
+    Vh(f64),
+    Vmin(f64),
+    Vmax(f64),
+}
+
+impl Length {
+    // https://drafts.csswg.org/css-values-4/#absolute-lengths
+    pub fn new(value: f64, unit: &str) -> Option<Length> {
+        let length = match unit.to_ascii_lowercase().as_str() {
+            "px" => Length::Px(value),
+            "cm" => Length::Px(value * 96.0 / 2.54),
+            "mm" => Length::Px(value * 96.0 / 25.4),
+            "q" => Length::Px(value * 96.0 / 101.6),
+            "in" => Length::Px(value * 96.0),
+            "pt" => Length::Px(value * 96.0 / 72.0),
+            "pc" => Length::Px(value * 16.0),
+            "em" => Length::Em(value),
+            "ex" => Length::Ex(value),
+            "ch" => Length::Ch(value),
+            "rem" => Length::Rem(value),
+            "vw" => Length::Vw(value),
+            "vh" => Length::Vh(value),
+            "vmin" => Length::Vmin(value),
+            "vmax" => Length::Vmax(value),
+            _ => return None,
+        };
+        Some(length)
+    }
+
+    // a dimension with a length unit, or a unitless zero
+    pub fn parse(value: &ComponentValue) -> Option<Length> {
+        match value {
+            ComponentValue::Token(CssToken::Dimension { value, unit, .. }) => {
+                Length::new(*value, unit)
+            }
+            ComponentValue::Token(CssToken::Number { value, .. }) if *value == 0.0 => {
+                Some(Length::Px(0.0))
+            }
+            _ => None,
+        }
+    }
+
+    // https://drafts.csswg.org/css-values-4/#font-relative-lengths
+    //
+    // Without font metrics, `ex` and `ch` use the 0.5em fallback the spec
+    // gives for when they can't be measured.
+    pub fn to_px(self, context: &ComputeContext) -> f64 {
+        let viewport = context.viewport;
+        match self {
+            Length::Px(v) => v,
+            Length::Em(v) => v * context.font_size,
+            Length::Ex(v) | Length::Ch(v) => v * context.font_size * 0.5,
+            Length::Rem(v) => v * context.root_font_size,
+            Length::Vw(v) => v * viewport.width / 100.0,
+            Length::Vh(v) => v * viewport.height / 100.0,
+            Length::Vmin(v) => v * viewport.width.min(viewport.height) / 100.0,
+            Length::Vmax(v) => v * viewport.width.max(viewport.height) / 100.0,
+        }
+    }
+
+    fn value_and_unit(self) -> (f64, &'static str) {
+        match self {
+            Length::Px(v) => (v, "px"),
+            Length::Em(v) => (v, "em"),
+            Length::Ex(v) => (v, "ex"),
+            Length::Ch(v) => (v, "ch"),
+            Length::Rem(v) => (v, "rem"),
+            Length::Vw(v) => (v, "vw"),
+            Length::Vh(v) => (v, "vh"),
+            Length::Vmin(v) => (v, "vmin"),
+            Length::Vmax(v) => (v, "vmax"),
+        }
+    }
+}
+
+impl fmt::Display for Length {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (value, unit) = self.value_and_unit();
+        write!(f, "{}{}", round(value), unit)
+    }
+}
+
+// https://drafts.csswg.org/css-values-4/#typedef-length-percentage
+//
+// A computed length-percentage. Percentages, alone or mixed with lengths in
+// calc(), are only resolved once the size they refer to is known.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LengthPercentage {
+    // in px
+    Length(f64),
+    Percentage(f64),
+    Calc(CalcNode),
+}
+
+impl LengthPercentage {
+    pub fn parse(values: &[ComponentValue]) -> Option<LengthPercentage> {
+        let mut values = values.iter().filter(|v| !v.is_whitespace());
+        let value = values.next()?;
+        if values.next().is_some() {
+            return None;
+        }
+
+        match value {
+            ComponentValue::Token(CssToken::Percentage(p)) => {
+                Some(LengthPercentage::Percentage(*p))
+            }
+            ComponentValue::Function(name, arguments) => {
+                let calc = CalcNode::parse_function(name, arguments)?;
+                match calc.kind()? {
+                    CalcKind::Number => None,
+                    _ => Some(LengthPercentage::Calc(calc)),
+                }
+            }
+            value => Length::parse(value)
+                .map(|length| LengthPercentage::Length(length.to_px(&ComputeContext::default()))),
+        }
+    }
+
+    // https://drafts.csswg.org/css-cascade-4/#used
+    //
+    // The length in px, with percentages taken of `basis`.
+    pub fn resolve(&self, basis: f64) -> f64 {
+        match self {
+            LengthPercentage::Length(px) => *px,
+            LengthPercentage::Percentage(p) => p * basis / 100.0,
+            LengthPercentage::Calc(calc) => {
+                match calc.simplify(&ComputeContext::default(), Some(basis)) {
+                    CalcNode::Length(length) => length.to_px(&ComputeContext::default()),
+                    CalcNode::Number(n) => n,
+                    _ => 0.0,
+                }
+            }
+        }
+    }
+}
+
+// https://drafts.csswg.org/css-fonts-4/#font-size-prop
+//
+// The computed `font-size` in px, from the parent's in `context`.
+pub fn compute_font_size(value: &[ComponentValue], context: &ComputeContext) -> Option<f64> {
+    let parent = context.font_size;
+    let mut values = value.iter().filter(|v| !v.is_whitespace());
+    let value = values.next()?;
+    if values.next().is_some() {
+        return None;
+    }
+
+    // https://drafts.csswg.org/css-fonts-4/#absolute-size-mapping
+    let size = match value {
+        ComponentValue::Token(CssToken::Ident(keyword)) => {
+            let scale = match keyword.to_ascii_lowercase().as_str() {
+                "xx-small" => 3.0 / 5.0,
+                "x-small" => 3.0 / 4.0,
+                "small" => 8.0 / 9.0,
+                "medium" => 1.0,
+                "large" => 6.0 / 5.0,
+                "x-large" => 3.0 / 2.0,
+                "xx-large" => 2.0,
+                "xxx-large" => 3.0,
+                // https://drafts.csswg.org/css-fonts-4/#relative-size-value
+                "larger" => return Some(parent * 1.2),
+                "smaller" => return Some(parent / 1.2),
+                _ => return None,
+            };
+            MEDIUM_FONT_SIZE * scale
+        }
+        ComponentValue::Token(CssToken::Percentage(p)) => parent * p / 100.0,
+        ComponentValue::Function(name, arguments) => {
+            match CalcNode::parse_function(name, arguments)?.simplify(context, Some(parent)) {
+                CalcNode::Length(length) => length.to_px(context),
+                _ => return None,
+            }
+        }
+        value => Length::parse(value)?.to_px(context),
+    };
+    Some(size.max(0.0))
+}
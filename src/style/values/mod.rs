@@ -0,0 +1,241 @@
+mod calc;
+mod color;
+mod display;
+mod length;
+
+pub use calc::{CalcKind, CalcNode};
+pub use color::{AbsoluteColor, Color, ColorSpace};
+pub use display::{Display, DisplayOutside};
+pub use length::{compute_font_size, Length, LengthPercentage};
+
+use super::Viewport;
+use crate::parser::css::{parse_component_values, ComponentValue, CssToken};
+
+// https://drafts.csswg.org/css-fonts-4/#valdef-font-size-medium
+pub const MEDIUM_FONT_SIZE: f64 = 16.0;
+
+// https://drafts.csswg.org/css-values-4/#relative-lengths
+//
+// What relative lengths are resolved against.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ComputeContext {
+    // the computed `font-size` of the element in px, or of its parent while
+    // computing `font-size` itself
+    pub font_size: f64,
+    // the computed `font-size` of the root element
+    pub root_font_size: f64,
+    pub viewport: Viewport,
+}
+
+impl Default for ComputeContext {
+    fn default() -> Self {
+        Self {
+            font_size: MEDIUM_FONT_SIZE,
+            root_font_size: MEDIUM_FONT_SIZE,
+            viewport: Viewport::default(),
+        }
+    }
+}
+
+// rounded for serialization, so that `0.1 + 0.2` prints as `0.3`
+pub(crate) fn round(value: f64) -> f64 {
+    // `+ 0.0` turns `-0` into `0`
+    (value * 1e6).round() / 1e6 + 0.0
+}
+
+// https://drafts.csswg.org/css-values-4/#lengths
+fn is_length_property(name: &str) -> bool {
+    matches!(
+        name,
+        "bottom"
+            | "height"
+            | "left"
+            | "letter-spacing"
+            | "max-height"
+            | "max-width"
+            | "min-height"
+            | "min-width"
+            | "right"
+            | "text-indent"
+            | "top"
+            | "width"
+            | "word-spacing"
+    ) || name.starts_with("margin-")
+        || name.starts_with("padding-")
+        || (name.starts_with("border-") && name.ends_with("-width"))
+}
+
+// https://drafts.csswg.org/css-cascade-4/#computed
+//
+// The computed value of `property` from its specified value: lengths are
+// made absolute, math functions simplified as far as they can be without
+// layout, and colors take their computed form.
+pub fn compute_value(
+    property: &str,
+    value: &[ComponentValue],
+    context: &ComputeContext,
+) -> Vec<ComponentValue> {
+    if property == "font-size" {
+        if let Some(size) = compute_font_size(value, context) {
+            return parse_component_values(&Length::Px(size).to_string());
+        }
+    }
+
+    // https://drafts.csswg.org/css-color-4/#resolving-color-values
+    if property == "color" || property.ends_with("-color") {
+        if let Some(color) = Color::parse(value) {
+            return parse_component_values(&color.to_string());
+        }
+    }
+
+    let trimmed: Vec<&ComponentValue> = value.iter().filter(|v| !v.is_whitespace()).collect();
+    match trimmed[..] {
+        // https://drafts.csswg.org/css-backgrounds-3/#border-width
+        [ComponentValue::Token(CssToken::Ident(keyword))]
+            if property.starts_with("border-") && property.ends_with("-width") =>
+        {
+            let width = match keyword.to_ascii_lowercase().as_str() {
+                "thin" => 1.0,
+                "medium" => 3.0,
+                "thick" => 5.0,
+                _ => return value.to_vec(),
+            };
+            return parse_component_values(&Length::Px(width).to_string());
+        }
+        // https://drafts.csswg.org/css-inline-3/#line-height-property
+        [ComponentValue::Token(CssToken::Percentage(p))] if property == "line-height" => {
+            let height = Length::Px(p * context.font_size / 100.0);
+            return parse_component_values(&height.to_string());
+        }
+        [value @ ComponentValue::Token(CssToken::Number { .. })]
+            if is_length_property(property) =>
+        {
+            if let Some(length) = Length::parse(value) {
+                return parse_component_values(&length.to_string());
+            }
+        }
+        _ => {}
+    }
+
+    value
+        .iter()
+        .map(|v| compute_component(v, context))
+        .collect()
+}
+
+fn compute_component(value: &ComponentValue, context: &ComputeContext) -> ComponentValue {
+    match value {
+        ComponentValue::Token(CssToken::Dimension { value: v, unit, .. }) => {
+            match Length::new(*v, unit) {
+                Some(length) => ComponentValue::Token(CssToken::Dimension {
+                    value: round(length.to_px(context)),
+                    integer: false,
+                    unit: String::from("px"),
+                }),
+                None => value.clone(),
+            }
+        }
+        ComponentValue::Function(name, arguments) => {
+            if let Some(calc) = CalcNode::parse_function(name, arguments) {
+                let css = calc.simplify(context, None).to_css();
+                if let Some(value) = parse_component_values(&css).pop() {
+                    return value;
+                }
+            }
+            ComponentValue::Function(
+                name.clone(),
+                arguments
+                    .iter()
+                    .map(|v| compute_component(v, context))
+                    .collect(),
+            )
+        }
+        ComponentValue::Block(open, values) => ComponentValue::Block(
+            *open,
+            values
+                .iter()
+                .map(|v| compute_component(v, context))
+                .collect(),
+        ),
+        ComponentValue::Token(_) => value.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::css::parse_declaration_list;
+
+    fn compute(property: &str, css: &str) -> String {
+        let context = ComputeContext {
+            font_size: 10.0,
+            root_font_size: 20.0,
+            viewport: Viewport {
+                width: 1000.0,
+                height: 500.0,
+            },
+        };
+        let value = compute_value(property, &parse_component_values(css), &context);
+        crate::parser::css::serialize_values(&value)
+    }
+
+    #[test]
+    fn test_compute_lengths() {
+        assert_eq!(compute("width", "2em"), "20px");
+        assert_eq!(compute("width", "1.5rem"), "30px");
+        assert_eq!(compute("width", "10vw"), "100px");
+        assert_eq!(compute("height", "10vh"), "50px");
+        assert_eq!(compute("height", "10vmax"), "100px");
+        assert_eq!(compute("width", "3ch"), "15px");
+        assert_eq!(compute("width", "1in"), "96px");
+        assert_eq!(compute("width", "12pt"), "16px");
+        assert_eq!(compute("width", "50%"), "50%");
+        assert_eq!(compute("width", "auto"), "auto");
+        assert_eq!(compute("margin-top", "0"), "0px");
+        assert_eq!(compute("opacity", "0"), "0");
+        assert_eq!(compute("width", "calc(100% - 2em)"), "calc(100% - 20px)");
+        assert_eq!(compute("width", "calc(1em + 1rem)"), "30px");
+        assert_eq!(compute("width", "min(10vw, 1em)"), "10px");
+        assert_eq!(compute("border-top-width", "thick"), "5px");
+        assert_eq!(compute("line-height", "150%"), "15px");
+        assert_eq!(compute("line-height", "1.5"), "1.5");
+        assert_eq!(compute("border-spacing", "1em 2px"), "10px 2px");
+    }
+
+    #[test]
+    fn test_compute_font_size() {
+        // relative to the parent's size
+        assert_eq!(compute("font-size", "2em"), "20px");
+        assert_eq!(compute("font-size", "150%"), "15px");
+        assert_eq!(compute("font-size", "2rem"), "40px");
+        assert_eq!(compute("font-size", "medium"), "16px");
+        assert_eq!(compute("font-size", "xx-large"), "32px");
+        assert_eq!(compute("font-size", "larger"), "12px");
+        assert_eq!(compute("font-size", "calc(50% + 1em)"), "15px");
+    }
+
+    #[test]
+    fn test_compute_colors() {
+        assert_eq!(compute("color", "red"), "rgb(255, 0, 0)");
+        assert_eq!(
+            compute("background-color", "#00f8"),
+            "rgba(0, 0, 255, 0.533)"
+        );
+        assert_eq!(compute("border-top-color", "currentColor"), "currentcolor");
+        assert_eq!(compute("color", "oklch(50% 0.1 180)"), "oklch(0.5 0.1 180)");
+        // a value that isn't a color never gets this far
+        assert!(parse_declaration_list("color: nonsense").is_empty());
+    }
+
+    #[test]
+    fn test_length_percentage() {
+        let parse = |css| LengthPercentage::parse(&parse_component_values(css)).unwrap();
+        assert_eq!(parse("10px"), LengthPercentage::Length(10.0));
+        assert_eq!(parse("0"), LengthPercentage::Length(0.0));
+        assert_eq!(parse("25%").resolve(200.0), 50.0);
+        assert_eq!(parse("calc(50% - 10px)").resolve(200.0), 90.0);
+        assert_eq!(parse("max(50%, 120px)").resolve(200.0), 120.0);
+        assert!(LengthPercentage::parse(&parse_component_values("calc(1 + 2)")).is_none());
+        assert!(LengthPercentage::parse(&parse_component_values("auto")).is_none());
+    }
+}
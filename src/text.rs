@@ -0,0 +1,356 @@
+use crate::element::HtmlElementKind;
+use crate::mutation_observer::{queue_mutation_record, MutationRecord, MutationRecordType};
+use crate::node::{DomException, Node, NodeKind};
+use crate::range::{adjust_for_replace_data, adjust_for_split, substring};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+impl Node {
+    // https://dom.spec.whatwg.org/#dom-node-textcontent
+    //
+    // None for documents and doctypes.
+    pub fn text_content(node: &Rc<RefCell<Node>>) -> Option<String> {
+        match node.borrow().kind {
+            NodeKind::Document(_) | NodeKind::DocumentType(_) => None,
+            NodeKind::Text(ref s) | NodeKind::Comment(ref s) => Some(s.clone()),
+            NodeKind::Element(_) | NodeKind::DocumentFragment => {
+                Some(Self::descendant_text_content(node))
+            }
+        }
+    }
+
+    // https://dom.spec.whatwg.org/#concept-descendant-text-content
+    fn descendant_text_content(node: &Rc<RefCell<Node>>) -> String {
+        Self::descendants(node)
+            .filter_map(|n| match n.borrow().kind {
+                NodeKind::Text(ref s) => Some(s.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // https://dom.spec.whatwg.org/#string-replace-all
+    //
+    // Replaces the children of an element or fragment with a single text
+    // node, or none when `value` is empty. Text and comments get `value` as
+    // their data.
+    pub fn set_text_content(node: &Rc<RefCell<Node>>, value: &str) {
+        if !node.borrow().is_element() && !node.borrow().is_document_fragment() {
+            Self::set_data(node, value);
+            return;
+        }
+
+        for child in Self::children(node).collect::<Vec<_>>() {
+            Self::detach(&child);
+        }
+        if !value.is_empty() {
+            let text = Rc::new(RefCell::new(Node::new(NodeKind::Text(value.to_string()))));
+            Self::insert(node, &text, None);
+        }
+    }
+
+    // Replaces the whole data of a text or comment node. Does nothing for
+    // other nodes.
+    pub fn set_data(node: &Rc<RefCell<Node>>, value: &str) {
+        let length = match node.borrow().kind {
+            NodeKind::Text(ref s) | NodeKind::Comment(ref s) => s.chars().count(),
+            _ => return,
+        };
+        Self::replace_data(node, 0, length, value);
+    }
+
+    // https://dom.spec.whatwg.org/#concept-cd-replace
+    //
+    // Replaces `count` chars from `offset` with `data`, clamping both to the
+    // current data. Does nothing for nodes other than text and comments.
+    pub fn replace_data(node: &Rc<RefCell<Node>>, offset: usize, count: usize, data: &str) {
+        let old_value = match node.borrow_mut().kind {
+            NodeKind::Text(ref mut s) | NodeKind::Comment(ref mut s) => {
+                let old_value = s.clone();
+                let length = old_value.chars().count();
+                let offset = offset.min(length);
+                let count = count.min(length - offset);
+                *s = format!(
+                    "{}{}{}",
+                    substring(&old_value, 0, offset),
+                    data,
+                    substring(&old_value, offset + count, length)
+                );
+                old_value
+            }
+            _ => return,
+        };
+        queue_mutation_record(
+            MutationRecord::new(MutationRecordType::CharacterData, node),
+            Some(old_value.clone()),
+        );
+        if let Some(document) = Self::connected_document(node) {
+            if let NodeKind::Document(ref mut d) = document.borrow_mut().kind {
+                d.bump_version();
+            }
+            Self::invalidate_character_data(&document, node);
+        }
+
+        let length = old_value.chars().count();
+        let offset = offset.min(length);
+        adjust_for_replace_data(
+            node,
+            offset,
+            count.min(length - offset),
+            data.chars().count(),
+        );
+    }
+
+    // https://dom.spec.whatwg.org/#concept-text-split
+    //
+    // Moves the data after `offset` into a new text node inserted after
+    // `node`, and returns the new node.
+    pub fn split_text(
+        node: &Rc<RefCell<Node>>,
+        offset: usize,
+    ) -> Result<Rc<RefCell<Node>>, DomException> {
+        let data = match node.borrow().kind {
+            NodeKind::Text(ref s) => s.clone(),
+            _ => return Err(DomException::NotSupportedError),
+        };
+        let length = data.chars().count();
+        if offset > length {
+            return Err(DomException::IndexSizeError);
+        }
+
+        let new_node = Rc::new(RefCell::new(Node::new(NodeKind::Text(substring(
+            &data,
+            offset,
+            length - offset,
+        )))));
+        if let Some(document) = Self::node_document(node) {
+            Self::adopt(&document, &new_node)?;
+        }
+        let parent = node.borrow().parent();
+        if let Some(parent) = parent {
+            let next_sibling = node.borrow().next_sibling();
+            Self::insert(&parent, &new_node, next_sibling.as_ref());
+            adjust_for_split(node, &new_node, offset);
+        }
+
+        Self::replace_data(node, offset, length - offset, "");
+        Ok(new_node)
+    }
+
+    // https://html.spec.whatwg.org/multipage/dom.html#the-innertext-idl-attribute
+    //
+    // Without layout this approximates the rendered text: elements that the
+    // default styles hide are skipped, whitespace is collapsed and block
+    // level elements are separated by line breaks.
+    pub fn inner_text(node: &Rc<RefCell<Node>>) -> String {
+        let mut items = Vec::new();
+        for child in Self::children(node) {
+            collect_rendered_text(&child, &mut items);
+        }
+
+        let mut output = String::new();
+        let mut pending_space = false;
+        let mut pending_line_breaks = 0;
+        let mut at_line_start = true;
+        for item in items {
+            match item {
+                RenderedText::Text(s) => {
+                    for c in s.chars() {
+                        if c == ' ' {
+                            pending_space = !at_line_start;
+                            continue;
+                        }
+                        if !output.is_empty() {
+                            output.push_str(&"\n".repeat(pending_line_breaks));
+                        }
+                        if pending_space && pending_line_breaks == 0 {
+                            output.push(' ');
+                        }
+                        output.push(c);
+                        pending_space = false;
+                        pending_line_breaks = 0;
+                        at_line_start = false;
+                    }
+                }
+                RenderedText::LineBreak => {
+                    output.push_str(&"\n".repeat(pending_line_breaks));
+                    output.push('\n');
+                    pending_space = false;
+                    pending_line_breaks = 0;
+                    at_line_start = true;
+                }
+                RenderedText::RequiredLineBreaks(count) => {
+                    pending_line_breaks = pending_line_breaks.max(count);
+                    pending_space = false;
+                    at_line_start = true;
+                }
+            }
+        }
+        output
+    }
+
+    // https://dom.spec.whatwg.org/#dom-node-normalize
+    pub fn normalize(node: &Rc<RefCell<Node>>) {
+        let texts: Vec<_> = Self::descendants(node)
+            .filter(|n| matches!(n.borrow().kind, NodeKind::Text(_)))
+            .collect();
+
+        for text in texts {
+            // already merged into a previous text node
+            if text.borrow().parent().is_none() {
+                continue;
+            }
+
+            let mut data = match text.borrow().kind {
+                NodeKind::Text(ref s) => s.clone(),
+                _ => unreachable!(),
+            };
+            if data.is_empty() {
+                Self::detach(&text);
+                continue;
+            }
+
+            let contiguous: Vec<_> = Self::following_siblings(&text)
+                .take_while(|n| matches!(n.borrow().kind, NodeKind::Text(_)))
+                .collect();
+            if contiguous.is_empty() {
+                continue;
+            }
+            for sibling in contiguous {
+                if let NodeKind::Text(ref s) = sibling.borrow().kind {
+                    data.push_str(s);
+                }
+                Self::detach(&sibling);
+            }
+            Self::set_data(&text, &data);
+        }
+    }
+}
+
+enum RenderedText {
+    // whitespace already collapsed to single spaces
+    Text(String),
+    // a <br>
+    LineBreak,
+    // separation around block level elements, collapsed with adjacent ones
+    RequiredLineBreaks(usize),
+}
+
+// https://html.spec.whatwg.org/multipage/dom.html#rendered-text-collection-steps
+fn collect_rendered_text(node: &Rc<RefCell<Node>>, items: &mut Vec<RenderedText>) {
+    let kind = match node.borrow().kind {
+        NodeKind::Text(ref s) => {
+            let collapsed = s
+                .split(|c: char| c.is_ascii_whitespace())
+                .collect::<Vec<_>>()
+                .join(" ");
+            items.push(RenderedText::Text(collapsed));
+            return;
+        }
+        NodeKind::Element(ref e) => e.kind().clone(),
+        _ => return,
+    };
+
+    let line_breaks = match kind {
+        HtmlElementKind::Head
+        | HtmlElementKind::Title
+        | HtmlElementKind::Meta
+        | HtmlElementKind::Link
+        | HtmlElementKind::Style
+        | HtmlElementKind::Script
+        | HtmlElementKind::Template => return,
+        HtmlElementKind::Br => {
+            items.push(RenderedText::LineBreak);
+            return;
+        }
+        HtmlElementKind::P => 2,
+        HtmlElementKind::Html
+        | HtmlElementKind::Body
+        | HtmlElementKind::Div
+        | HtmlElementKind::H1
+        | HtmlElementKind::H2
+        | HtmlElementKind::Hr => 1,
+        _ => 0,
+    };
+
+    if line_breaks > 0 {
+        items.push(RenderedText::RequiredLineBreaks(line_breaks));
+    }
+    for child in Node::children(node) {
+        collect_rendered_text(&child, items);
+    }
+    if line_breaks > 0 {
+        items.push(RenderedText::RequiredLineBreaks(line_breaks));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::element::Element;
+    use crate::parser::html_parser::parse;
+
+    fn text(s: &str) -> Rc<RefCell<Node>> {
+        Rc::new(RefCell::new(Node::new(NodeKind::Text(s.to_string()))))
+    }
+
+    #[test]
+    fn test_text_content() {
+        let document = parse(
+            "<html><head><title>t</title></head><body><div id=a>one <span>two</span><!--c--> three</div></body></html>",
+        );
+        let div = Node::get_element_by_id(&document, "a").unwrap();
+
+        assert_eq!(Node::text_content(&document), None);
+        assert_eq!(Node::text_content(&div).unwrap(), "one two three");
+        let comment = Node::children(&div).nth(2).unwrap();
+        assert_eq!(Node::text_content(&comment).unwrap(), "c");
+
+        Node::set_text_content(&div, "<b>");
+        assert_eq!(Node::inner_html(&div), "&lt;b&gt;");
+        Node::set_text_content(&div, "");
+        assert!(!div.borrow().has_child_nodes());
+    }
+
+    #[test]
+    fn test_inner_text() {
+        let document = parse(
+            "<html><head><title>hidden</title><style>p {}</style></head>\
+             <body>  <h1>Title</h1>\n  <div>first   line<br>second <span>line</span> </div>\
+             <p>para one</p><p> para two </p><script>var x;</script>tail</body></html>",
+        );
+
+        assert_eq!(
+            Node::inner_text(&document),
+            "Title\nfirst line\nsecond line\n\npara one\n\npara two\n\ntail"
+        );
+    }
+
+    #[test]
+    fn test_normalize() {
+        let document = parse("<html><body><div id=a>a</div></body></html>");
+        let div = Node::get_element_by_id(&document, "a").unwrap();
+        let span = Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new(
+            HtmlElementKind::Span,
+        )))));
+        Node::append_child_node(&div, &text("")).unwrap();
+        Node::append_child_node(&div, &text("b")).unwrap();
+        Node::append_child_node(&div, &span).unwrap();
+        Node::append_child_node(&div, &text("")).unwrap();
+        Node::append_child_node(&span, &text("c")).unwrap();
+        Node::append_child_node(&span, &text("d")).unwrap();
+
+        Node::normalize(&div);
+
+        let kinds: Vec<_> = Node::children(&div).map(|n| n.borrow().kind()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                NodeKind::Text(String::from("ab")),
+                NodeKind::Element(Element::new(HtmlElementKind::Span)),
+            ]
+        );
+        assert_eq!(Node::inner_html(&span), "cd");
+        assert_eq!(Node::children(&span).count(), 1);
+    }
+}
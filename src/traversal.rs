@@ -0,0 +1,328 @@
+use crate::node::Node;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// Iterators over the node tree. All of them hold `Rc`s rather than borrows,
+// so the tree may be inspected (but not mutated around the cursor) while
+// iterating.
+
+// Children of a node, front to back. Iterate with `.rev()` for back to front.
+pub struct Children {
+    front: Option<Rc<RefCell<Node>>>,
+    back: Option<Rc<RefCell<Node>>>,
+}
+
+impl Iterator for Children {
+    type Item = Rc<RefCell<Node>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.front.take()?;
+        if self.back.as_ref().is_some_and(|b| Rc::ptr_eq(b, &node)) {
+            self.back = None;
+        } else {
+            self.front = node.borrow().next_sibling();
+        }
+        Some(node)
+    }
+}
+
+impl DoubleEndedIterator for Children {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let node = self.back.take()?;
+        if self.front.as_ref().is_some_and(|f| Rc::ptr_eq(f, &node)) {
+            self.front = None;
+        } else {
+            self.back = node.borrow().previous_sibling();
+        }
+        Some(node)
+    }
+}
+
+// Ancestors of a node, nearest first.
+pub struct Ancestors {
+    next: Option<Rc<RefCell<Node>>>,
+}
+
+impl Iterator for Ancestors {
+    type Item = Rc<RefCell<Node>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next.take()?;
+        self.next = node.borrow().parent();
+        Some(node)
+    }
+}
+
+// Siblings after (or before) a node, moving away from it.
+pub struct Siblings {
+    next: Option<Rc<RefCell<Node>>>,
+    forward: bool,
+}
+
+impl Iterator for Siblings {
+    type Item = Rc<RefCell<Node>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next.take()?;
+        self.next = if self.forward {
+            node.borrow().next_sibling()
+        } else {
+            node.borrow().previous_sibling()
+        };
+        Some(node)
+    }
+}
+
+// Descendants of a root in tree order (pre-order, depth-first).
+pub struct Descendants {
+    root: Rc<RefCell<Node>>,
+    next: Option<Rc<RefCell<Node>>>,
+}
+
+impl Iterator for Descendants {
+    type Item = Rc<RefCell<Node>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next.take()?;
+        self.next = Node::following_within(&node, &self.root);
+        Some(node)
+    }
+}
+
+// Descendants of a root in reverse tree order, the last descendant first.
+pub struct ReverseDescendants {
+    root: Rc<RefCell<Node>>,
+    next: Option<Rc<RefCell<Node>>>,
+}
+
+impl Iterator for ReverseDescendants {
+    type Item = Rc<RefCell<Node>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next.take()?;
+        self.next =
+            Node::preceding_within(&node, &self.root).filter(|n| !Rc::ptr_eq(n, &self.root));
+        Some(node)
+    }
+}
+
+impl Node {
+    pub fn children(node: &Rc<RefCell<Node>>) -> Children {
+        let n = node.borrow();
+        Children {
+            front: n.first_child(),
+            back: n.last_child(),
+        }
+    }
+
+    // Ancestors of `node`, excluding `node` itself.
+    pub fn ancestors(node: &Rc<RefCell<Node>>) -> Ancestors {
+        Ancestors {
+            next: node.borrow().parent(),
+        }
+    }
+
+    pub fn inclusive_ancestors(node: &Rc<RefCell<Node>>) -> Ancestors {
+        Ancestors {
+            next: Some(node.clone()),
+        }
+    }
+
+    pub fn following_siblings(node: &Rc<RefCell<Node>>) -> Siblings {
+        Siblings {
+            next: node.borrow().next_sibling(),
+            forward: true,
+        }
+    }
+
+    pub fn preceding_siblings(node: &Rc<RefCell<Node>>) -> Siblings {
+        Siblings {
+            next: node.borrow().previous_sibling(),
+            forward: false,
+        }
+    }
+
+    // Descendants of `node` in tree order, excluding `node` itself.
+    pub fn descendants(node: &Rc<RefCell<Node>>) -> Descendants {
+        Descendants {
+            root: node.clone(),
+            next: node.borrow().first_child(),
+        }
+    }
+
+    // `node` followed by its descendants in tree order.
+    pub fn inclusive_descendants(node: &Rc<RefCell<Node>>) -> Descendants {
+        Descendants {
+            root: node.clone(),
+            next: Some(node.clone()),
+        }
+    }
+
+    // Descendants of `node` in reverse tree order, excluding `node` itself.
+    pub fn reverse_descendants(node: &Rc<RefCell<Node>>) -> ReverseDescendants {
+        let last = Self::last_inclusive_descendant(node);
+        ReverseDescendants {
+            root: node.clone(),
+            next: if Rc::ptr_eq(&last, node) {
+                None
+            } else {
+                Some(last)
+            },
+        }
+    }
+
+    // the node following `node` in tree order, without leaving `root`
+    pub(crate) fn following_within(
+        node: &Rc<RefCell<Node>>,
+        root: &Rc<RefCell<Node>>,
+    ) -> Option<Rc<RefCell<Node>>> {
+        if let Some(child) = node.borrow().first_child() {
+            return Some(child);
+        }
+        Self::following_skipping_children(node, root)
+    }
+
+    // the node following `node` in tree order that is not one of its
+    // descendants, without leaving `root`
+    pub(crate) fn following_skipping_children(
+        node: &Rc<RefCell<Node>>,
+        root: &Rc<RefCell<Node>>,
+    ) -> Option<Rc<RefCell<Node>>> {
+        for n in Self::inclusive_ancestors(node) {
+            if Rc::ptr_eq(&n, root) {
+                return None;
+            }
+            if let Some(sibling) = n.borrow().next_sibling() {
+                return Some(sibling);
+            }
+        }
+        None
+    }
+
+    // the node preceding `node` in tree order, without leaving `root`
+    pub(crate) fn preceding_within(
+        node: &Rc<RefCell<Node>>,
+        root: &Rc<RefCell<Node>>,
+    ) -> Option<Rc<RefCell<Node>>> {
+        if Rc::ptr_eq(node, root) {
+            return None;
+        }
+        if let Some(sibling) = node.borrow().previous_sibling() {
+            return Some(Self::last_inclusive_descendant(&sibling));
+        }
+        node.borrow().parent()
+    }
+
+    fn last_inclusive_descendant(node: &Rc<RefCell<Node>>) -> Rc<RefCell<Node>> {
+        let mut current = node.clone();
+        loop {
+            let last = current.borrow().last_child();
+            match last {
+                Some(l) => current = l,
+                None => return current,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::element::{Element, HtmlElementKind};
+    use crate::node::NodeKind;
+
+    fn new_node(kind: NodeKind) -> Rc<RefCell<Node>> {
+        Rc::new(RefCell::new(Node::new(kind)))
+    }
+
+    fn text(parent: &Rc<RefCell<Node>>, s: &str) -> Rc<RefCell<Node>> {
+        let node = new_node(NodeKind::Text(String::from(s)));
+        Node::append_child_node(parent, &node).unwrap();
+        node
+    }
+
+    fn element(parent: &Rc<RefCell<Node>>, kind: HtmlElementKind) -> Rc<RefCell<Node>> {
+        let node = new_node(NodeKind::Element(Element::new(kind)));
+        Node::append_child_node(parent, &node).unwrap();
+        node
+    }
+
+    fn names(nodes: impl Iterator<Item = Rc<RefCell<Node>>>) -> Vec<String> {
+        nodes
+            .map(|n| match n.borrow().kind {
+                NodeKind::Element(ref e) => e.to_string(),
+                NodeKind::Text(ref s) => s.clone(),
+                _ => String::from("?"),
+            })
+            .collect()
+    }
+
+    // <div>a<p>b<span>c</span></p>d<h1></h1></div>
+    fn tree() -> (Rc<RefCell<Node>>, Rc<RefCell<Node>>) {
+        let div = new_node(NodeKind::Element(Element::new(HtmlElementKind::Div)));
+        text(&div, "a");
+        let p = element(&div, HtmlElementKind::P);
+        text(&p, "b");
+        let span = element(&p, HtmlElementKind::Span);
+        text(&span, "c");
+        text(&div, "d");
+        element(&div, HtmlElementKind::H1);
+        (div, span)
+    }
+
+    #[test]
+    fn test_children() {
+        let (div, _) = tree();
+        assert_eq!(names(Node::children(&div)), vec!["a", "p", "d", "h1"]);
+        assert_eq!(names(Node::children(&div).rev()), vec!["h1", "d", "p", "a"]);
+
+        // both ends meet in the middle without yielding a node twice
+        let mut children = Node::children(&div);
+        children.next();
+        children.next_back();
+        assert_eq!(names(children), vec!["p", "d"]);
+    }
+
+    #[test]
+    fn test_descendants() {
+        let (div, _) = tree();
+        assert_eq!(
+            names(Node::descendants(&div)),
+            vec!["a", "p", "b", "span", "c", "d", "h1"]
+        );
+        assert_eq!(
+            names(Node::inclusive_descendants(&div)),
+            vec!["div", "a", "p", "b", "span", "c", "d", "h1"]
+        );
+        assert_eq!(
+            names(Node::reverse_descendants(&div)),
+            vec!["h1", "d", "c", "span", "b", "p", "a"]
+        );
+    }
+
+    #[test]
+    fn test_descendants_stay_within_root() {
+        let (div, span) = tree();
+        let p = span.borrow().parent().unwrap();
+        assert_eq!(names(Node::descendants(&p)), vec!["b", "span", "c"]);
+        assert_eq!(names(Node::reverse_descendants(&p)), vec!["c", "span", "b"]);
+        assert_eq!(
+            names(Node::descendants(&div.borrow().last_child().unwrap())).len(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_ancestors_and_siblings() {
+        let (_div, span) = tree();
+        assert_eq!(names(Node::ancestors(&span)), vec!["p", "div"]);
+        assert_eq!(
+            names(Node::inclusive_ancestors(&span)),
+            vec!["span", "p", "div"]
+        );
+
+        let p = span.borrow().parent().unwrap();
+        assert_eq!(names(Node::following_siblings(&p)), vec!["d", "h1"]);
+        assert_eq!(names(Node::preceding_siblings(&p)), vec!["a"]);
+    }
+}
@@ -0,0 +1,131 @@
+use crate::node::{Node, NodeKind};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// Human readable tree dumps in the format used by the html5lib tree
+// construction tests:
+//
+// | <!DOCTYPE html>
+// | <html>
+// |   <head>
+// |   <body>
+// |     <p>
+// |       class="intro"
+// |       "hello"
+// |     <template>
+// |       content
+// |         "world"
+impl Node {
+    // Dumps the descendants of `node`, one line per node or attribute.
+    pub fn dump_tree(node: &Rc<RefCell<Node>>) -> String {
+        let mut output = String::new();
+        for child in Node::children(node) {
+            dump_node(&child, 0, &mut output);
+        }
+        output
+    }
+}
+
+fn dump_node(node: &Rc<RefCell<Node>>, depth: usize, output: &mut String) {
+    let indent = "  ".repeat(depth);
+
+    match node.borrow().kind {
+        NodeKind::Document(_) | NodeKind::DocumentFragment => {}
+        NodeKind::DocumentType(ref name) => {
+            output.push_str(&format!("| {}<!DOCTYPE {}>\n", indent, name));
+        }
+        NodeKind::Element(ref e) => {
+            output.push_str(&format!("| {}<{}>\n", indent, e.to_string()));
+
+            let mut attributes: Vec<_> = e.attributes().iter().collect();
+            attributes.sort_by(|a, b| a.name().cmp(b.name()));
+            for attribute in attributes {
+                output.push_str(&format!(
+                    "| {}  {}=\"{}\"\n",
+                    indent,
+                    attribute.name(),
+                    attribute.value()
+                ));
+            }
+        }
+        NodeKind::Text(ref s) => {
+            output.push_str(&format!("| {}\"{}\"\n", indent, s));
+        }
+        NodeKind::Comment(ref s) => {
+            output.push_str(&format!("| {}<!-- {} -->\n", indent, s));
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/scripting.html#template-contents
+    let contents = node.borrow().template_contents();
+    if let Some(contents) = contents {
+        output.push_str(&format!("| {}  content\n", indent));
+        for child in Node::children(&contents) {
+            dump_node(&child, depth + 2, output);
+        }
+    }
+    for child in Node::children(node) {
+        dump_node(&child, depth + 1, output);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::html_parser::parse;
+
+    #[test]
+    fn test_dump_tree() {
+        let document = parse(
+            "<!DOCTYPE html><html><head><title>mmbr</title></head>\
+             <body><!--c--><div id=main class=box><p>hello</p>world</div></body></html>",
+        );
+
+        assert_eq!(
+            Node::dump_tree(&document),
+            "| <!DOCTYPE html>\n\
+             | <html>\n\
+             |   <head>\n\
+             |     <title>\n\
+             |       \"mmbr\"\n\
+             |   <body>\n\
+             |     <!-- c -->\n\
+             |     <div>\n\
+             |       class=\"box\"\n\
+             |       id=\"main\"\n\
+             |       <p>\n\
+             |         \"hello\"\n\
+             |       \"world\"\n"
+        );
+    }
+
+    #[test]
+    fn test_dump_implied_elements() {
+        let document = parse("hi");
+
+        assert_eq!(
+            Node::dump_tree(&document),
+            "| <html>\n\
+             |   <head>\n\
+             |   <body>\n\
+             |     \"hi\"\n"
+        );
+    }
+
+    #[test]
+    fn test_dump_template_contents() {
+        let document = parse("<template id=t><p>a</p></template>");
+
+        assert_eq!(
+            Node::dump_tree(&document),
+            "| <html>\n\
+             |   <head>\n\
+             |   <body>\n\
+             |     <template>\n\
+             |       id=\"t\"\n\
+             |       content\n\
+             |         <p>\n\
+             |           \"a\"\n"
+        );
+    }
+}
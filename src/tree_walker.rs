@@ -0,0 +1,482 @@
+use crate::node::Node;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// https://dom.spec.whatwg.org/#interface-nodefilter
+pub const SHOW_ALL: u32 = 0xFFFFFFFF;
+pub const SHOW_ELEMENT: u32 = 0x1;
+pub const SHOW_TEXT: u32 = 0x4;
+pub const SHOW_COMMENT: u32 = 0x80;
+pub const SHOW_DOCUMENT: u32 = 0x100;
+pub const SHOW_DOCUMENT_TYPE: u32 = 0x200;
+pub const SHOW_DOCUMENT_FRAGMENT: u32 = 0x400;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FilterResult {
+    Accept,
+    Reject,
+    Skip,
+}
+
+pub type NodeFilter = Box<dyn Fn(&Rc<RefCell<Node>>) -> FilterResult>;
+
+// https://dom.spec.whatwg.org/#concept-node-filter
+fn filter_node(
+    node: &Rc<RefCell<Node>>,
+    what_to_show: u32,
+    filter: &Option<NodeFilter>,
+) -> FilterResult {
+    let n = node.borrow().node_type() - 1;
+    if what_to_show & (1 << n) == 0 {
+        return FilterResult::Skip;
+    }
+    match filter {
+        Some(f) => f(node),
+        None => FilterResult::Accept,
+    }
+}
+
+// https://dom.spec.whatwg.org/#interface-treewalker
+pub struct TreeWalker {
+    root: Rc<RefCell<Node>>,
+    what_to_show: u32,
+    filter: Option<NodeFilter>,
+    current_node: Rc<RefCell<Node>>,
+}
+
+impl TreeWalker {
+    pub fn new(root: &Rc<RefCell<Node>>, what_to_show: u32, filter: Option<NodeFilter>) -> Self {
+        Self {
+            root: root.clone(),
+            what_to_show,
+            filter,
+            current_node: root.clone(),
+        }
+    }
+
+    pub fn root(&self) -> Rc<RefCell<Node>> {
+        self.root.clone()
+    }
+
+    pub fn current_node(&self) -> Rc<RefCell<Node>> {
+        self.current_node.clone()
+    }
+
+    pub fn set_current_node(&mut self, node: &Rc<RefCell<Node>>) {
+        self.current_node = node.clone();
+    }
+
+    fn filter(&self, node: &Rc<RefCell<Node>>) -> FilterResult {
+        filter_node(node, self.what_to_show, &self.filter)
+    }
+
+    fn accept(&mut self, node: Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+        self.current_node = node.clone();
+        Some(node)
+    }
+
+    // https://dom.spec.whatwg.org/#dom-treewalker-parentnode
+    pub fn parent_node(&mut self) -> Option<Rc<RefCell<Node>>> {
+        let mut node = Some(self.current_node.clone());
+        while let Some(n) = node {
+            if Rc::ptr_eq(&n, &self.root) {
+                break;
+            }
+            node = n.borrow().parent();
+            if let Some(ref p) = node {
+                if self.filter(p) == FilterResult::Accept {
+                    return self.accept(p.clone());
+                }
+            }
+        }
+        None
+    }
+
+    pub fn first_child(&mut self) -> Option<Rc<RefCell<Node>>> {
+        self.traverse_children(true)
+    }
+
+    pub fn last_child(&mut self) -> Option<Rc<RefCell<Node>>> {
+        self.traverse_children(false)
+    }
+
+    pub fn next_sibling(&mut self) -> Option<Rc<RefCell<Node>>> {
+        self.traverse_siblings(true)
+    }
+
+    pub fn previous_sibling(&mut self) -> Option<Rc<RefCell<Node>>> {
+        self.traverse_siblings(false)
+    }
+
+    // https://dom.spec.whatwg.org/#concept-traverse-children
+    fn traverse_children(&mut self, first: bool) -> Option<Rc<RefCell<Node>>> {
+        let child_of = |n: &Rc<RefCell<Node>>| {
+            if first {
+                n.borrow().first_child()
+            } else {
+                n.borrow().last_child()
+            }
+        };
+        let sibling_of = |n: &Rc<RefCell<Node>>| {
+            if first {
+                n.borrow().next_sibling()
+            } else {
+                n.borrow().previous_sibling()
+            }
+        };
+
+        let mut node = child_of(&self.current_node);
+        'outer: while let Some(n) = node.clone() {
+            match self.filter(&n) {
+                FilterResult::Accept => return self.accept(n),
+                FilterResult::Skip => {
+                    if let Some(child) = child_of(&n) {
+                        node = Some(child);
+                        continue;
+                    }
+                }
+                FilterResult::Reject => {}
+            }
+
+            let mut current = n;
+            loop {
+                if let Some(sibling) = sibling_of(&current) {
+                    node = Some(sibling);
+                    continue 'outer;
+                }
+                let parent = current.borrow().parent();
+                match parent {
+                    Some(p)
+                        if !Rc::ptr_eq(&p, &self.root) && !Rc::ptr_eq(&p, &self.current_node) =>
+                    {
+                        current = p
+                    }
+                    _ => return None,
+                }
+            }
+        }
+        None
+    }
+
+    // https://dom.spec.whatwg.org/#concept-traverse-siblings
+    fn traverse_siblings(&mut self, next: bool) -> Option<Rc<RefCell<Node>>> {
+        let sibling_of = |n: &Rc<RefCell<Node>>| {
+            if next {
+                n.borrow().next_sibling()
+            } else {
+                n.borrow().previous_sibling()
+            }
+        };
+        let child_of = |n: &Rc<RefCell<Node>>| {
+            if next {
+                n.borrow().first_child()
+            } else {
+                n.borrow().last_child()
+            }
+        };
+
+        let mut node = self.current_node.clone();
+        if Rc::ptr_eq(&node, &self.root) {
+            return None;
+        }
+
+        loop {
+            let mut sibling = sibling_of(&node);
+            while let Some(s) = sibling {
+                node = s;
+                let result = self.filter(&node);
+                if result == FilterResult::Accept {
+                    return self.accept(node);
+                }
+                sibling = child_of(&node);
+                if result == FilterResult::Reject || sibling.is_none() {
+                    sibling = sibling_of(&node);
+                }
+            }
+
+            let parent = node.borrow().parent();
+            match parent {
+                Some(p) if !Rc::ptr_eq(&p, &self.root) => node = p,
+                _ => return None,
+            }
+            if self.filter(&node) == FilterResult::Accept {
+                return None;
+            }
+        }
+    }
+
+    // https://dom.spec.whatwg.org/#dom-treewalker-previousnode
+    pub fn previous_node(&mut self) -> Option<Rc<RefCell<Node>>> {
+        let mut node = self.current_node.clone();
+        while !Rc::ptr_eq(&node, &self.root) {
+            let mut sibling = node.borrow().previous_sibling();
+            while let Some(s) = sibling {
+                node = s;
+                let mut result = self.filter(&node);
+                while result != FilterResult::Reject {
+                    let last_child = node.borrow().last_child();
+                    match last_child {
+                        Some(c) => {
+                            node = c;
+                            result = self.filter(&node);
+                        }
+                        None => break,
+                    }
+                }
+                if result == FilterResult::Accept {
+                    return self.accept(node);
+                }
+                sibling = node.borrow().previous_sibling();
+            }
+
+            let parent = node.borrow().parent();
+            if Rc::ptr_eq(&node, &self.root) {
+                return None;
+            }
+            node = parent?;
+            if self.filter(&node) == FilterResult::Accept {
+                return self.accept(node);
+            }
+        }
+        None
+    }
+
+    // https://dom.spec.whatwg.org/#dom-treewalker-nextnode
+    pub fn next_node(&mut self) -> Option<Rc<RefCell<Node>>> {
+        let mut node = self.current_node.clone();
+        let mut result = FilterResult::Accept;
+
+        loop {
+            while result != FilterResult::Reject {
+                let first_child = node.borrow().first_child();
+                match first_child {
+                    Some(c) => {
+                        node = c;
+                        result = self.filter(&node);
+                        if result == FilterResult::Accept {
+                            return self.accept(node);
+                        }
+                    }
+                    None => break,
+                }
+            }
+
+            node = Node::following_skipping_children(&node, &self.root)?;
+            result = self.filter(&node);
+            if result == FilterResult::Accept {
+                return self.accept(node);
+            }
+        }
+    }
+}
+
+// https://dom.spec.whatwg.org/#interface-nodeiterator
+//
+// Unlike the spec the iterator is not adjusted when its reference node is
+// removed from the tree; iteration then simply ends.
+pub struct NodeIterator {
+    root: Rc<RefCell<Node>>,
+    what_to_show: u32,
+    filter: Option<NodeFilter>,
+    reference_node: Rc<RefCell<Node>>,
+    pointer_before_reference_node: bool,
+}
+
+impl NodeIterator {
+    pub fn new(root: &Rc<RefCell<Node>>, what_to_show: u32, filter: Option<NodeFilter>) -> Self {
+        Self {
+            root: root.clone(),
+            what_to_show,
+            filter,
+            reference_node: root.clone(),
+            pointer_before_reference_node: true,
+        }
+    }
+
+    pub fn root(&self) -> Rc<RefCell<Node>> {
+        self.root.clone()
+    }
+
+    pub fn reference_node(&self) -> Rc<RefCell<Node>> {
+        self.reference_node.clone()
+    }
+
+    pub fn next_node(&mut self) -> Option<Rc<RefCell<Node>>> {
+        self.traverse(true)
+    }
+
+    pub fn previous_node(&mut self) -> Option<Rc<RefCell<Node>>> {
+        self.traverse(false)
+    }
+
+    // https://dom.spec.whatwg.org/#concept-nodeiterator-traverse
+    fn traverse(&mut self, next: bool) -> Option<Rc<RefCell<Node>>> {
+        let mut node = self.reference_node.clone();
+        let mut before_node = self.pointer_before_reference_node;
+
+        loop {
+            if next {
+                if !before_node {
+                    node = Node::following_within(&node, &self.root)?;
+                } else {
+                    before_node = false;
+                }
+            } else if before_node {
+                node = Node::preceding_within(&node, &self.root)?;
+            } else {
+                before_node = true;
+            }
+
+            if filter_node(&node, self.what_to_show, &self.filter) == FilterResult::Accept {
+                break;
+            }
+        }
+
+        self.reference_node = node.clone();
+        self.pointer_before_reference_node = before_node;
+        Some(node)
+    }
+}
+
+impl Iterator for NodeIterator {
+    type Item = Rc<RefCell<Node>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_node()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::element::{Element, HtmlElementKind};
+    use crate::node::NodeKind;
+
+    fn append(parent: &Rc<RefCell<Node>>, kind: NodeKind) -> Rc<RefCell<Node>> {
+        let node = Rc::new(RefCell::new(Node::new(kind)));
+        Node::append_child_node(parent, &node).unwrap();
+        node
+    }
+
+    fn name(node: &Rc<RefCell<Node>>) -> String {
+        match node.borrow().kind {
+            NodeKind::Element(ref e) => e.to_string(),
+            NodeKind::Text(ref s) => s.clone(),
+            NodeKind::Comment(ref s) => format!("<!--{}-->", s),
+            _ => String::from("?"),
+        }
+    }
+
+    // <div>a<p>b<!--c--><span>d</span></p><h1>e</h1></div>
+    fn tree() -> Rc<RefCell<Node>> {
+        let div = Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new(
+            HtmlElementKind::Div,
+        )))));
+        append(&div, NodeKind::Text(String::from("a")));
+        let p = append(&div, NodeKind::Element(Element::new(HtmlElementKind::P)));
+        append(&p, NodeKind::Text(String::from("b")));
+        append(&p, NodeKind::Comment(String::from("c")));
+        let span = append(&p, NodeKind::Element(Element::new(HtmlElementKind::Span)));
+        append(&span, NodeKind::Text(String::from("d")));
+        let h1 = append(&div, NodeKind::Element(Element::new(HtmlElementKind::H1)));
+        append(&h1, NodeKind::Text(String::from("e")));
+        div
+    }
+
+    fn is_kind(kind: HtmlElementKind) -> impl Fn(&Rc<RefCell<Node>>) -> bool {
+        move |n| match n.borrow().kind {
+            NodeKind::Element(ref e) => *e.kind() == kind,
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn test_tree_walker_what_to_show() {
+        let root = tree();
+        let mut walker = TreeWalker::new(&root, SHOW_ELEMENT, None);
+        let mut visited = Vec::new();
+        while let Some(n) = walker.next_node() {
+            visited.push(name(&n));
+        }
+        assert_eq!(visited, vec!["p", "span", "h1"]);
+
+        let mut visited = Vec::new();
+        while let Some(n) = walker.previous_node() {
+            visited.push(name(&n));
+        }
+        assert_eq!(visited, vec!["span", "p", "div"]);
+
+        let mut walker = TreeWalker::new(&root, SHOW_TEXT | SHOW_COMMENT, None);
+        let mut visited = Vec::new();
+        while let Some(n) = walker.next_node() {
+            visited.push(name(&n));
+        }
+        assert_eq!(visited, vec!["a", "b", "<!--c-->", "d", "e"]);
+    }
+
+    #[test]
+    fn test_tree_walker_navigation() {
+        let root = tree();
+        let mut walker = TreeWalker::new(&root, SHOW_ELEMENT, None);
+
+        assert_eq!(name(&walker.first_child().unwrap()), "p");
+        assert_eq!(name(&walker.last_child().unwrap()), "span");
+        assert!(walker.next_sibling().is_none());
+        assert_eq!(name(&walker.parent_node().unwrap()), "p");
+        assert_eq!(name(&walker.next_sibling().unwrap()), "h1");
+        assert_eq!(name(&walker.previous_sibling().unwrap()), "p");
+        assert_eq!(name(&walker.parent_node().unwrap()), "div");
+        assert!(walker.parent_node().is_none());
+    }
+
+    #[test]
+    fn test_tree_walker_filter_skip_and_reject() {
+        let root = tree();
+
+        // skipping <p> still visits its children
+        let skip_p = is_kind(HtmlElementKind::P);
+        let filter: NodeFilter = Box::new(move |n| {
+            if skip_p(n) {
+                FilterResult::Skip
+            } else {
+                FilterResult::Accept
+            }
+        });
+        let mut walker = TreeWalker::new(&root, SHOW_ELEMENT, Some(filter));
+        assert_eq!(name(&walker.first_child().unwrap()), "span");
+        assert_eq!(name(&walker.parent_node().unwrap()), "div");
+
+        // rejecting <p> hides its whole subtree
+        let reject_p = is_kind(HtmlElementKind::P);
+        let filter: NodeFilter = Box::new(move |n| {
+            if reject_p(n) {
+                FilterResult::Reject
+            } else {
+                FilterResult::Accept
+            }
+        });
+        let mut walker = TreeWalker::new(&root, SHOW_ALL, Some(filter));
+        let mut visited = Vec::new();
+        while let Some(n) = walker.next_node() {
+            visited.push(name(&n));
+        }
+        assert_eq!(visited, vec!["a", "h1", "e"]);
+    }
+
+    #[test]
+    fn test_node_iterator() {
+        let root = tree();
+        let mut iterator = NodeIterator::new(&root, SHOW_ELEMENT, None);
+
+        assert_eq!(name(&iterator.next_node().unwrap()), "div");
+        assert_eq!(name(&iterator.next_node().unwrap()), "p");
+        assert_eq!(name(&iterator.next_node().unwrap()), "span");
+        // changing direction returns the reference node again
+        assert_eq!(name(&iterator.previous_node().unwrap()), "span");
+        assert_eq!(name(&iterator.previous_node().unwrap()), "p");
+        assert_eq!(
+            iterator.map(|n| name(&n)).collect::<Vec<_>>(),
+            vec!["p", "span", "h1"]
+        );
+    }
+}